@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use raug::prelude::*;
+use raug::{graph::asset::Assets, prelude::*, processor::ProcessMode};
 
 const SAMPLE_RATE: Float = 48_000.0;
 const BLOCK_SIZES: &[usize] = &[128, 512, 2048];
@@ -67,9 +67,123 @@ pub fn bench_generative1(c: &mut Criterion) {
     group.finish();
 }
 
+pub fn bench_dense_math(c: &mut Criterion) {
+    // the same `Mul` processor, with inputs that are dense and inputs that hold the same samples but aren't marked as dense
+    let mut group = c.benchmark_group(name("dense_math"));
+
+    let assets = Assets::default();
+    let input_spec = vec![
+        SignalSpec::new("a", SignalType::Float),
+        SignalSpec::new("b", SignalType::Float),
+    ];
+    let output_spec = vec![SignalSpec::new("out", SignalType::Float)];
+
+    for &block_size in BLOCK_SIZES {
+        let samples: Vec<Float> = (0..block_size).map(|i| i as Float * 0.001).collect();
+        let sparse = SignalBuffer::Float(Buffer::from_slice(&samples));
+        let mut dense = Buffer::zeros(block_size);
+        dense.fill_dense(|out| out.copy_from_slice(&samples));
+        let dense = SignalBuffer::Float(dense);
+
+        let mut outputs = vec![SignalBuffer::new_of_type(&SignalType::Float, block_size)];
+        let mut mul = Mul::new(SignalType::Float);
+
+        group.throughput(criterion::Throughput::Elements(block_size as u64));
+        for (label, input) in [("sparse", &sparse), ("dense", &dense)] {
+            let inputs = [Some(input), Some(input)];
+            group.bench_function(format!("{}_block_size_{}", label, block_size), |b| {
+                b.iter(|| {
+                    let inputs = ProcessorInputs::new(
+                        &input_spec,
+                        &inputs,
+                        &assets,
+                        ProcessMode::Block,
+                        SAMPLE_RATE,
+                        block_size,
+                    );
+                    let outputs =
+                        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block);
+                    mul.process(inputs, outputs).unwrap();
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+pub fn bench_math_chain(c: &mut Criterion) {
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+
+    let sine1 = graph.add(SineOscillator::new(440.0));
+    let sine2 = graph.add(SineOscillator::new(3.0));
+    let mix = (sine1 * (sine2 * 0.5 + 0.5)).tanh() * 0.2;
+    let filter = graph.add(OnePole::new(2000.0));
+    filter.input(0).connect(mix.output(0));
+    filter.output(0).connect(&out1.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    let mut group = c.benchmark_group(name("math_chain"));
+
+    for &block_size in BLOCK_SIZES {
+        runtime.allocate_for_block_size(SAMPLE_RATE, block_size);
+
+        group.throughput(criterion::Throughput::Elements(block_size as u64));
+        group.bench_function(format!("block_size_{}", block_size), |b| {
+            b.iter(|| {
+                runtime.process().unwrap();
+            });
+        });
+    }
+
+    group.finish();
+}
+
+pub fn bench_feedback_delay(c: &mut Criterion) {
+    let mut group = c.benchmark_group(name("feedback_delay"));
+
+    // a feedback comb filter; a minimum delay of 0 forces the loop to be processed sample by sample
+    for min_delay in [0, 64] {
+        let graph = GraphBuilder::new();
+
+        let out1 = graph.add_audio_output();
+
+        let sine = graph.add(SineOscillator::new(440.0));
+        let delay = graph.add(SampleDelay::new(1024).with_min_delay(min_delay));
+        delay.input("delay").connect(256);
+        let comb = sine + delay.clone() * 0.5;
+        delay.input(0).connect(comb.output(0));
+        comb.output(0).connect(&out1.input(0));
+
+        let mut runtime = graph.build_runtime();
+
+        for &block_size in BLOCK_SIZES {
+            runtime.allocate_for_block_size(SAMPLE_RATE, block_size);
+
+            group.throughput(criterion::Throughput::Elements(block_size as u64));
+            group.bench_function(
+                format!("min_delay_{}_block_size_{}", min_delay, block_size),
+                |b| {
+                    b.iter(|| {
+                        runtime.process().unwrap();
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     // bench_demo,
-    bench_generative1
+    bench_generative1,
+    bench_dense_math,
+    bench_math_chain,
+    bench_feedback_delay
 );
 criterion_main!(benches);
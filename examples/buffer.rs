@@ -11,10 +11,11 @@ fn main() {
     let out1 = graph.add_audio_output();
     let out2 = graph.add_audio_output();
 
-    // add a buffer reader
-    let buf = Buffer::load_wav("examples/assets/piano1.wav").unwrap();
-    let len = buf.len() as Float;
-    graph.add_asset("piano", buf);
+    // add a buffer reader, resampling the file to the runtime's sample rate when the graph is allocated
+    let (buf, sample_rate) =
+        Buffer::load_wav_with_sample_rate("examples/assets/piano1.wav").unwrap();
+    let duration = buf.len() as Float / sample_rate;
+    graph.add_asset_resampled("piano", buf, sample_rate, ResampleQuality::High);
     let buffer = graph.add(AudioBuffer::new("piano"));
 
     // connect the buffer reader to the outputs
@@ -24,15 +25,15 @@ fn main() {
     // create a sawtooth oscillator to drive the buffer reader
     let saw = graph.add(SawOscillator::default());
 
-    // we want to read the sample to its full length, so set the frequency to the sample rate divided by the length
-    let freq = graph.sample_rate() / len;
+    // we want to read the sample to its full length, so set the frequency to one over its duration
+    let freq = graph.constant(1.0 / duration);
     freq.output(0).connect(&saw.input("frequency"));
 
-    // multiply the saw oscillator's amplitude by the length of the buffer, so it outputs the full range of the buffer
-    let saw = saw * len;
+    // multiply the saw oscillator's amplitude by the length of the resampled buffer, so it outputs the full range of the buffer
+    let saw = saw * (graph.sample_rate() * duration);
 
     // connect the saw oscillator to the buffer reader
-    saw.output(0).connect(&buffer.input("position"));
+    saw.output(0).connect(&buffer.input("index"));
 
     // build the graph
     let graph = graph.build();
@@ -0,0 +1,111 @@
+/*
+ * A test program for raug's C API (see include/raug.h).
+ *
+ * Build and run from the repository root:
+ *
+ *     cargo build --release -p raug-ffi
+ *     cargo run --release --features ffi --example ffi_patch target/patch.json
+ *     cc -Wall -Wextra -o target/raug_test examples/c/test.c -Iinclude -Ltarget/release -lraug_ffi -lm
+ *     LD_LIBRARY_PATH=target/release target/raug_test target/patch.json
+ *
+ * or let the `c_api` test of the raug-ffi crate do all of this (it needs a C compiler):
+ *
+ *     cargo test -p raug-ffi -- --ignored
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "raug.h"
+
+#define FRAMES 256
+
+static int failures = 0;
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            const char *error = raug_last_error();                      \
+            fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n", \
+                    __FILE__, __LINE__, #cond, error ? error : "none"); \
+            failures++;                                                 \
+        }                                                               \
+    } while (0)
+
+static int close_to(float a, float b) { return fabsf(a - b) < 1e-6f; }
+
+int main(int argc, char **argv) {
+    const char *path = argc > 1 ? argv[1] : "target/patch.json";
+
+    /* errors are reported without crashing */
+    CHECK(raug_runtime_from_patch_file("does/not/exist.json") == NULL);
+    CHECK(raug_last_error() != NULL);
+    CHECK(raug_runtime_from_patch_json("{ not json") == NULL);
+    CHECK(raug_runtime_num_inputs(NULL) == 0);
+
+    RaugRuntime *rt = raug_runtime_from_patch_file(path);
+    CHECK(rt != NULL);
+    if (rt == NULL) {
+        return 1;
+    }
+
+    CHECK(raug_runtime_num_inputs(rt) == 2);
+    CHECK(raug_runtime_num_outputs(rt) == 2);
+
+    float input[FRAMES * 2];
+    float output[FRAMES * 2];
+    for (size_t i = 0; i < FRAMES; i++) {
+        input[i * 2] = (float)i;
+        input[i * 2 + 1] = 1.0f;
+    }
+
+    /* processing before preparing fails */
+    CHECK(raug_runtime_process_interleaved(rt, input, output, FRAMES, NULL, NULL, 0) ==
+          RAUG_STATUS_NOT_PREPARED);
+
+    CHECK(raug_runtime_prepare(rt, 48000.0, 64) == RAUG_STATUS_OK);
+
+    /* the left channel is scaled by the "gain" param */
+    CHECK(raug_runtime_set_param(rt, "gain", 2.0) == RAUG_STATUS_OK);
+    CHECK(raug_runtime_set_param(rt, "missing", 1.0) == RAUG_STATUS_PARAM_NOT_FOUND);
+
+    /* params are set with a function for their type */
+    CHECK(raug_runtime_set_param(rt, "mute", 1.0) == RAUG_STATUS_TYPE_MISMATCH);
+    CHECK(raug_runtime_set_param_int(rt, "gain", 2) == RAUG_STATUS_TYPE_MISMATCH);
+    CHECK(raug_runtime_set_param_bool(rt, "transpose", true) == RAUG_STATUS_TYPE_MISMATCH);
+
+    /* the right channel follows the note number of the last note-on, from the frame it arrives at */
+    size_t midi_offsets[2] = {10, 100};
+    uint8_t midi_bytes[6] = {0x90, 60, 100, 0x90, 64, 100};
+
+    /* blocks larger than the prepared maximum are split up */
+    CHECK(raug_runtime_process_interleaved(rt, input, output, FRAMES, midi_offsets, midi_bytes,
+                                           2) == RAUG_STATUS_OK);
+
+    for (size_t i = 0; i < FRAMES; i++) {
+        float expected_note = i < 10 ? 0.0f : i < 100 ? 60.0f : 64.0f;
+        CHECK(close_to(output[i * 2], 2.0f * (float)i));
+        CHECK(close_to(output[i * 2 + 1], expected_note));
+    }
+
+    /* the "mute" param silences the left channel, and the "transpose" param is added to the note number */
+    CHECK(raug_runtime_set_param_bool(rt, "mute", true) == RAUG_STATUS_OK);
+    CHECK(raug_runtime_set_param_int(rt, "transpose", 12) == RAUG_STATUS_OK);
+    CHECK(raug_runtime_process_interleaved(rt, input, output, FRAMES, NULL, NULL, 0) ==
+          RAUG_STATUS_OK);
+
+    for (size_t i = 0; i < FRAMES; i++) {
+        CHECK(close_to(output[i * 2], 0.0f));
+        CHECK(close_to(output[i * 2 + 1], 76.0f));
+    }
+
+    raug_runtime_free(rt);
+
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("all checks passed\n");
+    return 0;
+}
@@ -0,0 +1,47 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // a four-on-the-floor kick drum at 120 bpm
+    let metro = graph.add(Metro::new(0.5));
+    let kick_env = graph.add(LinearDecayEnv::new(0.2));
+    kick_env.input("trig").connect(metro.output(0));
+    let kick_osc = graph.add(SineOscillator::default());
+    kick_osc
+        .input("frequency")
+        .connect((kick_env.output(0) * 100.0 + 50.0).output(0));
+    let kick = kick_osc * kick_env;
+
+    // a sustained pad
+    let pad = graph.add(BlSawOscillator::default());
+    pad.input("frequency")
+        .connect(graph.constant(110.0).output(0));
+
+    // duck the pad with the kick, listening only to its low end
+    let compressor = graph.add(
+        Compressor::new(0.1, 10.0, 0.9, 0.9998)
+            .with_sidechain_filter(AutoBiquad::lowpass(150.0, 0.707)),
+    );
+    compressor.input("in").connect(pad.output(0));
+    compressor.input("sidechain").connect(kick.output(0));
+
+    // print the kick's envelope as seen by a follower
+    let follower = graph.add(EnvelopeFollower::new(0.001, 0.1));
+    follower.input("in").connect(kick.output(0));
+    follower.output(0).gt(0.5).cast(SignalType::Float).print();
+
+    let mix = (kick * 0.5) + (compressor * 0.3);
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    runtime
+        .run_offline_to_file("target/ducking.wav", Duration::from_secs(4), 48_000.0, 512)
+        .unwrap();
+}
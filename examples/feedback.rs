@@ -0,0 +1,48 @@
+use raug::prelude::*;
+
+fn main() {
+    // initialize logging
+    env_logger::init();
+
+    // create a new graph
+    let graph = GraphBuilder::new();
+
+    // add some outputs
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // play a short sine blip once per second
+    let metro = graph.add(Metro::new(1.0));
+    let env = graph.add(DecayEnv::new(0.02));
+    env.input("trig").connect(metro.output(0));
+    let sine = graph.add(SineOscillator::new(880.0));
+    let blip = sine * env;
+
+    // add a feedback delay of 0.25 seconds (at 44.1 kHz)
+    let feedback = graph.feedback(11_025);
+
+    // darken each repeat with a lowpass filter in the feedback path
+    let filter = graph.add(OnePole::new(2000.0));
+    filter.input(0).connect(feedback.output(0));
+
+    // mix the dry signal with the filtered, attenuated repeats
+    let echo = blip + filter * 0.6;
+
+    // close the loop by feeding the mix back into the delay
+    feedback.input(0).connect(echo.output(0));
+
+    // set the output volume
+    let echo = echo * 0.2;
+
+    // connect the echo to the outputs
+    echo.output(0).connect(&out1.input(0));
+    echo.output(0).connect(&out2.input(0));
+
+    // build the graph
+    let mut runtime = graph.build_runtime();
+
+    // run the graph for 4 seconds
+    runtime
+        .run_offline_to_file("target/feedback.wav", Duration::from_secs(4), 44_100.0, 512)
+        .unwrap();
+}
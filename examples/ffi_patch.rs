@@ -0,0 +1,40 @@
+use raug::prelude::*;
+
+// Writes the patch used by the C API test program in `examples/c/test.c`.
+fn main() {
+    env_logger::init();
+
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "target/patch.json".to_string());
+
+    let graph = GraphBuilder::new();
+
+    let in_l = graph.add_audio_input();
+    let in_r = graph.add_audio_input();
+    let out_l = graph.add_audio_output();
+    let out_r = graph.add_audio_output();
+    let gain = graph.add_param(Param::new::<Float>("gain", 0.5));
+    let mute = graph.add_param(Param::new::<bool>("mute", false));
+    let transpose = graph.add_param(Param::new::<i64>("transpose", 0));
+    let midi = graph.add_midi_input("midi");
+
+    // the left channel is scaled by the gain and can be muted,
+    // and the right channel is replaced by the last MIDI note number plus the transposition
+    let left = graph.add(Cond::new(SignalType::Float));
+    left.input("cond").connect(mute.output(0));
+    left.input("then").connect(graph.constant(0.0));
+    left.input("else").connect((in_l * gain).output(0));
+
+    let note = graph.add(MidiNote::default());
+    note.input(0).connect(midi.output(0));
+
+    let right = in_r * 0.0 + note + transpose.cast(SignalType::Float);
+    left.output(0).connect(&out_l.input(0));
+    right.output(0).connect(&out_r.input(0));
+
+    let graph = graph.build();
+    let file = std::fs::File::create(&path).unwrap();
+    serde_json::to_writer(file, &graph).unwrap();
+    println!("wrote {}", path);
+}
@@ -0,0 +1,83 @@
+use raug::prelude::*;
+
+// A stand-in for a plugin host or a game engine: it owns its own sample buffers,
+// calls the graph with blocks of whatever size it likes, and passes MIDI events with sample offsets.
+fn main() {
+    env_logger::init();
+
+    // a stereo effect with a MIDI-controlled synth mixed in
+    let graph = GraphBuilder::new();
+    let in_l = graph.add_audio_input();
+    let in_r = graph.add_audio_input();
+    let out_l = graph.add_audio_output();
+    let out_r = graph.add_audio_output();
+    let midi = graph.add_midi_input("midi_in");
+
+    let synth = graph.add(Poly::build(4, |voice| {
+        let note = voice.add_input("note", SignalType::Float);
+        let gate = voice.add_input("gate", SignalType::Bool);
+        let out = voice.add_output("out", SignalType::Float);
+
+        let saw = voice.add(BlSawOscillator::default());
+        saw.input(0).connect(note.midi2freq());
+        let env = voice.add(ADSREnv::new(0.005, 0.05, 0.5, 0.1));
+        env.input("gate").connect(gate.output(0));
+
+        let voice_out = saw * env * 0.1;
+        voice_out.output(0).connect(&out.input(0));
+    }));
+    synth.input("midi").connect(midi.output(0));
+
+    let mix_l = in_l * 0.5 + synth.clone();
+    let mix_r = in_r * 0.5 + synth;
+    mix_l.output(0).connect(&out_l.input(0));
+    mix_r.output(0).connect(&out_r.input(0));
+
+    let mut runtime = EmbeddedRuntime::new(graph.build());
+
+    // the host tells the graph its configuration up front; this is the only place that allocates
+    let sample_rate = 48_000;
+    runtime.prepare(sample_rate as Float, 256);
+
+    // the host's own buffers
+    let total = sample_rate;
+    let input: Vec<f32> = (0..total)
+        .map(|i| (i as f32 / sample_rate as f32 * 220.0 * std::f32::consts::TAU).sin())
+        .collect();
+    let mut left = vec![0.0f32; total];
+    let mut right = vec![0.0f32; total];
+
+    // play a note for a quarter second, starting 100 samples into the first block
+    let note_on = MidiMessage::new([0x90, 60, 100]);
+    let note_off = MidiMessage::new([0x80, 60, 0]);
+
+    // hosts don't always use the same block size
+    let block_sizes = [256, 128, 200, 64, 256];
+    let mut position = 0;
+    let mut block = 0;
+    while position < total {
+        let len = block_sizes[block % block_sizes.len()].min(total - position);
+        let range = position..position + len;
+
+        let mut midi = Vec::new();
+        for (time, message) in [(100, note_on), (12_100, note_off)] {
+            if range.contains(&time) {
+                midi.push(MidiEvent::new(time - position, message));
+            }
+        }
+
+        let (l, r) = (&mut left[range.clone()], &mut right[range.clone()]);
+        runtime
+            .process(&[&input[range.clone()], &input[range]], &mut [l, r], &midi)
+            .unwrap();
+
+        position += len;
+        block += 1;
+    }
+
+    let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    println!("processed {} blocks", block);
+    println!("peak before the note: {:.3}", peak(&left[..100]));
+    println!("peak during the note: {:.3}", peak(&left[100..12_100]));
+    println!("peak after the note:  {:.3}", peak(&left[24_000..]));
+}
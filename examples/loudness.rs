@@ -0,0 +1,44 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // a sine wave that alternates between loud and quiet every 2 seconds
+    let sine = graph.add(SineOscillator::default());
+    sine.input("frequency")
+        .connect(graph.constant(440.0).output(0));
+    let lfo = graph.add(SawOscillator::default());
+    lfo.input("frequency")
+        .connect(graph.constant(0.25).output(0));
+    let gate = lfo.gt(0.5).cond(0.5, 0.05);
+    let mix = sine * gate;
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    // measure the render
+    let outputs = runtime
+        .run_offline(Duration::from_secs(10), 48_000.0, 512)
+        .unwrap();
+    let report = raug::loudness::analyze(&outputs, 48_000.0);
+    println!("Integrated: {:.1} LUFS", report.integrated);
+    println!("Loudness range: {:.1} LU", report.loudness_range);
+    println!("True peak: {:.1} dBTP", report.true_peak);
+
+    // render again, normalized for broadcast
+    runtime
+        .run_offline_to_file_normalized(
+            "target/loudness.wav",
+            Duration::from_secs(10),
+            48_000.0,
+            512,
+            raug::loudness::EBU_R128_TARGET,
+            Some(-1.0),
+        )
+        .unwrap();
+}
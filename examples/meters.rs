@@ -0,0 +1,60 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // a sine wave with a slow tremolo
+    let sine = graph.add(SineOscillator::default());
+    sine.input("frequency")
+        .connect(graph.constant(220.0).output(0));
+    let lfo = graph.add(SineOscillator::default());
+    lfo.input("frequency")
+        .connect(graph.constant(0.5).output(0));
+    let mix = sine * (lfo * 0.1 + 0.15);
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    // tap the output for a level meter and an oscilloscope
+    let meter = graph.add_tap(MeterTap::new("level"));
+    meter.input("in").connect(mix.output(0));
+    let scope = graph.add_tap(ScopeTap::new("scope", 64).with_decimation(4).with_trigger());
+    scope.input("in").connect(mix.output(0));
+
+    let mut runtime = graph.build_runtime();
+
+    let handle = runtime
+        .run(AudioBackend::Default, AudioDevice::Default, None)
+        .unwrap();
+
+    let meter = handle.tap("level").unwrap().as_meter().unwrap().clone();
+    let scope = handle.tap("scope").unwrap().as_scope().unwrap().clone();
+    let mut frame = vec![0.0; scope.frame_len()];
+
+    // draw the meter and the scope in the terminal for a few seconds
+    for _ in 0..50 {
+        std::thread::sleep(Duration::from_millis(100));
+
+        if let Some(reading) = meter.read() {
+            let bar = "#".repeat(((reading.rms_db() + 60.0).max(0.0) / 2.0) as usize);
+            println!("{:>6.1} dB {}", reading.rms_db(), bar);
+        }
+
+        if scope.read_latest(&mut frame) {
+            let line: String = frame
+                .iter()
+                .map(|&sample| match sample {
+                    s if s > 0.1 => '\u{2580}',
+                    s if s < -0.1 => '\u{2584}',
+                    _ => '-',
+                })
+                .collect();
+            println!("          {}", line);
+        }
+    }
+
+    handle.stop();
+}
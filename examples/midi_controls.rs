@@ -0,0 +1,66 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // a monophonic synth that listens on the first channel
+    let midi = graph.add(MidiChannelFilter::new(0));
+    midi.input(0)
+        .connect(graph.add_midi_input("midi_in").output(0));
+
+    let note = graph.add(MidiNote::default());
+    note.input(0).connect(midi.output(0));
+    let gate = graph.add(MidiGate::default());
+    gate.input(0).connect(midi.output(0));
+
+    // the pitch bend wheel bends by up to a whole tone
+    let bend = graph.add(MidiPitchBend::new().with_range(2.0));
+    bend.input(0).connect(midi.output(0));
+    let pitch = note.output(0) + bend.output("semitones");
+
+    let saw = graph.add(BlSawOscillator::default());
+    saw.input(0).connect(pitch.midi2freq());
+
+    // the mod wheel (CC 1) opens the filter, smoothed to avoid zipper noise
+    let wheel = graph.add(MidiControl::new(1).with_smoothing(0.01));
+    wheel.input(0).connect(midi.output(0));
+    let cutoff = (wheel * 60.0 + 40.0).midi2freq();
+
+    let filter = graph.add(MoogLadder::new(1000.0, 0.2));
+    filter.input("in").connect(saw.output(0));
+    filter.input("cutoff").connect(cutoff.output(0));
+
+    // aftertouch adds tremolo, dipping the level by up to the pressure
+    let pressure = graph.add(MidiChannelPressure::new().with_smoothing(0.02));
+    pressure.input(0).connect(midi.output(0));
+    let lfo = graph.add(SineOscillator::default());
+    lfo.input("frequency").connect(6.0);
+    let tremolo = pressure * (lfo * -0.5 - 0.5) + 1.0;
+
+    let env = graph.add(ADSREnv::new(0.01, 0.1, 0.7, 0.3));
+    env.input("gate").connect(gate.output(0));
+
+    let voice_out = filter * env * tremolo * 0.2;
+    voice_out.output(0).connect(&out1.input(0));
+    voice_out.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    raug::util::list_midi_ports();
+
+    let handle = runtime
+        .run(
+            AudioBackend::Default,
+            AudioDevice::Default,
+            Some(MidiPort::Name("MPK mini Plus".to_string())), // change this to the name of your MIDI device
+        )
+        .unwrap();
+
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+
+    handle.stop();
+}
@@ -0,0 +1,78 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+
+    // write a short arpeggio to a MIDI file (normally this would come from a DAW or notation program)
+    let mut events = Vec::new();
+    for (i, note) in [60, 64, 67, 72, 67, 64, 60].into_iter().enumerate() {
+        let time = i as f64 * 0.25;
+        events.push((time, MidiMessage::new([0x90, note, 100])));
+        events.push((time + 0.2, MidiMessage::new([0x80, note, 0])));
+    }
+    MidiFile::from_events(events)
+        .save("target/arpeggio.mid")
+        .unwrap();
+
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+    let recording = graph.add_midi_output("recording");
+
+    // play the file into a synth
+    let player = graph.add(MidiFilePlayer::load("target/arpeggio.mid").unwrap());
+
+    let poly = graph.add(Poly::build(4, |voice| {
+        let note = voice.add_input("note", SignalType::Float);
+        let gate = voice.add_input("gate", SignalType::Bool);
+        let out = voice.add_output("out", SignalType::Float);
+
+        let saw = voice.add(BlSawOscillator::default());
+        saw.input(0).connect(note.midi2freq());
+
+        let env = voice.add(ADSREnv::new(0.01, 0.1, 0.7, 0.3));
+        env.input("gate").connect(gate.output(0));
+
+        let voice_out = saw * env * 0.2;
+        voice_out.output(0).connect(&out.input(0));
+    }));
+    poly.input("midi").connect(player.output(0));
+
+    poly.output(0).connect(&out1.input(0));
+    poly.output(0).connect(&out2.input(0));
+
+    // transpose the sequence up a fifth and record it
+    let note = graph.add(MidiNote::default());
+    note.input(0).connect(player.output(0));
+    let gate = graph.add(MidiGate::default());
+    gate.input(0).connect(player.output(0));
+    let transposed = graph.add(MidiNoteMessage::new(0));
+    transposed.input("gate").connect(gate.output(0));
+    transposed.input("note").connect((note + 7.0).output(0));
+    transposed.output(0).connect(&recording.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    // render the audio fully offline
+    runtime
+        .run_offline_to_file(
+            "target/midi_file.wav",
+            Duration::from_secs(3),
+            48_000.0,
+            512,
+        )
+        .unwrap();
+
+    // and record the MIDI produced by the graph
+    let sample_rate = 48_000.0;
+    let (_, midi) = runtime
+        .run_offline_with_midi(Duration::from_secs(3), sample_rate, 512)
+        .unwrap();
+    let recorded = MidiFile::record(&midi[0], sample_rate);
+    recorded.save("target/transposed.mid").unwrap();
+
+    for (time, message) in recorded.timed_events() {
+        println!("{:.3}s: {:02x?}", time, message.data);
+    }
+}
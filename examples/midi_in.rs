@@ -9,22 +9,27 @@ fn main() {
 
     let midi = graph.add_midi_input("midi_in");
 
-    let sine = graph.add(BlSawOscillator::default());
-
-    let freq = graph.add(MidiNote::default());
-    freq.input("midi").connect(midi.output(0));
-    let freq = freq.make_register().midi2freq();
-    freq.output(0).connect(&sine.input(0));
-
-    let vel = graph.add(MidiVelocity::default());
-    vel.input("midi").connect(midi.output(0));
-    let vel = vel.make_register().smooth(0.001);
-    let vel = vel / 127.0 * 0.5;
-
-    let mix = sine * vel;
-
-    mix.output(0).connect(&out1.input(0));
-    mix.output(0).connect(&out2.input(0));
+    // play up to 8 notes at once, each on its own voice
+    let poly = graph.add(Poly::build(8, |voice| {
+        let note = voice.add_input("note", SignalType::Float);
+        let velocity = voice.add_input("velocity", SignalType::Float);
+        let gate = voice.add_input("gate", SignalType::Bool);
+        let out = voice.add_output("out", SignalType::Float);
+
+        let saw = voice.add(BlSawOscillator::default());
+        saw.input(0).connect(note.midi2freq());
+
+        let env = voice.add(ADSREnv::new(0.01, 0.1, 0.7, 0.3));
+        env.input("gate").connect(gate.output(0));
+
+        let amp = velocity / 127.0 * 0.2;
+        let voice_out = saw * env * amp;
+        voice_out.output(0).connect(&out.input(0));
+    }));
+    poly.input("midi").connect(midi.output(0));
+
+    poly.output(0).connect(&out1.input(0));
+    poly.output(0).connect(&out2.input(0));
 
     let mut runtime = graph.build_runtime();
 
@@ -0,0 +1,73 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let notes_out = graph.add_midi_output("notes");
+    let cc_out = graph.add_midi_output("cutoff");
+
+    // a gate that opens and closes 4 times per second
+    let clock = graph.add(SawOscillator::default());
+    clock.input("frequency").connect(4.0);
+    let gate = clock.gt(0.0);
+
+    // an arpeggio that rises by an octave every second
+    let ramp = graph.add(SawOscillator::default());
+    ramp.input("frequency").connect(1.0);
+    let note = (ramp * 6.0 + 66.0).round();
+
+    let notes = graph.add(MidiNoteMessage::new(0));
+    notes.input("gate").connect(gate.output(0));
+    notes.input("note").connect(note.output(0));
+    notes.input("velocity").connect(100.0);
+    notes.output(0).connect(&notes_out.input(0));
+
+    // sweep a filter cutoff (CC 74) on the external synth
+    let lfo = graph.add(SineOscillator::default());
+    lfo.input("frequency").connect(0.5);
+    let cutoff = graph.add(MidiControlMessage::new(0, 74));
+    cutoff.input("value").connect((lfo * 0.5 + 0.5).output(0));
+    cutoff.output(0).connect(&cc_out.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    // capture the MIDI offline
+    let sample_rate = 48_000.0;
+    let (_, midi) = runtime
+        .run_offline_with_midi(Duration::from_secs(1), sample_rate, 512)
+        .unwrap();
+    let names: Vec<String> = runtime
+        .graph()
+        .midi_output_iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    for (name, events) in names.iter().zip(&midi) {
+        println!("{}: {} messages", name, events.len());
+        for event in events.iter().take(8) {
+            println!(
+                "  {:.4}s: {:02x?}",
+                event.time as Float / sample_rate,
+                event.message.data
+            );
+        }
+    }
+
+    // or send it to an external synth in real time, if a port name is given
+    if let Some(port) = std::env::args().nth(1) {
+        raug::util::list_midi_ports();
+
+        let handle = runtime
+            .run_with_midi(
+                AudioBackend::Default,
+                AudioDevice::Default,
+                None,
+                Some(MidiPort::Name(port)),
+            )
+            .unwrap();
+
+        std::io::stdin().read_line(&mut String::new()).unwrap();
+
+        handle.stop();
+    }
+}
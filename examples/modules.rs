@@ -0,0 +1,75 @@
+use raug::prelude::*;
+
+fn main() {
+    // initialize logging
+    env_logger::init();
+
+    // define a reusable voice module with a filtered sawtooth oscillator
+    let voice = Module::new("voice", |m| {
+        // create parameters for the frequency and filter cutoff
+        // each instance starts with the values it was created with
+        let freq = m.param::<Float>("freq");
+        let cutoff = m.param::<Float>("cutoff");
+
+        let saw = m.add(BlSawOscillator::default());
+        saw.input("frequency").connect(freq.output(0));
+
+        let filter = m.add(OnePole::default());
+        filter.input("in").connect(saw.output(0));
+        filter.input("cutoff").connect(cutoff.output(0));
+
+        // scale the filtered signal by the gain input
+        let out = filter * m.input("gain");
+        out.output(0).connect(&m.output("out").input(0));
+    })
+    .with_input("gain", SignalType::Float)
+    .with_output("out", SignalType::Float)
+    .with_param("freq", 220.0)
+    .with_param("cutoff", 1000.0);
+
+    // create a new graph
+    let graph = GraphBuilder::new();
+
+    // add some outputs
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // register the template, so that it can also be looked up by name (e.g. when loading a patch)
+    let registry = ModuleRegistry::new().with(voice.clone());
+
+    // instantiate the voice twice with different arguments
+    let voice1 = graph.add_module("voice1", &voice, &ModuleArgs::new());
+    let voice2 = graph.add_module(
+        "voice2",
+        registry.get("voice").unwrap(),
+        &ModuleArgs::new().with("freq", 330.0).with("cutoff", 4000.0),
+    );
+
+    // set the gain of each voice
+    voice1.input("gain").connect(0.1);
+    voice2.input("gain").connect(0.1);
+
+    // mix the voices and connect them to the outputs
+    let mix = voice1 + voice2;
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    // build the runtime
+    let mut runtime = graph.build_runtime();
+
+    // the parameters of each instance are available by their hierarchical names
+    println!("Parameters: {:?}", runtime.graph().param_names());
+
+    // run the graph for 1 second
+    let handle = runtime
+        .run(AudioBackend::Default, AudioDevice::Default, None)
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    // open up the filter of the first voice
+    runtime.param_named("voice1/cutoff").unwrap().send(8000.0);
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    // stop playback
+    handle.stop();
+}
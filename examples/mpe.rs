@@ -0,0 +1,61 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    let midi = graph.add_midi_input("midi_in");
+
+    // an MPE synth: every note has its own pitch bend, pressure and timbre
+    let poly = graph.add(
+        Poly::build(15, |voice| {
+            let note = voice.add_input("note", SignalType::Float);
+            let gate = voice.add_input("gate", SignalType::Bool);
+            let bend = voice.add_input("bend", SignalType::Float);
+            let pressure = voice.add_input("pressure", SignalType::Float);
+            let timbre = voice.add_input("timbre", SignalType::Float);
+            let out = voice.add_output("out", SignalType::Float);
+
+            let saw = voice.add(BlSawOscillator::default());
+            saw.input(0).connect((note + bend).midi2freq());
+
+            // sliding up the key opens the filter
+            let cutoff = (timbre * 60.0 + 50.0).midi2freq();
+            let filter = voice.add(MoogLadder::new(1000.0, 0.3));
+            filter.input("in").connect(saw.output(0));
+            filter.input("cutoff").connect(cutoff.output(0));
+
+            // pressing harder makes the note louder
+            let env = voice.add(ADSREnv::new(0.005, 0.1, 1.0, 0.3));
+            env.input("gate").connect(gate.output(0));
+            let amp = env * (pressure * 0.15 + 0.05);
+
+            let voice_out = filter * amp;
+            voice_out.output(0).connect(&out.input(0));
+        })
+        .with_mpe(MpeZone::lower(15)),
+    );
+    poly.input("midi").connect(midi.output(0));
+
+    poly.output(0).connect(&out1.input(0));
+    poly.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    raug::util::list_midi_ports();
+
+    let handle = runtime
+        .run(
+            AudioBackend::Default,
+            AudioDevice::Default,
+            Some(MidiPort::Name("Seaboard BLOCK".to_string())), // change this to the name of your MPE controller
+        )
+        .unwrap();
+
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+
+    handle.stop();
+}
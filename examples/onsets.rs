@@ -0,0 +1,39 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // a noise "drum" hit every 250 ms
+    let metro = graph.add(Metro::new(0.25));
+    let drum_env = graph.add(LinearDecayEnv::new(0.05));
+    drum_env.input("trig").connect(metro.output(0));
+    let noise = graph.add(NoiseOscillator::new());
+    let drum = noise * drum_env;
+
+    // detect the hits, and count them
+    let detector = graph.add(OnsetDetector::default());
+    detector.input("in").connect(drum.output(0));
+    let counter = graph.add(Counter::default());
+    counter.input("trig").connect(detector.output("onset"));
+    counter.output(0).cast(SignalType::Float).print();
+
+    // play a short blip on every detected onset
+    let blip_env = graph.add(LinearDecayEnv::new(0.1));
+    blip_env.input("trig").connect(detector.output("onset"));
+    let sine = graph.add(SineOscillator::default());
+    sine.input("frequency")
+        .connect(graph.constant(880.0).output(0));
+    let mix = (drum * 0.2) + (sine * blip_env * 0.2);
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    runtime
+        .run_offline_to_file("target/onsets.wav", Duration::from_secs(2), 48_000.0, 512)
+        .unwrap();
+}
@@ -0,0 +1,64 @@
+use std::net::UdpSocket;
+
+use raug::osc::{OscArg, OscMessage};
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    let sine = graph.add(SineOscillator::default());
+    sine.input("frequency").param::<Float>("freq", Some(440.0));
+    let gain = graph.add_param(Param::bounded("gain", 0.2, 0.0, 1.0));
+
+    let mix = sine * gain;
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    // a meter, driven from within the graph, which is sent back out as feedback
+    let level = graph.add_param(Param::new::<Float>("level", None));
+    level
+        .input("set")
+        .connect(mix.abs().smooth(0.001).output(0));
+
+    let mut runtime = graph.build_runtime();
+
+    // `/raug/param/freq`, `/raug/param/gain` and `/raug/param/level` are now available on port 9000,
+    // and their values are sent to port 9001
+    let osc = OscServer::bind("0.0.0.0:9000")
+        .unwrap()
+        .with_graph(runtime.graph())
+        .with_feedback("127.0.0.1:9001")
+        .unwrap()
+        .run()
+        .unwrap();
+
+    let handle = runtime
+        .run(AudioBackend::Default, AudioDevice::Default, None)
+        .unwrap();
+
+    // act as an OSC client on the same machine
+    let client = UdpSocket::bind("127.0.0.1:9001").unwrap();
+    for freq in [330.0, 550.0, 440.0] {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        let message = OscMessage::new("/raug/param/freq").with_arg(OscArg::Float(freq));
+        client
+            .send_to(&message.to_bytes(), "127.0.0.1:9000")
+            .unwrap();
+    }
+
+    // print some of the feedback
+    let mut buf = [0; 1024];
+    for _ in 0..10 {
+        let len = client.recv(&mut buf).unwrap();
+        if let Ok(message) = OscMessage::from_bytes(&buf[..len]) {
+            println!("{} {:?}", message.address, message.args);
+        }
+    }
+
+    osc.stop();
+    handle.stop();
+}
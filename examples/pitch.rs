@@ -0,0 +1,34 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // a sawtooth wave playing G3
+    let saw = graph.add(BlSawOscillator::default());
+    saw.input("frequency")
+        .connect(graph.constant(196.0).output(0));
+
+    // track its pitch, and print the detected note
+    let detector = graph.add(PitchDetector::new(80.0, 1000.0));
+    detector.input("in").connect(saw.output(0));
+    detector.output("frequency").freq2midi().round().print();
+
+    // play a sine wave an octave above the detected pitch, while the detection is confident
+    let sine = graph.add(SineOscillator::default());
+    sine.input("frequency")
+        .connect((detector.output("frequency") * 2.0).output(0));
+    let confident = detector.output("confidence").gt(0.9);
+    let mix = (saw * 0.1) + (sine * confident.cond(0.1, 0.0));
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    runtime
+        .run_offline_to_file("target/pitch.wav", Duration::from_secs(1), 48_000.0, 512)
+        .unwrap();
+}
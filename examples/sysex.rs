@@ -0,0 +1,46 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let midi = graph.add_midi_input("midi_in");
+
+    // a (made up) parameter change message: manufacturer 0x7D (non-commercial), parameter 0x01, then the value
+    let cutoff = graph.add(MidiSysExValue::new(vec![0x7D, 0x01]));
+    cutoff.input(0).connect(midi.output(0));
+
+    // pass on all messages from the same manufacturer, e.g. to record them
+    let filter = graph.add(MidiSysExFilter::new(vec![0x7D]));
+    filter.input(0).connect(midi.output(0));
+    let sysex_out = graph.add_midi_output("sysex");
+    filter.output(0).connect(&sysex_out.input(0));
+
+    let out = graph.add_audio_output();
+    let cutoff = cutoff.output("value").cast(SignalType::Float);
+    cutoff.output(0).connect(&out.input(0));
+
+    // drive the graph like a plugin host would, with the SysEx message split into packets
+    let mut runtime = EmbeddedRuntime::new(graph.build());
+    runtime.prepare(48_000.0, 64);
+    let sysex_rx = runtime
+        .runtime()
+        .graph()
+        .midi_output_iter()
+        .map(|(_, rx)| rx)
+        .next()
+        .unwrap();
+
+    let message = [0xF0, 0x7D, 0x01, 0x40, 0xF7];
+    let events: Vec<MidiEvent> = MidiMessage::sysex_packets(&message)
+        .map(|packet| MidiEvent::new(10, packet))
+        .collect();
+
+    let mut output = vec![0.0; 64];
+    runtime.process(&[], &mut [&mut output], &events).unwrap();
+
+    println!("cutoff value: {}", output[63]);
+    for event in sysex_rx.try_iter() {
+        println!("{}: {:02x?}", event.time, event.message.as_bytes());
+    }
+}
@@ -0,0 +1,57 @@
+use raug::prelude::*;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    // follows MIDI clock from the input port, if one is given, and runs at 120 BPM otherwise
+    let midi = graph.add_midi_input("clock");
+    let transport = graph.add(Transport::new(120.0));
+    transport.input("midi").connect(midi.output(0));
+
+    // a sixteenth-note pulse, with every fourth pulse accented
+    let sixteenths = graph.add(TempoMetro::new(0.25));
+    sixteenths.input("beat").connect(transport.output("beat"));
+    sixteenths
+        .input("playing")
+        .connect(transport.output("playing"));
+
+    let accents = graph.add(ClockDivider::new(4));
+    accents.input("clock").connect(sixteenths.output(0));
+
+    let hits = graph.add(DecayEnv::new(0.02));
+    hits.input("trig").connect(sixteenths.output(0));
+    let accent_hits = graph.add(DecayEnv::new(0.08));
+    accent_hits.input("trig").connect(accents.output(0));
+
+    // the pitch sweeps up over each bar
+    let bar_phase = graph.add(BeatPhase::new(4.0));
+    bar_phase.input("beat").connect(transport.output("beat"));
+    let pitch = (bar_phase * 24.0 + 48.0).midi2freq();
+
+    let sine = graph.add(SineOscillator::default());
+    sine.input("frequency").connect(pitch.output(0));
+
+    let mix = sine * (hits * 0.1 + accent_hits * 0.2);
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    let mut runtime = graph.build_runtime();
+
+    raug::util::list_midi_ports();
+
+    let handle = runtime
+        .run(
+            AudioBackend::Default,
+            AudioDevice::Default,
+            std::env::args().nth(1).map(MidiPort::Name),
+        )
+        .unwrap();
+
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+
+    handle.stop();
+}
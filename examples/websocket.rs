@@ -0,0 +1,66 @@
+use raug::prelude::*;
+use raug::websocket::WebSocketServer;
+
+fn main() {
+    env_logger::init();
+    let graph = GraphBuilder::new();
+
+    let out1 = graph.add_audio_output();
+    let out2 = graph.add_audio_output();
+
+    let saw = graph.add(SawOscillator::default());
+    saw.input("frequency").param::<Float>("freq", Some(110.0));
+
+    let filter = graph.add(MoogLadder::default());
+    filter.input(0).connect(saw.output(0));
+    filter.input("cutoff").connect(
+        graph
+            .add_param(Param::bounded("cutoff", 1000.0, 20.0, 20000.0))
+            .output(0),
+    );
+    let gain = graph.add_param(Param::bounded("gain", 0.2, 0.0, 1.0));
+
+    let mix = filter * gain;
+    mix.output(0).connect(&out1.input(0));
+    mix.output(0).connect(&out2.input(0));
+
+    // a meter, driven from within the graph, that clients can subscribe to
+    let level = graph.add_param(Param::new::<Float>("level", None));
+    level
+        .input("set")
+        .connect(mix.abs().smooth(0.001).output(0));
+
+    let mut runtime = graph.build_runtime();
+
+    let server = WebSocketServer::bind("127.0.0.1:9002")
+        .unwrap()
+        .with_graph(runtime.graph())
+        .with_preset("dark", Preset::new().with_value("cutoff", 200.0))
+        .with_preset(
+            "bright",
+            Preset::new()
+                .with_value("cutoff", 8000.0)
+                .with_value("gain", 0.1),
+        )
+        .run()
+        .unwrap();
+
+    // try it from a browser's console:
+    //
+    //     const ws = new WebSocket("ws://127.0.0.1:9002");
+    //     ws.onmessage = (e) => console.log(JSON.parse(e.data));
+    //     ws.send(JSON.stringify({ type: "list" }));
+    //     ws.send(JSON.stringify({ type: "subscribe", names: ["level"] }));
+    //     ws.send(JSON.stringify({ type: "set", name: "freq", value: 220 }));
+    //     ws.send(JSON.stringify({ type: "load_preset", name: "bright" }));
+    println!("Listening on ws://{}", server.local_addr());
+
+    let handle = runtime
+        .run(AudioBackend::Default, AudioDevice::Default, None)
+        .unwrap();
+
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+
+    server.stop();
+    handle.stop();
+}
@@ -0,0 +1,155 @@
+#ifndef RAUG_H
+#define RAUG_H
+
+/* This file is generated by cbindgen from src/ffi.rs. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a call to the C API.
+typedef enum RaugStatus {
+  // The call succeeded.
+  RAUG_STATUS_OK = 0,
+  // A required pointer argument was null.
+  RAUG_STATUS_NULL_POINTER,
+  // A string argument was not valid UTF-8.
+  RAUG_STATUS_INVALID_UTF8,
+  // No param with the given name exists.
+  RAUG_STATUS_PARAM_NOT_FOUND,
+  // The param has a different signal type than the value.
+  RAUG_STATUS_TYPE_MISMATCH,
+  // The buffers didn't match the number of channels of the graph.
+  RAUG_STATUS_CHANNEL_MISMATCH,
+  // [`raug_runtime_prepare`] hasn't been called.
+  RAUG_STATUS_NOT_PREPARED,
+  // An error occurred while processing the graph.
+  RAUG_STATUS_PROCESS,
+  // A panic occurred inside raug.
+  RAUG_STATUS_PANIC,
+} RaugStatus;
+
+// A graph loaded from a patch, ready to be processed by the host.
+//
+// This is an opaque type; it is created with [`raug_runtime_from_patch_file`] or [`raug_runtime_from_patch_json`], and must be freed with [`raug_runtime_free`].
+typedef struct RaugRuntime RaugRuntime;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a message describing the last error that occurred on the calling thread, or null if there was none.
+//
+// The string is owned by raug and stays valid until the next call into raug on the same thread.
+const char *raug_last_error(void);
+
+// Loads a graph from the patch file at `path`.
+//
+// Returns null on failure; see [`raug_last_error`].
+//
+// # Safety
+//
+// `path` must be null or a valid, nul-terminated string.
+struct RaugRuntime *raug_runtime_from_patch_file(const char *path);
+
+// Loads a graph from a patch given as a JSON string.
+//
+// Returns null on failure; see [`raug_last_error`].
+//
+// # Safety
+//
+// `json` must be null or a valid, nul-terminated string.
+struct RaugRuntime *raug_runtime_from_patch_json(const char *json);
+
+// Frees a runtime. Does nothing if `runtime` is null.
+//
+// # Safety
+//
+// `runtime` must be null or a pointer returned by one of the `raug_runtime_from_*` functions that hasn't been freed yet.
+void raug_runtime_free(struct RaugRuntime *runtime);
+
+// Returns the number of interleaved input channels the graph expects, or 0 if `runtime` is null.
+//
+// # Safety
+//
+// `runtime` must be null or a valid runtime.
+size_t raug_runtime_num_inputs(const struct RaugRuntime *runtime);
+
+// Returns the number of interleaved output channels the graph produces, or 0 if `runtime` is null.
+//
+// # Safety
+//
+// `runtime` must be null or a valid runtime.
+size_t raug_runtime_num_outputs(const struct RaugRuntime *runtime);
+
+// Prepares the runtime for the given sample rate and maximum number of frames per call to [`raug_runtime_process_interleaved`].
+//
+// This allocates, so it must not be called from the audio callback. Larger blocks are still accepted, but are processed in several steps.
+//
+// # Safety
+//
+// `runtime` must be null or a valid runtime.
+enum RaugStatus raug_runtime_prepare(struct RaugRuntime *runtime,
+                                     double sample_rate,
+                                     size_t max_block_size);
+
+// Sets the `Float` param named `name` to `value`. The new value takes effect at the start of the next processed sample.
+//
+// Returns [`RaugStatus::TypeMismatch`] if the param is not a `Float` param; use [`raug_runtime_set_param_int`] or [`raug_runtime_set_param_bool`] for `Int` and `Bool` params.
+//
+// # Safety
+//
+// `runtime` must be null or a valid runtime, and `name` must be null or a valid, nul-terminated string.
+enum RaugStatus raug_runtime_set_param(struct RaugRuntime *runtime,
+                                       const char *name,
+                                       double value);
+
+// Sets the `Int` param named `name` to `value`. The new value takes effect at the start of the next processed sample.
+//
+// Returns [`RaugStatus::TypeMismatch`] if the param is not an `Int` param.
+//
+// # Safety
+//
+// `runtime` must be null or a valid runtime, and `name` must be null or a valid, nul-terminated string.
+enum RaugStatus raug_runtime_set_param_int(struct RaugRuntime *runtime,
+                                           const char *name,
+                                           int64_t value);
+
+// Sets the `Bool` param named `name` to `value`. The new value takes effect at the start of the next processed sample.
+//
+// Returns [`RaugStatus::TypeMismatch`] if the param is not a `Bool` param.
+//
+// # Safety
+//
+// `runtime` must be null or a valid runtime, and `name` must be null or a valid, nul-terminated string.
+enum RaugStatus raug_runtime_set_param_bool(struct RaugRuntime *runtime,
+                                            const char *name,
+                                            bool value);
+
+// Processes `num_frames` frames of interleaved audio.
+//
+// `input` must hold `num_frames * raug_runtime_num_inputs(runtime)` samples and `output` must have room for `num_frames * raug_runtime_num_outputs(runtime)` samples.
+// Either may be null if the corresponding channel count is 0.
+//
+// `midi_offsets` holds the frame offsets of `num_midi` MIDI events in ascending order, and `midi_bytes` their 3-byte messages (`3 * num_midi` bytes); both may be null if `num_midi` is 0.
+// Shorter messages are padded with zeros, and SysEx messages are passed as consecutive events, one per packet: a byte with the number of message bytes in the packet (1 or 2), followed by those bytes.
+// This doesn't allocate as long as the graph's processors don't and `num_midi` is at most 64.
+//
+// # Safety
+//
+// The pointers must be valid for the lengths above, and `runtime` must be null or a valid runtime.
+enum RaugStatus raug_runtime_process_interleaved(struct RaugRuntime *runtime,
+                                                 const float *input,
+                                                 float *output,
+                                                 size_t num_frames,
+                                                 const size_t *midi_offsets,
+                                                 const uint8_t *midi_bytes,
+                                                 size_t num_midi);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RAUG_H */
@@ -0,0 +1,5 @@
+//! Builds the C API in [`raug::ffi`] as a shared library, `libraug_ffi`.
+//!
+//! The header for the library is `include/raug.h` in the raug repository.
+
+pub use raug::ffi::*;
@@ -0,0 +1,79 @@
+//! Builds and runs the C test program in `examples/c/test.c` against the shared library.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn run(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{command:?} failed\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// builds the shared library, which integration tests don't build, and returns the directory it is in
+fn build_shared_library(root: &Path) -> PathBuf {
+    let messages = run(Command::new(env!("CARGO")).current_dir(root).args([
+        "build",
+        "-p",
+        "raug-ffi",
+        "--message-format=json",
+    ]));
+
+    for message in messages.lines() {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(message) else {
+            continue;
+        };
+        if message["reason"] != "compiler-artifact" || message["target"]["name"] != "raug_ffi" {
+            continue;
+        }
+        for file in message["filenames"].as_array().into_iter().flatten() {
+            let file = Path::new(file.as_str().unwrap());
+            if file.extension().is_some_and(|ext| ext != "rlib") {
+                return file.parent().unwrap().to_path_buf();
+            }
+        }
+    }
+    panic!("cargo didn't report the raug_ffi shared library");
+}
+
+#[test]
+#[ignore = "needs a C compiler"]
+fn c_api() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let lib_dir = build_shared_library(root);
+
+    let patch = out_dir.join("patch.json");
+    run(Command::new(env!("CARGO"))
+        .current_dir(root)
+        .args([
+            "run",
+            "-p",
+            "raug",
+            "--features",
+            "ffi",
+            "--example",
+            "ffi_patch",
+        ])
+        .arg(&patch));
+
+    let program = out_dir.join("raug_c_test");
+    run(Command::new("cc")
+        .current_dir(root)
+        .args(["-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&program)
+        .args(["examples/c/test.c", "-Iinclude"])
+        .arg(format!("-L{}", lib_dir.display()))
+        .args(["-lraug_ffi", "-lm"]));
+
+    run(Command::new(&program)
+        .arg(&patch)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir));
+}
@@ -0,0 +1,179 @@
+//! Procedural macros for [raug](https://docs.rs/raug).
+//!
+//! These macros expand to code that refers to the `raug` crate by name, so they are meant to be used through `raug::prelude`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, Token, Type,
+};
+
+// `<ident> as [<type>, ...]`
+struct Signals {
+    ident: Ident,
+    types: Vec<Type>,
+}
+
+impl Parse for Signals {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![as]>()?;
+        let content;
+        bracketed!(content in input);
+        let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+        Ok(Self {
+            ident,
+            types: types.into_iter().collect(),
+        })
+    }
+}
+
+// `<inputs> as [...], <outputs> as [...]`
+struct ProcIo {
+    inputs: Signals,
+    outputs: Signals,
+}
+
+impl Parse for ProcIo {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let inputs = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let outputs = input.parse()?;
+        let _ = input.parse::<Option<Token![,]>>()?;
+        Ok(Self { inputs, outputs })
+    }
+}
+
+// whether the type is the `Any` placeholder, which iterates over type-erased signals
+fn is_any(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("Any"))
+}
+
+// splits the outputs into one iterator per output, bound to `idents`
+fn split_outputs_impl(outputs: &Signals, idents: &[Ident]) -> TokenStream2 {
+    let ident = &outputs.ident;
+    let mut stmts = vec![quote! {
+        let __mode = #ident.mode;
+        let __rest: &mut [raug::signal::SignalBuffer] = &mut *#ident.outputs;
+    }];
+
+    for (index, (ty, ident)) in outputs.types.iter().zip(idents).enumerate() {
+        let take = quote! {
+            let (__buffer, __rest) = __rest
+                .split_first_mut()
+                .ok_or(raug::processor::ProcessorError::NumOutputsMismatch)?;
+            let __range = __mode.sample_range(__buffer.len());
+        };
+        let iter = if is_any(ty) {
+            quote! {
+                let #ident = __buffer.iter_mut().skip(__range.start).take(__range.len());
+            }
+        } else {
+            quote! {
+                let __actual = __buffer.signal_type();
+                let #ident = __buffer
+                    .as_type_mut::<#ty>()
+                    .ok_or(raug::processor::ProcessorError::OutputSpecMismatch {
+                        index: #index,
+                        expected: <#ty as raug::signal::Signal>::signal_type(),
+                        actual: __actual,
+                    })?[__range]
+                    .iter_mut();
+            }
+        };
+        stmts.push(take);
+        stmts.push(iter);
+    }
+
+    quote! { #(#stmts)* }
+}
+
+/// Iterates over the samples of a processor's inputs and outputs together.
+///
+/// `iter_proc_io_as!(inputs as [A, B, ...], outputs as [X, Y, ...])` evaluates to an iterator of tuples `(a, b, ..., x, y, ...)`,
+/// where each input item is a `&Option<A>` and each output item is a `&mut Option<X>`, one tuple per sample in the range given by the processing mode.
+/// The type `Any` yields type-erased `Option<AnySignalRef>` inputs and `AnySignalMut` outputs instead.
+///
+/// The macro uses `?` to return a [`ProcessorError`] if a signal is not of the given type, so it must be used in a function returning `Result<_, ProcessorError>`.
+/// Unconnected inputs yield `None` for every sample.
+///
+/// [`ProcessorError`]: https://docs.rs/raug/latest/raug/processor/enum.ProcessorError.html
+#[proc_macro]
+pub fn iter_proc_io_as(input: TokenStream) -> TokenStream {
+    let ProcIo { inputs, outputs } = parse_macro_input!(input as ProcIo);
+
+    let in_ident = &inputs.ident;
+    let in_idents: Vec<_> = (0..inputs.types.len())
+        .map(|i| format_ident!("__in{}", i))
+        .collect();
+    let in_stmts = inputs
+        .types
+        .iter()
+        .zip(&in_idents)
+        .enumerate()
+        .map(|(index, (ty, ident))| {
+            if is_any(ty) {
+                quote! { let #ident = #in_ident.iter_input(#index); }
+            } else {
+                quote! { let #ident = #in_ident.iter_input_as::<#ty>(#index)?; }
+            }
+        });
+
+    let out_idents: Vec<_> = (0..outputs.types.len())
+        .map(|i| format_ident!("__out{}", i))
+        .collect();
+    let out_stmts = split_outputs_impl(&outputs, &out_idents);
+
+    let idents: Vec<_> = in_idents.iter().chain(&out_idents).collect();
+    let Some((first, rest)) = idents.split_first() else {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "iter_proc_io_as! requires at least one input or output",
+        )
+        .into_compile_error()
+        .into();
+    };
+
+    // zip the iterators together, then flatten the nested tuples
+    let mut zipped = quote! { #first };
+    let mut pattern = quote! { #first };
+    for ident in rest {
+        zipped = quote! { #zipped.zip(#ident) };
+        pattern = quote! { (#pattern, #ident) };
+    }
+
+    let out_ident = &outputs.ident;
+    quote! {{
+        #[allow(unused_mut)]
+        let mut #out_ident = #out_ident;
+        #(#in_stmts)*
+        #out_stmts
+        #zipped.map(|#pattern| (#(#idents),*))
+    }}
+    .into()
+}
+
+/// Splits a processor's outputs into one iterator per output.
+///
+/// `split_outputs!(outputs as [X, Y, ...])` evaluates to a tuple of iterators over `&mut Option<X>`, `&mut Option<Y>`, ..., covering the range of samples given by the processing mode.
+/// As with [`iter_proc_io_as!`], the type `Any` yields `AnySignalMut` items, and the macro uses `?` to return a `ProcessorError` if an output is not of the given type.
+#[proc_macro]
+pub fn split_outputs(input: TokenStream) -> TokenStream {
+    let outputs = parse_macro_input!(input as Signals);
+
+    let idents: Vec<_> = (0..outputs.types.len())
+        .map(|i| format_ident!("__out{}", i))
+        .collect();
+    let stmts = split_outputs_impl(&outputs, &idents);
+
+    quote! {{
+        #stmts
+        (#(#idents,)*)
+    }}
+    .into()
+}
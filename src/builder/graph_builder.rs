@@ -7,11 +7,16 @@ use std::{
 
 use crate::{
     graph::{asset::Asset, Graph},
-    prelude::{Param, Processor},
+    prelude::{FeedbackDelay, Param, Processor, Tap},
+    resample::ResampleQuality,
     runtime::Runtime,
+    signal::{Buffer, Float, SignalType},
 };
 
-use super::node_builder::{IntoInputIdx, IntoNode, IntoOutputIdx, Node};
+use super::{
+    module::{Module, ModuleArgs, ModuleError},
+    node_builder::{IntoInputIdx, IntoNode, IntoOutputIdx, Node},
+};
 
 /// A builder for constructing audio graphs.
 #[derive(Clone, Default)]
@@ -41,6 +46,26 @@ impl GraphBuilder {
         })
     }
 
+    /// Adds an input node with the given name and signal type to the graph.
+    ///
+    /// See [`Graph::add_input`].
+    pub fn add_input(&self, name: impl Into<String>, signal_type: SignalType) -> Node {
+        self.with_graph_mut(|graph| Node {
+            graph: self.clone(),
+            node_id: graph.add_input(name, signal_type),
+        })
+    }
+
+    /// Adds an output node with the given name and signal type to the graph.
+    ///
+    /// See [`Graph::add_output`].
+    pub fn add_output(&self, name: impl Into<String>, signal_type: SignalType) -> Node {
+        self.with_graph_mut(|graph| Node {
+            graph: self.clone(),
+            node_id: graph.add_output(name, signal_type),
+        })
+    }
+
     /// Adds a MIDI input node to the graph.
     pub fn add_midi_input(&self, name: impl Into<String>) -> Node {
         self.with_graph_mut(|graph| Node {
@@ -49,6 +74,26 @@ impl GraphBuilder {
         })
     }
 
+    /// Adds a MIDI output node to the graph.
+    ///
+    /// See [`Graph::add_midi_output`].
+    pub fn add_midi_output(&self, name: impl Into<String>) -> Node {
+        self.with_graph_mut(|graph| Node {
+            graph: self.clone(),
+            node_id: graph.add_midi_output(name),
+        })
+    }
+
+    /// Adds a tap node to the graph.
+    ///
+    /// See [`Graph::add_tap`].
+    pub fn add_tap(&self, tap: impl Tap) -> Node {
+        self.with_graph_mut(|graph| Node {
+            graph: self.clone(),
+            node_id: graph.add_tap(tap),
+        })
+    }
+
     /// Adds a processor node to the graph.
     pub fn add(&self, processor: impl Processor) -> Node {
         self.with_graph_mut(|graph| Node {
@@ -57,11 +102,67 @@ impl GraphBuilder {
         })
     }
 
+    /// Adds a [`FeedbackDelay`] node to the graph, which delays its input by `delay` samples.
+    ///
+    /// Use this to close a feedback loop: the node's output can be used before its input is connected.
+    /// The runtime processes loops through this node in sub-blocks of up to `delay` samples instead of sample by sample.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is zero.
+    pub fn feedback(&self, delay: usize) -> Node {
+        self.add(FeedbackDelay::new(delay))
+    }
+
+    /// Instantiates the given [`Module`] with the given arguments and adds it to the graph under the given instance name.
+    ///
+    /// The instance's parameters can be controlled as `<name>/<param>` (see [`Graph::param_named`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an argument does not match a declared parameter of the module.
+    pub fn try_add_module(
+        &self,
+        name: impl Into<String>,
+        module: &Module,
+        args: &ModuleArgs,
+    ) -> Result<Node, ModuleError> {
+        let subgraph = module.instantiate(args)?;
+        Ok(self.with_graph_mut(|graph| Node {
+            graph: self.clone(),
+            node_id: graph.add_module(name, subgraph),
+        }))
+    }
+
+    /// Instantiates the given [`Module`] with the given arguments and adds it to the graph under the given instance name.
+    ///
+    /// This is a panicking shorthand for [`GraphBuilder::try_add_module`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an argument does not match a declared parameter of the module.
+    #[track_caller]
+    pub fn add_module(&self, name: impl Into<String>, module: &Module, args: &ModuleArgs) -> Node {
+        self.try_add_module(name, module, args)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
     /// Adds an asset to the graph.
     pub fn add_asset(&self, name: impl Into<String>, asset: impl Into<Asset>) {
         self.with_graph_mut(|graph| graph.add_asset(name, asset.into()));
     }
 
+    /// Adds a buffer recorded at the given sample rate to the graph, to be resampled to the runtime's sample rate when the graph is allocated.
+    pub fn add_asset_resampled(
+        &self,
+        name: impl Into<String>,
+        buffer: Buffer<Float>,
+        sample_rate: Float,
+        quality: ResampleQuality,
+    ) {
+        self.with_graph_mut(|graph| graph.add_asset_resampled(name, buffer, sample_rate, quality));
+    }
+
     /// Adds a parameter node to the graph.
     pub fn add_param(&self, value: Param) -> Node {
         self.with_graph_mut(|graph| Node {
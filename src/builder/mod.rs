@@ -1,4 +1,5 @@
 //! Structures for setting up the graph and nodes.
 
 pub mod graph_builder;
+pub mod module;
 pub mod node_builder;
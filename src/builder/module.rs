@@ -0,0 +1,466 @@
+//! Contains the [`Module`] type for defining reusable, parameterized graph templates.
+
+use std::{ops::Deref, sync::Arc};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    builtins::{Param, SubGraph},
+    processor::SignalSpec,
+    signal::{AnySignal, Signal, SignalType},
+};
+
+use super::{graph_builder::GraphBuilder, node_builder::Node};
+
+/// An error that can occur when instantiating a [`Module`].
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum ModuleError {
+    /// No module with the given name is registered.
+    #[error("no module named `{0}` is registered")]
+    UnknownModule(String),
+
+    /// An argument does not match a declared parameter of the module.
+    #[error("module `{module}` has no parameter named `{param}`")]
+    UnknownParam {
+        /// The name of the module.
+        module: String,
+        /// The name of the argument.
+        param: String,
+    },
+
+    /// An argument does not have the signal type of the parameter it sets.
+    #[error("module `{module}`: argument `{param}` has type {actual:?}, expected {expected:?}")]
+    ArgTypeMismatch {
+        /// The name of the module.
+        module: String,
+        /// The name of the argument.
+        param: String,
+        /// The signal type of the parameter.
+        expected: SignalType,
+        /// The signal type of the argument.
+        actual: SignalType,
+    },
+}
+
+/// A reusable template for a sub-graph, with declared inputs, outputs, and parameters.
+///
+/// A module is instantiated as a [`SubGraph`] with [`GraphBuilder::add_module`] (or [`GraphBuilder::try_add_module`]), which registers the instance under a name.
+/// The parameters of an instance can then be looked up with hierarchical names such as `voice1/cutoff` (see [`Graph::param_named`](crate::graph::Graph::param_named)).
+///
+/// The template's build function is called once per instance with a [`ModuleBuilder`], which gives access to the declared ports and the instance's arguments.
+///
+/// Templates are code, so they are not serialized: a serialized graph stores each instance as the [`SubGraph`] it was built into, and its parameters keep their hierarchical names after loading.
+/// To create new instances by template name (e.g. from a patch loader), register the templates in a [`ModuleRegistry`].
+#[derive(Clone)]
+pub struct Module {
+    name: String,
+    inputs: Vec<SignalSpec>,
+    outputs: Vec<SignalSpec>,
+    params: Vec<(String, AnySignal)>,
+    build: Arc<dyn Fn(&ModuleBuilder) + Send + Sync>,
+}
+
+impl Module {
+    /// Creates a new module template with the given name and build function.
+    pub fn new<F>(name: impl Into<String>, build: F) -> Self
+    where
+        F: Fn(&ModuleBuilder) + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            params: Vec::new(),
+            build: Arc::new(build),
+        }
+    }
+
+    /// Declares an input of the module with the given name and signal type.
+    pub fn with_input(mut self, name: impl Into<String>, signal_type: SignalType) -> Self {
+        self.inputs.push(SignalSpec::new(name, signal_type));
+        self
+    }
+
+    /// Declares an output of the module with the given name and signal type.
+    pub fn with_output(mut self, name: impl Into<String>, signal_type: SignalType) -> Self {
+        self.outputs.push(SignalSpec::new(name, signal_type));
+        self
+    }
+
+    /// Declares a parameter of the module with the given name and default value.
+    ///
+    /// Instances can override the default value with [`ModuleArgs`].
+    pub fn with_param(mut self, name: impl Into<String>, default: impl Signal) -> Self {
+        self.params.push((name.into(), default.into_any_signal()));
+        self
+    }
+
+    /// Returns the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the declared inputs of the module.
+    pub fn input_spec(&self) -> &[SignalSpec] {
+        &self.inputs
+    }
+
+    /// Returns the declared outputs of the module.
+    pub fn output_spec(&self) -> &[SignalSpec] {
+        &self.outputs
+    }
+
+    /// Returns the declared parameters of the module and their default values.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &AnySignal)> + '_ {
+        self.params
+            .iter()
+            .map(|(name, default)| (name.as_str(), default))
+    }
+
+    /// Builds a new instance of the module with the given arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an argument does not match a declared parameter in name and signal type.
+    pub fn instantiate(&self, args: &ModuleArgs) -> Result<SubGraph, ModuleError> {
+        let mut values: FxHashMap<String, AnySignal> = self.params.iter().cloned().collect();
+        for (name, value) in &args.values {
+            let Some(default) = values.get_mut(name) else {
+                return Err(ModuleError::UnknownParam {
+                    module: self.name.clone(),
+                    param: name.clone(),
+                });
+            };
+            if !default.is_same_type(value) {
+                return Err(ModuleError::ArgTypeMismatch {
+                    module: self.name.clone(),
+                    param: name.clone(),
+                    expected: default.signal_type(),
+                    actual: value.signal_type(),
+                });
+            }
+            *default = value.clone();
+        }
+
+        let graph = GraphBuilder::new();
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|spec| graph.add_input(&spec.name, spec.signal_type))
+            .collect();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|spec| graph.add_output(&spec.name, spec.signal_type))
+            .collect();
+
+        let builder = ModuleBuilder {
+            graph,
+            module_name: self.name.clone(),
+            input_spec: self.inputs.clone(),
+            output_spec: self.outputs.clone(),
+            inputs,
+            outputs,
+            args: values,
+        };
+        (self.build)(&builder);
+
+        Ok(SubGraph::new(builder.graph.build()))
+    }
+}
+
+impl std::fmt::Debug for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name)
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// A collection of [`Module`] templates, looked up by their names.
+///
+/// Since templates can't be serialized, an application registers the templates it knows about at startup, and a patch loader can then instantiate them by name.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleRegistry {
+    modules: FxHashMap<String, Module>,
+}
+
+impl ModuleRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the given template under its name, replacing and returning any template previously registered under that name.
+    pub fn register(&mut self, module: Module) -> Option<Module> {
+        self.modules.insert(module.name().to_string(), module)
+    }
+
+    /// Registers the given template under its name, and returns the registry.
+    pub fn with(mut self, module: Module) -> Self {
+        self.register(module);
+        self
+    }
+
+    /// Returns the template with the given name, if it is registered.
+    pub fn get(&self, name: &str) -> Option<&Module> {
+        self.modules.get(name)
+    }
+
+    /// Returns the names of all registered templates.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.modules.keys().map(|name| name.as_str())
+    }
+
+    /// Builds a new instance of the template with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ModuleError::UnknownModule`] if no such template is registered, or an error if an argument does not match a declared parameter of the template (see [`Module::instantiate`]).
+    pub fn instantiate(&self, name: &str, args: &ModuleArgs) -> Result<SubGraph, ModuleError> {
+        self.get(name)
+            .ok_or_else(|| ModuleError::UnknownModule(name.to_string()))?
+            .instantiate(args)
+    }
+}
+
+/// Arguments for an instance of a [`Module`], overriding the default values of its parameters.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleArgs {
+    values: FxHashMap<String, AnySignal>,
+}
+
+impl ModuleArgs {
+    /// Creates an empty set of arguments, so that every parameter takes its default value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the parameter with the given name.
+    pub fn with(mut self, name: impl Into<String>, value: impl Signal) -> Self {
+        self.values.insert(name.into(), value.into_any_signal());
+        self
+    }
+
+    /// Returns the value of the parameter with the given name, if it was set.
+    pub fn get(&self, name: &str) -> Option<&AnySignal> {
+        self.values.get(name)
+    }
+}
+
+/// The builder passed to a [`Module`]'s build function when it is instantiated.
+///
+/// This dereferences to the [`GraphBuilder`] of the instance's inner graph.
+pub struct ModuleBuilder {
+    graph: GraphBuilder,
+    module_name: String,
+    input_spec: Vec<SignalSpec>,
+    output_spec: Vec<SignalSpec>,
+    inputs: Vec<Node>,
+    outputs: Vec<Node>,
+    args: FxHashMap<String, AnySignal>,
+}
+
+impl ModuleBuilder {
+    /// Returns the name of the module being instantiated.
+    pub fn module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    /// Returns the node for the declared input with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module has no input with the given name.
+    #[track_caller]
+    pub fn input(&self, name: &str) -> Node {
+        let Some(index) = self.input_spec.iter().position(|spec| spec.name == name) else {
+            panic!(
+                "module `{}` has no input named `{}`",
+                self.module_name, name
+            );
+        };
+        self.inputs[index].clone()
+    }
+
+    /// Returns the node for the declared output with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module has no output with the given name.
+    #[track_caller]
+    pub fn output(&self, name: &str) -> Node {
+        let Some(index) = self.output_spec.iter().position(|spec| spec.name == name) else {
+            panic!(
+                "module `{}` has no output named `{}`",
+                self.module_name, name
+            );
+        };
+        self.outputs[index].clone()
+    }
+
+    /// Returns the value of the declared parameter with the given name for this instance.
+    ///
+    /// This can be used to change the structure of the instance depending on its arguments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module has no parameter with the given name, or if it is not of type `S`.
+    #[track_caller]
+    pub fn arg<S: Signal>(&self, name: &str) -> Option<S> {
+        let Some(value) = self.args.get(name) else {
+            panic!(
+                "module `{}` has no parameter named `{}`",
+                self.module_name, name
+            );
+        };
+        assert!(
+            value.is_type::<S>(),
+            "module `{}`: parameter `{}` has type {:?}, not {:?}",
+            self.module_name,
+            name,
+            value.signal_type(),
+            S::signal_type()
+        );
+        S::try_from_any_signal(value.clone())
+    }
+
+    /// Adds a [`Param`] node for the declared parameter with the given name, initialized with this instance's value.
+    ///
+    /// The parameter can be controlled from outside the graph as `<instance>/<name>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module has no parameter with the given name, or if it is not of type `S`.
+    #[track_caller]
+    pub fn param<S: Signal>(&self, name: &str) -> Node {
+        let value = self.arg::<S>(name);
+        self.graph.add_param(Param::new::<S>(name, value))
+    }
+}
+
+impl Deref for ModuleBuilder {
+    type Target = GraphBuilder;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::prelude::*;
+
+    // multiplies its input by `gain` once per stage
+    fn amp() -> Module {
+        Module::new("amp", |m| {
+            let gain = m.param::<Float>("gain");
+            let mut out = m.input("in");
+            for _ in 0..m.arg::<i64>("stages").unwrap() {
+                out = out * gain.clone();
+            }
+            out.output(0).connect(&m.output("out").input(0));
+        })
+        .with_input("in", SignalType::Float)
+        .with_output("out", SignalType::Float)
+        .with_param("gain", 1.0 as Float)
+        .with_param("stages", 1_i64)
+    }
+
+    fn render(runtime: &mut Runtime) -> Float {
+        let outputs = runtime
+            .run_offline(Duration::from_millis(4), 1000.0, 4)
+            .unwrap();
+        outputs[0][3]
+    }
+
+    #[test]
+    fn registered_modules_are_instantiated_with_args() {
+        let registry = ModuleRegistry::new().with(amp());
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["amp"]);
+        assert!(matches!(
+            registry.instantiate("filter", &ModuleArgs::new()),
+            Err(ModuleError::UnknownModule(name)) if name == "filter"
+        ));
+
+        let args = ModuleArgs::new()
+            .with("gain", 0.5 as Float)
+            .with("stages", 2_i64);
+        let instance = registry.instantiate("amp", &args).unwrap();
+        assert_eq!(instance.input_spec()[0].name, "in");
+        assert_eq!(instance.output_spec()[0].name, "out");
+
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let amp = graph.add(instance);
+        amp.input("in").connect(graph.constant(1.0));
+        amp.output("out").connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        assert_eq!(render(&mut runtime), 0.25);
+    }
+
+    #[test]
+    fn module_params_are_reached_by_path() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let amp = graph.add_module("amp1", &amp(), &ModuleArgs::new());
+        amp.input("in").connect(graph.constant(2.0));
+        amp.output("out").connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        assert_eq!(render(&mut runtime), 2.0);
+
+        // `stages` only shapes the instance, so it is not a parameter
+        assert_eq!(runtime.graph().param_names(), ["amp1/gain"]);
+        runtime
+            .graph()
+            .param_named("amp1/gain")
+            .unwrap()
+            .send(0.75 as Float);
+        assert_eq!(render(&mut runtime), 1.5);
+
+        for name in ["gain", "amp2/gain", "amp1/stages", "amp1/gain/x", "amp1"] {
+            assert!(runtime.graph().param_named(name).is_none(), "{name}");
+        }
+    }
+
+    #[test]
+    fn mismatched_args_are_rejected() {
+        let result = amp().instantiate(&ModuleArgs::new().with("cutoff", 100.0 as Float));
+        let Err(err) = result else {
+            panic!("unknown argument was accepted");
+        };
+        assert_eq!(
+            err.to_string(),
+            "module `amp` has no parameter named `cutoff`"
+        );
+
+        let result = amp().instantiate(&ModuleArgs::new().with("stages", 2.0 as Float));
+        assert!(matches!(
+            result,
+            Err(ModuleError::ArgTypeMismatch {
+                expected: SignalType::Int,
+                actual: SignalType::Float,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "module `amp` has no parameter named `cutoff`")]
+    fn unknown_args_panic() {
+        GraphBuilder::new().add_module(
+            "amp1",
+            &amp(),
+            &ModuleArgs::new().with("cutoff", 100.0 as Float),
+        );
+    }
+}
@@ -531,6 +531,23 @@ impl Output {
         self.node.clone()
     }
 
+    /// Connects the output to the input of another node through a [`FeedbackDelay`] of `delay` samples.
+    ///
+    /// This declares the connection as a feedback path, so the runtime can use it to break the cycle it closes.
+    /// Returns the [`FeedbackDelay`] node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is zero, or if the input is not a `Float` signal.
+    #[inline]
+    #[track_caller]
+    pub fn connect_feedback(&self, input: &Input, delay: usize) -> Node {
+        let feedback = self.node.graph().feedback(delay);
+        feedback.input(0).connect(self);
+        feedback.output(0).connect(input);
+        feedback
+    }
+
     /// Creates a [`Cast`] processor and connects it to the output.
     ///
     /// The `signal_type` parameter specifies the type to cast the signal to.
@@ -631,7 +648,7 @@ impl Output {
     #[inline]
     pub fn len(&self) -> Node {
         assert!(
-            matches!(self.signal_type(), SignalType::List { .. }),
+            matches!(self.signal_type(), SignalType::List),
             "output signal must be a list"
         );
         let proc = self.node.graph().add(Len);
@@ -0,0 +1,898 @@
+//! Analysis processors, such as pitch and onset detectors.
+
+use crate::prelude::*;
+
+/// A monophonic pitch detector using the YIN algorithm.
+///
+/// Every `hop` seconds, the last `window` seconds of the input are compared with delayed copies of themselves, for every delay (lag) between one period of `max_freq` and one period of `min_freq`.
+/// The difference function is normalized by its running mean, and the first lag whose normalized difference dips below `threshold` is taken as the period, refined by parabolic interpolation.
+///
+/// The confidence is `1.0` minus the normalized difference at the chosen lag, so a pure periodic signal reads close to `1.0` and noise or silence close to `0.0`.
+/// The frequency is only updated while the confidence is at least `1.0 - threshold`, and holds its last value otherwise. It is `None` until a pitch has been detected.
+///
+/// The window is always at least one period of `min_freq`. The analysis costs about `window * sample_rate / (min_freq * hop)` multiplications per sample, so lowering `min_freq` or `hop` makes it more expensive.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The signal to analyze. |
+/// | `1` | `threshold` | `Float` | The largest normalized difference that counts as periodic, usually between `0.1` and `0.2`. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `frequency` | `Float` | The detected frequency in Hz. |
+/// | `1` | `confidence` | `Float` | The confidence of the detection, between `0.0` and `1.0`. |
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchDetector {
+    /// The lowest frequency that can be detected, in Hz.
+    pub min_freq: Float,
+
+    /// The highest frequency that can be detected, in Hz.
+    pub max_freq: Float,
+
+    /// The length of the analysis window in seconds.
+    pub window: Float,
+
+    /// The time between analyses in seconds.
+    pub hop: Float,
+
+    /// The largest normalized difference that counts as periodic.
+    pub threshold: Float,
+
+    sample_rate: Float,
+    window_len: usize,
+    min_lag: usize,
+    max_lag: usize,
+    hop_len: usize,
+    since_hop: usize,
+
+    // the last `window_len + max_lag` samples, oldest at `write_pos`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: Vec<Float>,
+    write_pos: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    frame: Vec<Float>,
+    // the normalized difference function, indexed by lag
+    #[cfg_attr(feature = "serde", serde(skip))]
+    diff: Vec<Float>,
+
+    frequency: Option<Float>,
+    confidence: Float,
+}
+
+impl PitchDetector {
+    /// Creates a new `PitchDetector` that detects frequencies between `min_freq` and `max_freq`.
+    pub fn new(min_freq: Float, max_freq: Float) -> Self {
+        Self {
+            min_freq,
+            max_freq,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the length of the analysis window in seconds.
+    pub fn with_window(mut self, window: Float) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the time between analyses in seconds.
+    pub fn with_hop(mut self, hop: Float) -> Self {
+        self.hop = hop;
+        self
+    }
+
+    /// Sets the largest normalized difference that counts as periodic.
+    pub fn with_threshold(mut self, threshold: Float) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn analyze(&mut self) {
+        // unroll the history, oldest first
+        let (newer, older) = self.history.split_at(self.write_pos);
+        for (frame, sample) in self.frame.iter_mut().zip(older.iter().chain(newer)) {
+            *frame = *sample;
+        }
+
+        // the window is the newest `window_len` samples, compared with the samples `lag` before them
+        let window = &self.frame[self.max_lag..];
+        let mut running_sum = 0.0;
+        self.diff[0] = 1.0;
+        for lag in 1..=self.max_lag {
+            let delayed = &self.frame[self.max_lag - lag..self.frame.len() - lag];
+            let d: Float = window
+                .iter()
+                .zip(delayed)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum();
+            running_sum += d;
+            self.diff[lag] = if running_sum > 0.0 {
+                d * lag as Float / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        // the first dip below the threshold, or else the deepest dip
+        let range = self.min_lag..=self.max_lag;
+        let mut best = None;
+        let mut lag = self.min_lag;
+        while lag <= self.max_lag {
+            if self.diff[lag] < self.threshold {
+                while lag < self.max_lag && self.diff[lag + 1] < self.diff[lag] {
+                    lag += 1;
+                }
+                best = Some(lag);
+                break;
+            }
+            lag += 1;
+        }
+        let best = best.unwrap_or_else(|| {
+            range
+                .min_by(|a, b| self.diff[*a].total_cmp(&self.diff[*b]))
+                .unwrap_or(self.max_lag)
+        });
+
+        self.confidence = (1.0 - self.diff[best]).clamp(0.0, 1.0);
+        if self.diff[best] >= self.threshold {
+            return;
+        }
+
+        // parabolic interpolation around the dip
+        let mut period = best as Float;
+        if best > 1 && best < self.max_lag {
+            let (a, b, c) = (self.diff[best - 1], self.diff[best], self.diff[best + 1]);
+            let denom = a - 2.0 * b + c;
+            if denom.abs() > Float::EPSILON {
+                period += 0.5 * (a - c) / denom;
+            }
+        }
+
+        self.frequency = Some(self.sample_rate / period);
+    }
+}
+
+impl Default for PitchDetector {
+    fn default() -> Self {
+        Self {
+            min_freq: 50.0,
+            max_freq: 2000.0,
+            window: 0.02,
+            hop: 0.01,
+            threshold: 0.15,
+            sample_rate: 0.0,
+            window_len: 0,
+            min_lag: 0,
+            max_lag: 0,
+            hop_len: 1,
+            since_hop: 0,
+            history: Vec::new(),
+            write_pos: 0,
+            frame: Vec::new(),
+            diff: Vec::new(),
+            frequency: None,
+            confidence: 0.0,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for PitchDetector {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("in", SignalType::Float),
+            SignalSpec::new("threshold", SignalType::Float),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("frequency", SignalType::Float),
+            SignalSpec::new("confidence", SignalType::Float),
+        ]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.sample_rate = sample_rate;
+        self.min_lag = ((sample_rate / self.max_freq).floor() as usize).max(2);
+        self.max_lag = ((sample_rate / self.min_freq).ceil() as usize).max(self.min_lag + 1);
+        self.window_len = ((self.window * sample_rate).round() as usize).max(self.max_lag);
+        self.hop_len = ((self.hop * sample_rate).round() as usize).max(1);
+        self.since_hop = 0;
+
+        self.history = vec![0.0; self.window_len + self.max_lag];
+        self.write_pos = 0;
+        self.frame = vec![0.0; self.window_len + self.max_lag];
+        self.diff = vec![1.0; self.max_lag + 1];
+        self.frequency = None;
+        self.confidence = 0.0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if self.history.is_empty() {
+            return Err(ProcessorError::Other);
+        }
+
+        for (in_signal, threshold, frequency, confidence) in iter_proc_io_as!(
+            inputs as [Float, Float],
+            outputs as [Float, Float]
+        ) {
+            self.threshold = threshold.unwrap_or(self.threshold);
+
+            self.history[self.write_pos] = in_signal.unwrap_or_default();
+            self.write_pos = (self.write_pos + 1) % self.history.len();
+
+            self.since_hop += 1;
+            if self.since_hop >= self.hop_len {
+                self.since_hop = 0;
+                self.analyze();
+            }
+
+            *frequency = self.frequency;
+            *confidence = Some(self.confidence);
+        }
+
+        Ok(())
+    }
+}
+
+/// An adaptive threshold for onset detection functions.
+///
+/// A value is an onset if it exceeds the mean of the recent values times `mean_factor`, plus `threshold`, and enough time has passed since the last onset.
+#[derive(Clone, Debug, Default)]
+struct AdaptiveThreshold {
+    history: Vec<Float>,
+    pos: usize,
+    since_onset: usize,
+}
+
+impl AdaptiveThreshold {
+    fn allocate(&mut self, len: usize) {
+        self.history = vec![0.0; len.max(1)];
+        self.pos = 0;
+        self.since_onset = usize::MAX;
+    }
+
+    fn is_allocated(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    fn detect(
+        &mut self,
+        value: Float,
+        threshold: Float,
+        mean_factor: Float,
+        min_interval: usize,
+    ) -> bool {
+        let mean = self.history.iter().sum::<Float>() / self.history.len() as Float;
+        self.history[self.pos] = value;
+        self.pos = (self.pos + 1) % self.history.len();
+        self.since_onset = self.since_onset.saturating_add(1);
+
+        let onset = value > mean * mean_factor + threshold && self.since_onset > min_interval;
+        if onset {
+            self.since_onset = 0;
+        }
+        onset
+    }
+}
+
+/// An onset detector that reacts to sudden rises in the energy of its input, such as drum hits.
+///
+/// Every `hop` seconds, the level of the last `frame` seconds of the input is compared with the level of the frame before it.
+/// An onset is detected when the rise in level exceeds the mean rise over the last `window` seconds times `mean_factor`, plus `threshold`,
+/// so that the detector adapts to the density of the material. Frames quieter than `floor` never trigger an onset, and onsets are at least `min_interval` seconds apart.
+///
+/// The output is a trigger, compatible with processors such as [`ADSREnv`], [`Counter`] and [`SampleAndHold`]. For percussive material with soft attacks or dense mixes, see also `SpectralFluxOnset`.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The signal to analyze. |
+/// | `1` | `threshold` | `Float` | The rise in dB above the adaptive mean that triggers an onset. |
+/// | `2` | `min_interval` | `Float` | The minimum time between onsets in seconds. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `onset` | `Bool` | Whether an onset was detected. |
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OnsetDetector {
+    /// The rise in dB above the adaptive mean that triggers an onset.
+    pub threshold: Float,
+
+    /// The factor applied to the mean rise over the last `window` seconds.
+    pub mean_factor: Float,
+
+    /// The minimum time between onsets in seconds.
+    pub min_interval: Float,
+
+    /// The level in dBFS below which no onsets are detected.
+    pub floor: Float,
+
+    /// The length of each frame whose level is measured, in seconds.
+    pub frame: Float,
+
+    /// The time between measurements in seconds.
+    pub hop: Float,
+
+    /// The length of the history of rises that the adaptive mean is taken over, in seconds.
+    pub window: Float,
+
+    sample_rate: Float,
+    hop_len: usize,
+    since_hop: usize,
+
+    // the last `frame` seconds of squared samples
+    #[cfg_attr(feature = "serde", serde(skip))]
+    squares: Vec<Float>,
+    squares_pos: usize,
+    // the levels of the last frame's worth of hops, oldest at `levels_pos`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    levels: Vec<Float>,
+    levels_pos: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    adaptive: AdaptiveThreshold,
+}
+
+impl OnsetDetector {
+    /// Creates a new `OnsetDetector` with the given threshold in dB and minimum time between onsets in seconds.
+    pub fn new(threshold: Float, min_interval: Float) -> Self {
+        Self {
+            threshold,
+            min_interval,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the factor applied to the mean rise over the last `window` seconds.
+    pub fn with_mean_factor(mut self, mean_factor: Float) -> Self {
+        self.mean_factor = mean_factor;
+        self
+    }
+
+    /// Sets the level in dBFS below which no onsets are detected.
+    pub fn with_floor(mut self, floor: Float) -> Self {
+        self.floor = floor;
+        self
+    }
+
+    /// Sets the length of each frame and the time between measurements, in seconds.
+    pub fn with_frame(mut self, frame: Float, hop: Float) -> Self {
+        self.frame = frame;
+        self.hop = hop;
+        self
+    }
+
+    /// Sets the length of the history of rises that the adaptive mean is taken over, in seconds.
+    pub fn with_window(mut self, window: Float) -> Self {
+        self.window = window;
+        self
+    }
+
+    fn measure(&mut self) -> bool {
+        let mean_square = self.squares.iter().sum::<Float>() / self.squares.len() as Float;
+        let level = 10.0 * (mean_square + 1e-12).log10();
+
+        // the oldest level is from one frame ago
+        let previous = std::mem::replace(&mut self.levels[self.levels_pos], level);
+        self.levels_pos = (self.levels_pos + 1) % self.levels.len();
+
+        let rise = if level < self.floor {
+            0.0
+        } else {
+            (level - previous.max(self.floor)).max(0.0)
+        };
+
+        let min_interval = (self.min_interval * self.sample_rate / self.hop_len as Float) as usize;
+        self.adaptive
+            .detect(rise, self.threshold, self.mean_factor, min_interval)
+    }
+}
+
+impl Default for OnsetDetector {
+    fn default() -> Self {
+        Self {
+            threshold: 6.0,
+            mean_factor: 1.5,
+            min_interval: 0.05,
+            floor: -60.0,
+            frame: 0.02,
+            hop: 0.005,
+            window: 0.5,
+            sample_rate: 0.0,
+            hop_len: 1,
+            since_hop: 0,
+            squares: Vec::new(),
+            squares_pos: 0,
+            levels: Vec::new(),
+            levels_pos: 0,
+            adaptive: AdaptiveThreshold::default(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for OnsetDetector {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("in", SignalType::Float),
+            SignalSpec::new("threshold", SignalType::Float),
+            SignalSpec::new("min_interval", SignalType::Float),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("onset", SignalType::Bool)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.sample_rate = sample_rate;
+        let frame_len = ((self.frame * sample_rate).round() as usize).max(1);
+        self.hop_len = ((self.hop * sample_rate).round() as usize).clamp(1, frame_len);
+        self.since_hop = 0;
+
+        self.squares = vec![0.0; frame_len];
+        self.squares_pos = 0;
+        self.levels = vec![self.floor; frame_len.div_ceil(self.hop_len)];
+        self.levels_pos = 0;
+        self.adaptive
+            .allocate((self.window * sample_rate / self.hop_len as Float).round() as usize);
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if !self.adaptive.is_allocated() {
+            return Err(ProcessorError::Other);
+        }
+
+        for (in_signal, threshold, min_interval, onset) in iter_proc_io_as!(
+            inputs as [Float, Float, Float],
+            outputs as [bool]
+        ) {
+            self.threshold = threshold.unwrap_or(self.threshold);
+            self.min_interval = min_interval.unwrap_or(self.min_interval);
+
+            let sample = in_signal.unwrap_or_default();
+            self.squares[self.squares_pos] = sample * sample;
+            self.squares_pos = (self.squares_pos + 1) % self.squares.len();
+
+            *onset = None;
+            self.since_hop += 1;
+            if self.since_hop >= self.hop_len {
+                self.since_hop = 0;
+                if self.measure() {
+                    *onset = Some(true);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An onset detector that reacts to sudden rises in the magnitude spectrum of its input (spectral flux), such as note onsets and drum hits.
+///
+/// Every `hop` samples, the last `fft_size` samples are windowed and transformed, and the log-compressed magnitude of each bin is compared with the previous frame.
+/// The flux is the mean rise over all bins, ignoring bins that got quieter. An onset is detected when the flux exceeds the mean flux over the last `window` seconds times `mean_factor`, plus `threshold`.
+/// Frames quieter than `floor` never trigger an onset, and onsets are at least `min_interval` seconds apart.
+///
+/// Compared to [`OnsetDetector`], this also detects onsets that don't raise the overall level much, such as a new note in a legato phrase, at the cost of an FFT every hop.
+///
+/// The output is a trigger, compatible with processors such as [`ADSREnv`], [`Counter`] and [`SampleAndHold`].
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The signal to analyze. |
+/// | `1` | `threshold` | `Float` | The flux above the adaptive mean that triggers an onset. |
+/// | `2` | `min_interval` | `Float` | The minimum time between onsets in seconds. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `onset` | `Bool` | Whether an onset was detected. |
+#[cfg(feature = "fft")]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectralFluxOnset {
+    /// The flux above the adaptive mean that triggers an onset.
+    pub threshold: Float,
+
+    /// The factor applied to the mean flux over the last `window` seconds.
+    pub mean_factor: Float,
+
+    /// The minimum time between onsets in seconds.
+    pub min_interval: Float,
+
+    /// The level in dBFS below which no onsets are detected.
+    pub floor: Float,
+
+    /// The length of the history of fluxes that the adaptive mean is taken over, in seconds.
+    pub window: Float,
+
+    fft_size: usize,
+    hop: usize,
+    window_function: WindowFunction,
+
+    sample_rate: Float,
+    since_hop: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    plan: Option<std::sync::Arc<dyn realfft::RealToComplex<Float>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    fft_window: Vec<Float>,
+    scale: Float,
+    // the last `fft_size` samples, oldest at `write_pos`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: Vec<Float>,
+    write_pos: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    input: Vec<Float>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    spectrum: Vec<num::Complex<Float>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    scratch: Vec<num::Complex<Float>>,
+    // the compressed magnitudes of the previous frame
+    #[cfg_attr(feature = "serde", serde(skip))]
+    previous: Vec<Float>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    adaptive: AdaptiveThreshold,
+}
+
+#[cfg(feature = "fft")]
+impl SpectralFluxOnset {
+    /// Creates a new `SpectralFluxOnset` with the given FFT size, a hop of a quarter of the FFT size and a Hann window.
+    pub fn new(fft_size: usize) -> Self {
+        let fft_size = fft_size.max(2);
+        Self {
+            threshold: 0.05,
+            mean_factor: 1.5,
+            min_interval: 0.05,
+            floor: -60.0,
+            window: 0.5,
+            fft_size,
+            hop: (fft_size / 4).max(1),
+            window_function: WindowFunction::Hann,
+            sample_rate: 0.0,
+            since_hop: 0,
+            plan: None,
+            fft_window: Vec::new(),
+            scale: 1.0,
+            history: Vec::new(),
+            write_pos: 0,
+            input: Vec::new(),
+            spectrum: Vec::new(),
+            scratch: Vec::new(),
+            previous: Vec::new(),
+            adaptive: AdaptiveThreshold::default(),
+        }
+    }
+
+    /// Sets the number of samples between frames.
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    /// Sets the window function applied before each transform.
+    pub fn with_window_function(mut self, window_function: WindowFunction) -> Self {
+        self.window_function = window_function;
+        self
+    }
+
+    /// Sets the flux above the adaptive mean that triggers an onset.
+    pub fn with_threshold(mut self, threshold: Float) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the factor applied to the mean flux over the last `window` seconds.
+    pub fn with_mean_factor(mut self, mean_factor: Float) -> Self {
+        self.mean_factor = mean_factor;
+        self
+    }
+
+    /// Sets the level in dBFS below which no onsets are detected.
+    pub fn with_floor(mut self, floor: Float) -> Self {
+        self.floor = floor;
+        self
+    }
+
+    /// Sets the length of the history of fluxes that the adaptive mean is taken over, in seconds.
+    pub fn with_window(mut self, window: Float) -> Self {
+        self.window = window;
+        self
+    }
+
+    fn analyze(&mut self) -> Result<bool, ProcessorError> {
+        let Some(plan) = &self.plan else {
+            return Err(ProcessorError::Other);
+        };
+
+        // the oldest sample is at the write position
+        let (newer, older) = self.history.split_at(self.write_pos);
+        let mut sum_sq = 0.0;
+        for ((input, sample), window) in self
+            .input
+            .iter_mut()
+            .zip(older.iter().chain(newer))
+            .zip(&self.fft_window)
+        {
+            sum_sq += sample * sample;
+            *input = sample * window;
+        }
+
+        plan.process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+            .map_err(|e| ProcessorError::Fft(crate::fft::FftError::RealFft(e.to_string())))?;
+
+        let mut flux = 0.0;
+        for (previous, bin) in self.previous.iter_mut().zip(&self.spectrum) {
+            // log compression, so that quiet partials count as well as loud ones
+            let magnitude = (1.0 + 100.0 * bin.norm() * self.scale).ln();
+            flux += (magnitude - *previous).max(0.0);
+            *previous = magnitude;
+        }
+        flux /= self.previous.len() as Float;
+
+        let level = 10.0 * (sum_sq / self.fft_size as Float + 1e-12).log10();
+        if level < self.floor {
+            flux = 0.0;
+        }
+
+        let min_interval = (self.min_interval * self.sample_rate / self.hop as Float) as usize;
+        Ok(self
+            .adaptive
+            .detect(flux, self.threshold, self.mean_factor, min_interval))
+    }
+}
+
+#[cfg(feature = "fft")]
+impl Default for SpectralFluxOnset {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[cfg(feature = "fft")]
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for SpectralFluxOnset {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("in", SignalType::Float),
+            SignalSpec::new("threshold", SignalType::Float),
+            SignalSpec::new("min_interval", SignalType::Float),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("onset", SignalType::Bool)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.sample_rate = sample_rate;
+        self.since_hop = 0;
+
+        let mut planner = realfft::RealFftPlanner::new();
+        let plan = planner.plan_fft_forward(self.fft_size);
+        self.fft_window = self.window_function.generate(self.fft_size).0.into_vec();
+        self.scale = 2.0 / self.fft_window.iter().sum::<Float>().max(Float::EPSILON);
+        self.input = plan.make_input_vec();
+        self.spectrum = plan.make_output_vec();
+        self.scratch = plan.make_scratch_vec();
+        self.previous = vec![0.0; self.spectrum.len()];
+        self.plan = Some(plan);
+
+        self.history = vec![0.0; self.fft_size];
+        self.write_pos = 0;
+        self.adaptive
+            .allocate((self.window * sample_rate / self.hop as Float).round() as usize);
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if self.plan.is_none() {
+            return Err(ProcessorError::Other);
+        }
+
+        for (in_signal, threshold, min_interval, onset) in iter_proc_io_as!(
+            inputs as [Float, Float, Float],
+            outputs as [bool]
+        ) {
+            self.threshold = threshold.unwrap_or(self.threshold);
+            self.min_interval = min_interval.unwrap_or(self.min_interval);
+
+            self.history[self.write_pos] = in_signal.unwrap_or_default();
+            self.write_pos = (self.write_pos + 1) % self.fft_size;
+
+            *onset = None;
+            self.since_hop += 1;
+            if self.since_hop >= self.hop {
+                self.since_hop = 0;
+                if self.analyze()? {
+                    *onset = Some(true);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: Float = 48000.0;
+
+    // runs the detector on the output of the node built by the closure, returning the detector's outputs, with triggers as 1.0
+    fn analyze(
+        seconds: Float,
+        detector: impl Processor,
+        f: impl FnOnce(&GraphBuilder) -> Node,
+    ) -> Box<[Box<[Float]>]> {
+        let graph = GraphBuilder::new();
+        let detector = graph.add(detector);
+        detector.input(0).connect(f(&graph).output(0));
+        for output in 0..detector.num_outputs() {
+            let out = graph.add_audio_output();
+            let output = detector.output(output as u32);
+            if output.signal_type() == SignalType::Bool {
+                let triggers = graph.add(Cast::new(SignalType::Bool, SignalType::Float));
+                triggers.input(0).connect(output);
+                triggers.output(0).connect(&out.input(0));
+            } else {
+                output.connect(&out.input(0));
+            }
+        }
+        let mut runtime = graph.build_runtime();
+        runtime
+            .run_offline(Duration::from_secs_f64(seconds as f64), SAMPLE_RATE, 64)
+            .unwrap()
+    }
+
+    #[test]
+    fn detects_the_pitch_of_sines() {
+        for freq in [82.41, 220.0, 440.0, 1000.0] {
+            let outputs = analyze(0.2, PitchDetector::default(), |graph| {
+                graph.add(SineOscillator::new(freq))
+            });
+            let settled = outputs[0].len() - 1000;
+            for (frequency, confidence) in outputs[0][settled..].iter().zip(&outputs[1][settled..])
+            {
+                assert!(
+                    (frequency - freq).abs() < freq * 0.005,
+                    "{}: {}",
+                    freq,
+                    frequency
+                );
+                assert!(*confidence > 0.95, "{}: {}", freq, confidence);
+            }
+        }
+    }
+
+    #[test]
+    fn detects_the_fundamental_of_harmonic_signals() {
+        // a sawtooth has a strong second harmonic, which must not be mistaken for the fundamental
+        let outputs = analyze(0.2, PitchDetector::default(), |graph| {
+            graph.add(BlSawOscillator::new(110.0))
+        });
+        let settled = outputs[0].len() - 1000;
+        for frequency in &outputs[0][settled..] {
+            assert!((frequency - 110.0).abs() < 0.5, "{}", frequency);
+        }
+    }
+
+    #[test]
+    fn ignores_noise_and_silence() {
+        let outputs = analyze(0.2, PitchDetector::default(), |graph| {
+            graph.add(NoiseOscillator::new())
+        });
+        // no pitch is ever detected, so the frequency output stays empty
+        assert!(outputs[0].iter().all(|frequency| *frequency == 0.0));
+        let mean_confidence = outputs[1].iter().sum::<Float>() / outputs[1].len() as Float;
+        assert!(mean_confidence < 0.5, "{}", mean_confidence);
+
+        let outputs = analyze(0.1, PitchDetector::default(), |graph| {
+            graph.constant(0.0 as Float)
+        });
+        assert!(outputs[0].iter().all(|frequency| *frequency == 0.0));
+        assert!(outputs[1].iter().all(|confidence| *confidence == 0.0));
+    }
+
+    // noise bursts that start every quarter of a second, beginning at 0
+    fn hits(graph: &GraphBuilder) -> Node {
+        let metro = graph.add(Metro::new(0.25));
+        let env = graph.add(DecayEnv::new(0.02));
+        env.input("trig").connect(metro.output(0));
+        graph.add(NoiseOscillator::new()) * env * 0.5
+    }
+
+    // returns the times of the triggers in seconds
+    fn onset_times(triggers: &[Float]) -> Vec<Float> {
+        (0..triggers.len())
+            .filter(|&i| triggers[i] == 1.0)
+            .map(|i| i as Float / SAMPLE_RATE)
+            .collect()
+    }
+
+    fn assert_onsets_follow_hits(onsets: &[Float], latency: Float) {
+        assert_eq!(onsets.len(), 4, "{:?}", onsets);
+        for (i, onset) in onsets.iter().enumerate() {
+            let hit = i as Float * 0.25;
+            assert!(
+                (hit..hit + latency).contains(onset),
+                "hit at {}, onset at {}",
+                hit,
+                onset
+            );
+        }
+    }
+
+    #[test]
+    fn energy_onsets_follow_hits() {
+        let outputs = analyze(1.0, OnsetDetector::default(), hits);
+        assert_onsets_follow_hits(&onset_times(&outputs[0]), 0.02);
+    }
+
+    #[test]
+    fn energy_onsets_ignore_steady_signals_and_silence() {
+        // only the start of the sine is an onset
+        let outputs = analyze(0.5, OnsetDetector::default(), |graph| {
+            graph.add(SineOscillator::new(440.0)) * 0.5
+        });
+        assert_eq!(onset_times(&outputs[0]).len(), 1);
+
+        let outputs = analyze(0.5, OnsetDetector::default(), |graph| {
+            graph.constant(0.0 as Float)
+        });
+        assert!(onset_times(&outputs[0]).is_empty());
+    }
+
+    #[test]
+    fn energy_onsets_respect_the_minimum_interval() {
+        let outputs = analyze(1.0, OnsetDetector::new(6.0, 0.3), hits);
+        assert_eq!(onset_times(&outputs[0]).len(), 2);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn spectral_flux_onsets_follow_hits() {
+        let outputs = analyze(1.0, SpectralFluxOnset::new(1024), hits);
+        // the hop is a quarter of the FFT size
+        assert_onsets_follow_hits(&onset_times(&outputs[0]), 512.0 / SAMPLE_RATE);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn spectral_flux_onsets_ignore_steady_signals_and_silence() {
+        let outputs = analyze(0.5, SpectralFluxOnset::new(1024), |graph| {
+            graph.add(SineOscillator::new(440.0)) * 0.5
+        });
+        assert_eq!(onset_times(&outputs[0]).len(), 1);
+
+        let outputs = analyze(0.5, SpectralFluxOnset::new(1024), |graph| {
+            graph.constant(0.0 as Float)
+        });
+        assert!(onset_times(&outputs[0]).is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+//! Running sub-graphs at a reduced control rate.
+
+use crate::{prelude::*, runtime::RuntimeError};
+
+/// How a [`ControlRate`] processor fills in its `Float` outputs between control ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlInterpolation {
+    /// Hold the value of the last tick until the next one.
+    Hold,
+    /// Ramp linearly from the value of the previous tick to the value of the last tick, reaching it just before the next tick.
+    ///
+    /// This delays the outputs by one control period, but avoids steps in the output.
+    #[default]
+    Linear,
+}
+
+/// A processor that runs a sub-graph once every `divisor` samples, for control logic that doesn't need to run at audio rate.
+///
+/// The sub-graph's processors see the divided sample rate in [`Processor::allocate`], and the number of control ticks in the current block as the block size.
+/// Its inputs are sampled at each tick.
+/// Its `Float` outputs are brought back to audio rate according to the [`ControlInterpolation`] mode, and its other outputs are only written at the ticks and are `None` in between.
+///
+/// # Inputs
+///
+/// The inputs of the sub-graph, with the names and signal types they were declared with (see [`Graph::add_input`]).
+/// Unconnected inputs are filled with `None`.
+///
+/// # Outputs
+///
+/// The outputs of the sub-graph, with the names and signal types they were declared with (see [`Graph::add_output`]).
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlRate {
+    rt: Runtime,
+    divisor: usize,
+    interpolation: ControlInterpolation,
+
+    // the number of samples until the next tick
+    until_tick: usize,
+    // the values of the `Float` outputs at the previous and the last tick
+    prev: Vec<Float>,
+    current: Vec<Float>,
+}
+
+impl ControlRate {
+    /// Creates a new `ControlRate` processor that runs the given graph once every `divisor` samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn new(divisor: usize, graph: Graph) -> Self {
+        assert!(divisor > 0, "Control rate divisor must be at least 1");
+        let num_outputs = graph.output_specs().len();
+        Self {
+            rt: Runtime::new(graph),
+            divisor,
+            interpolation: ControlInterpolation::default(),
+            until_tick: 0,
+            prev: vec![0.0; num_outputs],
+            current: vec![0.0; num_outputs],
+        }
+    }
+
+    /// Creates a new `ControlRate` processor by building its graph with the given closure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn build<F>(divisor: usize, f: F) -> Self
+    where
+        F: FnOnce(&GraphBuilder),
+    {
+        let builder = GraphBuilder::new();
+        f(&builder);
+        Self::new(divisor, builder.build())
+    }
+
+    /// Sets how the `Float` outputs are filled in between control ticks.
+    pub fn with_interpolation(mut self, interpolation: ControlInterpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Returns the number of audio samples per control tick.
+    #[inline]
+    pub fn divisor(&self) -> usize {
+        self.divisor
+    }
+
+    /// Returns a reference to the sub-graph.
+    #[inline]
+    pub fn graph(&self) -> &Graph {
+        self.rt.graph()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for ControlRate {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        self.rt.graph().input_specs().to_vec()
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        self.rt.graph().output_specs().to_vec()
+    }
+
+    fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
+        self.rt.allocate_for_block_size(
+            sample_rate / self.divisor as Float,
+            max_block_size.div_ceil(self.divisor),
+        );
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        // in a feedback loop, only the current sub-block (or sample) is processed
+        let range = inputs.mode.sample_range(inputs.block_size);
+        let block_size = range.len();
+        let divisor = self.divisor;
+        let first_tick = self.until_tick;
+        let num_ticks = if first_tick < block_size {
+            (block_size - first_tick).div_ceil(divisor)
+        } else {
+            0
+        };
+
+        if num_ticks > 0 {
+            self.rt
+                .set_block_size(num_ticks)
+                .map_err(|_| ProcessorError::Other)?;
+
+            for i in 0..self.rt.graph().input_indices().len() {
+                let input = self
+                    .rt
+                    .get_input_mut(i)
+                    .ok_or(ProcessorError::NumInputsMismatch)?;
+                let Some(signal) = inputs.input(i) else {
+                    input.fill_default();
+                    continue;
+                };
+                for tick in 0..num_ticks {
+                    input.set(
+                        tick,
+                        signal
+                            .get(range.start + first_tick + tick * divisor)
+                            .unwrap(),
+                    );
+                }
+            }
+
+            match self.rt.process() {
+                Ok(()) => {}
+                Err(RuntimeError::GraphRunError(e)) => {
+                    return Err(ProcessorError::SubGraph(Box::new(e)))
+                }
+                Err(_) => {
+                    return Err(ProcessorError::Other);
+                }
+            }
+        }
+
+        for i in 0..self.current.len() {
+            let output = self
+                .rt
+                .get_output(i)
+                .ok_or(ProcessorError::NumOutputsMismatch)?;
+            let mut signal = outputs.output(i);
+
+            let SignalBuffer::Float(output) = output else {
+                for sample_index in 0..block_size {
+                    signal.set_none(sample_index);
+                }
+                for tick in 0..num_ticks {
+                    signal.set(first_tick + tick * divisor, output.get(tick).unwrap());
+                }
+                continue;
+            };
+
+            // the number of samples since the last tick, counting from 1
+            let mut since_tick = divisor - first_tick.min(divisor);
+            let mut tick = 0;
+            for sample_index in 0..block_size {
+                if tick < num_ticks && sample_index == first_tick + tick * divisor {
+                    self.prev[i] = self.current[i];
+                    if let Some(value) = output[tick] {
+                        self.current[i] = value;
+                    }
+                    tick += 1;
+                    since_tick = 0;
+                }
+                since_tick += 1;
+
+                let value = match self.interpolation {
+                    ControlInterpolation::Hold => self.current[i],
+                    ControlInterpolation::Linear => {
+                        let t = since_tick as Float / divisor as Float;
+                        lerp(self.prev[i], self.current[i], t)
+                    }
+                };
+                signal.set_as(sample_index, value);
+            }
+        }
+
+        self.until_tick = if num_ticks > 0 {
+            first_tick + num_ticks * divisor - block_size
+        } else {
+            first_tick - block_size
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::builtins::test_util::TestSource;
+
+    const DIVISOR: usize = 4;
+
+    // renders the number of ticks a control rate sub-graph has run, and the audio sample index it sampled at its last tick
+    fn render(interpolation: ControlInterpolation, block_size: usize) -> [Box<[Float]>; 2] {
+        let graph = GraphBuilder::new();
+        let out_ticks = graph.add_audio_output();
+        let out_sampled = graph.add_audio_output();
+
+        let control = graph.add(
+            ControlRate::build(DIVISOR, |graph| {
+                let input = graph.add_input("in", SignalType::Float);
+                let ticks = graph.add_output("ticks", SignalType::Float);
+                let sampled = graph.add_output("sampled", SignalType::Float);
+                graph
+                    .add(TestSource::count())
+                    .output(0)
+                    .connect(&ticks.input(0));
+                input.output(0).connect(&sampled.input(0));
+            })
+            .with_interpolation(interpolation),
+        );
+        control
+            .input("in")
+            .connect(graph.add(TestSource::count()).output(0));
+        control.output("ticks").connect(&out_ticks.input(0));
+        control.output("sampled").connect(&out_sampled.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let mut outputs = runtime
+            .run_offline(Duration::from_millis(64), 1000.0, block_size)
+            .unwrap();
+        [
+            std::mem::take(&mut outputs[0]),
+            std::mem::take(&mut outputs[1]),
+        ]
+    }
+
+    #[test]
+    fn ticks_every_divisor_samples() {
+        // block sizes that are not multiples of the divisor carry the phase of the ticks over to the next block
+        for block_size in [1, 3, 4, 7, 64] {
+            let [ticks, sampled] = render(ControlInterpolation::Hold, block_size);
+            for n in 0..ticks.len() {
+                let tick = (n / DIVISOR) as Float;
+                assert_eq!(ticks[n], tick, "block size {block_size}, sample {n}");
+                assert_eq!(
+                    sampled[n],
+                    tick * DIVISOR as Float,
+                    "block size {block_size}, sample {n}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn interpolates_linearly_between_ticks() {
+        for block_size in [1, 3, 4, 7, 64] {
+            let [_, sampled] = render(ControlInterpolation::Linear, block_size);
+
+            // the ramp sampled at each tick is reconstructed, one control period late
+            assert_eq!(sampled[..DIVISOR], [0.0; DIVISOR]);
+            for n in DIVISOR..sampled.len() {
+                let expected = (n + 1 - DIVISOR) as Float;
+                assert_eq!(sampled[n], expected, "block size {block_size}, sample {n}");
+            }
+        }
+    }
+}
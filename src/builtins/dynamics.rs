@@ -1,9 +1,162 @@
-//! Dynamics processors, such as compressors and limiters.
+//! Dynamics processors, such as compressors, limiters, and envelope followers.
 
 use crate::prelude::*;
 
+/// Returns the signal a dynamics processor detects on: the sidechain key if one is connected, otherwise the input, passed through the sidechain filter if there is one.
+#[inline]
+fn detector_input(
+    filter: &mut Option<AutoBiquad>,
+    in_signal: Float,
+    sidechain: &Option<Float>,
+) -> Float {
+    let key = sidechain.unwrap_or(in_signal);
+    match filter {
+        Some(filter) => filter.tick(key),
+        None => key,
+    }
+}
+
+/// The detection mode of an [`EnvelopeFollower`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EnvelopeMode {
+    /// Follows the absolute value of the signal.
+    #[default]
+    Peak,
+    /// Follows the root mean square of the signal.
+    Rms,
+}
+
+/// An envelope follower that tracks the amplitude of its input.
+///
+/// The envelope rises towards the input's level with the attack time and falls with the release time.
+/// In [`EnvelopeMode::Rms`] mode, the squared signal is smoothed instead, and the output is its square root.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The input signal. |
+/// | `1` | `attack` | `Float` | The attack time in seconds. |
+/// | `2` | `release` | `Float` | The release time in seconds. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Float` | The envelope of the input signal. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvelopeFollower {
+    envelope: Float,
+
+    /// The detection mode of the follower.
+    pub mode: EnvelopeMode,
+
+    /// The attack time in seconds.
+    pub attack: Float,
+
+    /// The release time in seconds.
+    pub release: Float,
+}
+
+impl EnvelopeFollower {
+    /// Creates a new peak `EnvelopeFollower` processor with the given attack and release times.
+    pub fn new(attack: Float, release: Float) -> Self {
+        Self {
+            attack,
+            release,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a new RMS `EnvelopeFollower` processor with the given attack and release times.
+    pub fn rms(attack: Float, release: Float) -> Self {
+        Self::new(attack, release).with_mode(EnvelopeMode::Rms)
+    }
+
+    /// Sets the detection mode of the follower.
+    pub fn with_mode(mut self, mode: EnvelopeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Default for EnvelopeFollower {
+    fn default() -> Self {
+        Self {
+            envelope: 0.0,
+            mode: EnvelopeMode::Peak,
+            attack: 0.01,
+            release: 0.1,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for EnvelopeFollower {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("in", SignalType::Float),
+            SignalSpec::new("attack", SignalType::Float),
+            SignalSpec::new("release", SignalType::Float),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Float)]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.envelope = 0.0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let sample_rate = inputs.sample_rate();
+
+        for (in_signal, attack, release, out) in iter_proc_io_as!(
+            inputs as [Float, Float, Float],
+            outputs as [Float]
+        ) {
+            self.attack = attack.unwrap_or(self.attack);
+            self.release = release.unwrap_or(self.release);
+
+            let level = match self.mode {
+                EnvelopeMode::Peak => in_signal.unwrap_or_default().abs(),
+                EnvelopeMode::Rms => in_signal.unwrap_or_default().powi(2),
+            };
+
+            let time = if level > self.envelope {
+                self.attack
+            } else {
+                self.release
+            };
+            let coeff = if time > 0.0 {
+                Float::exp(-1.0 / (time * sample_rate))
+            } else {
+                0.0
+            };
+
+            self.envelope = level + (self.envelope - level) * coeff;
+
+            *out = Some(match self.mode {
+                EnvelopeMode::Peak => self.envelope,
+                EnvelopeMode::Rms => self.envelope.sqrt(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
 /// A simple peak limiter.
 ///
+/// If the `sidechain` input is connected, the limiter reduces the gain of its input according to the level of the sidechain signal instead, which can be used for ducking.
+///
 /// # Inputs
 ///
 /// | Index | Name | Type | Description |
@@ -12,6 +165,7 @@ use crate::prelude::*;
 /// | `1` | `threshold` | `Float` | The amplitude threshold of the limiter. |
 /// | `2` | `attack` | `Float` | The attack factor of the limiter. |
 /// | `3` | `release` | `Float` | The release factor of the limiter. |
+/// | `4` | `sidechain` | `Float` | The sidechain key signal (optional). |
 ///
 /// # Outputs
 ///
@@ -32,6 +186,9 @@ pub struct PeakLimiter {
 
     /// The release factor of the limiter.
     pub release: Float,
+
+    /// The filter applied to the detected signal, if any.
+    pub sidechain_filter: Option<AutoBiquad>,
 }
 
 impl PeakLimiter {
@@ -44,6 +201,12 @@ impl PeakLimiter {
             ..Default::default()
         }
     }
+
+    /// Sets the filter applied to the detected signal, e.g. a highpass to keep low frequencies from triggering the limiter.
+    pub fn with_sidechain_filter(mut self, filter: AutoBiquad) -> Self {
+        self.sidechain_filter = Some(filter);
+        self
+    }
 }
 
 impl Default for PeakLimiter {
@@ -55,6 +218,7 @@ impl Default for PeakLimiter {
             threshold: 0.9885530946569389,
             attack: 0.9,
             release: 0.9995,
+            sidechain_filter: None,
         }
     }
 }
@@ -67,6 +231,7 @@ impl Processor for PeakLimiter {
             SignalSpec::new("threshold", SignalType::Float),
             SignalSpec::new("attack", SignalType::Float),
             SignalSpec::new("release", SignalType::Float),
+            SignalSpec::new("sidechain", SignalType::Float),
         ]
     }
 
@@ -74,13 +239,19 @@ impl Processor for PeakLimiter {
         vec![SignalSpec::new("out", SignalType::Float)]
     }
 
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        if let Some(filter) = &mut self.sidechain_filter {
+            filter.set_coefficients(sample_rate);
+        }
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
-        for (in_signal, threshold, attack, release, out) in iter_proc_io_as!(
-            inputs as [Float, Float, Float, Float],
+        for (in_signal, threshold, attack, release, sidechain, out) in iter_proc_io_as!(
+            inputs as [Float, Float, Float, Float, Float],
             outputs as [Float]
         ) {
             self.threshold = threshold.unwrap_or(self.threshold);
@@ -92,7 +263,8 @@ impl Processor for PeakLimiter {
                 continue;
             };
 
-            self.envelope = in_signal.abs().max(self.envelope * self.release);
+            let key = detector_input(&mut self.sidechain_filter, *in_signal, sidechain);
+            self.envelope = key.abs().max(self.envelope * self.release);
 
             let target_gain = if self.envelope > self.threshold {
                 self.threshold / self.envelope
@@ -111,6 +283,8 @@ impl Processor for PeakLimiter {
 
 /// A simple compressor.
 ///
+/// If the `sidechain` input is connected, the compressor reduces the gain of its input according to the level of the sidechain signal instead, which can be used for ducking and pumping effects.
+///
 /// # Inputs
 ///
 /// | Index | Name | Type | Description |
@@ -120,6 +294,7 @@ impl Processor for PeakLimiter {
 /// | `2` | `ratio` | `Float` | The compression ratio of the compressor. |
 /// | `3` | `attack` | `Float` | The attack factor of the compressor. |
 /// | `4` | `release` | `Float` | The release factor of the compressor. |
+/// | `5` | `sidechain` | `Float` | The sidechain key signal (optional). |
 ///
 /// # Outputs
 ///
@@ -143,6 +318,9 @@ pub struct Compressor {
 
     /// The release factor of the compressor.
     pub release: Float,
+
+    /// The filter applied to the detected signal, if any.
+    pub sidechain_filter: Option<AutoBiquad>,
 }
 
 impl Compressor {
@@ -156,6 +334,12 @@ impl Compressor {
             ..Default::default()
         }
     }
+
+    /// Sets the filter applied to the detected signal, e.g. a lowpass so that only the kick drum of a sidechain key triggers the compressor.
+    pub fn with_sidechain_filter(mut self, filter: AutoBiquad) -> Self {
+        self.sidechain_filter = Some(filter);
+        self
+    }
 }
 
 impl Default for Compressor {
@@ -169,6 +353,7 @@ impl Default for Compressor {
             ratio: 4.0,
             attack: 0.9,
             release: 0.9995,
+            sidechain_filter: None,
         }
     }
 }
@@ -182,6 +367,7 @@ impl Processor for Compressor {
             SignalSpec::new("ratio", SignalType::Float),
             SignalSpec::new("attack", SignalType::Float),
             SignalSpec::new("release", SignalType::Float),
+            SignalSpec::new("sidechain", SignalType::Float),
         ]
     }
 
@@ -189,13 +375,19 @@ impl Processor for Compressor {
         vec![SignalSpec::new("out", SignalType::Float)]
     }
 
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        if let Some(filter) = &mut self.sidechain_filter {
+            filter.set_coefficients(sample_rate);
+        }
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
-        for (in_signal, threshold, ratio, attack, release, out) in iter_proc_io_as!(
-            inputs as [Float, Float, Float, Float, Float],
+        for (in_signal, threshold, ratio, attack, release, sidechain, out) in iter_proc_io_as!(
+            inputs as [Float, Float, Float, Float, Float, Float],
             outputs as [Float]
         ) {
             self.threshold = threshold.unwrap_or(self.threshold);
@@ -208,7 +400,8 @@ impl Processor for Compressor {
                 continue;
             };
 
-            self.envelope = in_signal.abs().max(self.envelope * self.release);
+            let key = detector_input(&mut self.sidechain_filter, *in_signal, sidechain);
+            self.envelope = key.abs().max(self.envelope * self.release);
 
             let target_gain = if self.envelope > self.threshold {
                 self.threshold + (self.envelope - self.threshold) / self.ratio
@@ -227,6 +420,8 @@ impl Processor for Compressor {
 
 /// An RMS compressor.
 ///
+/// If the `sidechain` input is connected, the compressor reduces the gain of its input according to the level of the sidechain signal instead, which can be used for ducking and pumping effects.
+///
 /// # Inputs
 ///
 /// | Index | Name | Type | Description |
@@ -236,7 +431,8 @@ impl Processor for Compressor {
 /// | `2` | `ratio` | `Float` | The compression ratio of the compressor. |
 /// | `3` | `attack` | `Float` | The attack factor of the compressor. |
 /// | `4` | `release` | `Float` | The release factor of the compressor. |
-/// | `5` | `window_size` | `Float` | The window size of the RMS detector in seconds. |
+/// | `5` | `window_size` | `Float` | The window size of the RMS detector in seconds, up to the maximum window size. |
+/// | `6` | `sidechain` | `Float` | The sidechain key signal (optional). |
 ///
 /// # Outputs
 ///
@@ -249,7 +445,17 @@ pub struct RmsCompressor {
     gain: Float,
     envelope: Float,
     rms: Float,
+    // ring buffer of the squared detector input
+    #[cfg_attr(feature = "serde", serde(skip))]
     window: Vec<Float>,
+    // the index the next squared sample is written to
+    #[cfg_attr(feature = "serde", serde(skip))]
+    write_index: usize,
+    // the number of samples in the current window, and the sum of their squares
+    #[cfg_attr(feature = "serde", serde(skip))]
+    window_len: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    window_sum: Float,
 
     /// The amplitude threshold of the compressor.
     pub threshold: Float,
@@ -265,6 +471,13 @@ pub struct RmsCompressor {
 
     /// The window size of the RMS detector in seconds.
     pub window_size: Float,
+
+    /// The maximum window size of the RMS detector in seconds, which determines how much memory is allocated for the window.
+    #[cfg_attr(feature = "serde", serde(default = "default_max_window_size"))]
+    pub max_window_size: Float,
+
+    /// The filter applied to the detected signal, if any.
+    pub sidechain_filter: Option<AutoBiquad>,
 }
 
 impl RmsCompressor {
@@ -285,6 +498,67 @@ impl RmsCompressor {
             ..Default::default()
         }
     }
+
+    /// Sets the filter applied to the detected signal, e.g. a lowpass so that only the kick drum of a sidechain key triggers the compressor.
+    pub fn with_sidechain_filter(mut self, filter: AutoBiquad) -> Self {
+        self.sidechain_filter = Some(filter);
+        self
+    }
+
+    /// Sets the maximum window size of the RMS detector in seconds.
+    pub fn with_max_window_size(mut self, max_window_size: Float) -> Self {
+        self.max_window_size = max_window_size;
+        self
+    }
+
+    // sizes the window for the maximum window size, clearing it if its size changes
+    fn resize_window(&mut self, sample_rate: Float) {
+        let len = ((self.max_window_size * sample_rate) as usize).max(1);
+        if len != self.window.len() {
+            self.window.clear();
+            self.window.resize(len, 0.0);
+            self.write_index = 0;
+            self.window_len = 0;
+            self.window_sum = 0.0;
+        }
+    }
+
+    // returns the sum of the last `window_len` squared samples
+    fn sum_window(&self) -> Float {
+        let capacity = self.window.len();
+        (1..=self.window_len)
+            .map(|age| self.window[(self.write_index + capacity - age) % capacity])
+            .sum()
+    }
+
+    // adds a squared sample to the window, and returns the mean of the squared samples in a window of the given length
+    #[inline]
+    fn push_squared(&mut self, squared: Float, window_len: usize) -> Float {
+        let capacity = self.window.len();
+        let window_len = window_len.clamp(1, capacity);
+        if window_len != self.window_len {
+            self.window_len = window_len;
+            self.window_sum = self.sum_window();
+        }
+
+        // the sample that leaves the window is the one `window_len` samples older than the new one
+        let oldest = self.window[(self.write_index + capacity - window_len) % capacity];
+        self.window[self.write_index] = squared;
+        self.window_sum += squared - oldest;
+
+        self.write_index += 1;
+        if self.write_index == capacity {
+            self.write_index = 0;
+            // recompute the running sum once per lap, so that rounding errors don't accumulate
+            self.window_sum = self.sum_window();
+        }
+
+        self.window_sum.max(0.0) / window_len as Float
+    }
+}
+
+fn default_max_window_size() -> Float {
+    1.0
 }
 
 impl Default for RmsCompressor {
@@ -293,7 +567,10 @@ impl Default for RmsCompressor {
             gain: 1.0,
             envelope: 0.0,
             rms: 0.0,
-            window: vec![0.0; 44100],
+            window: Vec::new(),
+            write_index: 0,
+            window_len: 0,
+            window_sum: 0.0,
             // -0.1 dBFS
             threshold: 0.9885530946569389,
             // 4:1
@@ -301,6 +578,8 @@ impl Default for RmsCompressor {
             attack: 0.9,
             release: 0.9995,
             window_size: 0.01,
+            max_window_size: default_max_window_size(),
+            sidechain_filter: None,
         }
     }
 }
@@ -314,7 +593,8 @@ impl Processor for RmsCompressor {
             SignalSpec::new("ratio", SignalType::Float),
             SignalSpec::new("attack", SignalType::Float),
             SignalSpec::new("release", SignalType::Float),
-            SignalSpec::new("window_size", SignalType::Int),
+            SignalSpec::new("window_size", SignalType::Float),
+            SignalSpec::new("sidechain", SignalType::Float),
         ]
     }
 
@@ -323,12 +603,17 @@ impl Processor for RmsCompressor {
     }
 
     fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
-        self.window
-            .resize((self.window_size * sample_rate) as usize, 0.0);
+        self.resize_window(sample_rate);
+        if let Some(filter) = &mut self.sidechain_filter {
+            filter.set_coefficients(sample_rate);
+        }
     }
 
     fn resize_buffers(&mut self, sample_rate: Float, _block_size: usize) {
-        self.window.resize(sample_rate as usize, 0.0);
+        self.resize_window(sample_rate);
+        if let Some(filter) = &mut self.sidechain_filter {
+            filter.set_coefficients(sample_rate);
+        }
     }
 
     fn process(
@@ -336,8 +621,8 @@ impl Processor for RmsCompressor {
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
-        for (in_signal, threshold, ratio, attack, release, window_size, out) in iter_proc_io_as!(
-            inputs as [Float, Float, Float, Float, Float, Float],
+        for (in_signal, threshold, ratio, attack, release, window_size, sidechain, out) in iter_proc_io_as!(
+            inputs as [Float, Float, Float, Float, Float, Float, Float],
             outputs as [Float]
         ) {
             self.threshold = threshold.unwrap_or(self.threshold);
@@ -351,14 +636,10 @@ impl Processor for RmsCompressor {
                 continue;
             };
 
-            self.window.rotate_left(1);
-            self.window[0] = in_signal.powi(2);
+            let key = detector_input(&mut self.sidechain_filter, *in_signal, sidechain);
 
-            let window_size = (self.window_size * inputs.sample_rate()) as usize;
-
-            self.rms =
-                self.window[..window_size].iter().sum::<Float>() / self.window.len() as Float;
-            self.rms = self.rms.sqrt();
+            let window_len = (self.window_size * inputs.sample_rate()) as usize;
+            self.rms = self.push_squared(key.powi(2), window_len).sqrt();
             self.envelope = self.rms.max(self.envelope * self.release);
 
             let target_gain = if self.envelope > self.threshold {
@@ -375,3 +656,209 @@ impl Processor for RmsCompressor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtins::test_util::TestSource;
+    use std::time::Duration;
+
+    const SAMPLE_RATE: Float = 1000.0;
+
+    // renders `num_samples` of the processor with the signals connected to its inputs, by name
+    fn render(
+        processor: impl Processor,
+        inputs: &[(&str, TestSource)],
+        num_samples: usize,
+    ) -> Box<[Float]> {
+        let graph = GraphBuilder::new();
+        let node = graph.add(processor);
+        for (name, signal) in inputs {
+            let signal = graph.add(signal.clone());
+            signal.output(0).connect(&node.input(*name));
+        }
+        let out = graph.add_audio_output();
+        node.output(0).connect(&out.input(0));
+
+        let duration = Duration::from_secs_f64(num_samples as f64 / SAMPLE_RATE as f64);
+        let mut outputs = graph
+            .build_runtime()
+            .run_offline(duration, SAMPLE_RATE, 7)
+            .unwrap();
+        std::mem::take(&mut outputs[0])
+    }
+
+    // the steady gain of a compressor with a threshold of 0.5 and a ratio of 4:1, for the given envelope level
+    fn gain(envelope: Float) -> Float {
+        if envelope > 0.5 {
+            0.5 + (envelope - 0.5) / 4.0
+        } else {
+            envelope
+        }
+    }
+
+    #[test]
+    fn compressor_follows_its_static_curve() {
+        for level in [0.1, 0.25, 0.5, 0.75, 1.0, 2.0] {
+            for sign in [1.0, -1.0] {
+                // with no attack or release smoothing, the gain follows the input level immediately
+                let compressor = Compressor::new(0.5, 4.0, 0.0, 0.0);
+                let out = render(
+                    compressor,
+                    &[("in", TestSource::repeat([sign * level]))],
+                    20,
+                );
+                for &sample in out.iter() {
+                    assert!((sample - sign * level * gain(level)).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn limiter_holds_the_threshold() {
+        for level in [0.25, 1.0, 2.0] {
+            let limiter = PeakLimiter::new(0.5, 0.0, 0.0);
+            let out = render(limiter, &[("in", TestSource::repeat([level]))], 20);
+            assert!(out
+                .iter()
+                .all(|&sample| (sample - level.min(0.5)).abs() < 1e-6));
+        }
+    }
+
+    #[test]
+    fn rms_compressor_detects_the_windowed_rms() {
+        // a 10 sample window of alternating samples, whose RMS is the level over the square root of 2
+        let compressor = || RmsCompressor::new(0.5, 4.0, 0.0, 0.0, 10.0 / SAMPLE_RATE);
+        for level in [0.5, 1.0, 2.0] {
+            let signal = TestSource::repeat([level, 0.0]);
+            let out = render(compressor(), &[("in", signal)], 40);
+
+            let gain = gain(level / Float::sqrt(2.0));
+            // once the window is full
+            for (n, &sample) in out.iter().enumerate().skip(10) {
+                let expected = if n % 2 == 0 { level * gain } else { 0.0 };
+                assert!((sample - expected).abs() < 1e-6, "{n}: {sample}");
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn loads_rms_compressors_serialized_before_the_ring_buffer() {
+        let json = r#"{
+            "gain": 1.0, "envelope": 0.0, "rms": 0.0, "window": [0.0, 0.0, 0.0],
+            "threshold": 0.5, "ratio": 4.0, "attack": 0.0, "release": 0.0, "window_size": 0.01
+        }"#;
+        let compressor: RmsCompressor = serde_json::from_str(json).unwrap();
+        assert_eq!(compressor.max_window_size, 1.0);
+
+        // a 10 sample window, as in `rms_compressor_detects_the_windowed_rms`
+        let out = render(compressor, &[("in", TestSource::repeat([1.0, 0.0]))], 40);
+        let gain = gain(1.0 / Float::sqrt(2.0));
+        for (n, &sample) in out.iter().enumerate().skip(10) {
+            let expected = if n % 2 == 0 { gain } else { 0.0 };
+            assert!((sample - expected).abs() < 1e-6, "{n}: {sample}");
+        }
+    }
+
+    #[test]
+    fn rms_compressor_window_is_at_least_one_sample() {
+        // a window shorter than a sample, or no room for a window at all, detects the current sample
+        let compressors = [
+            RmsCompressor::new(0.5, 4.0, 0.0, 0.0, 0.0),
+            RmsCompressor::new(0.5, 4.0, 0.0, 0.0, 0.01).with_max_window_size(0.0),
+        ];
+        for compressor in compressors {
+            let out = render(compressor, &[("in", TestSource::repeat([1.0, 0.25]))], 20);
+            for (n, &sample) in out.iter().enumerate() {
+                let expected = if n % 2 == 0 {
+                    gain(1.0)
+                } else {
+                    0.25 * gain(0.25)
+                };
+                assert!((sample - expected).abs() < 1e-6, "{n}: {sample}");
+            }
+        }
+    }
+
+    #[test]
+    fn rms_compressor_window_slides_past_the_end_of_its_buffer() {
+        // a window longer than the maximum is clamped to it, and the running sum stays exact as the buffer wraps
+        let compressor = RmsCompressor::new(0.5, 4.0, 0.0, 0.0, 1.0).with_max_window_size(0.1);
+        let signal = TestSource::repeat([[2.0; 50], [0.0; 50]].concat());
+        let out = render(compressor, &[("in", signal)], 1000);
+
+        // a 100 sample window always holds 50 loud samples
+        let gain = gain(2.0 / Float::sqrt(2.0));
+        for (n, &sample) in out.iter().enumerate().skip(100) {
+            let expected = if n % 100 < 50 { 2.0 * gain } else { 0.0 };
+            assert!((sample - expected).abs() < 1e-6, "{n}: {sample}");
+        }
+    }
+
+    #[test]
+    fn sidechain_key_drives_the_gain_reduction() {
+        // the key is silent for 10 samples and then loud for 10
+        let key = TestSource::repeat([[0.0; 10], [1.0; 10]].concat());
+
+        // the gain follows the key's level, whatever the input's level is
+        for level in [0.25, 1.0] {
+            let compressor = Compressor::new(0.5, 4.0, 0.0, 0.0);
+            let out = render(
+                compressor,
+                &[
+                    ("in", TestSource::repeat([level])),
+                    ("sidechain", key.clone()),
+                ],
+                40,
+            );
+            for (n, &sample) in out.iter().enumerate() {
+                let expected = if n % 20 < 10 { gain(0.0) } else { gain(1.0) };
+                assert!((sample - level * expected).abs() < 1e-6, "{n}: {sample}");
+            }
+        }
+
+        let compressor = RmsCompressor::new(0.5, 4.0, 0.0, 0.0, 1.0 / SAMPLE_RATE);
+        let out = render(
+            compressor,
+            &[("in", TestSource::repeat([0.25])), ("sidechain", key)],
+            20,
+        );
+        assert!(out[..10]
+            .iter()
+            .all(|&sample| (sample - 0.25 * gain(0.0)).abs() < 1e-6));
+        assert!(out[10..]
+            .iter()
+            .all(|&sample| (sample - 0.25 * gain(1.0)).abs() < 1e-6));
+    }
+
+    #[test]
+    fn envelope_follower_attacks_and_releases() {
+        // a time constant of 10 samples for the attack and 50 for the release
+        let follower = EnvelopeFollower::new(10.0 / SAMPLE_RATE, 50.0 / SAMPLE_RATE);
+        let step = TestSource::repeat([[1.0; 100], [0.0; 100]].concat());
+        let out = render(follower, &[("in", step)], 200);
+
+        for (n, &envelope) in out[..100].iter().enumerate() {
+            let expected = 1.0 - (-(n as Float + 1.0) / 10.0).exp();
+            assert!((envelope - expected).abs() < 1e-6, "{n}: {envelope}");
+        }
+        let peak = out[99];
+        for (n, &envelope) in out[100..].iter().enumerate() {
+            let expected = peak * (-(n as Float + 1.0) / 50.0).exp();
+            assert!((envelope - expected).abs() < 1e-6, "{n}: {envelope}");
+        }
+    }
+
+    #[test]
+    fn rms_envelope_follower_smooths_the_squared_signal() {
+        let follower = EnvelopeFollower::rms(10.0 / SAMPLE_RATE, 50.0 / SAMPLE_RATE);
+        let out = render(follower, &[("in", TestSource::repeat([1.0, -1.0]))], 100);
+
+        for (n, &envelope) in out.iter().enumerate() {
+            let expected = (1.0 - (-(n as Float + 1.0) / 10.0).exp()).sqrt();
+            assert!((envelope - expected).abs() < 1e-6, "{n}: {envelope}");
+        }
+    }
+}
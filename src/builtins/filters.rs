@@ -212,6 +212,37 @@ impl Biquad {
             ..Default::default()
         }
     }
+
+    // filters the block as a contiguous slice if the input is dense and the coefficient inputs are unconnected
+    // returns `false` if the block has to be processed sample by sample instead
+    #[inline]
+    fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+        if (1..6).any(|index| inputs.input(index).is_some()) {
+            return false;
+        }
+        let Some(in_signal) = inputs.dense_input(0) else {
+            return false;
+        };
+        let Some(out) = outputs.dense_output(0) else {
+            return false;
+        };
+
+        let (a0, a1, a2, b1, b2) = (self.a0, self.a1, self.a2, self.b1, self.b2);
+        let (mut x1, mut x2, mut y1, mut y2) = (self.x1, self.x2, self.y1, self.y2);
+        out.fill_dense(|out| {
+            for (out, in_signal) in out.iter_mut().zip(in_signal) {
+                let filtered = a0 * in_signal + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+                x2 = x1;
+                x1 = *in_signal;
+                y2 = y1;
+                y1 = filtered;
+                *out = filtered;
+            }
+        });
+        (self.x1, self.x2, self.y1, self.y2) = (x1, x2, y1, y2);
+
+        true
+    }
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
@@ -234,8 +265,12 @@ impl Processor for Biquad {
     fn process(
         &mut self,
         inputs: ProcessorInputs,
-        outputs: ProcessorOutputs,
+        mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if self.process_dense(&inputs, &mut outputs) {
+            return Ok(());
+        }
+
         for (in_signal, a0, a1, a2, b1, b2, out) in iter_proc_io_as!(
             inputs as [Float, Float, Float, Float, Float, Float],
             outputs as [Float]
@@ -416,9 +451,47 @@ impl AutoBiquad {
         self.biquad_type
     }
 
+    /// Filters a single sample with the current coefficients (see [`AutoBiquad::set_coefficients`]).
+    #[inline]
+    pub(crate) fn tick(&mut self, in_signal: Float) -> Float {
+        let filtered = self.a0 * in_signal + self.a1 * self.x1 + self.a2 * self.x2
+            - self.b1 * self.y1
+            - self.b2 * self.y2;
+
+        self.x2 = self.x1;
+        self.x1 = in_signal;
+        self.y2 = self.y1;
+        self.y1 = filtered;
+
+        filtered
+    }
+
+    // filters the block as a contiguous slice if the input is dense and the other inputs are unconnected, so that the coefficients don't change
+    // returns `false` if the block has to be processed sample by sample instead
+    #[inline]
+    fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+        if (1..4).any(|index| inputs.input(index).is_some()) {
+            return false;
+        }
+        let Some(in_signal) = inputs.dense_input(0) else {
+            return false;
+        };
+        let Some(out) = outputs.dense_output(0) else {
+            return false;
+        };
+
+        out.fill_dense(|out| {
+            for (out, in_signal) in out.iter_mut().zip(in_signal) {
+                *out = self.tick(*in_signal);
+            }
+        });
+
+        true
+    }
+
     // http://www.earlevel.com/scripts/widgets/20131013/biquads2.js
     #[inline]
-    fn set_coefficients(&mut self, sample_rate: Float) {
+    pub(crate) fn set_coefficients(&mut self, sample_rate: Float) {
         if self.q < 0.01 {
             self.q = 0.01;
         }
@@ -546,8 +619,12 @@ impl Processor for AutoBiquad {
     fn process(
         &mut self,
         inputs: ProcessorInputs,
-        outputs: ProcessorOutputs,
+        mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if self.process_dense(&inputs, &mut outputs) {
+            return Ok(());
+        }
+
         for (in_signal, frequency, q, gain, out) in iter_proc_io_as!(
             inputs as [Float, Float, Float, Float],
             outputs as [Float]
@@ -573,16 +650,7 @@ impl Processor for AutoBiquad {
                 self.set_coefficients(inputs.sample_rate());
             }
 
-            let filtered = self.a0 * in_signal + self.a1 * self.x1 + self.a2 * self.x2
-                - self.b1 * self.y1
-                - self.b2 * self.y2;
-
-            self.x2 = self.x1;
-            self.x1 = *in_signal;
-            self.y2 = self.y1;
-            self.y1 = filtered;
-
-            *out = Some(filtered);
+            *out = Some(self.tick(*in_signal));
         }
 
         Ok(())
@@ -631,6 +699,54 @@ impl OnePole {
             ..Default::default()
         }
     }
+
+    // clamps the cutoff frequency and updates the coefficients
+    #[inline]
+    fn set_cutoff(&mut self, cutoff: Float, sample_rate: Float) {
+        self.cutoff = cutoff.clamp(0.0, sample_rate * 0.5);
+        self.b1 = Float::exp(-2.0 * PI * self.cutoff / sample_rate);
+        self.a0 = 1.0 - self.b1;
+    }
+
+    // filters the block as a contiguous slice if the input is dense and the cutoff input is dense or unconnected
+    // returns `false` if the block has to be processed sample by sample instead
+    #[inline]
+    fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+        let cutoff = inputs.dense_input(1);
+        if cutoff.is_none() && inputs.input(1).is_some() {
+            return false;
+        }
+        let Some(in_signal) = inputs.dense_input(0) else {
+            return false;
+        };
+        let Some(out) = outputs.dense_output(0) else {
+            return false;
+        };
+
+        let sample_rate = inputs.sample_rate();
+        let mut x1 = self.x1;
+        out.fill_dense(|out| match cutoff {
+            Some(cutoff) => {
+                for ((out, in_signal), cutoff) in out.iter_mut().zip(in_signal).zip(cutoff) {
+                    self.set_cutoff(*cutoff, sample_rate);
+                    *out = self.a0 * in_signal + self.b1 * x1;
+                    x1 = *in_signal;
+                }
+            }
+            None => {
+                // the coefficients are the same for the whole block, so the loop only carries the previous input and can be vectorized
+                self.set_cutoff(self.cutoff, sample_rate);
+                let (a0, b1) = (self.a0, self.b1);
+                for (out, in_signal) in out.iter_mut().zip(in_signal) {
+                    *out = a0 * in_signal + b1 * x1;
+                    x1 = *in_signal;
+                }
+            }
+        });
+        self.x1 = x1;
+
+        true
+    }
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
@@ -649,17 +765,17 @@ impl Processor for OnePole {
     fn process(
         &mut self,
         inputs: ProcessorInputs,
-        outputs: ProcessorOutputs,
+        mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if self.process_dense(&inputs, &mut outputs) {
+            return Ok(());
+        }
+
         for (in_signal, cutoff, out) in iter_proc_io_as!(
             inputs as [Float, Float],
             outputs as [Float]
         ) {
-            self.cutoff = cutoff
-                .unwrap_or(self.cutoff)
-                .clamp(0.0, inputs.sample_rate() * 0.5);
-            self.b1 = Float::exp(-2.0 * PI * self.cutoff / inputs.sample_rate());
-            self.a0 = 1.0 - self.b1;
+            self.set_cutoff(cutoff.unwrap_or(self.cutoff), inputs.sample_rate());
 
             let Some(in_signal) = in_signal else {
                 *out = None;
@@ -0,0 +1,198 @@
+//! Loudness processors per ITU-R BS.1770 and EBU R128 (see [`crate::loudness`]).
+
+use crate::{
+    loudness::{KWeighting, LoudnessAnalyzer},
+    prelude::*,
+};
+
+/// A processor that applies the K-weighting filter of BS.1770 to its input.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The input signal. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Float` | The K-weighted signal. |
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KWeightingFilter {
+    #[cfg_attr(feature = "serde", serde(skip))]
+    filter: Option<KWeighting>,
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for KWeightingFilter {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("in", SignalType::Float)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Float)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.filter = Some(KWeighting::new(sample_rate));
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let filter = self.filter.as_mut().ok_or(ProcessorError::Other)?;
+
+        for (in_signal, out) in iter_proc_io_as!(inputs as [Float], outputs as [Float]) {
+            *out = in_signal.map(|in_signal| filter.process(in_signal));
+        }
+
+        Ok(())
+    }
+}
+
+/// A processor that measures the loudness of one or more channels, as described in [`crate::loudness`].
+///
+/// The loudness outputs are in LUFS, and are negative infinity until enough signal has been measured.
+/// They are updated every 100 ms. Missing input samples are treated as silence.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0..N` | `0..N` | `Float` | The channels to measure. |
+/// | `N` | `reset` | `Bool` | Clears all measurements, e.g. at the start of a program. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `momentary` | `Float` | The loudness over the last 400 ms. |
+/// | `1` | `short_term` | `Float` | The loudness over the last 3 s. |
+/// | `2` | `integrated` | `Float` | The gated loudness since the start or the last reset. |
+/// | `3` | `range` | `Float` | The loudness range since the start or the last reset, in LU. |
+/// | `4` | `true_peak` | `Float` | The true peak since the start or the last reset, in dBTP. |
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoudnessMeter {
+    weights: Vec<Float>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    analyzer: Option<LoudnessAnalyzer>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    frames: Vec<Float>,
+    integrated: Float,
+    range: Float,
+}
+
+impl LoudnessMeter {
+    /// Creates a new `LoudnessMeter` with the given number of channels, all weighted equally.
+    pub fn new(num_channels: usize) -> Self {
+        Self {
+            weights: vec![1.0; num_channels.max(1)],
+            analyzer: None,
+            frames: Vec::new(),
+            integrated: Float::NEG_INFINITY,
+            range: 0.0,
+        }
+    }
+
+    /// Creates a new `LoudnessMeter` with one channel per weight (see [`LoudnessAnalyzer::with_channel_weights`]).
+    pub fn with_channel_weights(weights: &[Float]) -> Self {
+        Self {
+            weights: weights.to_vec(),
+            ..Self::new(weights.len())
+        }
+    }
+
+    /// Returns the number of channels.
+    pub fn num_channels(&self) -> usize {
+        self.weights.len()
+    }
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for LoudnessMeter {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        (0..self.num_channels())
+            .map(|i| SignalSpec::new(i.to_string(), SignalType::Float))
+            .chain(std::iter::once(SignalSpec::new("reset", SignalType::Bool)))
+            .collect()
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("momentary", SignalType::Float),
+            SignalSpec::new("short_term", SignalType::Float),
+            SignalSpec::new("integrated", SignalType::Float),
+            SignalSpec::new("range", SignalType::Float),
+            SignalSpec::new("true_peak", SignalType::Float),
+        ]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
+        self.analyzer = Some(
+            LoudnessAnalyzer::new(self.num_channels(), sample_rate)
+                .with_channel_weights(&self.weights),
+        );
+        self.frames = vec![0.0; max_block_size * self.num_channels()];
+        self.integrated = Float::NEG_INFINITY;
+        self.range = 0.0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let num_channels = self.num_channels();
+        let len = inputs.mode.sample_range(inputs.block_size()).len();
+        let analyzer = self.analyzer.as_mut().ok_or(ProcessorError::Other)?;
+
+        // interleave the channels into frames
+        for ch in 0..num_channels {
+            for (i, sample) in inputs.iter_input_as::<Float>(ch)?.take(len).enumerate() {
+                self.frames[i * num_channels + ch] = sample.unwrap_or_default();
+            }
+        }
+
+        let resets = inputs.iter_input_as::<bool>(num_channels)?;
+        for (i, (frame, reset)) in self
+            .frames
+            .chunks_exact(num_channels)
+            .take(len)
+            .zip(resets)
+            .enumerate()
+        {
+            if reset.unwrap_or(false) {
+                analyzer.reset();
+                self.integrated = Float::NEG_INFINITY;
+                self.range = 0.0;
+            }
+
+            if analyzer.process_frame(frame) {
+                self.integrated = analyzer.integrated();
+                self.range = analyzer.loudness_range();
+            }
+
+            outputs.output(0).set_as(i, analyzer.momentary());
+            outputs.output(1).set_as(i, analyzer.short_term());
+            outputs.output(2).set_as(i, self.integrated);
+            outputs.output(3).set_as(i, self.range);
+            outputs
+                .output(4)
+                .set_as(i, 20.0 * analyzer.true_peak().log10());
+        }
+
+        Ok(())
+    }
+}
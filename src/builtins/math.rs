@@ -44,11 +44,20 @@ impl Processor for Constant {
         vec![SignalSpec::new("out", self.value.signal_type())]
     }
 
+    fn is_steady(&self) -> bool {
+        true
+    }
+
     fn process(
         &mut self,
         _inputs: ProcessorInputs,
         mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if let (AnySignal::Float(Some(value)), Some(out)) = (&self.value, outputs.dense_output(0)) {
+            out.fill_dense(|out| out.fill(*value));
+            return Ok(());
+        }
+
         outputs.output(0).fill(self.value.clone());
 
         Ok(())
@@ -89,6 +98,10 @@ impl Processor for MidiToFreq {
         vec![SignalSpec::new("freq", SignalType::Float)]
     }
 
+    fn is_steady(&self) -> bool {
+        true
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
@@ -130,6 +143,10 @@ impl Processor for FreqToMidi {
         vec![SignalSpec::new("note", SignalType::Float)]
     }
 
+    fn is_steady(&self) -> bool {
+        true
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
@@ -245,6 +262,66 @@ impl Processor for Expr {
     }
 }
 
+/// An operand of an elementwise processor working on dense [`Float`] signals.
+#[derive(Clone, Copy)]
+enum DenseOperand<'a> {
+    /// The samples of a dense input.
+    Samples(&'a [Float]),
+    /// The value of an unconnected input, held for the whole block.
+    Held(Float),
+}
+
+impl<'a> DenseOperand<'a> {
+    /// Returns the operand for the input at the given index, or `None` if the input is connected but not dense.
+    #[inline]
+    fn new(inputs: &ProcessorInputs<'_, 'a>, index: usize, held: &AnySignal) -> Option<Self> {
+        if inputs.input(index).is_none() {
+            let held = held.as_type::<Float>()?;
+            return Some(DenseOperand::Held(held.unwrap_or_default()));
+        }
+        inputs.dense_input(index).map(DenseOperand::Samples)
+    }
+
+    /// Returns the last sample of the operand, if it comes from an input.
+    #[inline]
+    fn last_sample(&self) -> Option<Float> {
+        match self {
+            DenseOperand::Samples(samples) => samples.last().copied(),
+            DenseOperand::Held(_) => None,
+        }
+    }
+}
+
+/// Applies `f` to each pair of samples of the operands, writing the results to `out`.
+///
+/// Each combination of operands gets its own loop, without any branches inside it, so that the compiler can vectorize it.
+#[inline(always)]
+fn map_dense_binary(
+    a: DenseOperand,
+    b: DenseOperand,
+    out: &mut [Float],
+    f: impl Fn(Float, Float) -> Float,
+) {
+    match (a, b) {
+        (DenseOperand::Samples(a), DenseOperand::Samples(b)) => {
+            for ((out, a), b) in out.iter_mut().zip(a).zip(b) {
+                *out = f(*a, *b);
+            }
+        }
+        (DenseOperand::Samples(a), DenseOperand::Held(b)) => {
+            for (out, a) in out.iter_mut().zip(a) {
+                *out = f(*a, b);
+            }
+        }
+        (DenseOperand::Held(a), DenseOperand::Samples(b)) => {
+            for (out, b) in out.iter_mut().zip(b) {
+                *out = f(a, *b);
+            }
+        }
+        (DenseOperand::Held(a), DenseOperand::Held(b)) => out.fill(f(a, b)),
+    }
+}
+
 macro_rules! impl_binary_proc {
     ($name:ident, $method:ident, ($($data:ident = $ty:ty),*), $doc:literal) => {
         #[derive(Clone, Debug)]
@@ -263,6 +340,36 @@ macro_rules! impl_binary_proc {
                     b: AnySignal::default_of_type(&signal_type),
                 }
             }
+
+            // processes the block as contiguous slices if the processor works on `Float`s, at least one input is connected, and the connected inputs are dense
+            // returns `false` if the block has to be processed sample by sample instead
+            #[inline]
+            fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+                if inputs.input(0).is_none() && inputs.input(1).is_none() {
+                    return false;
+                }
+                let (Some(a), Some(b)) = (
+                    DenseOperand::new(inputs, 0, &self.a),
+                    DenseOperand::new(inputs, 1, &self.b),
+                ) else {
+                    return false;
+                };
+                let Some(out) = outputs.dense_output(0) else {
+                    return false;
+                };
+
+                out.fill_dense(|out| map_dense_binary(a, b, out, |a: Float, b: Float| a.$method(b)));
+
+                // keep the last values in case the inputs are disconnected
+                if let Some(a) = a.last_sample() {
+                    self.a = AnySignal::Float(Some(a));
+                }
+                if let Some(b) = b.last_sample() {
+                    self.b = AnySignal::Float(Some(b));
+                }
+
+                true
+            }
         }
 
         #[cfg_attr(feature = "serde", typetag::serde)]
@@ -278,11 +385,19 @@ macro_rules! impl_binary_proc {
                 vec![SignalSpec::new("out", self.a.signal_type())]
             }
 
+            fn is_steady(&self) -> bool {
+                true
+            }
+
             fn process(
                 &mut self,
                 inputs: ProcessorInputs,
-                outputs: ProcessorOutputs,
+                mut outputs: ProcessorOutputs,
             ) -> Result<(), ProcessorError> {
+                if self.process_dense(&inputs, &mut outputs) {
+                    return Ok(());
+                }
+
                 for (in1, in2, sample) in iter_proc_io_as!(inputs as [Any, Any], outputs as [Any]) {
                     if let Some(in1) = in1 {
                         if in1.signal_type() != self.a.signal_type() {
@@ -422,6 +537,34 @@ macro_rules! impl_unary_proc {
                     a: AnySignal::default_of_type(&signal_type),
                 }
             }
+
+            // processes the block as contiguous slices if the processor works on `Float`s and its input is dense
+            // returns `false` if the block has to be processed sample by sample instead
+            #[inline]
+            fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+                if !matches!(self.a, AnySignal::Float(_)) {
+                    return false;
+                }
+                let Some(a) = inputs.dense_input(0) else {
+                    return false;
+                };
+                let Some(out) = outputs.dense_output(0) else {
+                    return false;
+                };
+
+                out.fill_dense(|out| {
+                    for (out, a) in out.iter_mut().zip(a) {
+                        *out = a.$method();
+                    }
+                });
+
+                // keep the last value in case the input is disconnected
+                if let Some(a) = a.last() {
+                    self.a = AnySignal::Float(Some(*a));
+                }
+
+                true
+            }
         }
 
         #[cfg_attr(feature = "serde", typetag::serde)]
@@ -434,11 +577,19 @@ macro_rules! impl_unary_proc {
                 vec![SignalSpec::new("out", self.a.signal_type())]
             }
 
+            fn is_steady(&self) -> bool {
+                true
+            }
+
             fn process(
                 &mut self,
                 inputs: ProcessorInputs,
-                outputs: ProcessorOutputs,
+                mut outputs: ProcessorOutputs,
             ) -> Result<(), ProcessorError> {
+                if self.process_dense(&inputs, &mut outputs) {
+                    return Ok(());
+                }
+
                 for (a, sample) in iter_proc_io_as!(inputs as [Any], outputs as [Any]) {
                     if let Some(a) = a {
                         if a.signal_type() != self.a.signal_type() {
@@ -621,3 +772,82 @@ mod serde_impl {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // renders a chain of math, filter and oscillator processors whose frequency and gain are either constants, which output dense signals, or params, which don't
+    fn render_chain(dense: bool) -> (Vec<Float>, bool) {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+
+        let (frequency, gain) = if dense {
+            (graph.constant(220.0), graph.constant(0.5))
+        } else {
+            (
+                graph.add_param(Param::new::<Float>("frequency", 220.0)),
+                graph.add_param(Param::new::<Float>("gain", 0.5)),
+            )
+        };
+
+        let sine = graph.add(SineOscillator::default());
+        sine.input("frequency").connect(frequency.output(0));
+        let lfo = graph.add(SawOscillator::new(3.0));
+        let mix = (sine * (lfo * gain + 0.5)).tanh().abs().sqrt() - 0.5;
+
+        let one_pole = graph.add(OnePole::new(2000.0));
+        one_pole.input(0).connect(mix.output(0));
+        let biquad = graph.add(Biquad::new(0.5, 0.2, 0.1, -0.3, 0.1));
+        biquad.input(0).connect(one_pole.output(0));
+        let lowpass = graph.add(AutoBiquad::lowpass(1000.0, 0.7));
+        lowpass.input(0).connect(biquad.output(0));
+        lowpass.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        runtime.allocate_for_block_size(48_000.0, 64);
+        let mut samples = Vec::new();
+        for _ in 0..4 {
+            runtime.process().unwrap();
+            let output = runtime.get_output(0).unwrap().as_type::<Float>().unwrap();
+            samples.extend(output.iter().map(|sample| sample.unwrap()));
+        }
+        let is_dense = runtime
+            .get_output(0)
+            .unwrap()
+            .as_type::<Float>()
+            .unwrap()
+            .is_dense();
+        (samples, is_dense)
+    }
+
+    #[test]
+    fn dense_signals_match_per_sample_processing() {
+        let (dense, output_is_dense) = render_chain(true);
+        let (sparse, output_is_sparse) = render_chain(false);
+        assert!(output_is_dense);
+        assert!(!output_is_sparse);
+        assert_eq!(dense, sparse);
+    }
+
+    #[test]
+    fn dense_math_holds_unconnected_inputs() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let sine = graph.add(SineOscillator::new(100.0));
+        let sub = graph.add(Sub::new(SignalType::Float));
+        sub.input(1).connect(sine.output(0));
+        sub.output(0).connect(&out.input(0));
+
+        let outputs = graph
+            .build_runtime()
+            .run_offline(std::time::Duration::from_millis(10), 1000.0, 4)
+            .unwrap();
+
+        // the unconnected `a` input is 0
+        for (n, sample) in outputs[0].iter().enumerate() {
+            let t = (n * 100 % 1000) as Float;
+            assert_eq!(*sample, -(t / 1000.0 * TAU).cos());
+        }
+    }
+}
@@ -1,8 +1,14 @@
 //! Built-in processors for MIDI messages.
 
-use crate::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
 
-/// A processor that extracts the note number from a MIDI message.
+use super::lerp;
+use crate::{
+    prelude::*,
+    smf::{MidiFile, MidiFileError},
+};
+
+/// A processor that extracts the note number from MIDI note on messages.
 ///
 /// # Inputs
 ///
@@ -14,7 +20,7 @@ use crate::prelude::*;
 ///
 /// | Index | Name | Type | Description |
 /// | --- | --- | --- | --- |
-/// | `0` | `note` | `Float` | The note number of the input MIDI message. |
+/// | `0` | `note` | `Float` | The note number of the last note on message. |
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MidiNote {
@@ -38,7 +44,7 @@ impl Processor for MidiNote {
     ) -> Result<(), ProcessorError> {
         for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [Float]) {
             if let Some(msg) = midi {
-                if msg.status() == 0x90 {
+                if msg.is_note_on() {
                     self.note = msg.data1() as Float;
                 }
             }
@@ -49,7 +55,7 @@ impl Processor for MidiNote {
     }
 }
 
-/// A processor that extracts the velocity from a MIDI message.
+/// A processor that extracts the velocity from MIDI note on messages.
 ///
 /// # Inputs
 ///
@@ -61,7 +67,7 @@ impl Processor for MidiNote {
 ///
 /// | Index | Name | Type | Description |
 /// | --- | --- | --- | --- |
-/// | `0` | `velocity` | `Float` | The velocity of the input MIDI message. |
+/// | `0` | `velocity` | `Float` | The velocity of the last note on message. |
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MidiVelocity {
@@ -85,8 +91,7 @@ impl Processor for MidiVelocity {
     ) -> Result<(), ProcessorError> {
         for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [Float]) {
             if let Some(msg) = midi {
-                // Note on, note off, and polyphonic aftertouch messages.
-                if [0x90, 0x80, 0xa8].contains(&msg.status()) {
+                if msg.is_note_on() {
                     self.velocity = msg.data2() as Float;
                 }
             }
@@ -97,7 +102,9 @@ impl Processor for MidiVelocity {
     }
 }
 
-/// A processor that outputs a gate signal from a MIDI note on/off message.
+/// A processor that outputs a gate signal from MIDI note on and note off messages.
+///
+/// The gate opens on a note on message, and closes when the most recently started note is released (by a note off message or a note on message with a velocity of 0), or on an "all notes off" or "all sound off" control change message.
 ///
 /// # Inputs
 ///
@@ -113,7 +120,8 @@ impl Processor for MidiVelocity {
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MidiGate {
-    gate: bool,
+    // the note that is currently held
+    note: Option<u8>,
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
@@ -133,14 +141,20 @@ impl Processor for MidiGate {
     ) -> Result<(), ProcessorError> {
         for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [bool]) {
             if let Some(msg) = midi {
-                if msg.status() == 0x90 {
-                    self.gate = msg.data2() > 0;
-                } else if msg.status() == 0x80 {
-                    self.gate = false;
+                match msg.kind() {
+                    MidiMessageKind::NoteOn { note, .. } => self.note = Some(note),
+                    MidiMessageKind::NoteOff { note, .. } if self.note == Some(note) => {
+                        self.note = None
+                    }
+                    MidiMessageKind::ControlChange {
+                        controller: 120 | 123,
+                        ..
+                    } => self.note = None,
+                    _ => {}
                 }
             }
 
-            *out = Some(self.gate);
+            *out = Some(self.note.is_some());
         }
         Ok(())
     }
@@ -181,7 +195,7 @@ impl Processor for MidiTrigger {
         for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [bool]) {
             *out = None;
             if let Some(msg) = midi {
-                if msg.status() == 0x90 && msg.data2() > 0 {
+                if msg.is_note_on() {
                     *out = Some(true);
                 }
             }
@@ -232,3 +246,1703 @@ impl Processor for MidiChannel {
         Ok(())
     }
 }
+
+/// A processor that passes on only the MIDI messages of one channel.
+///
+/// System messages (e.g. MIDI clock), which don't belong to a channel, are always passed on.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message, if it is on the channel. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiChannelFilter {
+    channel: u8,
+}
+
+impl MidiChannelFilter {
+    /// Creates a new `MidiChannelFilter` processor for the given channel (0 to 15).
+    pub fn new(channel: u8) -> Self {
+        Self {
+            channel: channel & 0x0F,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiChannelFilter {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [MidiMessage]) {
+            *out = midi.filter(|msg| on_channel(msg, Some(self.channel)));
+        }
+        Ok(())
+    }
+}
+
+// whether a message should be handled by a processor listening on the given channel (or all channels)
+fn on_channel(msg: &MidiMessage, channel: Option<u8>) -> bool {
+    !msg.is_channel_message() || channel.is_none_or(|channel| msg.channel() == channel)
+}
+
+// smooths a value decoded from MIDI messages, which would otherwise change in steps
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ControlSmoother {
+    // the time constant in seconds
+    time: Float,
+    factor: Float,
+    current: Float,
+    target: Float,
+}
+
+impl ControlSmoother {
+    fn new(value: Float) -> Self {
+        Self {
+            time: 0.0,
+            factor: 1.0,
+            current: value,
+            target: value,
+        }
+    }
+
+    fn allocate(&mut self, sample_rate: Float) {
+        self.factor = if self.time > 0.0 {
+            1.0 - (-1.0 / (self.time * sample_rate)).exp()
+        } else {
+            1.0
+        };
+    }
+
+    fn next(&mut self) -> Float {
+        self.current = lerp(self.current, self.target, self.factor);
+        self.current
+    }
+}
+
+/// A processor that outputs the value of a MIDI controller, from control change messages.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `value` | `Float` | The controller value, scaled to the range 0 to 1. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiControl {
+    controller: u8,
+    channel: Option<u8>,
+    value: ControlSmoother,
+}
+
+impl MidiControl {
+    /// Creates a new `MidiControl` processor for the given controller number (0 to 127), listening on all channels.
+    pub fn new(controller: u8) -> Self {
+        Self {
+            controller: controller & 0x7F,
+            channel: None,
+            value: ControlSmoother::new(0.0),
+        }
+    }
+
+    /// Only listens to messages on the given channel (0 to 15).
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel & 0x0F);
+        self
+    }
+
+    /// Sets the value output before the first message arrives (0 to 1).
+    pub fn with_initial_value(mut self, value: Float) -> Self {
+        self.value = ControlSmoother {
+            current: value,
+            target: value,
+            ..self.value
+        };
+        self
+    }
+
+    /// Smooths changes of the value with the given time constant in seconds.
+    pub fn with_smoothing(mut self, time: Float) -> Self {
+        self.value.time = time;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiControl {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("value", SignalType::Float)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.value.allocate(sample_rate);
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [Float]) {
+            if let Some(msg) = midi.filter(|msg| on_channel(msg, self.channel)) {
+                if let MidiMessageKind::ControlChange { controller, value } = msg.kind() {
+                    if controller == self.controller {
+                        self.value.target = value as Float / 127.0;
+                    }
+                }
+            }
+
+            *out = Some(self.value.next());
+        }
+        Ok(())
+    }
+}
+
+/// A processor that outputs the pitch bend amount from MIDI pitch bend messages, with their full 14-bit resolution.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `bend` | `Float` | The bend amount, from -1 to 1. |
+/// | `1` | `semitones` | `Float` | The bend amount in semitones, scaled by the bend range. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiPitchBend {
+    range: Float,
+    channel: Option<u8>,
+    bend: ControlSmoother,
+}
+
+impl MidiPitchBend {
+    /// Creates a new `MidiPitchBend` processor with a bend range of 2 semitones, listening on all channels.
+    pub fn new() -> Self {
+        Self {
+            range: 2.0,
+            channel: None,
+            bend: ControlSmoother::new(0.0),
+        }
+    }
+
+    /// Sets the bend range in semitones, i.e. the `semitones` output at full bend.
+    pub fn with_range(mut self, range: Float) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Only listens to messages on the given channel (0 to 15).
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel & 0x0F);
+        self
+    }
+
+    /// Smooths changes of the bend amount with the given time constant in seconds.
+    pub fn with_smoothing(mut self, time: Float) -> Self {
+        self.bend.time = time;
+        self
+    }
+}
+
+impl Default for MidiPitchBend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiPitchBend {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("bend", SignalType::Float),
+            SignalSpec::new("semitones", SignalType::Float),
+        ]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.bend.allocate(sample_rate);
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, bend_out, semitones_out) in iter_proc_io_as!(
+            inputs as [MidiMessage],
+            outputs as [Float, Float]
+        ) {
+            if let Some(msg) = midi.filter(|msg| on_channel(msg, self.channel)) {
+                if let MidiMessageKind::PitchBend { value } = msg.kind() {
+                    // 8192 is the center, so the upward range is one step shorter than the downward one
+                    let offset = value as Float - 8192.0;
+                    self.bend.target = if offset < 0.0 {
+                        offset / 8192.0
+                    } else {
+                        offset / 8191.0
+                    };
+                }
+            }
+
+            let bend = self.bend.next();
+            *bend_out = Some(bend);
+            *semitones_out = Some(bend * self.range);
+        }
+        Ok(())
+    }
+}
+
+/// A processor that outputs the pressure from MIDI channel pressure (aftertouch) messages.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `pressure` | `Float` | The pressure, scaled to the range 0 to 1. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiChannelPressure {
+    channel: Option<u8>,
+    pressure: ControlSmoother,
+}
+
+impl MidiChannelPressure {
+    /// Creates a new `MidiChannelPressure` processor, listening on all channels.
+    pub fn new() -> Self {
+        Self {
+            channel: None,
+            pressure: ControlSmoother::new(0.0),
+        }
+    }
+
+    /// Only listens to messages on the given channel (0 to 15).
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel & 0x0F);
+        self
+    }
+
+    /// Smooths changes of the pressure with the given time constant in seconds.
+    pub fn with_smoothing(mut self, time: Float) -> Self {
+        self.pressure.time = time;
+        self
+    }
+}
+
+impl Default for MidiChannelPressure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiChannelPressure {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("pressure", SignalType::Float)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.pressure.allocate(sample_rate);
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [Float]) {
+            if let Some(msg) = midi.filter(|msg| on_channel(msg, self.channel)) {
+                if let MidiMessageKind::ChannelPressure { pressure } = msg.kind() {
+                    self.pressure.target = pressure as Float / 127.0;
+                }
+            }
+
+            *out = Some(self.pressure.next());
+        }
+        Ok(())
+    }
+}
+
+/// A processor that outputs the pressure of the most recently played note, from MIDI polyphonic key pressure (aftertouch) messages.
+///
+/// The pressure is reset to 0 when a new note starts.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `pressure` | `Float` | The pressure, scaled to the range 0 to 1. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiPolyPressure {
+    channel: Option<u8>,
+    note: Option<u8>,
+    pressure: ControlSmoother,
+}
+
+impl MidiPolyPressure {
+    /// Creates a new `MidiPolyPressure` processor, listening on all channels.
+    pub fn new() -> Self {
+        Self {
+            channel: None,
+            note: None,
+            pressure: ControlSmoother::new(0.0),
+        }
+    }
+
+    /// Only listens to messages on the given channel (0 to 15).
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel & 0x0F);
+        self
+    }
+
+    /// Smooths changes of the pressure with the given time constant in seconds.
+    pub fn with_smoothing(mut self, time: Float) -> Self {
+        self.pressure.time = time;
+        self
+    }
+}
+
+impl Default for MidiPolyPressure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiPolyPressure {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("pressure", SignalType::Float)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.pressure.allocate(sample_rate);
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [Float]) {
+            if let Some(msg) = midi.filter(|msg| on_channel(msg, self.channel)) {
+                match msg.kind() {
+                    MidiMessageKind::NoteOn { note, .. } => {
+                        self.note = Some(note);
+                        self.pressure.target = 0.0;
+                    }
+                    MidiMessageKind::PolyPressure { note, pressure } if self.note == Some(note) => {
+                        self.pressure.target = pressure as Float / 127.0;
+                    }
+                    _ => {}
+                }
+            }
+
+            *out = Some(self.pressure.next());
+        }
+        Ok(())
+    }
+}
+
+/// A processor that outputs the program number from MIDI program change messages.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `program` | `Int` | The program number (0 to 127). |
+/// | `1` | `changed` | `Bool` | Whether a program change message was received on this sample. |
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiProgram {
+    channel: Option<u8>,
+    program: i64,
+}
+
+impl MidiProgram {
+    /// Creates a new `MidiProgram` processor, listening on all channels.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only listens to messages on the given channel (0 to 15).
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel & 0x0F);
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiProgram {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("program", SignalType::Int),
+            SignalSpec::new("changed", SignalType::Bool),
+        ]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, program_out, changed_out) in iter_proc_io_as!(
+            inputs as [MidiMessage],
+            outputs as [i64, bool]
+        ) {
+            *changed_out = None;
+            if let Some(msg) = midi.filter(|msg| on_channel(msg, self.channel)) {
+                if let MidiMessageKind::ProgramChange { program } = msg.kind() {
+                    self.program = program as i64;
+                    *changed_out = Some(true);
+                }
+            }
+
+            *program_out = Some(self.program);
+        }
+        Ok(())
+    }
+}
+
+/// The default number of bytes a [`SysExAssembler`] can hold.
+pub const SYSEX_CAPACITY: usize = 256;
+
+/// Reassembles SysEx messages from the packets that carry them in a MIDI signal (see [`MidiMessage`]), without allocating once it has been created.
+///
+/// Messages longer than the capacity are dropped. Other messages, such as MIDI clock, may be interleaved with the packets and are ignored.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SysExAssembler {
+    buffer: Vec<u8>,
+    capacity: usize,
+    // whether a message is being received
+    receiving: bool,
+    overflowed: bool,
+    // whether the buffer holds a complete message that was already returned
+    complete: bool,
+}
+
+impl SysExAssembler {
+    /// Creates a new `SysExAssembler` that can hold messages of up to the given number of bytes, including the `0xF0` and `0xF7` bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            receiving: false,
+            overflowed: false,
+            complete: false,
+        }
+    }
+
+    /// Returns the maximum length of a message in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Discards any partially received message, and makes sure the buffer is allocated.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.buffer
+            .reserve(self.capacity.saturating_sub(self.buffer.capacity()));
+        self.receiving = false;
+        self.overflowed = false;
+        self.complete = false;
+    }
+
+    /// Adds a packet, returning the complete message, from the `0xF0` byte to the `0xF7` byte, when its last packet arrives.
+    pub fn push(&mut self, message: &MidiMessage) -> Option<&[u8]> {
+        if self.complete {
+            self.buffer.clear();
+            self.complete = false;
+        }
+
+        if message.is_sysex_start() {
+            self.buffer.clear();
+            self.receiving = true;
+            self.overflowed = false;
+        } else if !message.is_sysex() || !self.receiving {
+            return None;
+        }
+
+        let bytes = message.as_bytes();
+        if self.buffer.len() + bytes.len() > self.capacity {
+            self.overflowed = true;
+        } else {
+            self.buffer.extend_from_slice(bytes);
+        }
+
+        if !message.is_sysex_end() {
+            return None;
+        }
+        self.receiving = false;
+        if self.overflowed {
+            self.buffer.clear();
+            return None;
+        }
+        self.complete = true;
+        Some(&self.buffer)
+    }
+}
+
+impl Default for SysExAssembler {
+    fn default() -> Self {
+        Self::new(SYSEX_CAPACITY)
+    }
+}
+
+/// A processor that passes on only the SysEx messages that start with the given bytes (e.g. a manufacturer ID), and no other messages.
+///
+/// Each message is passed on once it has been received completely, so it is delayed by its own length in packets.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The packets of the matching SysEx messages. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiSysExFilter {
+    prefix: Vec<u8>,
+    assembler: SysExAssembler,
+    // the packets waiting to be output
+    pending: Vec<MidiMessage>,
+    cursor: usize,
+}
+
+impl MidiSysExFilter {
+    /// Creates a new `MidiSysExFilter` processor for messages whose bytes after the `0xF0` byte start with the given prefix.
+    pub fn new(prefix: impl Into<Vec<u8>>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            assembler: SysExAssembler::default(),
+            pending: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Sets the maximum length of a message in bytes (default [`SYSEX_CAPACITY`]). Longer messages are dropped.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.assembler = SysExAssembler::new(capacity);
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiSysExFilter {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.assembler.reset();
+        // room for a few messages to queue up while the previous ones are output
+        self.pending = Vec::with_capacity(self.assembler.capacity().div_ceil(2) * 4);
+        self.cursor = 0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, out) in iter_proc_io_as!(inputs as [MidiMessage], outputs as [MidiMessage]) {
+            if self.cursor == self.pending.len() {
+                self.pending.clear();
+                self.cursor = 0;
+            }
+
+            if let Some(sysex) = midi.as_ref().and_then(|msg| self.assembler.push(msg)) {
+                if sysex[1..].starts_with(&self.prefix)
+                    && self.pending.len() + sysex.len().div_ceil(2) <= self.pending.capacity()
+                {
+                    self.pending.extend(MidiMessage::sysex_packets(sysex));
+                }
+            }
+
+            *out = self.pending.get(self.cursor).copied();
+            if out.is_some() {
+                self.cursor += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A processor that reads a value from SysEx messages that start with the given bytes, such as parameter changes sent by a synthesizer.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI message. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `value` | `Int` | The data byte that follows the prefix in the last matching message. |
+/// | `1` | `received` | `Bool` | Whether a matching message was completed on this sample. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiSysExValue {
+    prefix: Vec<u8>,
+    assembler: SysExAssembler,
+    value: i64,
+}
+
+impl MidiSysExValue {
+    /// Creates a new `MidiSysExValue` processor for messages whose bytes after the `0xF0` byte start with the given prefix.
+    pub fn new(prefix: impl Into<Vec<u8>>) -> Self {
+        let prefix = prefix.into();
+        Self {
+            assembler: SysExAssembler::new(SYSEX_CAPACITY.max(prefix.len() + 3)),
+            prefix,
+            value: 0,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiSysExValue {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("value", SignalType::Int),
+            SignalSpec::new("received", SignalType::Bool),
+        ]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.assembler.reset();
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (midi, value_out, received_out) in iter_proc_io_as!(
+            inputs as [MidiMessage],
+            outputs as [i64, bool]
+        ) {
+            *received_out = None;
+
+            if let Some(sysex) = midi.as_ref().and_then(|msg| self.assembler.push(msg)) {
+                let data = &sysex[1..];
+                if let (true, Some(&value)) = (
+                    data.starts_with(&self.prefix),
+                    data.get(self.prefix.len()).filter(|&&byte| byte < 0x80),
+                ) {
+                    self.value = value as i64;
+                    *received_out = Some(true);
+                }
+            }
+
+            *value_out = Some(self.value);
+        }
+        Ok(())
+    }
+}
+
+/// A processor that generates MIDI note on and note off messages from a gate signal.
+///
+/// A note on message is sent when the gate turns on, with the current note number and velocity, and a note off message for the same note when it turns off.
+/// Changes of the note number while the gate is on don't retrigger the note.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `gate` | `Bool` | The gate signal. |
+/// | `1` | `note` | `Float` | The note number (0 to 127). |
+/// | `2` | `velocity` | `Float` | The velocity (1 to 127). Defaults to 100. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The output MIDI message. |
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiNoteMessage {
+    channel: u8,
+    gate: bool,
+    // the note that is currently on
+    playing: u8,
+}
+
+impl MidiNoteMessage {
+    /// Creates a new `MidiNoteMessage` processor that sends on the given channel (0 to 15).
+    pub fn new(channel: u8) -> Self {
+        Self {
+            channel: channel & 0x0F,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiNoteMessage {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("gate", SignalType::Bool),
+            SignalSpec::new("note", SignalType::Float),
+            SignalSpec::new("velocity", SignalType::Float),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (gate, note, velocity, out) in iter_proc_io_as!(
+            inputs as [bool, Float, Float],
+            outputs as [MidiMessage]
+        ) {
+            *out = None;
+
+            let gate = gate.unwrap_or(self.gate);
+            if gate == self.gate {
+                continue;
+            }
+            self.gate = gate;
+
+            if gate {
+                self.playing = note.unwrap_or_default().round().clamp(0.0, 127.0) as u8;
+                let velocity = velocity.unwrap_or(100.0).round().clamp(1.0, 127.0) as u8;
+                *out = Some(MidiMessage::note_on(self.channel, self.playing, velocity));
+            } else {
+                *out = Some(MidiMessage::note_off(self.channel, self.playing, 0));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A processor that generates MIDI control change messages from a signal.
+///
+/// The input is scaled from the range 0 to 1 to the range 0 to 127, and a message is sent whenever the scaled value changes.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `value` | `Float` | The controller value (0 to 1). |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The output MIDI message. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiControlMessage {
+    channel: u8,
+    controller: u8,
+    last: Option<u8>,
+}
+
+impl MidiControlMessage {
+    /// Creates a new `MidiControlMessage` processor that sends the given controller number (0 to 119) on the given channel (0 to 15).
+    pub fn new(channel: u8, controller: u8) -> Self {
+        Self {
+            channel: channel & 0x0F,
+            controller: controller & 0x7F,
+            last: None,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiControlMessage {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("value", SignalType::Float)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (value, out) in iter_proc_io_as!(inputs as [Float], outputs as [MidiMessage]) {
+            *out = None;
+
+            let Some(value) = value else {
+                continue;
+            };
+            let value = (value * 127.0).round().clamp(0.0, 127.0) as u8;
+            if self.last != Some(value) {
+                self.last = Some(value);
+                *out = Some(MidiMessage::control_change(
+                    self.channel,
+                    self.controller,
+                    value,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The number of messages a [`MidiOut`] can queue before further messages are dropped.
+///
+/// [`Runtime::run_offline_with_midi`](crate::runtime::Runtime::run_offline_with_midi) receives the messages after every block, so this is the number of messages each output can send per block; the rest of the block's messages are silently dropped.
+pub const MIDI_OUT_CAPACITY: usize = 1024;
+
+/// A MIDI message sent out of a graph by a [`MidiOut`] processor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiOutEvent {
+    /// The number of samples processed by the [`MidiOut`] since it was allocated, when the message was sent.
+    pub time: u64,
+    /// The MIDI message.
+    pub message: MidiMessage,
+}
+
+/// The receiving end of a [`MidiOut`] processor.
+#[derive(Clone, Debug)]
+pub struct MidiOutRx {
+    rx: Receiver<MidiOutEvent>,
+}
+
+impl MidiOutRx {
+    /// Receives the next message sent by the processor, if any.
+    pub fn recv(&self) -> Option<MidiOutEvent> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Returns an iterator over the messages that have been sent by the processor and not yet received.
+    pub fn try_iter(&self) -> impl Iterator<Item = MidiOutEvent> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+/// A processor that sends MIDI messages out of the graph, e.g. to a MIDI output port or to an offline capture.
+///
+/// Usually created with [`Graph::add_midi_output`], which registers it as one of the graph's MIDI outputs.
+/// The messages are received from its [`MidiOutRx`]; if they aren't received, messages beyond [`MIDI_OUT_CAPACITY`] are silently dropped, keeping the oldest ones.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The MIDI messages to send. |
+///
+/// # Outputs
+///
+/// None.
+#[derive(Clone, Debug)]
+pub struct MidiOut {
+    name: String,
+    tx: Sender<MidiOutEvent>,
+    rx: MidiOutRx,
+    time: u64,
+}
+
+impl MidiOut {
+    /// Creates a new `MidiOut` processor with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        let (tx, rx) = crossbeam_channel::bounded(MIDI_OUT_CAPACITY);
+        Self {
+            name: name.into(),
+            tx,
+            rx: MidiOutRx { rx },
+            time: 0,
+        }
+    }
+
+    /// Returns the name of the MIDI output.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the receiver for the messages sent by the processor.
+    pub fn rx(&self) -> &MidiOutRx {
+        &self.rx
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiOut {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.time = 0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        _outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if inputs.input(0).is_some() {
+            for (i, message) in inputs.iter_input_as::<MidiMessage>(0)?.enumerate() {
+                if let Some(message) = message {
+                    let event = MidiOutEvent {
+                        time: self.time + i as u64,
+                        message: *message,
+                    };
+                    self.tx.try_send(event).ok();
+                }
+            }
+        }
+        self.time += inputs.mode.sample_range(inputs.block_size).len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MidiOut {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct MidiOutSer<'a> {
+            name: &'a str,
+        }
+
+        MidiOutSer { name: &self.name }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MidiOut {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct MidiOutDe {
+            name: String,
+        }
+
+        let de = MidiOutDe::deserialize(deserializer)?;
+        Ok(MidiOut::new(de.name))
+    }
+}
+
+/// A processor that plays the MIDI messages of a [`MidiFile`] with the timing of its tempo map.
+///
+/// Playback starts when the processor is allocated, and restarts from the beginning when `restart` is `true`.
+/// A MIDI signal carries at most one message per sample, so messages at the same time (e.g. the notes of a chord) are output on consecutive samples.
+/// When looping, messages that are still waiting for their sample at the end of the loop are output at the start of the next pass, before its own messages.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `restart` | `Bool` | Whether to restart playback from the beginning. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The MIDI messages of the file. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiFilePlayer {
+    // the messages and their times in seconds
+    events: Vec<(f64, MidiMessage)>,
+    // the times of the messages in samples, parallel to `events`
+    event_samples: Vec<u64>,
+    looping: bool,
+    // the length of the loop in seconds, or `None` to end it just after the last message
+    length: Option<f64>,
+
+    time: u64,
+    next: usize,
+    // messages of the previous pass of the loop that are still to be output
+    carry: std::ops::Range<usize>,
+}
+
+impl MidiFilePlayer {
+    /// Creates a new `MidiFilePlayer` processor that plays the given file.
+    pub fn new(file: &MidiFile) -> Self {
+        let events = file.timed_events();
+        Self {
+            event_samples: vec![0; events.len()],
+            events,
+            looping: false,
+            length: None,
+            time: 0,
+            next: 0,
+            carry: 0..0,
+        }
+    }
+
+    /// Creates a new `MidiFilePlayer` processor that plays the Standard MIDI File at the given path.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, MidiFileError> {
+        Ok(Self::new(&MidiFile::load(path)?))
+    }
+
+    /// Sets whether playback starts over after the last message.
+    ///
+    /// The loop ends just after the last message, unless its length is set with [`MidiFilePlayer::with_loop_length`].
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Sets the length of the loop in seconds, e.g. to a whole number of bars.
+    ///
+    /// The end of the loop is exclusive: messages at or after it are not played while looping.
+    pub fn with_loop_length(mut self, length: f64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Returns the time of the last message in seconds.
+    pub fn duration(&self) -> f64 {
+        self.events
+            .last()
+            .map(|(time, _)| *time)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MidiFilePlayer {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("restart", SignalType::Bool)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("midi", SignalType::Midi)]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        for ((time, _), sample) in self.events.iter().zip(&mut self.event_samples) {
+            *sample = (time * sample_rate as f64).round() as u64;
+        }
+        self.time = 0;
+        self.next = 0;
+        self.carry = 0..0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let loop_samples = match self.length {
+            Some(length) => ((length * inputs.sample_rate as f64).round() as u64).max(1),
+            None => self.event_samples.last().map_or(1, |&last| last + 1),
+        };
+        // the messages inside the loop
+        let loop_events = if self.looping {
+            self.event_samples
+                .partition_point(|&sample| sample < loop_samples)
+        } else {
+            self.events.len()
+        };
+
+        for (restart, out) in iter_proc_io_as!(inputs as [bool], outputs as [MidiMessage]) {
+            if restart.unwrap_or(false) {
+                self.time = 0;
+                self.next = 0;
+                self.carry = 0..0;
+            }
+            if self.looping && self.time >= loop_samples {
+                self.carry = self.next.min(loop_events)..loop_events;
+                self.time = 0;
+                self.next = 0;
+            }
+
+            *out = None;
+            if let Some(index) = self.carry.next() {
+                *out = Some(self.events[index].1);
+            } else if self.next < loop_events && self.event_samples[self.next] <= self.time {
+                *out = Some(self.events[self.next].1);
+                self.next += 1;
+            }
+
+            self.time += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtins::test_util::TestSource;
+
+    // MIDI files are written with 1920 ticks per second, so at this sample rate each tick is one sample
+    const SAMPLE_RATE: Float = 1920.0;
+
+    fn note_on(note: u8) -> MidiMessage {
+        MidiMessage::new([0x90, note, 100])
+    }
+
+    fn note_off(note: u8) -> MidiMessage {
+        MidiMessage::new([0x80, note, 0])
+    }
+
+    // plays the messages (with their times in samples) and returns the samples at which they are output
+    fn play(
+        events: &[(u64, MidiMessage)],
+        num_samples: u64,
+        f: impl FnOnce(MidiFilePlayer) -> MidiFilePlayer,
+    ) -> Vec<(u64, MidiMessage)> {
+        let file = MidiFile::from_events(
+            events
+                .iter()
+                .map(|&(sample, message)| (sample as f64 / SAMPLE_RATE as f64, message)),
+        );
+
+        let graph = GraphBuilder::new();
+        let player = graph.add(f(MidiFilePlayer::new(&file)));
+        let out = graph.add_midi_output("out");
+        player.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let duration = Duration::from_secs_f64(num_samples as f64 / SAMPLE_RATE as f64);
+        let (_, midi) = runtime
+            .run_offline_with_midi(duration, SAMPLE_RATE, 64)
+            .unwrap();
+        midi[0]
+            .iter()
+            .map(|event| (event.time, event.message))
+            .collect()
+    }
+
+    #[test]
+    fn plays_simultaneous_messages_on_consecutive_samples() {
+        let events = [
+            (10, note_on(60)),
+            (10, note_on(64)),
+            (10, note_on(67)),
+            (20, note_off(60)),
+        ];
+        let played = play(&events, 100, |player| player);
+        assert_eq!(
+            played,
+            [
+                (10, note_on(60)),
+                (11, note_on(64)),
+                (12, note_on(67)),
+                (20, note_off(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn loops_at_the_loop_length() {
+        let events = [(0, note_on(60)), (100, note_off(60)), (300, note_on(62))];
+        let played = play(&events, 600, |player| {
+            player
+                .with_looping(true)
+                .with_loop_length(200.0 / SAMPLE_RATE as f64)
+        });
+        // the message after the end of the loop is never played
+        assert_eq!(
+            played,
+            [
+                (0, note_on(60)),
+                (100, note_off(60)),
+                (200, note_on(60)),
+                (300, note_off(60)),
+                (400, note_on(60)),
+                (500, note_off(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_loop_includes_the_last_message() {
+        let events = [(0, note_on(60)), (100, note_off(60))];
+        let played = play(&events, 250, |player| player.with_looping(true));
+        assert_eq!(
+            played,
+            [
+                (0, note_on(60)),
+                (100, note_off(60)),
+                (101, note_on(60)),
+                (201, note_off(60)),
+                (202, note_on(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn carries_messages_over_the_loop_end() {
+        let events = [
+            (0, note_on(60)),
+            (99, note_off(60)),
+            (99, note_off(64)),
+            (99, note_off(67)),
+        ];
+        let played = play(&events, 150, |player| {
+            player
+                .with_looping(true)
+                .with_loop_length(100.0 / SAMPLE_RATE as f64)
+        });
+        // the chord at the end of the loop is finished before the next pass starts
+        assert_eq!(
+            played,
+            [
+                (0, note_on(60)),
+                (99, note_off(60)),
+                (100, note_off(64)),
+                (101, note_off(67)),
+                (102, note_on(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn captures_note_and_control_messages_with_their_sample_times() {
+        // a gate that is on for samples 10 to 19 and 40 to 49, the note 60 for the first and 62 for the second,
+        // and a controller value that rises by one step of 1/127 every 10 samples
+        let graph = GraphBuilder::new();
+        let gate = graph
+            .add(TestSource::repeat((0..64).map(|n| {
+                if (10..20).contains(&n) || (40..50).contains(&n) {
+                    1.0
+                } else {
+                    0.0
+                }
+            })))
+            .cast(SignalType::Bool);
+        let note = graph.add(TestSource::repeat((0..64).map(|n| {
+            if n >= 30 {
+                62.0
+            } else {
+                60.0
+            }
+        })));
+        let value = graph.add(TestSource::repeat(
+            (0..64).map(|n| (n / 10) as Float / 127.0),
+        ));
+        let notes = graph.add(MidiNoteMessage::new(1));
+        let controls = graph.add(MidiControlMessage::new(2, 7));
+        gate.output(0).connect(&notes.input(0));
+        note.output(0).connect(&notes.input(1));
+        value.output(0).connect(&controls.input(0));
+        let notes_out = graph.add_midi_output("notes");
+        let controls_out = graph.add_midi_output("controls");
+        notes.output(0).connect(&notes_out.input(0));
+        controls.output(0).connect(&controls_out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        // the block size doesn't divide the message times, so they are spread over the blocks at different offsets
+        let duration = Duration::from_secs_f64(64.0 / SAMPLE_RATE as f64);
+        let (_, midi) = runtime
+            .run_offline_with_midi(duration, SAMPLE_RATE, 7)
+            .unwrap();
+
+        let captured = |events: &[MidiOutEvent]| -> Vec<(u64, Vec<u8>)> {
+            events
+                .iter()
+                .map(|event| (event.time, event.message.as_bytes().to_vec()))
+                .collect()
+        };
+        assert_eq!(
+            captured(&midi[0]),
+            [
+                (10, vec![0x91, 60, 100]),
+                (20, vec![0x81, 60, 0]),
+                (40, vec![0x91, 62, 100]),
+                (50, vec![0x81, 62, 0]),
+            ]
+        );
+        assert_eq!(
+            captured(&midi[1]),
+            (0..7)
+                .map(|step| (step * 10, vec![0xB2, 7, step as u8]))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn messages_beyond_the_capacity_of_a_block_are_dropped() {
+        const BLOCK_SIZE: usize = MIDI_OUT_CAPACITY + 500;
+
+        let graph = GraphBuilder::new();
+        // 0 and 1 on alternate samples
+        let value = graph.add(TestSource::repeat([0.0, 1.0]));
+        let controls = graph.add(MidiControlMessage::new(0, 1));
+        value.output(0).connect(&controls.input(0));
+        let out = graph.add_midi_output("out");
+        controls.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let duration = Duration::from_secs_f64(2.0 * BLOCK_SIZE as f64 / SAMPLE_RATE as f64);
+        let (_, midi) = runtime
+            .run_offline_with_midi(duration, SAMPLE_RATE, BLOCK_SIZE)
+            .unwrap();
+
+        // a message is sent on every sample, and only the first MIDI_OUT_CAPACITY of each block are kept
+        let times: Vec<u64> = midi[0].iter().map(|event| event.time).collect();
+        let expected: Vec<u64> = (0..MIDI_OUT_CAPACITY as u64)
+            .chain(BLOCK_SIZE as u64..(BLOCK_SIZE + MIDI_OUT_CAPACITY) as u64)
+            .collect();
+        assert_eq!(times, expected);
+    }
+
+    // sends the events to the processor and renders its outputs, cast to `Float`
+    fn render(
+        processor: impl Processor,
+        events: &[MidiEvent],
+        num_samples: usize,
+    ) -> Vec<Vec<f32>> {
+        let graph = GraphBuilder::new();
+        let midi = graph.add_midi_input("midi");
+        let output_spec = processor.output_spec();
+        let node = graph.add(processor);
+        midi.output(0).connect(&node.input(0));
+        for (i, spec) in output_spec.iter().enumerate() {
+            let out = graph.add_audio_output();
+            if spec.signal_type == SignalType::Float {
+                node.output(i as u32).connect(&out.input(0));
+            } else {
+                node.output(i as u32)
+                    .cast(SignalType::Float)
+                    .output(0)
+                    .connect(&out.input(0));
+            }
+        }
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(SAMPLE_RATE, num_samples);
+        let mut outputs = vec![vec![0.0f32; num_samples]; output_spec.len()];
+        let mut slices: Vec<&mut [f32]> =
+            outputs.iter_mut().map(|output| &mut output[..]).collect();
+        runtime.process(&[], &mut slices, events).unwrap();
+        outputs
+    }
+
+    fn at(offset: usize, message: MidiMessage) -> MidiEvent {
+        MidiEvent::new(offset, message)
+    }
+
+    #[test]
+    fn pitch_bend_decodes_14_bit_values() {
+        let events = [
+            at(0, MidiMessage::pitch_bend(0, 0x2000)),
+            at(10, MidiMessage::pitch_bend(0, 0)),
+            at(20, MidiMessage::pitch_bend(0, 0x3FFF)),
+            at(30, MidiMessage::pitch_bend(0, 0x1000)),
+            // the low 7 bits count: one step up from the center
+            at(40, MidiMessage::pitch_bend(0, 0x2001)),
+        ];
+        let [bend, semitones] = &render(MidiPitchBend::new().with_range(12.0), &events, 50)[..]
+        else {
+            unreachable!()
+        };
+
+        assert_eq!(bend[5], 0.0);
+        assert_eq!(bend[15], -1.0);
+        assert_eq!(bend[25], 1.0);
+        assert_eq!(bend[35], -0.5);
+        assert_eq!(bend[45], 1.0 / 8191.0);
+        assert_eq!(semitones[15], -12.0);
+        assert_eq!(semitones[25], 12.0);
+        assert_eq!(semitones[35], -6.0);
+    }
+
+    #[test]
+    fn control_follows_its_controller() {
+        let events = [
+            at(0, MidiMessage::control_change(0, 7, 127)),
+            at(10, MidiMessage::control_change(0, 8, 0)),
+            at(20, MidiMessage::control_change(0, 7, 64)),
+        ];
+        let outputs = render(MidiControl::new(7).with_initial_value(0.5), &events, 30);
+
+        assert_eq!(outputs[0][0], 1.0);
+        assert_eq!(outputs[0][15], 1.0);
+        assert_eq!(outputs[0][25], 64.0 / 127.0);
+
+        // before the first message, the initial value is output
+        let outputs = render(
+            MidiControl::new(7).with_initial_value(0.5),
+            &events[1..],
+            30,
+        );
+        assert_eq!(outputs[0][15], 0.5);
+    }
+
+    #[test]
+    fn processors_listen_to_their_channel() {
+        let events = [
+            at(0, MidiMessage::control_change(1, 7, 127)),
+            at(10, MidiMessage::control_change(2, 7, 64)),
+            at(20, MidiMessage::pitch_bend(1, 0)),
+            at(30, MidiMessage::channel_pressure(1, 127)),
+            at(40, MidiMessage::program_change(1, 9)),
+        ];
+
+        let control = render(MidiControl::new(7).with_channel(2), &events, 50);
+        assert_eq!(control[0][5], 0.0);
+        assert_eq!(control[0][15], 64.0 / 127.0);
+
+        let bend = render(MidiPitchBend::new().with_channel(2), &events, 50);
+        assert_eq!(bend[0][25], 0.0);
+
+        let pressure = render(MidiChannelPressure::new().with_channel(2), &events, 50);
+        assert_eq!(pressure[0][35], 0.0);
+
+        let program = render(MidiProgram::new().with_channel(2), &events, 50);
+        assert_eq!(program[0][45], 0.0);
+
+        // without a channel, all channels are heard
+        let control = render(MidiControl::new(7), &events, 50);
+        assert_eq!(control[0][5], 1.0);
+        let program = render(MidiProgram::new(), &events, 50);
+        assert_eq!(program[0][45], 9.0);
+    }
+
+    #[test]
+    fn channel_filter_passes_its_channel_and_system_messages() {
+        let events = [
+            at(0, MidiMessage::note_on(0, 60, 100)),
+            at(1, MidiMessage::note_on(3, 62, 100)),
+            at(2, MidiMessage::new([0xF8, 0, 0])),
+            at(3, MidiMessage::control_change(5, 1, 1)),
+            at(4, MidiMessage::control_change(3, 1, 1)),
+        ];
+
+        let graph = GraphBuilder::new();
+        let midi = graph.add_midi_input("midi");
+        let filter = graph.add(MidiChannelFilter::new(3));
+        midi.output(0).connect(&filter.input(0));
+        let out = graph.add_midi_output("out");
+        filter.output(0).connect(&out.input(0));
+        // the block size is taken from the audio buffers
+        graph.add_audio_output();
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(SAMPLE_RATE, 10);
+        runtime
+            .process(&[], &mut [&mut [0.0; 10]], &events)
+            .unwrap();
+
+        let (_, rx) = runtime.runtime().graph().midi_output_iter().next().unwrap();
+        let passed: Vec<(u64, MidiMessage)> = rx
+            .try_iter()
+            .map(|event| (event.time, event.message))
+            .collect();
+        assert_eq!(
+            passed,
+            [
+                (1, MidiMessage::note_on(3, 62, 100)),
+                (2, MidiMessage::new([0xF8, 0, 0])),
+                (4, MidiMessage::control_change(3, 1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn channel_pressure_is_scaled() {
+        let events = [
+            at(0, MidiMessage::channel_pressure(0, 127)),
+            at(10, MidiMessage::channel_pressure(0, 32)),
+        ];
+        let outputs = render(MidiChannelPressure::new(), &events, 20);
+        assert_eq!(outputs[0][5], 1.0);
+        assert_eq!(outputs[0][15], 32.0 / 127.0);
+    }
+
+    #[test]
+    fn poly_pressure_follows_the_last_note() {
+        let events = [
+            at(0, MidiMessage::note_on(0, 60, 100)),
+            at(10, MidiMessage::poly_pressure(0, 60, 127)),
+            // pressure on a note other than the last one played is ignored
+            at(20, MidiMessage::poly_pressure(0, 62, 0)),
+            // a new note resets the pressure
+            at(30, MidiMessage::note_on(0, 62, 100)),
+            at(40, MidiMessage::poly_pressure(0, 62, 64)),
+        ];
+        let outputs = render(MidiPolyPressure::new(), &events, 50);
+        assert_eq!(outputs[0][5], 0.0);
+        assert_eq!(outputs[0][15], 1.0);
+        assert_eq!(outputs[0][25], 1.0);
+        assert_eq!(outputs[0][35], 0.0);
+        assert_eq!(outputs[0][45], 64.0 / 127.0);
+    }
+
+    #[test]
+    fn program_changes_are_flagged_once() {
+        let events = [at(10, MidiMessage::program_change(0, 5))];
+        let [program, changed] = &render(MidiProgram::new(), &events, 20)[..] else {
+            unreachable!()
+        };
+        assert!(program[..10].iter().all(|&program| program == 0.0));
+        assert!(program[10..].iter().all(|&program| program == 5.0));
+        let changes: Vec<usize> = (0..20).filter(|&n| changed[n] != 0.0).collect();
+        assert_eq!(changes, [10]);
+    }
+
+    #[test]
+    fn smoothing_converges_exponentially() {
+        // a time constant of 20 samples
+        let time = 20.0 / SAMPLE_RATE;
+        let events = [at(0, MidiMessage::control_change(0, 1, 127))];
+        let outputs = render(MidiControl::new(1).with_smoothing(time), &events, 200);
+        let value = &outputs[0];
+
+        for (n, &value) in value.iter().enumerate() {
+            let expected = 1.0 - (-(n as f32 + 1.0) / 20.0).exp();
+            assert!((value - expected).abs() < 1e-4, "{n}: {value}");
+        }
+        assert!(value.windows(2).all(|pair| pair[1] > pair[0]));
+        // within 0.01% of the target after 10 time constants
+        assert!(1.0 - value[199] < 1e-4);
+    }
+
+    // SysEx messages of 3n, 3n + 1 and 3n + 2 bytes, with zero data bytes
+    fn sysex_messages() -> Vec<Vec<u8>> {
+        (2..=9)
+            .map(|len| {
+                let mut message = vec![0xF0];
+                message.extend((0..len - 2).map(|i| i as u8 % 2));
+                message.push(0xF7);
+                message
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sysex_round_trips_through_packets() {
+        let mut assembler = SysExAssembler::default();
+        for message in sysex_messages() {
+            let packets: Vec<_> = MidiMessage::sysex_packets(&message).collect();
+            assert!(packets.iter().all(MidiMessage::is_sysex));
+            assert!(packets[0].is_sysex_start());
+            assert!(packets[1..].iter().all(|packet| !packet.is_sysex_start()));
+            assert!(packets.last().unwrap().is_sysex_end());
+            assert!(packets[..packets.len() - 1]
+                .iter()
+                .all(|packet| !packet.is_sysex_end()));
+
+            let (last, rest) = packets.split_last().unwrap();
+            for packet in rest {
+                assert_eq!(assembler.push(packet), None);
+            }
+            assert_eq!(assembler.push(last), Some(&message[..]));
+        }
+    }
+
+    #[test]
+    fn sysex_round_trips_through_midi_files() {
+        for message in sysex_messages() {
+            let packets: Vec<_> = MidiMessage::sysex_packets(&message).collect();
+            let file = MidiFile::from_events(packets.iter().map(|&packet| (0.5, packet)));
+            let timed: Vec<_> = file
+                .timed_events()
+                .into_iter()
+                .map(|(_, message)| message)
+                .collect();
+            assert_eq!(timed, packets);
+        }
+    }
+
+    #[test]
+    fn zero_messages_are_not_sysex() {
+        let zero = MidiMessage::new([0; 3]);
+        assert!(!zero.is_sysex());
+        assert_eq!(zero.kind(), MidiMessageKind::System);
+
+        // other messages interleaved with the packets are ignored
+        let mut assembler = SysExAssembler::default();
+        let message = [0xF0, 0x7D, 0x00, 0x00, 0xF7];
+        let mut packets = MidiMessage::sysex_packets(&message);
+        assert_eq!(assembler.push(&packets.next().unwrap()), None);
+        assert_eq!(assembler.push(&zero), None);
+        assert_eq!(assembler.push(&packets.next().unwrap()), None);
+        assert_eq!(assembler.push(&packets.next().unwrap()), Some(&message[..]));
+    }
+}
@@ -1,28 +1,47 @@
 //! Built-in processors and utilities for the audio graph.
 
+pub mod analysis;
 pub mod control;
+pub mod control_rate;
 pub mod dynamics;
 pub mod filters;
 pub mod list;
+pub mod loudness;
 pub mod math;
 pub mod midi;
 pub mod oscillators;
+pub mod oversample;
+pub mod poly;
+pub mod resampled;
 pub mod storage;
+pub mod tap;
 pub mod time;
+pub mod transport;
 pub mod util;
 
 #[cfg(feature = "fft")]
 pub mod simple_fft;
 
+#[cfg(test)]
+mod test_util;
+
+pub use analysis::*;
 pub use control::*;
+pub use control_rate::*;
 pub use dynamics::*;
 pub use filters::*;
 pub use list::*;
+pub use loudness::*;
 pub use math::*;
 pub use midi::*;
 pub use oscillators::*;
+pub use oversample::*;
+pub use poly::*;
+pub use resampled::*;
 pub use storage::*;
+pub use tap::*;
 pub use time::*;
+pub use transport::*;
 pub use util::*;
 
 #[cfg(feature = "fft")]
@@ -42,11 +61,12 @@ pub fn lerp(a: Float, b: Float, t: Float) -> Float {
 ///
 /// # Inputs
 ///
-/// The inputs of the sub-graph.
+/// The inputs of the sub-graph, with the names and signal types they were declared with (see [`Graph::add_input`]).
+/// Unconnected inputs are filled with `None`.
 ///
 /// # Outputs
 ///
-/// The outputs of the sub-graph.
+/// The outputs of the sub-graph, with the names and signal types they were declared with (see [`Graph::add_output`]).
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubGraph {
@@ -61,6 +81,9 @@ impl SubGraph {
         }
     }
 
+    /// Creates a new [`SubGraph`] processor by building its graph with the given closure.
+    ///
+    /// See also [`Module`] for reusable, parameterized sub-graphs.
     pub fn build<F>(f: F) -> Self
     where
         F: FnOnce(&GraphBuilder),
@@ -69,24 +92,22 @@ impl SubGraph {
         f(&builder);
         Self::new(builder.build())
     }
+
+    /// Returns a reference to the sub-graph.
+    #[inline]
+    pub fn graph(&self) -> &Graph {
+        self.rt.graph()
+    }
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
 impl Processor for SubGraph {
     fn input_spec(&self) -> Vec<SignalSpec> {
-        let mut spec = vec![];
-        for (i, _input) in self.rt.graph().input_indices().iter().enumerate() {
-            spec.push(SignalSpec::new(format!("{}", i), SignalType::Float));
-        }
-        spec
+        self.rt.graph().input_specs().to_vec()
     }
 
     fn output_spec(&self) -> Vec<SignalSpec> {
-        let mut spec = vec![];
-        for (i, _output) in self.rt.graph().output_indices().iter().enumerate() {
-            spec.push(SignalSpec::new(format!("{}", i), SignalType::Float));
-        }
-        spec
+        self.rt.graph().output_specs().to_vec()
     }
 
     fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
@@ -110,16 +131,26 @@ impl Processor for SubGraph {
         inputs: ProcessorInputs,
         mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        // in a feedback loop, only the current sub-block (or sample) is processed, so the sub-graph only runs for those samples
+        let range = inputs.mode.sample_range(inputs.block_size());
+
         for i in 0..self.num_inputs() {
-            let signal = inputs.input(i).ok_or(ProcessorError::NumInputsMismatch)?;
             let input = self
                 .rt
                 .get_input_mut(i)
                 .ok_or(ProcessorError::NumInputsMismatch)?;
-            input.clone_from(signal);
+            if let Some(signal) = inputs.input(i) {
+                for sample_index in range.clone() {
+                    input.set(sample_index, signal.get(sample_index).unwrap());
+                }
+            } else {
+                for sample_index in range.clone() {
+                    input.set_none(sample_index);
+                }
+            }
         }
 
-        match self.rt.process() {
+        match self.rt.process_with_mode(inputs.mode) {
             Ok(()) => {}
             Err(RuntimeError::GraphRunError(e)) => {
                 return Err(ProcessorError::SubGraph(Box::new(e)))
@@ -135,11 +166,210 @@ impl Processor for SubGraph {
                 .get_output(i)
                 .ok_or(ProcessorError::NumOutputsMismatch)?;
             let mut signal = outputs.output(i);
-            for i in 0..output.len() {
-                signal.set(i, output.get(i).unwrap());
+            for (j, sample_index) in range.clone().enumerate() {
+                signal.set(j, output.get(sample_index).unwrap());
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::ProcessMode;
+
+    // renders a decaying echo, with the feedback path optionally running through a sub-graph
+    fn render_echo(delay: usize, sub_graph: bool) -> Box<[Float]> {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+
+        let metro = graph.add(Metro::new(0.5));
+        let env = graph.add(DecayEnv::new(0.002));
+        env.input("trig").connect(metro.output(0));
+
+        let feedback = graph.feedback(delay);
+        let repeats = if sub_graph {
+            let node = graph.add(SubGraph::build(|graph| {
+                let input = graph.add_input("in", SignalType::Float);
+                let output = graph.add_output("out", SignalType::Float);
+                let scaled = input * 0.5;
+                scaled.output(0).connect(&output.input(0));
+            }));
+            node.input(0).connect(feedback.output(0));
+            node
+        } else {
+            feedback.clone() * 0.5
+        };
+
+        let mix = env + repeats;
+        feedback.input(0).connect(mix.output(0));
+        mix.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let mut outputs = runtime
+            .run_offline(Duration::from_millis(20), 1000.0, 7)
+            .unwrap();
+        std::mem::take(&mut outputs[0])
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn loads_sub_graphs_serialized_before_sleeping() {
+        // a sub-graph that doubles its input, as serialized before runtimes could put nodes to sleep
+        let json = r#"
+        {
+            "type": "SubGraph",
+            "rt": {
+                "graph": {
+                    "digraph": {
+                        "nodes": [
+                            {"processor": {"type": "Null"}, "input_spec": [], "output_spec": [{"name": "out", "signal_type": "Float"}]},
+                            {"processor": {"type": "Passthrough", "signal_type": "Float"}, "input_spec": [{"name": "in", "signal_type": "Float"}], "output_spec": [{"name": "out", "signal_type": "Float"}]},
+                            {"processor": {"type": "Constant", "value": {"Float": 2.0}}, "input_spec": [], "output_spec": [{"name": "out", "signal_type": "Float"}]},
+                            {"processor": {"type": "Mul", "a": {"Float": null}, "b": {"Float": null}}, "input_spec": [{"name": "a", "signal_type": "Float"}, {"name": "b", "signal_type": "Float"}], "output_spec": [{"name": "out", "signal_type": "Float"}]}
+                        ],
+                        "node_holes": [],
+                        "edge_property": "directed",
+                        "edges": [
+                            [0, 3, {"source_output": 0, "target_input": 0, "source_output_name": "out", "target_input_name": "a"}],
+                            [2, 3, {"source_output": 0, "target_input": 1, "source_output_name": "out", "target_input_name": "b"}],
+                            [3, 1, {"source_output": 0, "target_input": 0, "source_output_name": "out", "target_input_name": "in"}]
+                        ]
+                    },
+                    "assets": {},
+                    "params": {},
+                    "midi_params": [],
+                    "input_nodes": [0],
+                    "output_nodes": [1],
+                    "visit_path": [0, 2, 3, 1],
+                    "sccs": [[0], [2], [3], [1]]
+                },
+                "buffer_cache": {
+                    "0": {"input_spec": [], "output_spec": [{"name": "out", "signal_type": "Float"}], "outputs": [{"Float": {"buf": []}}]},
+                    "3": {"input_spec": [{"name": "a", "signal_type": "Float"}, {"name": "b", "signal_type": "Float"}], "output_spec": [{"name": "out", "signal_type": "Float"}], "outputs": [{"Float": {"buf": []}}]},
+                    "1": {"input_spec": [{"name": "in", "signal_type": "Float"}], "output_spec": [{"name": "out", "signal_type": "Float"}], "outputs": [{"Float": {"buf": []}}]},
+                    "2": {"input_spec": [], "output_spec": [{"name": "out", "signal_type": "Float"}], "outputs": [{"Float": {"buf": []}}]}
+                },
+                "sample_rate": 0.0,
+                "block_size": 0,
+                "max_block_size": 0
+            }
+        }
+        "#;
+        let sub_graph: Box<dyn Processor> = serde_json::from_str(json).unwrap();
+
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let param = graph.add_param(Param::new::<Float>("x", 1.0));
+        let node = graph.add(*sub_graph.downcast::<SubGraph>().ok().unwrap());
+        node.input(0).connect(param.output(0));
+        node.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        assert!(runtime.sleep_enabled());
+        runtime.allocate_for_block_size(1000.0, 8);
+        for _ in 0..3 {
+            runtime.process().unwrap();
+        }
+        assert_eq!(runtime.get_output(0).unwrap().get_copy_as(0), Some(2.0));
+    }
+
+    #[test]
+    fn sub_graph_wakes_when_input_changes() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let param = graph.add_param(Param::new::<Float>("x", 1.0));
+        let node = graph.add(SubGraph::build(|graph| {
+            let input = graph.add_input("in", SignalType::Float);
+            let output = graph.add_output("out", SignalType::Float);
+            let scaled = input * 2.0;
+            scaled.output(0).connect(&output.input(0));
+        }));
+        node.input(0).connect(param.output(0));
+        node.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        runtime.allocate_for_block_size(1000.0, 8);
+        for _ in 0..3 {
+            runtime.process().unwrap();
+        }
+        // the whole graph is idle
+        assert!(runtime.num_sleeping_nodes() > 0);
+        assert_eq!(runtime.get_output(0).unwrap().get_copy_as(0), Some(2.0));
+
+        runtime.param_named("x").unwrap().send(3.0);
+        runtime.process().unwrap();
+        assert_eq!(runtime.get_output(0).unwrap().get_copy_as(7), Some(6.0));
+    }
+
+    #[test]
+    fn sleeping_string_output_refills_when_block_grows() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_output("out", SignalType::String);
+        let text = graph.constant("hello".to_string());
+        text.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        runtime.allocate_for_block_size(1000.0, 8);
+        runtime.set_block_size(4).unwrap();
+        for _ in 0..3 {
+            runtime.process().unwrap();
+        }
+        assert!(runtime.num_sleeping_nodes() > 0);
+
+        // the new samples aren't held, so the nodes wake up to fill them
+        runtime.set_block_size(8).unwrap();
+        runtime.process().unwrap();
+        let output = runtime.get_output(0).unwrap();
+        for i in 0..8 {
+            assert_eq!(output.get_as::<String>(i), Some(&Some("hello".to_string())));
+        }
+    }
+
+    #[test]
+    fn processes_part_of_a_block_in_place() {
+        let graph = GraphBuilder::new();
+        let input = graph.add_input("in", SignalType::Float);
+        let out = graph.add_output("out", SignalType::Float);
+        let doubled = input * 2.0;
+        doubled.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        runtime.allocate_for_block_size(1000.0, 8);
+        for (i, sample) in runtime
+            .get_input_mut(0)
+            .unwrap()
+            .as_type_mut::<Float>()
+            .unwrap()
+            .iter_mut()
+            .enumerate()
+        {
+            *sample = Some(i as Float + 1.0);
+        }
+
+        runtime
+            .process_with_mode(ProcessMode::SubBlock(2, 3))
+            .unwrap();
+        assert_eq!(runtime.block_size(), 8);
+        let output = runtime.get_output(0).unwrap();
+        for i in 0..8 {
+            let expected = (2..5).contains(&i).then_some((i as Float + 1.0) * 2.0);
+            assert_eq!(output.get_copy_as::<Float>(i), expected, "sample {i}");
+        }
+
+        assert!(matches!(
+            runtime.process_with_mode(ProcessMode::SubBlock(6, 3)),
+            Err(RuntimeError::OutOfBlock(6, 9, 8))
+        ));
+    }
+
+    #[test]
+    fn sub_graph_in_feedback_loop() {
+        // a one-sample delay is processed sample by sample, a longer one in sub-blocks
+        for delay in [1, 3] {
+            assert_eq!(render_echo(delay, true), render_echo(delay, false));
+        }
+    }
+}
@@ -8,6 +8,57 @@ use crate::{
     signal::{PI, TAU},
 };
 
+/// Fills `out` with the values of a phase accumulator that starts at `t` and wraps around at the sample rate, and updates `t` past the last sample.
+///
+/// Each sample's increment is the corresponding sample of `frequency`, or `held_frequency` if that input is unconnected.
+#[inline]
+fn accumulate_phase(
+    t: &mut Float,
+    frequency: Option<&[Float]>,
+    held_frequency: Float,
+    sample_rate: Float,
+    out: &mut [Float],
+) {
+    match frequency {
+        Some(frequency) => {
+            for (out, frequency) in out.iter_mut().zip(frequency) {
+                *out = *t;
+                *t = (*t + frequency) % sample_rate;
+            }
+        }
+        None => {
+            for out in out.iter_mut() {
+                *out = *t;
+                *t = (*t + held_frequency) % sample_rate;
+            }
+        }
+    }
+}
+
+/// The dense `frequency` and `phase` inputs of an oscillator. Unconnected inputs are `None`.
+struct DenseOscillatorInputs<'a> {
+    frequency: Option<&'a [Float]>,
+    phase: Option<&'a [Float]>,
+}
+
+impl<'a> DenseOscillatorInputs<'a> {
+    /// Returns the inputs if both are dense or unconnected, and the `reset` input is unconnected.
+    #[inline]
+    fn new(inputs: &ProcessorInputs<'_, 'a>) -> Option<Self> {
+        if inputs.input(2).is_some() {
+            return None;
+        }
+        let frequency = inputs.dense_input(0);
+        let phase = inputs.dense_input(1);
+        if (frequency.is_none() && inputs.input(0).is_some())
+            || (phase.is_none() && inputs.input(1).is_some())
+        {
+            return None;
+        }
+        Some(Self { frequency, phase })
+    }
+}
+
 /// A processor that accumulates a phase value.
 ///
 /// The phase value will be incremented by the `increment` input signal each sample, and can be reset to 0 by the `reset` input signal.
@@ -108,6 +159,46 @@ impl SineOscillator {
             ..Default::default()
         }
     }
+
+    // generates the block as a contiguous slice if the frequency and phase inputs are dense or unconnected, and the reset input is unconnected
+    // returns `false` if the block has to be processed sample by sample instead
+    #[inline]
+    fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+        let Some(DenseOscillatorInputs { frequency, phase }) = DenseOscillatorInputs::new(inputs)
+        else {
+            return false;
+        };
+        let Some(out) = outputs.dense_output(0) else {
+            return false;
+        };
+
+        let sample_rate = inputs.sample_rate();
+        out.fill_dense(|out| {
+            accumulate_phase(&mut self.t, frequency, self.frequency, sample_rate, out);
+            match phase {
+                Some(phase) => {
+                    for (out, phase) in out.iter_mut().zip(phase) {
+                        *out = (*out / sample_rate * TAU + phase).cos();
+                    }
+                }
+                None => {
+                    for out in out.iter_mut() {
+                        *out = (*out / sample_rate * TAU + self.phase).cos();
+                    }
+                }
+            }
+        });
+
+        if let Some(frequency) = frequency.and_then(<[Float]>::last) {
+            self.frequency = *frequency;
+        }
+        if let Some(phase) = phase.and_then(<[Float]>::last) {
+            self.phase = *phase;
+        }
+        self.t_step = self.frequency;
+
+        true
+    }
 }
 
 impl Default for SineOscillator {
@@ -138,8 +229,12 @@ impl Processor for SineOscillator {
     fn process(
         &mut self,
         inputs: ProcessorInputs,
-        outputs: ProcessorOutputs,
+        mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if self.process_dense(&inputs, &mut outputs) {
+            return Ok(());
+        }
+
         for (frequency, phase, reset, out) in iter_proc_io_as!(
             inputs as [Float, Float, bool],
             outputs as [Float]
@@ -215,6 +310,46 @@ impl SawOscillator {
             ..Default::default()
         }
     }
+
+    // generates the block as a contiguous slice if the frequency and phase inputs are dense or unconnected, and the reset input is unconnected
+    // returns `false` if the block has to be processed sample by sample instead
+    #[inline]
+    fn process_dense(&mut self, inputs: &ProcessorInputs, outputs: &mut ProcessorOutputs) -> bool {
+        let Some(DenseOscillatorInputs { frequency, phase }) = DenseOscillatorInputs::new(inputs)
+        else {
+            return false;
+        };
+        let Some(out) = outputs.dense_output(0) else {
+            return false;
+        };
+
+        let sample_rate = inputs.sample_rate();
+        out.fill_dense(|out| {
+            accumulate_phase(&mut self.t, frequency, self.frequency, sample_rate, out);
+            match phase {
+                Some(phase) => {
+                    for (out, phase) in out.iter_mut().zip(phase) {
+                        *out = (*out / sample_rate + phase) % 1.0;
+                    }
+                }
+                None => {
+                    for out in out.iter_mut() {
+                        *out = (*out / sample_rate + self.phase) % 1.0;
+                    }
+                }
+            }
+        });
+
+        if let Some(frequency) = frequency.and_then(<[Float]>::last) {
+            self.frequency = *frequency;
+        }
+        if let Some(phase) = phase.and_then(<[Float]>::last) {
+            self.phase = *phase;
+        }
+        self.t_step = self.frequency;
+
+        true
+    }
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
@@ -234,8 +369,12 @@ impl Processor for SawOscillator {
     fn process(
         &mut self,
         inputs: ProcessorInputs,
-        outputs: ProcessorOutputs,
+        mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if self.process_dense(&inputs, &mut outputs) {
+            return Ok(());
+        }
+
         for (frequency, phase, reset, out) in iter_proc_io_as!(
             inputs as [Float, Float, bool],
             outputs as [Float]
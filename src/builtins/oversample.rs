@@ -0,0 +1,426 @@
+//! Oversampling of sub-graphs, for nonlinear processors that alias at the outer sample rate.
+
+use crate::{prelude::*, processor::ProcessMode, resample::bessel_i0, runtime::RuntimeError};
+
+/// The number of taps in each polyphase branch of the oversampling filters.
+const TAPS_PER_PHASE: usize = 48;
+
+/// The `beta` parameter of the Kaiser window used to design the oversampling filters (about 72 dB of stopband attenuation).
+const KAISER_BETA: f64 = 7.0;
+
+/// Designs a linear-phase lowpass FIR filter with a Kaiser-windowed sinc.
+///
+/// `cutoff` is given in cycles per sample (0.5 is the Nyquist frequency). The coefficients are normalized to unity gain at DC.
+pub(crate) fn kaiser_sinc(len: usize, cutoff: f64, beta: f64) -> Vec<f64> {
+    let center = (len - 1) as f64 / 2.0;
+    let norm = bessel_i0(beta);
+    let mut taps: Vec<f64> = (0..len)
+        .map(|i| {
+            let t = i as f64 - center;
+            let sinc = if t == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * std::f64::consts::PI * cutoff * t).sin() / (std::f64::consts::PI * t)
+            };
+            let r = if center > 0.0 { t / center } else { 0.0 };
+            let window = bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / norm;
+            sinc * window
+        })
+        .collect();
+
+    let sum: f64 = taps.iter().sum();
+    for tap in &mut taps {
+        *tap /= sum;
+    }
+    taps
+}
+
+/// A history of the most recent samples of a signal, newest first, stored twice so that it can always be read as one contiguous slice.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct History {
+    samples: Vec<Float>,
+    pos: usize,
+}
+
+impl History {
+    fn new(len: usize) -> Self {
+        Self {
+            samples: vec![0.0; len * 2],
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    #[inline]
+    fn push(&mut self, sample: Float) {
+        let len = self.len();
+        self.pos = (self.pos + len - 1) % len;
+        self.samples[self.pos] = sample;
+        self.samples[self.pos + len] = sample;
+    }
+
+    #[inline]
+    fn dot(&self, taps: &[Float]) -> Float {
+        let history = &self.samples[self.pos..self.pos + self.len()];
+        history.iter().zip(taps).map(|(x, h)| x * h).sum()
+    }
+}
+
+/// The state of the interpolator for one `Float` input.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Upsampler {
+    history: History,
+    // the last input sample, held while the input is `None`
+    last: Float,
+}
+
+/// A processor that runs a sub-graph at a multiple of the outer sample rate.
+///
+/// Nonlinear processors (e.g. [`Tanh`], [`MoogLadder`], or waveshapers built from math nodes) create harmonics above the Nyquist frequency, which alias back into the audible range.
+/// Running them at 2×, 4×, or 8× the sample rate and filtering the result before returning to the outer rate removes most of that aliasing.
+///
+/// The sub-graph's processors see the oversampled rate and block size in [`Processor::allocate`] and [`Processor::resize_buffers`].
+/// `Float` inputs are interpolated and the outputs decimated with polyphase windowed-sinc filters, which delay the signal by [`Oversample::latency`] samples.
+/// `Bool` and MIDI inputs are events (e.g. triggers and note messages), so they are only delivered on the first oversampled sample of each outer sample, and are `None` on the others.
+/// Inputs of other types are held for all the oversampled samples of each outer sample.
+///
+/// # Inputs
+///
+/// The inputs of the sub-graph, with the names and signal types they were declared with (see [`Graph::add_input`]).
+/// Unconnected inputs are filled with `None`.
+///
+/// # Outputs
+///
+/// The outputs of the sub-graph, which must all be `Float`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Oversample {
+    rt: Runtime,
+    factor: usize,
+
+    // the interpolation filter, split into `factor` branches of `TAPS_PER_PHASE` taps, scaled by `factor`
+    up_phases: Vec<Float>,
+    // the decimation filter, `factor * TAPS_PER_PHASE` taps long
+    down_taps: Vec<Float>,
+
+    upsamplers: Vec<Option<Upsampler>>,
+    downsamplers: Vec<History>,
+}
+
+impl Oversample {
+    /// Creates a new `Oversample` processor that runs the given graph at `factor` times the outer sample rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is not 2, 4, or 8, or if any output of the graph is not `Float`.
+    pub fn new(factor: usize, graph: Graph) -> Self {
+        assert!(
+            matches!(factor, 2 | 4 | 8),
+            "Oversampling factor must be 2, 4, or 8"
+        );
+        assert!(
+            graph
+                .output_specs()
+                .iter()
+                .all(|spec| spec.signal_type == SignalType::Float),
+            "Oversample outputs must be of type Float"
+        );
+
+        // the prototype filter is one tap shorter than a whole number of branches, so that its delay is a whole number of outer samples
+        let len = (TAPS_PER_PHASE - 1) * factor + 1;
+        let cutoff = 0.45 / factor as f64;
+        let mut prototype = kaiser_sinc(len, cutoff, KAISER_BETA);
+        prototype.resize(TAPS_PER_PHASE * factor, 0.0);
+
+        let mut up_phases = vec![0.0; TAPS_PER_PHASE * factor];
+        for phase in 0..factor {
+            for tap in 0..TAPS_PER_PHASE {
+                up_phases[phase * TAPS_PER_PHASE + tap] =
+                    (prototype[tap * factor + phase] * factor as f64) as Float;
+            }
+        }
+        let down_taps = prototype.iter().map(|&tap| tap as Float).collect();
+
+        let upsamplers = graph
+            .input_specs()
+            .iter()
+            .map(|spec| {
+                (spec.signal_type == SignalType::Float).then(|| Upsampler {
+                    history: History::new(TAPS_PER_PHASE),
+                    last: 0.0,
+                })
+            })
+            .collect();
+        let downsamplers = graph
+            .output_specs()
+            .iter()
+            .map(|_| History::new(TAPS_PER_PHASE * factor))
+            .collect();
+
+        Self {
+            rt: Runtime::new(graph),
+            factor,
+            up_phases,
+            down_taps,
+            upsamplers,
+            downsamplers,
+        }
+    }
+
+    /// Creates a new `Oversample` processor by building its graph with the given closure.
+    ///
+    /// # Panics
+    ///
+    /// See [`Oversample::new`].
+    pub fn build<F>(factor: usize, f: F) -> Self
+    where
+        F: FnOnce(&GraphBuilder),
+    {
+        let builder = GraphBuilder::new();
+        f(&builder);
+        Self::new(factor, builder.build())
+    }
+
+    /// Creates a new `Oversample` processor that runs a single processor, with the same inputs and outputs as the processor.
+    ///
+    /// # Panics
+    ///
+    /// See [`Oversample::new`].
+    pub fn wrap(factor: usize, processor: impl Processor) -> Self {
+        Self::build(factor, |graph| {
+            let input_spec = processor.input_spec();
+            let output_spec = processor.output_spec();
+            let node = graph.add(processor);
+            for (i, spec) in input_spec.iter().enumerate() {
+                let input = graph.add_input(&spec.name, spec.signal_type);
+                node.input(i as u32).connect(input.output(0));
+            }
+            for (i, spec) in output_spec.iter().enumerate() {
+                let output = graph.add_output(&spec.name, spec.signal_type);
+                node.output(i as u32).connect(&output.input(0));
+            }
+        })
+    }
+
+    /// Returns the oversampling factor.
+    #[inline]
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Returns the delay in samples (at the outer sample rate) added by the oversampling filters.
+    #[inline]
+    pub fn latency(&self) -> usize {
+        TAPS_PER_PHASE - 1
+    }
+
+    /// Returns a reference to the sub-graph.
+    #[inline]
+    pub fn graph(&self) -> &Graph {
+        self.rt.graph()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for Oversample {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        self.rt.graph().input_specs().to_vec()
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        self.rt.graph().output_specs().to_vec()
+    }
+
+    fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
+        self.rt.allocate_for_block_size(
+            sample_rate * self.factor as Float,
+            max_block_size * self.factor,
+        );
+    }
+
+    fn resize_buffers(&mut self, _sample_rate: Float, block_size: usize) {
+        self.rt.set_block_size(block_size * self.factor).unwrap();
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let factor = self.factor;
+        // in a feedback loop, only the current sub-block (or sample) is processed, so the sub-graph only runs for the oversampled samples of that range
+        let range = inputs.mode.sample_range(inputs.block_size);
+        let inner_mode = match inputs.mode {
+            ProcessMode::Block => ProcessMode::Block,
+            _ => ProcessMode::SubBlock(range.start * factor, range.len() * factor),
+        };
+
+        for (i, upsampler) in self.upsamplers.iter_mut().enumerate() {
+            let input = self
+                .rt
+                .get_input_mut(i)
+                .ok_or(ProcessorError::NumInputsMismatch)?;
+            let Some(signal) = inputs.input(i) else {
+                for sample_index in range.start * factor..range.end * factor {
+                    input.set_none(sample_index);
+                }
+                continue;
+            };
+
+            let Some(upsampler) = upsampler else {
+                // deliver events once, and hold other signals for the whole outer sample
+                let is_event = matches!(signal.signal_type(), SignalType::Bool | SignalType::Midi);
+                for sample_index in range.clone() {
+                    let value = signal.get(sample_index).unwrap();
+                    input.set(sample_index * factor, value);
+                    for phase in 1..factor {
+                        if is_event {
+                            input.set_none(sample_index * factor + phase);
+                        } else {
+                            input.set(sample_index * factor + phase, value);
+                        }
+                    }
+                }
+                continue;
+            };
+
+            let (Some(signal), Some(input)) =
+                (signal.as_type::<Float>(), input.as_type_mut::<Float>())
+            else {
+                return Err(ProcessorError::InputSpecMismatch {
+                    index: i,
+                    expected: SignalType::Float,
+                    actual: signal.signal_type(),
+                });
+            };
+            for sample_index in range.clone() {
+                if let Some(sample) = signal[sample_index] {
+                    upsampler.last = sample;
+                }
+                upsampler.history.push(upsampler.last);
+                for (phase, taps) in self.up_phases.chunks_exact(TAPS_PER_PHASE).enumerate() {
+                    input[sample_index * factor + phase] = Some(upsampler.history.dot(taps));
+                }
+            }
+        }
+
+        match self.rt.process_with_mode(inner_mode) {
+            Ok(()) => {}
+            Err(RuntimeError::GraphRunError(e)) => {
+                return Err(ProcessorError::SubGraph(Box::new(e)))
+            }
+            Err(_) => {
+                return Err(ProcessorError::Other);
+            }
+        }
+
+        for (i, downsampler) in self.downsamplers.iter_mut().enumerate() {
+            let Some(SignalBuffer::Float(output)) = self.rt.get_output(i) else {
+                return Err(ProcessorError::NumOutputsMismatch);
+            };
+
+            for (out, samples) in outputs
+                .iter_output_mut_as::<Float>(i)?
+                .zip(output[range.start * factor..range.end * factor].chunks_exact(factor))
+            {
+                // only the first of each group of oversampled samples is kept, so the filter only needs to run once per outer sample
+                downsampler.push(samples[0].unwrap_or_default());
+                *out = Some(downsampler.dot(&self.down_taps));
+                for sample in &samples[1..] {
+                    downsampler.push(sample.unwrap_or_default());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // renders the output of the node built by the closure
+    fn render(sample_rate: Float, f: impl FnOnce(&GraphBuilder) -> Node) -> Box<[Float]> {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        f(&graph).output(0).connect(&out.input(0));
+        let mut runtime = graph.build_runtime();
+        let mut outputs = runtime
+            .run_offline(Duration::from_millis(100), sample_rate, 64)
+            .unwrap();
+        std::mem::take(&mut outputs[0])
+    }
+
+    #[test]
+    fn passes_low_frequencies_with_latency() {
+        for factor in [2, 4, 8] {
+            let graph = GraphBuilder::new();
+            let sine = graph.add(SineOscillator::new(1000.0));
+            let oversample = Oversample::wrap(factor, Passthrough::new(SignalType::Float));
+            let latency = oversample.latency();
+
+            let node = graph.add(oversample);
+            node.input(0).connect(sine.output(0));
+            let out = graph.add_audio_output();
+            node.output(0).connect(&out.input(0));
+            let dry = graph.add_audio_output();
+            sine.output(0).connect(&dry.input(0));
+
+            let mut runtime = graph.build_runtime();
+            let outputs = runtime
+                .run_offline(Duration::from_millis(100), 48_000.0, 64)
+                .unwrap();
+            // skip the ringing of the filters at the onset of the sine
+            let wet = &outputs[0][latency + TAPS_PER_PHASE..];
+            let dry = &outputs[1][TAPS_PER_PHASE..];
+            for (wet, dry) in wet.iter().zip(dry) {
+                assert!((wet - dry).abs() < 1e-3, "factor {factor}: {wet} != {dry}");
+            }
+        }
+    }
+
+    #[test]
+    fn removes_frequencies_above_the_outer_nyquist() {
+        // a 30 kHz sine is representable at 4 * 48 kHz, but would alias to 18 kHz at 48 kHz
+        let oversample = Oversample::build(4, |graph| {
+            let sine = graph.add(SineOscillator::new(30_000.0));
+            let out = graph.add_output("out", SignalType::Float);
+            sine.output(0).connect(&out.input(0));
+        });
+        let output = render(48_000.0, |graph| graph.add(oversample));
+        let peak = output[TAPS_PER_PHASE..]
+            .iter()
+            .fold(0.0 as Float, |peak, sample| peak.max(sample.abs()));
+        assert!(peak < 1e-3, "peak {peak}");
+    }
+
+    #[test]
+    fn delivers_events_once() {
+        let output = render(1000.0, |graph| {
+            let metro = graph.add(Metro::new(1.0));
+            // counts the triggers delivered to the oversampled graph
+            let node = graph.add(Oversample::build(4, |graph| {
+                let trig = graph.add_input("trig", SignalType::Bool);
+                let count = graph.add_output("count", SignalType::Float);
+                let counter = graph.add(Counter::default());
+                let cast = graph.add(Cast::new(SignalType::Int, SignalType::Float));
+                counter.input(0).connect(trig.output(0));
+                cast.input(0).connect(counter.output(0));
+                cast.output(0).connect(&count.input(0));
+            }));
+            node.input(0).connect(metro.output(0));
+            node
+        });
+
+        // the count is delayed and smoothed by the filters, but settles at the number of triggers
+        let last = *output.last().unwrap();
+        assert!((last - 1.0).abs() < 1e-3, "count {last}");
+    }
+}
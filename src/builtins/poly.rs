@@ -0,0 +1,992 @@
+//! Polyphonic voice allocation for MIDI instruments.
+
+use crate::{
+    builder::module::{Module, ModuleArgs, ModuleError},
+    prelude::*,
+    runtime::RuntimeError,
+};
+
+/// How a [`Poly`] processor chooses which voice to reuse for a new note when no voice is idle.
+///
+/// Voices whose note has been released are always preferred over voices whose note is still held.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VoiceStealing {
+    /// Steal the voice whose note started the longest time ago.
+    #[default]
+    Oldest,
+    /// Steal the voice with the lowest output level in the last block.
+    Quietest,
+    /// Retrigger the voice already playing the same note, even if another voice is idle.
+    /// If no voice is playing the note, steal the oldest voice.
+    SameNote,
+}
+
+/// An MPE (MIDI Polyphonic Expression) zone, as used by controllers that give each note its own pitch bend, pressure and timbre.
+///
+/// A zone has a manager channel, whose messages apply to every note in the zone, and a number of member channels.
+/// The controller plays each note on its own member channel, so that channel-wide messages like pitch bend only affect that note.
+///
+/// See [`Poly::with_mpe`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MpeZone {
+    manager_channel: u8,
+    num_member_channels: u8,
+    member_bend_range: Float,
+    manager_bend_range: Float,
+}
+
+impl MpeZone {
+    /// Creates a lower zone, with channel 0 (MIDI channel 1) as the manager channel, followed by the given number of member channels (1 to 15).
+    pub fn lower(num_member_channels: u8) -> Self {
+        Self {
+            manager_channel: 0,
+            num_member_channels: num_member_channels.clamp(1, 15),
+            member_bend_range: 48.0,
+            manager_bend_range: 2.0,
+        }
+    }
+
+    /// Creates an upper zone, with channel 15 (MIDI channel 16) as the manager channel, preceded by the given number of member channels (1 to 15).
+    pub fn upper(num_member_channels: u8) -> Self {
+        Self {
+            manager_channel: 15,
+            ..Self::lower(num_member_channels)
+        }
+    }
+
+    /// Sets the pitch bend range of the member channels in semitones (default 48).
+    pub fn with_member_bend_range(mut self, range: Float) -> Self {
+        self.member_bend_range = range;
+        self
+    }
+
+    /// Sets the pitch bend range of the manager channel in semitones (default 2).
+    pub fn with_manager_bend_range(mut self, range: Float) -> Self {
+        self.manager_bend_range = range;
+        self
+    }
+
+    /// Returns the manager channel.
+    pub fn manager_channel(&self) -> u8 {
+        self.manager_channel
+    }
+
+    /// Returns the number of member channels.
+    pub fn num_member_channels(&self) -> u8 {
+        self.num_member_channels
+    }
+
+    /// Returns `true` if the channel is one of the zone's member channels.
+    pub fn is_member_channel(&self, channel: u8) -> bool {
+        if self.manager_channel == 0 {
+            (1..=self.num_member_channels).contains(&channel)
+        } else {
+            (15 - self.num_member_channels..15).contains(&channel)
+        }
+    }
+
+    /// Returns `true` if the channel is the zone's manager channel or one of its member channels.
+    pub fn contains(&self, channel: u8) -> bool {
+        channel == self.manager_channel || self.is_member_channel(channel)
+    }
+}
+
+// the expression state of a MIDI channel
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ChannelState {
+    // from -1 to 1
+    bend: Float,
+    // in semitones
+    bend_range: Float,
+    pressure: Float,
+    timbre: Float,
+    // the registered parameter selected for data entry
+    rpn: Option<u16>,
+}
+
+impl ChannelState {
+    fn new(bend_range: Float) -> Self {
+        Self {
+            bend: 0.0,
+            bend_range,
+            pressure: 0.0,
+            timbre: 0.5,
+            rpn: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Voice {
+    rt: Runtime,
+    note: Option<u8>,
+    // the channel the voice's current note was played on
+    channel: u8,
+    velocity: u8,
+    pressure: Float,
+    gate: bool,
+    // whether the gate should be lowered for one sample before the new note starts
+    retrigger: bool,
+    // the order in which the voice's current note started
+    started: u64,
+    // the peak output level of the last processed block
+    level: Float,
+    // whether the voice is processed at all
+    active: bool,
+}
+
+/// A processor that plays MIDI notes on a number of copies of a voice graph.
+///
+/// Each voice graph can declare the following inputs (see [`Graph::add_input`]), which are set per voice:
+///
+/// | Name | Type | Description |
+/// | --- | --- | --- |
+/// | `note` | `Float` | The MIDI note number of the voice's current note. |
+/// | `velocity` | `Float` | The velocity (0-127) of the voice's current note. |
+/// | `gate` | `Bool` | Whether the voice's current note is held. |
+/// | `bend` | `Float` | The pitch bend of the voice's current note in semitones. |
+/// | `pressure` | `Float` | The pressure (aftertouch) of the voice's current note, from 0 to 1. |
+/// | `timbre` | `Float` | The timbre of the voice's current note (controller 74), from 0 to 1, starting at 0.5. |
+/// | `midi` | `Midi` | The MIDI messages for the voice's current note, for use with processors like [`MidiPitchBend`] or [`MidiControl`]. |
+///
+/// Any other inputs of the voice graph become inputs of this processor, shared by all voices.
+/// The `Float` outputs of all voices are summed.
+///
+/// Note-on messages are assigned to an idle voice if there is one, otherwise a voice is stolen according to the [`VoiceStealing`] mode.
+/// Pitch bend, channel pressure and controller messages apply to the voices playing notes on the same channel, and polyphonic key pressure messages to the voice playing the same note.
+/// With an [MPE zone](Poly::with_mpe), each note is played on its own channel, so this gives every voice its own expression.
+///
+/// The `bend`, `pressure` and `timbre` inputs follow the state of the voice's channel, so they always reflect every message received.
+/// The `midi` input only receives the messages that arrive while the voice is playing a note on their channel, on the sample they arrive on.
+/// MPE controllers send the initial pitch bend, pressure and timbre of a note just before its note on message, before a voice has been assigned to it,
+/// so those messages never reach the `midi` input; use the dedicated inputs for expression and the `midi` input for other messages.
+/// A voice becomes idle, and is no longer processed, once its note has been released and its output has decayed below the silence threshold.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `midi` | `Midi` | The input MIDI messages. |
+/// | `1..` | | | The shared inputs of the voice graph. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0..` | | `Float` | The summed outputs of the voices. |
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Poly {
+    voices: Vec<Voice>,
+    stealing: VoiceStealing,
+    silence_threshold: Float,
+    note_count: u64,
+    mpe: Option<MpeZone>,
+    bend_range: Float,
+    channels: [ChannelState; 16],
+
+    // indices of the voice graph's per-voice inputs
+    note_input: Option<usize>,
+    velocity_input: Option<usize>,
+    gate_input: Option<usize>,
+    bend_input: Option<usize>,
+    pressure_input: Option<usize>,
+    timbre_input: Option<usize>,
+    midi_input: Option<usize>,
+    // indices of the voice graph's shared inputs
+    shared_inputs: Vec<usize>,
+
+    input_spec: Vec<SignalSpec>,
+    output_spec: Vec<SignalSpec>,
+}
+
+impl Poly {
+    /// Creates a new `Poly` processor from the given voice graphs, one per voice.
+    ///
+    /// Each voice should be built separately rather than cloned from the same graph, so that their [`Param`]s are independent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `voices` is empty, if the voice graphs don't all have the same inputs and outputs, if a per-voice input has the wrong type, or if an output is not `Float`.
+    pub fn new(voices: Vec<Graph>) -> Self {
+        assert!(!voices.is_empty(), "Poly requires at least one voice");
+
+        let voice_inputs = voices[0].input_specs().to_vec();
+        let voice_outputs = voices[0].output_specs().to_vec();
+        for voice in &voices {
+            assert!(
+                voice.input_specs().len() == voice_inputs.len()
+                    && voice.output_specs().len() == voice_outputs.len(),
+                "Poly voices must all have the same inputs and outputs"
+            );
+        }
+
+        let find_input = |name: &str, signal_type: SignalType| {
+            let index = voice_inputs.iter().position(|spec| spec.name == name)?;
+            assert_eq!(
+                voice_inputs[index].signal_type, signal_type,
+                "Poly voice input `{}` must be of type {:?}",
+                name, signal_type
+            );
+            Some(index)
+        };
+        let note_input = find_input("note", SignalType::Float);
+        let velocity_input = find_input("velocity", SignalType::Float);
+        let gate_input = find_input("gate", SignalType::Bool);
+        let bend_input = find_input("bend", SignalType::Float);
+        let pressure_input = find_input("pressure", SignalType::Float);
+        let timbre_input = find_input("timbre", SignalType::Float);
+        let midi_input = find_input("midi", SignalType::Midi);
+
+        let per_voice_inputs = [
+            note_input,
+            velocity_input,
+            gate_input,
+            bend_input,
+            pressure_input,
+            timbre_input,
+            midi_input,
+        ];
+        let shared_inputs: Vec<usize> = (0..voice_inputs.len())
+            .filter(|i| !per_voice_inputs.contains(&Some(*i)))
+            .collect();
+
+        let mut input_spec = vec![SignalSpec::new("midi", SignalType::Midi)];
+        input_spec.extend(shared_inputs.iter().map(|&i| voice_inputs[i].clone()));
+
+        for spec in &voice_outputs {
+            assert_eq!(
+                spec.signal_type,
+                SignalType::Float,
+                "Poly voice output `{}` must be of type Float",
+                spec.name
+            );
+        }
+
+        let voices = voices
+            .into_iter()
+            .map(|graph| Voice {
+                rt: Runtime::new(graph),
+                note: None,
+                channel: 0,
+                velocity: 0,
+                pressure: 0.0,
+                gate: false,
+                retrigger: false,
+                started: 0,
+                level: 0.0,
+                active: false,
+            })
+            .collect();
+
+        Self {
+            voices,
+            stealing: VoiceStealing::default(),
+            silence_threshold: 1e-4,
+            note_count: 0,
+            mpe: None,
+            bend_range: 2.0,
+            channels: [ChannelState::new(2.0); 16],
+            note_input,
+            velocity_input,
+            gate_input,
+            bend_input,
+            pressure_input,
+            timbre_input,
+            midi_input,
+            shared_inputs,
+            input_spec,
+            output_spec: voice_outputs,
+        }
+    }
+
+    /// Creates a new `Poly` processor with the given number of voices, building each voice's graph with the given closure.
+    pub fn build<F>(num_voices: usize, f: F) -> Self
+    where
+        F: Fn(&GraphBuilder),
+    {
+        let voices = (0..num_voices)
+            .map(|_| {
+                let builder = GraphBuilder::new();
+                f(&builder);
+                builder.build()
+            })
+            .collect();
+        Self::new(voices)
+    }
+
+    /// Creates a new `Poly` processor with the given number of voices, each an instance of the given [`Module`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an argument does not match a declared parameter of the module.
+    pub fn from_module(
+        module: &Module,
+        args: &ModuleArgs,
+        num_voices: usize,
+    ) -> Result<Self, ModuleError> {
+        let voices = (0..num_voices)
+            .map(|_| Ok(module.instantiate(args)?.graph().clone()))
+            .collect::<Result<_, ModuleError>>()?;
+        Ok(Self::new(voices))
+    }
+
+    /// Sets the voice stealing mode.
+    pub fn with_stealing(mut self, stealing: VoiceStealing) -> Self {
+        self.stealing = stealing;
+        self
+    }
+
+    /// Sets the output level below which a released voice is considered silent and stops being processed.
+    pub fn with_silence_threshold(mut self, silence_threshold: Float) -> Self {
+        self.silence_threshold = silence_threshold;
+        self
+    }
+
+    /// Sets the pitch bend range in semitones (default 2), for notes that are not played in an MPE zone.
+    pub fn with_pitch_bend_range(mut self, range: Float) -> Self {
+        self.bend_range = range;
+        self.reset_channels();
+        self
+    }
+
+    /// Plays notes from the given MPE zone, giving each note the pitch bend, pressure and timbre of its member channel.
+    ///
+    /// Pitch bend on the manager channel bends every note in the zone, and messages on channels outside the zone are ignored.
+    ///
+    /// An MPE configuration message (registered parameter 6 on channel 0 or 15) reconfigures the zone while playing, or turns MPE on if it wasn't already, and a pitch bend sensitivity message (registered parameter 0) changes the bend range of the manager channel or of all member channels.
+    pub fn with_mpe(mut self, zone: MpeZone) -> Self {
+        self.mpe = Some(zone);
+        self.reset_channels();
+        self
+    }
+
+    /// Returns the current MPE zone, if any.
+    pub fn mpe_zone(&self) -> Option<&MpeZone> {
+        self.mpe.as_ref()
+    }
+
+    /// Returns the number of voices.
+    pub fn num_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Returns the number of voices that are currently being processed.
+    pub fn num_active_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.active).count()
+    }
+
+    fn reset_channels(&mut self) {
+        self.channels = [ChannelState::new(self.bend_range); 16];
+        if let Some(zone) = &self.mpe {
+            for (channel, state) in self.channels.iter_mut().enumerate() {
+                if channel as u8 == zone.manager_channel {
+                    state.bend_range = zone.manager_bend_range;
+                } else if zone.is_member_channel(channel as u8) {
+                    state.bend_range = zone.member_bend_range;
+                }
+            }
+        }
+    }
+
+    fn is_manager_channel(&self, channel: u8) -> bool {
+        self.mpe.is_some_and(|zone| zone.manager_channel == channel)
+    }
+
+    fn note_on(&mut self, channel: u8, note: u8, velocity: u8) -> usize {
+        let same_note = if self.stealing == VoiceStealing::SameNote {
+            self.voices.iter().position(|voice| {
+                voice.active && voice.note == Some(note) && voice.channel == channel
+            })
+        } else {
+            None
+        };
+
+        let index = same_note
+            .or_else(|| {
+                // prefer the idle voice that has been idle the longest
+                (0..self.voices.len())
+                    .filter(|&i| !self.voices[i].active)
+                    .min_by_key(|&i| self.voices[i].started)
+            })
+            .unwrap_or_else(|| self.steal());
+
+        self.note_count += 1;
+
+        let voice = &mut self.voices[index];
+        voice.retrigger = voice.active && voice.gate;
+        voice.note = Some(note);
+        voice.channel = channel;
+        voice.velocity = velocity;
+        // MPE controllers send the initial expression of a note before the note on message
+        voice.pressure = self.channels[channel as usize].pressure;
+        voice.gate = true;
+        voice.started = self.note_count;
+        voice.active = true;
+
+        index
+    }
+
+    /// Handles a channel message, sending it to the `midi` inputs of the voices it applies to at the given sample.
+    fn handle_message(&mut self, msg: &MidiMessage, sample_index: usize) {
+        let channel = msg.channel();
+        let is_manager = self.is_manager_channel(channel);
+        let state = &mut self.channels[channel as usize];
+
+        match msg.kind() {
+            MidiMessageKind::NoteOn { note, velocity } => {
+                let index = self.note_on(channel, note, velocity);
+                self.send_to_voices(msg, sample_index, |i, _| i == index);
+                return;
+            }
+            MidiMessageKind::NoteOff { note, .. } => {
+                let held = |voice: &Voice| {
+                    voice.gate && voice.note == Some(note) && voice.channel == channel
+                };
+                self.send_to_voices(msg, sample_index, |_, voice| held(voice));
+                for voice in &mut self.voices {
+                    if held(voice) {
+                        voice.gate = false;
+                    }
+                }
+                return;
+            }
+            MidiMessageKind::PolyPressure { note, pressure } => {
+                for voice in &mut self.voices {
+                    if voice.note == Some(note) && voice.channel == channel {
+                        voice.pressure = pressure as Float / 127.0;
+                    }
+                }
+                self.send_to_voices(msg, sample_index, |_, voice| {
+                    voice.note == Some(note) && voice.channel == channel
+                });
+                return;
+            }
+            MidiMessageKind::ChannelPressure { pressure } => {
+                state.pressure = pressure as Float / 127.0;
+                for voice in &mut self.voices {
+                    if voice.channel == channel {
+                        voice.pressure = state.pressure;
+                    }
+                }
+            }
+            MidiMessageKind::PitchBend { value } => {
+                let offset = value as Float - 8192.0;
+                state.bend = if offset < 0.0 {
+                    offset / 8192.0
+                } else {
+                    offset / 8191.0
+                };
+            }
+            MidiMessageKind::ControlChange { controller, value } => match controller {
+                74 => state.timbre = value as Float / 127.0,
+                // registered parameter number
+                101 => state.rpn = Some(((value as u16) << 7) | (state.rpn.unwrap_or(0) & 0x7F)),
+                100 => state.rpn = Some((state.rpn.unwrap_or(0) & !0x7F) | value as u16),
+                // data entry
+                6 | 38 => {
+                    let rpn = state.rpn;
+                    self.data_entry(channel, rpn, controller == 6, value);
+                }
+                // all sound off, all notes off
+                120 | 123 => {
+                    for voice in &mut self.voices {
+                        if voice.channel == channel || is_manager {
+                            voice.gate = false;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
+        // messages on the manager channel apply to the whole zone
+        self.send_to_voices(msg, sample_index, |_, voice| {
+            is_manager || voice.channel == channel
+        });
+    }
+
+    fn data_entry(&mut self, channel: u8, rpn: Option<u16>, coarse: bool, value: u8) {
+        match rpn {
+            // pitch bend sensitivity, in semitones and cents
+            Some(0) => {
+                let range = self.channels[channel as usize].bend_range;
+                let range = if coarse {
+                    value as Float
+                } else {
+                    range.trunc() + value as Float / 100.0
+                };
+
+                match self.mpe {
+                    // a member channel sets the range of all member channels
+                    Some(zone) if zone.is_member_channel(channel) => {
+                        for (member, state) in self.channels.iter_mut().enumerate() {
+                            if zone.is_member_channel(member as u8) {
+                                state.bend_range = range;
+                            }
+                        }
+                    }
+                    _ => self.channels[channel as usize].bend_range = range,
+                }
+            }
+            // MPE configuration message
+            Some(6) if coarse && (channel == 0 || channel == 15) => {
+                if value > 0 {
+                    // the bend ranges are reset to their defaults
+                    self.mpe = Some(if channel == 0 {
+                        MpeZone::lower(value)
+                    } else {
+                        MpeZone::upper(value)
+                    });
+                } else if self.is_manager_channel(channel) {
+                    self.mpe = None;
+                }
+                self.reset_channels();
+            }
+            _ => {}
+        }
+    }
+
+    /// Sends the message to the `midi` inputs of the voices that the filter selects.
+    ///
+    /// Each input sample holds at most one message, so each voice receives at most one message per sample too.
+    /// Messages for a channel that no voice is playing on are dropped; the channel's state still tracks them for the dedicated inputs.
+    fn send_to_voices(
+        &mut self,
+        msg: &MidiMessage,
+        sample_index: usize,
+        filter: impl Fn(usize, &Voice) -> bool,
+    ) {
+        let Some(input) = self.midi_input else {
+            return;
+        };
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if !filter(i, voice) {
+                continue;
+            }
+            if let Some(SignalBuffer::Midi(buffer)) = voice.rt.get_input_mut(input) {
+                if let Some(slot) = buffer.get_mut(sample_index) {
+                    *slot = Some(*msg);
+                }
+            }
+        }
+    }
+
+    fn steal(&self) -> usize {
+        // prefer voices whose notes have been released
+        let released = self.voices.iter().any(|voice| !voice.gate);
+        let candidates = (0..self.voices.len()).filter(|&i| !released || !self.voices[i].gate);
+
+        match self.stealing {
+            VoiceStealing::Oldest | VoiceStealing::SameNote => {
+                candidates.min_by_key(|&i| self.voices[i].started)
+            }
+            VoiceStealing::Quietest => candidates.min_by(|&a, &b| {
+                self.voices[a]
+                    .level
+                    .total_cmp(&self.voices[b].level)
+                    .then(self.voices[a].started.cmp(&self.voices[b].started))
+            }),
+        }
+        .unwrap()
+    }
+
+    /// Writes the current state of each voice to its per-voice inputs for the given range of samples.
+    fn write_voice_inputs(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+
+        for voice in &mut self.voices {
+            if let Some(i) = self.note_input {
+                if let Some(SignalBuffer::Float(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[start..end].fill(voice.note.map(|note| note as Float));
+                }
+            }
+
+            if let Some(i) = self.velocity_input {
+                if let Some(SignalBuffer::Float(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[start..end].fill(Some(voice.velocity as Float));
+                }
+            }
+
+            if let Some(i) = self.gate_input {
+                if let Some(SignalBuffer::Bool(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[start..end].fill(Some(voice.gate));
+                    if voice.retrigger {
+                        buffer[start] = Some(false);
+                    }
+                }
+            }
+
+            let state = &self.channels[voice.channel as usize];
+
+            if let Some(i) = self.bend_input {
+                let mut bend = state.bend * state.bend_range;
+                if let Some(zone) = &self.mpe {
+                    if zone.is_member_channel(voice.channel) {
+                        let manager = &self.channels[zone.manager_channel as usize];
+                        bend += manager.bend * manager.bend_range;
+                    }
+                }
+                if let Some(SignalBuffer::Float(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[start..end].fill(Some(bend));
+                }
+            }
+
+            if let Some(i) = self.pressure_input {
+                if let Some(SignalBuffer::Float(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[start..end].fill(Some(voice.pressure));
+                }
+            }
+
+            if let Some(i) = self.timbre_input {
+                if let Some(SignalBuffer::Float(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[start..end].fill(Some(state.timbre));
+                }
+            }
+
+            voice.retrigger = false;
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for Poly {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        self.input_spec.clone()
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        self.output_spec.clone()
+    }
+
+    fn is_steady(&self) -> bool {
+        // idle voices are silent
+        self.voices.iter().all(|voice| !voice.active)
+    }
+
+    fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
+        for voice in &mut self.voices {
+            voice
+                .rt
+                .allocate_for_block_size(sample_rate, max_block_size);
+        }
+    }
+
+    fn resize_buffers(&mut self, _sample_rate: Float, block_size: usize) {
+        for voice in &mut self.voices {
+            voice.rt.set_block_size(block_size).unwrap();
+        }
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        // in a feedback loop, only the current sub-block (or sample) is processed, so the voices only run for those samples
+        let range = inputs.mode.sample_range(inputs.block_size);
+        for voice in &mut self.voices {
+            if let Some(i) = self.midi_input {
+                if let Some(SignalBuffer::Midi(buffer)) = voice.rt.get_input_mut(i) {
+                    buffer[range.clone()].fill(None);
+                }
+            }
+        }
+
+        // route the MIDI messages to the voices, sample-accurately
+        let mut start = range.start;
+        for (sample_index, msg) in range.clone().zip(inputs.iter_input_as::<MidiMessage>(0)?) {
+            let Some(msg) = msg else {
+                continue;
+            };
+            if !msg.is_channel_message()
+                || self.mpe.is_some_and(|zone| !zone.contains(msg.channel()))
+            {
+                continue;
+            }
+
+            self.write_voice_inputs(start, sample_index);
+            start = sample_index;
+            self.handle_message(msg, sample_index);
+        }
+        self.write_voice_inputs(start, range.end);
+
+        for output in 0..self.output_spec.len() {
+            for out in outputs.iter_output_mut_as::<Float>(output)? {
+                *out = Some(0.0);
+            }
+        }
+
+        for voice in &mut self.voices {
+            if !voice.active {
+                continue;
+            }
+
+            for (shared, &i) in self.shared_inputs.iter().enumerate() {
+                let input = voice
+                    .rt
+                    .get_input_mut(i)
+                    .ok_or(ProcessorError::NumInputsMismatch)?;
+                if let Some(signal) = inputs.input(shared + 1) {
+                    for sample_index in range.clone() {
+                        input.set(sample_index, signal.get(sample_index).unwrap());
+                    }
+                } else {
+                    for sample_index in range.clone() {
+                        input.set_none(sample_index);
+                    }
+                }
+            }
+
+            match voice.rt.process_with_mode(inputs.mode) {
+                Ok(()) => {}
+                Err(RuntimeError::GraphRunError(e)) => {
+                    return Err(ProcessorError::SubGraph(Box::new(e)))
+                }
+                Err(_) => {
+                    return Err(ProcessorError::Other);
+                }
+            }
+
+            voice.level = 0.0;
+            for output in 0..self.output_spec.len() {
+                let Some(SignalBuffer::Float(buffer)) = voice.rt.get_output(output) else {
+                    return Err(ProcessorError::NumOutputsMismatch);
+                };
+
+                for (out, sample) in outputs
+                    .iter_output_mut_as::<Float>(output)?
+                    .zip(buffer[range.clone()].iter())
+                {
+                    let sample = sample.unwrap_or_default();
+                    voice.level = voice.level.max(sample.abs());
+                    if let Some(out) = out {
+                        *out += sample;
+                    }
+                }
+            }
+
+            if !voice.gate && voice.level < self.silence_threshold {
+                voice.active = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::NodeIndex;
+
+    #[test]
+    fn dedicated_inputs_follow_expression_sent_before_the_note() {
+        let graph = GraphBuilder::new();
+        let midi = graph.add_midi_input("midi");
+        let poly = graph.add(
+            Poly::build(2, |voice| {
+                for name in ["bend", "pressure", "timbre"] {
+                    let input = voice.add_input(name, SignalType::Float);
+                    let output = voice.add_output(name, SignalType::Float);
+                    input.output(0).connect(&output.input(0));
+                }
+            })
+            .with_mpe(MpeZone::lower(15)),
+        );
+        poly.input("midi").connect(midi.output(0));
+        for name in ["bend", "pressure", "timbre"] {
+            let out = graph.add_audio_output();
+            poly.output(name).connect(&out.input(0));
+        }
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(48_000.0, 64);
+
+        // an MPE controller sends the note's expression and the note on at the same offset
+        let events = [
+            MidiEvent::new(10, MidiMessage::pitch_bend(1, 0x3FFF)),
+            MidiEvent::new(10, MidiMessage::channel_pressure(1, 127)),
+            MidiEvent::new(10, MidiMessage::control_change(1, 74, 0)),
+            MidiEvent::new(10, MidiMessage::note_on(1, 60, 100)),
+        ];
+        let mut bend = vec![0.0; 64];
+        let mut pressure = vec![0.0; 64];
+        let mut timbre = vec![0.0; 64];
+        runtime
+            .process(&[], &mut [&mut bend, &mut pressure, &mut timbre], &events)
+            .unwrap();
+
+        // the note starts on the fourth sample of the events, with all of its expression applied
+        assert_eq!(bend[13], 48.0);
+        assert_eq!(pressure[13], 1.0);
+        assert_eq!(timbre[13], 0.0);
+    }
+
+    const BLOCK_SIZE: usize = 16;
+
+    // three voices that output their held note on their own output, so that the outputs show which voice plays which note
+    fn note_voices(stealing: VoiceStealing) -> (EmbeddedRuntime, NodeIndex) {
+        let voices = (0..3)
+            .map(|i| {
+                let voice = GraphBuilder::new();
+                let note = voice.add_input("note", SignalType::Float);
+                let gate = voice.add_input("gate", SignalType::Bool);
+                let outputs: Vec<_> = (0..3)
+                    .map(|j| voice.add_output(j.to_string(), SignalType::Float))
+                    .collect();
+
+                let held = voice.add(Cond::new(SignalType::Float));
+                held.input("cond").connect(gate.output(0));
+                held.input("then").connect(note.output(0));
+                held.input("else").connect(voice.constant(0.0));
+                held.output(0).connect(&outputs[i].input(0));
+                voice.build()
+            })
+            .collect();
+
+        let graph = GraphBuilder::new();
+        let midi = graph.add_midi_input("midi");
+        let poly = graph.add(Poly::new(voices).with_stealing(stealing));
+        poly.input("midi").connect(midi.output(0));
+        for i in 0..3 {
+            let out = graph.add_audio_output();
+            poly.output(i).connect(&out.input(0));
+        }
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(1000.0, BLOCK_SIZE);
+        (runtime, poly.id())
+    }
+
+    // processes a block and returns the output of each voice
+    fn play(runtime: &mut EmbeddedRuntime, events: &[MidiEvent]) -> [Vec<f32>; 3] {
+        let mut outputs = [(); 3].map(|_| vec![0.0; BLOCK_SIZE]);
+        let [a, b, c] = &mut outputs;
+        runtime.process(&[], &mut [a, b, c], events).unwrap();
+        outputs
+    }
+
+    fn active_voices(runtime: &EmbeddedRuntime, poly: NodeIndex) -> usize {
+        let processor = runtime.runtime().graph().digraph()[poly].processor();
+        let poly: &Poly = (*processor).downcast_ref().unwrap();
+        poly.num_active_voices()
+    }
+
+    #[test]
+    fn allocates_and_steals_voices() {
+        let (mut runtime, poly) = note_voices(VoiceStealing::Oldest);
+
+        // three notes take the three voices, the fourth steals the oldest one
+        let out = play(
+            &mut runtime,
+            &[
+                MidiEvent::new(0, MidiMessage::note_on(0, 60, 100)),
+                MidiEvent::new(1, MidiMessage::note_on(0, 62, 100)),
+                MidiEvent::new(2, MidiMessage::note_on(0, 64, 100)),
+                MidiEvent::new(8, MidiMessage::note_on(0, 65, 100)),
+            ],
+        );
+        assert_eq!(out[0][..8], [60.0; 8]);
+        // the stolen voice's gate drops for one sample so that envelopes retrigger
+        assert_eq!(out[0][8], 0.0);
+        assert_eq!(out[0][9..], [65.0; 7]);
+        assert_eq!(out[1][1..], [62.0; 15]);
+        assert_eq!(out[2][2..], [64.0; 14]);
+        assert_eq!(active_voices(&runtime, poly), 3);
+
+        // a released voice is stolen before the oldest held one
+        let out = play(
+            &mut runtime,
+            &[
+                MidiEvent::new(0, MidiMessage::note_off(0, 62, 0)),
+                MidiEvent::new(4, MidiMessage::note_on(0, 67, 100)),
+            ],
+        );
+        assert_eq!(out[1][..4], [0.0; 4]);
+        assert_eq!(out[1][4..], [67.0; 12]);
+        assert_eq!(out[0], [65.0; BLOCK_SIZE]);
+        assert_eq!(out[2], [64.0; BLOCK_SIZE]);
+    }
+
+    #[test]
+    fn note_off_releases_only_its_voice() {
+        let (mut runtime, poly) = note_voices(VoiceStealing::Oldest);
+        play(
+            &mut runtime,
+            &[
+                MidiEvent::new(0, MidiMessage::note_on(0, 60, 100)),
+                MidiEvent::new(0, MidiMessage::note_on(0, 62, 100)),
+                MidiEvent::new(0, MidiMessage::note_on(1, 60, 100)),
+            ],
+        );
+
+        // the note off matches the note and the channel
+        let out = play(
+            &mut runtime,
+            &[MidiEvent::new(5, MidiMessage::note_off(0, 60, 0))],
+        );
+        assert_eq!(out[0][..5], [60.0; 5]);
+        assert_eq!(out[0][5..], [0.0; 11]);
+        assert_eq!(out[1], [62.0; BLOCK_SIZE]);
+        assert_eq!(out[2], [60.0; BLOCK_SIZE]);
+
+        // the runtime processes the samples after the event separately, and the released voice is silent for all of them, so it is now idle
+        assert_eq!(active_voices(&runtime, poly), 2);
+        let out = play(&mut runtime, &[]);
+        assert_eq!(out[0], [0.0; BLOCK_SIZE]);
+        assert_eq!(active_voices(&runtime, poly), 2);
+    }
+
+    #[test]
+    fn idle_voices_are_used_before_stealing() {
+        let (mut runtime, poly) = note_voices(VoiceStealing::Oldest);
+        play(
+            &mut runtime,
+            &[
+                MidiEvent::new(0, MidiMessage::note_on(0, 60, 100)),
+                MidiEvent::new(1, MidiMessage::note_on(0, 62, 100)),
+                MidiEvent::new(2, MidiMessage::note_on(0, 64, 100)),
+                MidiEvent::new(3, MidiMessage::note_off(0, 62, 0)),
+            ],
+        );
+        assert_eq!(active_voices(&runtime, poly), 2);
+
+        // the idle voice takes the next note, and no held note is interrupted
+        let out = play(
+            &mut runtime,
+            &[MidiEvent::new(0, MidiMessage::note_on(0, 69, 100))],
+        );
+        assert_eq!(out[0], [60.0; BLOCK_SIZE]);
+        assert_eq!(out[1], [69.0; BLOCK_SIZE]);
+        assert_eq!(out[2], [64.0; BLOCK_SIZE]);
+        assert_eq!(active_voices(&runtime, poly), 3);
+    }
+
+    #[test]
+    fn same_note_retriggers_its_voice() {
+        let (mut runtime, _) = note_voices(VoiceStealing::SameNote);
+        play(
+            &mut runtime,
+            &[
+                MidiEvent::new(0, MidiMessage::note_on(0, 60, 100)),
+                MidiEvent::new(0, MidiMessage::note_on(0, 62, 100)),
+            ],
+        );
+
+        // a repeated note is a new event, so it retriggers the voice instead of taking the idle one
+        let out = play(
+            &mut runtime,
+            &[MidiEvent::new(6, MidiMessage::note_on(0, 60, 100))],
+        );
+        assert_eq!(out[0][5..8], [60.0, 0.0, 60.0]);
+        assert_eq!(out[2], [0.0; BLOCK_SIZE]);
+    }
+}
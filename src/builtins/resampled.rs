@@ -0,0 +1,383 @@
+//! Running sub-graphs at a different sample rate.
+
+use crate::{
+    prelude::*,
+    processor::ProcessMode,
+    resample::{ResampleQuality, Resampler},
+    runtime::RuntimeError,
+};
+
+/// A fixed-capacity FIFO of samples, allocated up front so that it never allocates while processing.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Queue {
+    samples: Vec<Float>,
+    head: usize,
+    len: usize,
+}
+
+impl Queue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            samples: vec![0.0; capacity],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, sample: Float) {
+        let capacity = self.samples.len();
+        if self.len == capacity {
+            // the queues are sized for the priming and the largest block, so this shouldn't happen, but dropping the oldest sample is better than allocating
+            self.head = (self.head + 1) % capacity;
+            self.len -= 1;
+        }
+        self.samples[(self.head + self.len) % capacity] = sample;
+        self.len += 1;
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<Float> {
+        if self.len == 0 {
+            return None;
+        }
+        let sample = self.samples[self.head];
+        self.head = (self.head + 1) % self.samples.len();
+        self.len -= 1;
+        Some(sample)
+    }
+}
+
+/// One direction of sample rate conversion for a single channel, with a queue for the samples that have been converted but not yet consumed.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Channel {
+    resampler: Resampler,
+    queue: Queue,
+    // the last input sample, held while the input is `None`
+    last: Float,
+}
+
+impl Channel {
+    /// Creates a new channel whose queue holds up to `capacity` samples, starting with `priming` samples of silence.
+    fn new(
+        from_rate: Float,
+        to_rate: Float,
+        quality: ResampleQuality,
+        capacity: usize,
+        priming: usize,
+    ) -> Self {
+        let mut queue = Queue::new(capacity);
+        for _ in 0..priming {
+            queue.push(0.0);
+        }
+        Self {
+            resampler: Resampler::new(from_rate, to_rate, quality),
+            queue,
+            last: 0.0,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, sample: Option<Float>) {
+        if let Some(sample) = sample {
+            self.last = sample;
+        }
+        let queue = &mut self.queue;
+        self.resampler
+            .process(std::slice::from_ref(&self.last), |sample| {
+                queue.push(sample)
+            });
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Float {
+        self.queue.pop().unwrap_or_default()
+    }
+}
+
+/// A processor that runs a sub-graph at a fixed sample rate, independent of the outer sample rate.
+///
+/// This is useful for sub-graphs that are tuned for a specific sample rate, or to run expensive processing at a lower rate.
+/// The sub-graph's processors see its own sample rate in [`Processor::allocate`], and a block size that varies slightly from block to block so that it keeps pace with the outer graph.
+/// The inputs and outputs are converted with [`Resampler`]s, which delay the signal by about [`Resampled::latency`] samples.
+///
+/// # Inputs
+///
+/// The inputs of the sub-graph, with the names they were declared with (see [`Graph::add_input`]). They must all be `Float`.
+///
+/// # Outputs
+///
+/// The outputs of the sub-graph, with the names they were declared with (see [`Graph::add_output`]). They must all be `Float`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Resampled {
+    rt: Runtime,
+    sample_rate: Float,
+    quality: ResampleQuality,
+
+    outer_rate: Float,
+    inputs: Vec<Channel>,
+    outputs: Vec<Channel>,
+    // the total number of samples processed at the outer and the inner sample rate
+    outer_count: u64,
+    inner_count: u64,
+    latency: usize,
+}
+
+impl Resampled {
+    /// Creates a new `Resampled` processor that runs the given graph at the given sample rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is not positive, or if any input or output of the graph is not `Float`.
+    pub fn new(sample_rate: Float, graph: Graph) -> Self {
+        assert!(sample_rate > 0.0, "Sample rate must be positive");
+        assert!(
+            graph
+                .input_specs()
+                .iter()
+                .chain(graph.output_specs())
+                .all(|spec| spec.signal_type == SignalType::Float),
+            "Resampled inputs and outputs must be of type Float"
+        );
+
+        Self {
+            rt: Runtime::new(graph),
+            sample_rate,
+            quality: ResampleQuality::default(),
+            outer_rate: 0.0,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            outer_count: 0,
+            inner_count: 0,
+            latency: 0,
+        }
+    }
+
+    /// Creates a new `Resampled` processor by building its graph with the given closure.
+    ///
+    /// # Panics
+    ///
+    /// See [`Resampled::new`].
+    pub fn build<F>(sample_rate: Float, f: F) -> Self
+    where
+        F: FnOnce(&GraphBuilder),
+    {
+        let builder = GraphBuilder::new();
+        f(&builder);
+        Self::new(sample_rate, builder.build())
+    }
+
+    /// Sets the quality of the sample rate conversion.
+    pub fn with_quality(mut self, quality: ResampleQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Returns the sample rate the sub-graph runs at.
+    #[inline]
+    pub fn sample_rate(&self) -> Float {
+        self.sample_rate
+    }
+
+    /// Returns the approximate delay in samples (at the outer sample rate) added by the sample rate conversion.
+    ///
+    /// This is only known once the processor has been allocated, and is `0` before that.
+    #[inline]
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Returns a reference to the sub-graph.
+    #[inline]
+    pub fn graph(&self) -> &Graph {
+        self.rt.graph()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for Resampled {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        self.rt.graph().input_specs().to_vec()
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        self.rt.graph().output_specs().to_vec()
+    }
+
+    fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        let max_inner_block_size = (max_block_size as f64 * ratio).ceil() as usize + 1;
+        self.rt
+            .allocate_for_block_size(self.sample_rate, max_inner_block_size);
+
+        self.outer_rate = sample_rate;
+        self.outer_count = 0;
+        self.inner_count = 0;
+
+        // the resamplers only emit a sample once they have seen enough input after it, so the queues are primed with
+        // enough silence to always be able to provide a full block
+        let input_latency = Resampler::new(sample_rate, self.sample_rate, self.quality).latency();
+        let input_priming = ((input_latency + 1) as f64 * ratio).ceil() as usize + 1;
+        let output_latency = Resampler::new(self.sample_rate, sample_rate, self.quality).latency();
+        let output_priming = ((output_latency + 2) as f64 / ratio).ceil() as usize + 1;
+
+        self.inputs = (0..self.rt.graph().input_specs().len())
+            .map(|_| {
+                Channel::new(
+                    sample_rate,
+                    self.sample_rate,
+                    self.quality,
+                    input_priming + max_inner_block_size + 4,
+                    input_priming,
+                )
+            })
+            .collect();
+        self.outputs = (0..self.rt.graph().output_specs().len())
+            .map(|_| {
+                Channel::new(
+                    self.sample_rate,
+                    sample_rate,
+                    self.quality,
+                    output_priming + max_block_size + 4,
+                    output_priming,
+                )
+            })
+            .collect();
+
+        // a sample waits behind the silence in both queues; the resamplers' own latency is absorbed by the priming
+        self.latency = (input_priming as f64 / ratio + output_priming as f64).round() as usize;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        // in a feedback loop, only the current sub-block (or sample) is processed
+        let block_size = inputs.mode.sample_range(inputs.block_size).len();
+        let ratio = self.sample_rate as f64 / self.outer_rate as f64;
+
+        // run the sub-graph for as many samples as keeps it in step with the outer graph
+        let inner_target = ((self.outer_count + block_size as u64) as f64 * ratio).floor() as u64;
+        let inner_block_size = (inner_target - self.inner_count) as usize;
+
+        for (i, channel) in self.inputs.iter_mut().enumerate() {
+            if inputs.input(i).is_some() {
+                for sample in inputs.iter_input_as::<Float>(i)?.take(block_size) {
+                    channel.push(*sample);
+                }
+            } else {
+                for _ in 0..block_size {
+                    channel.push(None);
+                }
+            }
+        }
+
+        if inner_block_size > 0 {
+            for (i, channel) in self.inputs.iter_mut().enumerate() {
+                let Some(SignalBuffer::Float(input)) = self.rt.get_input_mut(i) else {
+                    return Err(ProcessorError::NumInputsMismatch);
+                };
+                for sample in input.iter_mut().take(inner_block_size) {
+                    *sample = Some(channel.pop());
+                }
+            }
+
+            // the sub-graph is allocated for the largest number of samples it can be asked for, and runs for the first `inner_block_size` of them
+            match self
+                .rt
+                .process_with_mode(ProcessMode::SubBlock(0, inner_block_size))
+            {
+                Ok(()) => {}
+                Err(RuntimeError::GraphRunError(e)) => {
+                    return Err(ProcessorError::SubGraph(Box::new(e)))
+                }
+                Err(_) => {
+                    return Err(ProcessorError::Other);
+                }
+            }
+
+            for (i, channel) in self.outputs.iter_mut().enumerate() {
+                let Some(SignalBuffer::Float(output)) = self.rt.get_output(i) else {
+                    return Err(ProcessorError::NumOutputsMismatch);
+                };
+                for sample in output.iter().take(inner_block_size) {
+                    channel.push(*sample);
+                }
+            }
+        }
+
+        for (i, channel) in self.outputs.iter_mut().enumerate() {
+            for out in outputs.iter_output_mut_as::<Float>(i)? {
+                *out = Some(channel.pop());
+            }
+        }
+
+        self.outer_count += block_size as u64;
+        self.inner_count = inner_target;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_queue_drops_the_oldest_sample() {
+        let mut queue = Queue::new(3);
+        for sample in 0..5 {
+            queue.push(sample as Float);
+        }
+        assert_eq!(queue.pop(), Some(2.0));
+        assert_eq!(queue.pop(), Some(3.0));
+        assert_eq!(queue.pop(), Some(4.0));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn follows_the_outer_graph_with_latency() {
+        for sample_rate in [32_000.0, 96_000.0] {
+            let graph = GraphBuilder::new();
+            let sine = graph.add(SineOscillator::new(100.0));
+            let resampled = Resampled::build(sample_rate, |graph| {
+                let input = graph.add_input("in", SignalType::Float);
+                let output = graph.add_output("out", SignalType::Float);
+                input.output(0).connect(&output.input(0));
+            });
+
+            let node = graph.add(resampled);
+            node.input(0).connect(sine.output(0));
+            let wet = graph.add_audio_output();
+            node.output(0).connect(&wet.input(0));
+            let dry = graph.add_audio_output();
+            sine.output(0).connect(&dry.input(0));
+
+            let mut runtime = graph.build_runtime();
+            // the block size doesn't divide evenly into the inner sample rate, so the inner block size varies
+            let outputs = runtime
+                .run_offline(Duration::from_millis(200), 48_000.0, 100)
+                .unwrap();
+
+            let latency = runtime
+                .graph()
+                .digraph()
+                .node_weights()
+                .find_map(|node| node.processor().downcast_ref::<Resampled>())
+                .unwrap()
+                .latency();
+            assert!(latency > 0);
+
+            // skip the ringing of the filters at the onset of the sine, and allow for the latency being rounded to whole samples
+            let wet = &outputs[0][latency + 64..];
+            let dry = &outputs[1][64..];
+            for (wet, dry) in wet.iter().zip(dry) {
+                assert!((wet - dry).abs() < 1e-2, "{sample_rate}: {wet} != {dry}");
+            }
+        }
+    }
+}
@@ -0,0 +1,965 @@
+//! Taps for getting signal data out of a running graph, e.g. for the meters, scopes and spectrum analyzers of a UI.
+//!
+//! Each tap processor pushes its readings or frames into a lock-free queue without allocating, and the queue is read from another thread through a [`TapReader`].
+//! Taps added with [`Graph::add_tap`] can be looked up by name with [`Graph::tap_named`], [`Runtime::tap_named`](crate::runtime::Runtime::tap_named) or [`RuntimeHandle::tap`](crate::runtime::RuntimeHandle::tap).
+//! If the queue isn't read, the oldest readings or frames are overwritten.
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::prelude::*;
+
+/// The default number of readings or frames a tap queues before the oldest are overwritten.
+pub const DEFAULT_TAP_CAPACITY: usize = 16;
+
+/// A processor that can be added to a graph with [`Graph::add_tap`], and read from outside the graph by name.
+pub trait Tap: Processor {
+    /// Returns the name the tap is registered under.
+    fn tap_name(&self) -> &str;
+
+    /// Returns the reader for the data pushed by the tap.
+    fn tap_reader(&self) -> TapReader;
+}
+
+/// The reading end of a [`Tap`].
+#[derive(Clone, Debug)]
+pub enum TapReader {
+    /// The readings of a [`MeterTap`].
+    Meter(MeterReader),
+    /// The frames of a [`ScopeTap`].
+    Scope(FrameReader),
+    /// The frames of a `SpectrumTap`.
+    Spectrum(FrameReader),
+}
+
+impl TapReader {
+    /// Returns the meter reader, if this is the reader of a [`MeterTap`].
+    pub fn as_meter(&self) -> Option<&MeterReader> {
+        match self {
+            Self::Meter(reader) => Some(reader),
+            _ => None,
+        }
+    }
+
+    /// Returns the frame reader, if this is the reader of a [`ScopeTap`].
+    pub fn as_scope(&self) -> Option<&FrameReader> {
+        match self {
+            Self::Scope(reader) => Some(reader),
+            _ => None,
+        }
+    }
+
+    /// Returns the frame reader, if this is the reader of a `SpectrumTap`.
+    pub fn as_spectrum(&self) -> Option<&FrameReader> {
+        match self {
+            Self::Spectrum(reader) => Some(reader),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the name and reader of the processor, if it is one of the built-in taps.
+pub(crate) fn tap_reader(processor: &dyn Processor) -> Option<(&str, TapReader)> {
+    if let Some(tap) = processor.downcast_ref::<MeterTap>() {
+        return Some((tap.tap_name(), tap.tap_reader()));
+    }
+    if let Some(tap) = processor.downcast_ref::<ScopeTap>() {
+        return Some((tap.tap_name(), tap.tap_reader()));
+    }
+    #[cfg(feature = "fft")]
+    if let Some(tap) = processor.downcast_ref::<SpectrumTap>() {
+        return Some((tap.tap_name(), tap.tap_reader()));
+    }
+    None
+}
+
+/// A peak and RMS reading of a [`MeterTap`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeterReading {
+    /// The largest absolute sample value.
+    pub peak: Float,
+    /// The root mean square of the samples.
+    pub rms: Float,
+}
+
+impl MeterReading {
+    /// Returns the peak level in decibels relative to full scale.
+    pub fn peak_db(&self) -> Float {
+        20.0 * self.peak.log10()
+    }
+
+    /// Returns the RMS level in decibels relative to full scale.
+    pub fn rms_db(&self) -> Float {
+        20.0 * self.rms.log10()
+    }
+}
+
+/// The reading end of a [`MeterTap`].
+#[derive(Clone, Debug)]
+pub struct MeterReader {
+    rx: Receiver<MeterReading>,
+}
+
+impl MeterReader {
+    /// Returns a reading that combines all readings since the last call, or `None` if there are none.
+    ///
+    /// The peak is the largest of their peaks, and the RMS is taken over all of their samples.
+    pub fn read(&self) -> Option<MeterReading> {
+        let mut combined: Option<MeterReading> = None;
+        let mut count = 0;
+        for reading in self.rx.try_iter() {
+            let combined = combined.get_or_insert_with(MeterReading::default);
+            combined.peak = combined.peak.max(reading.peak);
+            // the readings are taken over windows of the same length
+            combined.rms += reading.rms * reading.rms;
+            count += 1;
+        }
+        combined.map(|combined| MeterReading {
+            peak: combined.peak,
+            rms: (combined.rms / count as Float).sqrt(),
+        })
+    }
+
+    /// Returns an iterator over the readings that have been pushed since they were last read, oldest first.
+    pub fn try_iter(&self) -> impl Iterator<Item = MeterReading> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+/// The reading end of a tap that pushes frames of samples, such as a [`ScopeTap`].
+///
+/// The frames are preallocated, and are returned to the tap once they have been read.
+#[derive(Clone, Debug)]
+pub struct FrameReader {
+    queue: FrameQueue,
+}
+
+impl FrameReader {
+    /// Returns the length of each frame.
+    pub fn frame_len(&self) -> usize {
+        self.queue.frame_len
+    }
+
+    /// Copies the most recent frame into `out` and discards any older ones.
+    ///
+    /// Returns `false`, leaving `out` unchanged, if no frame has been pushed since the last read.
+    pub fn read_latest(&self, out: &mut [Float]) -> bool {
+        let mut latest = None;
+        while let Ok(frame) = self.queue.filled_rx.try_recv() {
+            if let Some(older) = latest.replace(frame) {
+                self.queue.recycle(older);
+            }
+        }
+
+        let Some(frame) = latest else {
+            return false;
+        };
+        let len = out.len().min(frame.len());
+        out[..len].copy_from_slice(&frame[..len]);
+        self.queue.recycle(frame);
+        true
+    }
+
+    /// Calls `f` with each frame that has been pushed since the last read, oldest first, and returns the number of frames.
+    pub fn read_each(&self, mut f: impl FnMut(&[Float])) -> usize {
+        let mut count = 0;
+        while let Ok(frame) = self.queue.filled_rx.try_recv() {
+            f(&frame);
+            self.queue.recycle(frame);
+            count += 1;
+        }
+        count
+    }
+}
+
+/// A fixed pool of frames that circulate between a tap and its reader.
+#[derive(Clone, Debug)]
+struct FrameQueue {
+    frame_len: usize,
+    capacity: usize,
+    filled_tx: Sender<Box<[Float]>>,
+    filled_rx: Receiver<Box<[Float]>>,
+    free_tx: Sender<Box<[Float]>>,
+    free_rx: Receiver<Box<[Float]>>,
+}
+
+impl FrameQueue {
+    fn new(frame_len: usize, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        // one extra frame for the tap to fill while `capacity` frames are queued
+        let (filled_tx, filled_rx) = crossbeam_channel::bounded(capacity + 1);
+        let (free_tx, free_rx) = crossbeam_channel::bounded(capacity + 1);
+        for _ in 0..=capacity {
+            free_tx
+                .try_send(vec![0.0; frame_len].into_boxed_slice())
+                .unwrap();
+        }
+        Self {
+            frame_len,
+            capacity,
+            filled_tx,
+            filled_rx,
+            free_tx,
+            free_rx,
+        }
+    }
+
+    /// Takes a free frame to fill, or the oldest unread frame if there are none.
+    fn take(&self) -> Option<Box<[Float]>> {
+        self.free_rx
+            .try_recv()
+            .or_else(|_| self.filled_rx.try_recv())
+            .ok()
+    }
+
+    fn push(&self, frame: Box<[Float]>) {
+        if self.filled_rx.len() >= self.capacity {
+            // overwrite the oldest frame
+            if let Ok(oldest) = self.filled_rx.try_recv() {
+                self.recycle(oldest);
+            }
+        }
+        self.filled_tx.try_send(frame).ok();
+    }
+
+    fn recycle(&self, frame: Box<[Float]>) {
+        self.free_tx.try_send(frame).ok();
+    }
+}
+
+/// A processor that measures the peak and RMS level of its input over consecutive windows, and pushes a [`MeterReading`] for each window.
+///
+/// Usually added with [`Graph::add_tap`], and read from its [`MeterReader`].
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The signal to measure. |
+///
+/// # Outputs
+///
+/// None.
+#[derive(Clone, Debug)]
+pub struct MeterTap {
+    name: String,
+    window: Float,
+    tx: Sender<MeterReading>,
+    reader: MeterReader,
+    window_samples: usize,
+    count: usize,
+    peak: Float,
+    sum_sq: Float,
+}
+
+impl MeterTap {
+    /// Creates a new `MeterTap` with the given name and a window of 50 ms.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self::with_config(name.into(), 0.05, DEFAULT_TAP_CAPACITY)
+    }
+
+    fn with_config(name: String, window: Float, capacity: usize) -> Self {
+        let (tx, rx) = crossbeam_channel::bounded(capacity.max(1));
+        Self {
+            name,
+            window,
+            tx,
+            reader: MeterReader { rx },
+            window_samples: 1,
+            count: 0,
+            peak: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    /// Sets the length of the window each reading is measured over, in seconds.
+    pub fn with_window(mut self, window: Float) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of readings that are queued before the oldest are overwritten.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self::with_config(self.name, self.window, capacity)
+    }
+
+    /// Returns the name of the tap.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the reader for the readings pushed by the tap.
+    pub fn reader(&self) -> &MeterReader {
+        &self.reader
+    }
+
+    fn push(&self, reading: MeterReading) {
+        if self.tx.try_send(reading).is_err() {
+            // overwrite the oldest reading
+            self.reader.rx.try_recv().ok();
+            self.tx.try_send(reading).ok();
+        }
+    }
+}
+
+impl Tap for MeterTap {
+    fn tap_name(&self) -> &str {
+        &self.name
+    }
+
+    fn tap_reader(&self) -> TapReader {
+        TapReader::Meter(self.reader.clone())
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for MeterTap {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("in", SignalType::Float)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![]
+    }
+
+    fn allocate(&mut self, sample_rate: Float, _max_block_size: usize) {
+        self.window_samples = ((self.window * sample_rate).round() as usize).max(1);
+        self.count = 0;
+        self.peak = 0.0;
+        self.sum_sq = 0.0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        _outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if inputs.input(0).is_none() {
+            return Ok(());
+        }
+
+        for sample in inputs.iter_input_as::<Float>(0)? {
+            let sample = sample.unwrap_or_default();
+            self.peak = self.peak.max(sample.abs());
+            self.sum_sq += sample * sample;
+            self.count += 1;
+
+            if self.count >= self.window_samples {
+                self.push(MeterReading {
+                    peak: self.peak,
+                    rms: (self.sum_sq / self.count as Float).sqrt(),
+                });
+                self.count = 0;
+                self.peak = 0.0;
+                self.sum_sq = 0.0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MeterTap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct MeterTapSer<'a> {
+            name: &'a str,
+            window: Float,
+            capacity: usize,
+        }
+
+        MeterTapSer {
+            name: &self.name,
+            window: self.window,
+            capacity: self.tx.capacity().unwrap_or_default(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MeterTap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct MeterTapDe {
+            name: String,
+            window: Float,
+            capacity: usize,
+        }
+
+        let de = MeterTapDe::deserialize(deserializer)?;
+        Ok(MeterTap::with_config(de.name, de.window, de.capacity))
+    }
+}
+
+/// A processor that pushes frames of its input, optionally decimated, for display in an oscilloscope.
+///
+/// With a trigger, each frame starts at a rising zero crossing so that periodic signals are displayed steadily.
+/// If no zero crossing arrives within a frame's worth of samples, the frame starts anyway.
+///
+/// Usually added with [`Graph::add_tap`], and read from its [`FrameReader`].
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The signal to capture. |
+///
+/// # Outputs
+///
+/// None.
+#[derive(Clone, Debug)]
+pub struct ScopeTap {
+    name: String,
+    decimation: usize,
+    trigger: bool,
+    reader: FrameReader,
+    frame: Option<Box<[Float]>>,
+    pos: usize,
+    phase: usize,
+    waited: usize,
+    last: Float,
+}
+
+impl ScopeTap {
+    /// Creates a new `ScopeTap` with the given name and frame length, in samples after decimation.
+    pub fn new(name: impl Into<String>, frame_len: usize) -> Self {
+        Self {
+            name: name.into(),
+            decimation: 1,
+            trigger: false,
+            reader: FrameReader {
+                queue: FrameQueue::new(frame_len.max(1), DEFAULT_TAP_CAPACITY),
+            },
+            frame: None,
+            pos: 0,
+            phase: 0,
+            waited: 0,
+            last: 0.0,
+        }
+    }
+
+    /// Keeps only every `decimation`-th sample, so that each frame spans a longer time.
+    pub fn with_decimation(mut self, decimation: usize) -> Self {
+        self.decimation = decimation.max(1);
+        self
+    }
+
+    /// Starts each frame at a rising zero crossing.
+    pub fn with_trigger(mut self) -> Self {
+        self.trigger = true;
+        self
+    }
+
+    /// Sets the number of frames that are queued before the oldest are overwritten.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.reader.queue = FrameQueue::new(self.reader.frame_len(), capacity);
+        self
+    }
+
+    /// Returns the name of the tap.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the reader for the frames pushed by the tap.
+    pub fn reader(&self) -> &FrameReader {
+        &self.reader
+    }
+}
+
+impl Tap for ScopeTap {
+    fn tap_name(&self) -> &str {
+        &self.name
+    }
+
+    fn tap_reader(&self) -> TapReader {
+        TapReader::Scope(self.reader.clone())
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for ScopeTap {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("in", SignalType::Float)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.pos = 0;
+        self.phase = 0;
+        self.waited = 0;
+        self.last = 0.0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        _outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if inputs.input(0).is_none() {
+            return Ok(());
+        }
+
+        let frame_len = self.reader.frame_len();
+
+        for sample in inputs.iter_input_as::<Float>(0)? {
+            let phase = self.phase;
+            self.phase = (self.phase + 1) % self.decimation;
+            if phase != 0 {
+                continue;
+            }
+
+            let sample = sample.unwrap_or_default();
+            let last = std::mem::replace(&mut self.last, sample);
+
+            if self.pos == 0 {
+                if self.trigger && !(last < 0.0 && sample >= 0.0) && self.waited < frame_len {
+                    self.waited += 1;
+                    continue;
+                }
+                self.waited = 0;
+            }
+
+            if self.frame.is_none() {
+                self.frame = self.reader.queue.take();
+            }
+            let Some(frame) = &mut self.frame else {
+                continue;
+            };
+
+            frame[self.pos] = sample;
+            self.pos += 1;
+            if self.pos == frame_len {
+                self.reader.queue.push(self.frame.take().unwrap());
+                self.pos = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ScopeTap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct ScopeTapSer<'a> {
+            name: &'a str,
+            frame_len: usize,
+            decimation: usize,
+            trigger: bool,
+            capacity: usize,
+        }
+
+        ScopeTapSer {
+            name: &self.name,
+            frame_len: self.reader.frame_len(),
+            decimation: self.decimation,
+            trigger: self.trigger,
+            capacity: self.reader.queue.capacity,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ScopeTap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct ScopeTapDe {
+            name: String,
+            frame_len: usize,
+            decimation: usize,
+            trigger: bool,
+            capacity: usize,
+        }
+
+        let de = ScopeTapDe::deserialize(deserializer)?;
+        let mut tap = ScopeTap::new(de.name, de.frame_len)
+            .with_decimation(de.decimation)
+            .with_capacity(de.capacity);
+        tap.trigger = de.trigger;
+        Ok(tap)
+    }
+}
+
+/// A processor that pushes the magnitude spectrum of its input, for display in a spectrum analyzer.
+///
+/// Every `hop` samples, the last `fft_size` samples are windowed and transformed, and a frame of `fft_size / 2 + 1` magnitudes is pushed.
+/// Bin `k` is centered on `k * sample_rate / fft_size` Hz, and the magnitudes are scaled so that a full-scale sine wave reads about `1.0`.
+///
+/// Usually added with [`Graph::add_tap`], and read from its [`FrameReader`].
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The signal to analyze. |
+///
+/// # Outputs
+///
+/// None.
+#[cfg(feature = "fft")]
+#[derive(Clone)]
+pub struct SpectrumTap {
+    name: String,
+    fft_size: usize,
+    hop: usize,
+    window_function: WindowFunction,
+    reader: FrameReader,
+    plan: std::sync::Arc<dyn realfft::RealToComplex<Float>>,
+    window: Box<[Float]>,
+    scale: Float,
+    history: Box<[Float]>,
+    write_pos: usize,
+    since_hop: usize,
+    input: Box<[Float]>,
+    spectrum: Box<[num::Complex<Float>]>,
+    scratch: Box<[num::Complex<Float>]>,
+}
+
+#[cfg(feature = "fft")]
+impl SpectrumTap {
+    /// Creates a new `SpectrumTap` with the given name and FFT size, a hop of half the FFT size and a Hann window.
+    pub fn new(name: impl Into<String>, fft_size: usize) -> Self {
+        let fft_size = fft_size.max(2);
+        Self::with_config(
+            name.into(),
+            fft_size,
+            fft_size / 2,
+            WindowFunction::Hann,
+            DEFAULT_TAP_CAPACITY,
+        )
+    }
+
+    fn with_config(
+        name: String,
+        fft_size: usize,
+        hop: usize,
+        window_function: WindowFunction,
+        capacity: usize,
+    ) -> Self {
+        let mut planner = realfft::RealFftPlanner::new();
+        let plan = planner.plan_fft_forward(fft_size);
+        let window = window_function.generate(fft_size).0;
+        let scale = 2.0 / window.iter().sum::<Float>().max(Float::EPSILON);
+        Self {
+            name,
+            fft_size,
+            hop: hop.max(1),
+            window_function,
+            reader: FrameReader {
+                queue: FrameQueue::new(fft_size / 2 + 1, capacity),
+            },
+            input: plan.make_input_vec().into_boxed_slice(),
+            spectrum: plan.make_output_vec().into_boxed_slice(),
+            scratch: plan.make_scratch_vec().into_boxed_slice(),
+            plan,
+            window,
+            scale,
+            history: vec![0.0; fft_size].into_boxed_slice(),
+            write_pos: 0,
+            since_hop: 0,
+        }
+    }
+
+    /// Sets the number of samples between frames.
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    /// Sets the window function applied before each transform.
+    pub fn with_window(self, window_function: WindowFunction) -> Self {
+        let capacity = self.reader.queue.capacity;
+        Self::with_config(
+            self.name,
+            self.fft_size,
+            self.hop,
+            window_function,
+            capacity,
+        )
+    }
+
+    /// Sets the number of frames that are queued before the oldest are overwritten.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self::with_config(
+            self.name,
+            self.fft_size,
+            self.hop,
+            self.window_function,
+            capacity,
+        )
+    }
+
+    /// Returns the name of the tap.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the reader for the frames pushed by the tap.
+    pub fn reader(&self) -> &FrameReader {
+        &self.reader
+    }
+
+    fn analyze(&mut self) -> Result<(), ProcessorError> {
+        // the oldest sample is at the write position
+        let (newer, older) = self.history.split_at(self.write_pos);
+        for ((input, sample), window) in self
+            .input
+            .iter_mut()
+            .zip(older.iter().chain(newer))
+            .zip(&self.window)
+        {
+            *input = sample * window;
+        }
+
+        self.plan
+            .process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+            .map_err(|e| ProcessorError::Fft(crate::fft::FftError::RealFft(e.to_string())))?;
+
+        let Some(mut frame) = self.reader.queue.take() else {
+            return Ok(());
+        };
+        let last = self.spectrum.len() - 1;
+        for (k, (magnitude, bin)) in frame.iter_mut().zip(&self.spectrum).enumerate() {
+            // DC and Nyquist have no mirrored negative-frequency bin
+            let scale = if k == 0 || k == last {
+                self.scale * 0.5
+            } else {
+                self.scale
+            };
+            *magnitude = bin.norm() * scale;
+        }
+        self.reader.queue.push(frame);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fft")]
+impl Tap for SpectrumTap {
+    fn tap_name(&self) -> &str {
+        &self.name
+    }
+
+    fn tap_reader(&self) -> TapReader {
+        TapReader::Spectrum(self.reader.clone())
+    }
+}
+
+#[cfg(feature = "fft")]
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for SpectrumTap {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("in", SignalType::Float)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.history.fill(0.0);
+        self.write_pos = 0;
+        self.since_hop = 0;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        _outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        if inputs.input(0).is_none() {
+            return Ok(());
+        }
+
+        for sample in inputs.iter_input_as::<Float>(0)? {
+            self.history[self.write_pos] = sample.unwrap_or_default();
+            self.write_pos = (self.write_pos + 1) % self.fft_size;
+            self.since_hop += 1;
+
+            if self.since_hop >= self.hop {
+                self.since_hop = 0;
+                self.analyze()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "fft", feature = "serde"))]
+impl serde::Serialize for SpectrumTap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct SpectrumTapSer<'a> {
+            name: &'a str,
+            fft_size: usize,
+            hop: usize,
+            window_function: &'a WindowFunction,
+            capacity: usize,
+        }
+
+        SpectrumTapSer {
+            name: &self.name,
+            fft_size: self.fft_size,
+            hop: self.hop,
+            window_function: &self.window_function,
+            capacity: self.reader.queue.capacity,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(all(feature = "fft", feature = "serde"))]
+impl<'de> serde::Deserialize<'de> for SpectrumTap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct SpectrumTapDe {
+            name: String,
+            fft_size: usize,
+            hop: usize,
+            window_function: WindowFunction,
+            capacity: usize,
+        }
+
+        let de = SpectrumTapDe::deserialize(deserializer)?;
+        Ok(SpectrumTap::with_config(
+            de.name,
+            de.fft_size,
+            de.hop,
+            de.window_function,
+            de.capacity,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtins::test_util::TestSource;
+    use std::time::Duration;
+
+    const SAMPLE_RATE: Float = 1000.0;
+
+    // renders `num_samples` sample indices through the tap and returns its reader
+    fn tap_indices(tap: impl Tap, name: &str, num_samples: usize) -> TapReader {
+        let graph = GraphBuilder::new();
+        let index = graph.add(TestSource::count());
+        let tap = graph.add_tap(tap);
+        index.output(0).connect(&tap.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let duration = Duration::from_secs_f64(num_samples as f64 / SAMPLE_RATE as f64);
+        // a block size that doesn't divide the frames or windows
+        runtime.run_offline(duration, SAMPLE_RATE, 7).unwrap();
+        runtime.tap_named(name).unwrap()
+    }
+
+    fn frames(reader: &FrameReader) -> Vec<Vec<Float>> {
+        let mut frames = Vec::new();
+        reader.read_each(|frame| frames.push(frame.to_vec()));
+        frames
+    }
+
+    #[test]
+    fn scope_frames_hold_consecutive_samples() {
+        let reader = tap_indices(ScopeTap::new("scope", 16).with_capacity(32), "scope", 200);
+        let frames = frames(reader.as_scope().unwrap());
+
+        // the last 8 samples don't fill a frame
+        assert_eq!(frames.len(), 12);
+        for (k, frame) in frames.iter().enumerate() {
+            let expected: Vec<Float> = (k * 16..(k + 1) * 16).map(|n| n as Float).collect();
+            assert_eq!(*frame, expected);
+        }
+    }
+
+    #[test]
+    fn scope_frames_are_decimated() {
+        let reader = tap_indices(ScopeTap::new("scope", 10).with_decimation(3), "scope", 100);
+        let frames = frames(reader.as_scope().unwrap());
+
+        assert_eq!(frames.len(), 3);
+        for (k, frame) in frames.iter().enumerate() {
+            let expected: Vec<Float> = (k * 10..(k + 1) * 10).map(|n| (n * 3) as Float).collect();
+            assert_eq!(*frame, expected);
+        }
+    }
+
+    #[test]
+    fn unread_scope_frames_are_overwritten_oldest_first() {
+        let reader = tap_indices(ScopeTap::new("scope", 10).with_capacity(2), "scope", 100);
+        let frames = frames(reader.as_scope().unwrap());
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0][0], 80.0);
+        assert_eq!(frames[1][0], 90.0);
+    }
+
+    #[test]
+    fn meter_reads_each_window() {
+        // windows of 10 samples
+        let meter = MeterTap::new("meter").with_window(0.01).with_capacity(32);
+        let reader = tap_indices(meter, "meter", 105);
+        let readings: Vec<MeterReading> = reader.as_meter().unwrap().try_iter().collect();
+
+        assert_eq!(readings.len(), 10);
+        for (k, reading) in readings.iter().enumerate() {
+            let window = k * 10..(k + 1) * 10;
+            let mean_sq = window.clone().map(|n| (n * n) as Float).sum::<Float>() / 10.0;
+            assert_eq!(reading.peak, window.end as Float - 1.0);
+            assert!((reading.rms - mean_sq.sqrt()).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn meter_reader_combines_unread_readings() {
+        let meter = MeterTap::new("meter").with_window(0.01).with_capacity(32);
+        let reader = tap_indices(meter, "meter", 20);
+        let reading = reader.as_meter().unwrap().read().unwrap();
+
+        let mean_sq = (0..20).map(|n| (n * n) as Float).sum::<Float>() / 20.0;
+        assert_eq!(reading.peak, 19.0);
+        assert!((reading.rms - mean_sq.sqrt()).abs() < 1e-3);
+        assert_eq!(reader.as_meter().unwrap().read(), None);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn spectrum_peaks_at_the_sine_frequency() {
+        const FFT_SIZE: usize = 64;
+        const BIN: usize = 8;
+
+        let graph = GraphBuilder::new();
+        let sine = graph.add(SineOscillator::new(
+            BIN as Float * SAMPLE_RATE / FFT_SIZE as Float,
+        ));
+        let tap = graph.add_tap(SpectrumTap::new("spectrum", FFT_SIZE).with_capacity(32));
+        sine.output(0).connect(&tap.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let duration = Duration::from_secs_f64(4.0 * FFT_SIZE as f64 / SAMPLE_RATE as f64);
+        runtime.run_offline(duration, SAMPLE_RATE, 7).unwrap();
+        let reader = runtime.tap_named("spectrum").unwrap();
+        let frames = frames(reader.as_spectrum().unwrap());
+
+        // a frame every half FFT size
+        assert_eq!(frames.len(), 8);
+        // once the history is full, the full-scale sine reads about 1.0 in its bin
+        for frame in &frames[1..] {
+            assert_eq!(frame.len(), FFT_SIZE / 2 + 1);
+            assert!((frame[BIN] - 1.0).abs() < 0.01);
+            for (k, &magnitude) in frame.iter().enumerate() {
+                if k.abs_diff(BIN) > 1 {
+                    assert!(magnitude < 0.01, "bin {k}: {magnitude}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Processors shared by the tests of the built-in processors.
+
+use crate::prelude::*;
+
+/// A source of scripted `Float` values for driving the processors under test.
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Float` | The scripted values, or the index of each sample. |
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TestSource {
+    // the values output in a loop, or empty to output the sample index
+    values: Vec<Float>,
+    index: usize,
+}
+
+impl TestSource {
+    /// Creates a source that outputs the index of each sample, starting at 0.
+    pub(crate) fn count() -> Self {
+        Self::default()
+    }
+
+    /// Creates a source that outputs the given values in a loop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no values.
+    pub(crate) fn repeat(values: impl IntoIterator<Item = Float>) -> Self {
+        let values: Vec<Float> = values.into_iter().collect();
+        assert!(!values.is_empty(), "TestSource needs at least one value");
+        Self { values, index: 0 }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for TestSource {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Float)]
+    }
+
+    fn process(
+        &mut self,
+        _inputs: ProcessorInputs,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for out in outputs.iter_output_mut_as::<Float>(0)? {
+            *out = Some(if self.values.is_empty() {
+                self.index as Float
+            } else {
+                self.values[self.index % self.values.len()]
+            });
+            self.index += 1;
+        }
+        Ok(())
+    }
+}
@@ -104,7 +104,8 @@ impl Processor for Metro {
 
 /// A processor that delays a signal by one sample.
 ///
-/// Note that feedback loops in a [`Graph`] implicitly introduce a delay of one sample, so this processor is not usually required to be used manually.
+/// Note that feedback loops in a [`Graph`] without any delaying processor implicitly introduce a delay of one sample, so this processor is not usually required to be used manually.
+/// Placing it in a feedback loop makes the delay explicit, which lets the runtime schedule the loop without relying on the implicit delay.
 ///
 /// # Inputs
 ///
@@ -121,6 +122,8 @@ impl Processor for Metro {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnitDelay {
     value: Option<Float>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    steady: bool,
 }
 
 impl UnitDelay {
@@ -140,13 +143,45 @@ impl Processor for UnitDelay {
         vec![SignalSpec::new("out", SignalType::Float)]
     }
 
+    fn is_steady(&self) -> bool {
+        self.steady
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        let mut last_out = None;
         for (in_signal, out) in iter_proc_io_as!(inputs as [Float], outputs as [Float]) {
             *out = self.value;
+            last_out = *out;
+            self.value = *in_signal;
+        }
+
+        self.steady = last_out == self.value;
+
+        Ok(())
+    }
+
+    fn feedback_delay(&self) -> usize {
+        1
+    }
+
+    fn process_delay_outputs(
+        &mut self,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for out in outputs.iter_output_mut_as::<Float>(0)? {
+            *out = self.value;
+        }
+
+        Ok(())
+    }
+
+    fn process_delay_inputs(&mut self, inputs: ProcessorInputs) -> Result<(), ProcessorError> {
+        let len = inputs.mode.sample_range(inputs.block_size).len();
+        if let Some(in_signal) = inputs.iter_input_as::<Float>(0)?.take(len).last() {
             self.value = *in_signal;
         }
 
@@ -156,6 +191,10 @@ impl Processor for UnitDelay {
 
 /// A processor that delays a signal by a number of samples.
 ///
+/// A minimum delay can be set with [`SampleDelay::with_min_delay`].
+/// Feedback loops through a `SampleDelay` with a minimum delay of `n` samples are processed in sub-blocks of up to `n` samples instead of sample by sample.
+/// Within such a loop, changes to the `delay` input take effect at the start of the next sub-block.
+///
 /// # Inputs
 ///
 /// | Index | Name | Type | Description |
@@ -173,6 +212,14 @@ impl Processor for UnitDelay {
 pub struct SampleDelay {
     ring_buffer: Vec<Float>,
     head: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    min_delay: usize,
+    // the delay used for the current sub-block of a feedback loop
+    #[cfg_attr(feature = "serde", serde(skip))]
+    delay: usize,
+    // the number of consecutive identical samples written to the ring buffer
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unchanged: usize,
 }
 
 impl SampleDelay {
@@ -182,13 +229,38 @@ impl SampleDelay {
         Self {
             ring_buffer,
             head: 0,
+            min_delay: 0,
+            delay: 0,
+            unchanged: 0,
         }
     }
 
+    /// Sets the minimum delay in samples. Smaller values of the `delay` input are clamped to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_delay` is not less than the maximum delay.
+    pub fn with_min_delay(mut self, min_delay: usize) -> Self {
+        assert!(
+            min_delay < self.ring_buffer.len(),
+            "Minimum delay must be less than the maximum delay"
+        );
+        self.min_delay = min_delay;
+        self.delay = min_delay;
+        self
+    }
+
     #[inline]
     fn index_modulo(&self, delay: usize) -> usize {
         (self.head + self.ring_buffer.len() - delay) % self.ring_buffer.len()
     }
+
+    #[inline]
+    fn write(&mut self, value: Float) {
+        let last = self.ring_buffer[self.index_modulo(1)];
+        self.unchanged = if value == last { self.unchanged + 1 } else { 0 };
+        self.ring_buffer[self.head] = value;
+    }
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
@@ -204,6 +276,11 @@ impl Processor for SampleDelay {
         vec![SignalSpec::new("out", SignalType::Float)]
     }
 
+    fn is_steady(&self) -> bool {
+        // once the whole ring buffer holds the same value, so will every delayed sample
+        self.unchanged >= self.ring_buffer.len()
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
@@ -215,9 +292,9 @@ impl Processor for SampleDelay {
         ) {
             let in_signal = in_signal.unwrap_or_default();
 
-            let delay = delay.unwrap_or_default() as usize;
+            let delay = (delay.unwrap_or_default() as usize).max(self.min_delay);
 
-            self.ring_buffer[self.head] = in_signal;
+            self.write(in_signal);
 
             let index = self.index_modulo(delay);
             *out = Some(self.ring_buffer[index]);
@@ -227,6 +304,153 @@ impl Processor for SampleDelay {
 
         Ok(())
     }
+
+    fn feedback_delay(&self) -> usize {
+        self.min_delay
+    }
+
+    fn process_delay_outputs(
+        &mut self,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        // the sub-block is no longer than `min_delay`, so every sample read here has already been written
+        let len = self.ring_buffer.len();
+        let delay = self.delay.max(self.min_delay);
+        for (i, out) in outputs.iter_output_mut_as::<Float>(0)?.enumerate() {
+            let index = (self.head + i + len - delay) % len;
+            *out = Some(self.ring_buffer[index]);
+        }
+
+        Ok(())
+    }
+
+    fn process_delay_inputs(&mut self, inputs: ProcessorInputs) -> Result<(), ProcessorError> {
+        let len = inputs.mode.sample_range(inputs.block_size).len();
+        for (in_signal, delay) in inputs
+            .iter_input_as::<Float>(0)?
+            .zip(inputs.iter_input_as::<i64>(1)?)
+            .take(len)
+        {
+            self.write(in_signal.unwrap_or_default());
+            self.head = (self.head + 1) % self.ring_buffer.len();
+
+            if let Some(delay) = delay {
+                self.delay = (*delay as usize).clamp(self.min_delay, self.ring_buffer.len() - 1);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A processor that delays a signal by a fixed number of samples, for closing feedback loops.
+///
+/// Unlike [`SampleDelay`], the delay cannot be modulated, so the runtime can always process feedback loops through this processor in sub-blocks as long as the delay.
+/// See [`GraphBuilder::feedback`] and [`Output::connect_feedback`] for convenient ways to create one.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `in` | `Float` | The input signal. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Float` | The delayed signal. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeedbackDelay {
+    ring_buffer: Vec<Float>,
+    head: usize,
+    // the number of consecutive identical samples written to the ring buffer
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unchanged: usize,
+}
+
+impl FeedbackDelay {
+    /// Creates a new `FeedbackDelay` processor with the given delay in samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is zero.
+    pub fn new(delay: usize) -> Self {
+        assert!(delay > 0, "Feedback delay must be at least one sample");
+        Self {
+            ring_buffer: vec![0.0; delay],
+            head: 0,
+            unchanged: 0,
+        }
+    }
+
+    /// Returns the delay in samples.
+    pub fn delay(&self) -> usize {
+        self.ring_buffer.len()
+    }
+
+    #[inline]
+    fn write(&mut self, value: Float) {
+        let len = self.ring_buffer.len();
+        let last = self.ring_buffer[(self.head + len - 1) % len];
+        self.unchanged = if value == last { self.unchanged + 1 } else { 0 };
+        self.ring_buffer[self.head] = value;
+        self.head = (self.head + 1) % len;
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for FeedbackDelay {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("in", SignalType::Float)]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Float)]
+    }
+
+    fn is_steady(&self) -> bool {
+        // once the whole ring buffer holds the same value, so will every delayed sample
+        self.unchanged >= self.ring_buffer.len()
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (in_signal, out) in iter_proc_io_as!(inputs as [Float], outputs as [Float]) {
+            *out = Some(self.ring_buffer[self.head]);
+            self.write(in_signal.unwrap_or_default());
+        }
+
+        Ok(())
+    }
+
+    fn feedback_delay(&self) -> usize {
+        self.ring_buffer.len()
+    }
+
+    fn process_delay_outputs(
+        &mut self,
+        mut outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let len = self.ring_buffer.len();
+        for (i, out) in outputs.iter_output_mut_as::<Float>(0)?.enumerate() {
+            *out = Some(self.ring_buffer[(self.head + i) % len]);
+        }
+
+        Ok(())
+    }
+
+    fn process_delay_inputs(&mut self, inputs: ProcessorInputs) -> Result<(), ProcessorError> {
+        let len = inputs.mode.sample_range(inputs.block_size).len();
+        for in_signal in inputs.iter_input_as::<Float>(0)?.take(len) {
+            self.write(in_signal.unwrap_or_default());
+        }
+
+        Ok(())
+    }
 }
 
 /// A processor that delays a signal by a number of samples with linear interpolation.
@@ -584,6 +808,11 @@ impl Processor for AREnv {
         vec![SignalSpec::new("out", SignalType::Float)]
     }
 
+    fn is_steady(&self) -> bool {
+        // the envelope only changes when the gate does, unless it is ramping
+        self.state == ADSRState::Sustain
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
@@ -696,6 +925,11 @@ impl Processor for ADSREnv {
         vec![SignalSpec::new("out", SignalType::Float)]
     }
 
+    fn is_steady(&self) -> bool {
+        // the envelope only changes when the gate does, unless it is ramping
+        self.state == ADSRState::Sustain
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
@@ -746,3 +980,99 @@ impl Processor for ADSREnv {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::builtins::test_util::TestSource;
+
+    // renders `y[n] = x[n] + 0.5 * y[n - delay]` as a feedback loop, returning `(x, y)`
+    fn render_comb(delay: usize, block_size: usize) -> (Box<[Float]>, Box<[Float]>) {
+        let graph = GraphBuilder::new();
+        let out_y = graph.add_audio_output();
+        let out_x = graph.add_audio_output();
+
+        let x = graph.add(SineOscillator::new(30.0));
+        let delayed = graph.add(Passthrough::new(SignalType::Float));
+        let y = x.clone() + delayed.clone() * 0.5;
+        y.output(0).connect_feedback(&delayed.input(0), delay);
+
+        y.output(0).connect(&out_y.input(0));
+        x.output(0).connect(&out_x.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let mut outputs = runtime
+            .run_offline(Duration::from_millis(100), 1000.0, block_size)
+            .unwrap();
+        (
+            std::mem::take(&mut outputs[1]),
+            std::mem::take(&mut outputs[0]),
+        )
+    }
+
+    // renders the input ramp 1, 2, 3, ... through the delay
+    #[cfg(feature = "serde")]
+    fn render_delayed(delay: impl Processor) -> Box<[Float]> {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let ramp = graph.add(TestSource::count()) + 1.0;
+        let delay = graph.add(delay);
+        delay.input(0).connect(ramp.output(0));
+        delay.output(0).connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let mut outputs = runtime
+            .run_offline(Duration::from_millis(8), 1000.0, 3)
+            .unwrap();
+        std::mem::take(&mut outputs[0])
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn loads_unit_delays_serialized_before_sleeping() {
+        let delay: UnitDelay = serde_json::from_str(r#"{"value":null}"#).unwrap();
+        assert_eq!(
+            render_delayed(delay)[..],
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn loads_sample_delays_serialized_before_minimum_delays() {
+        let delay: SampleDelay =
+            serde_json::from_str(r#"{"ring_buffer":[0.0,0.0,0.0,0.0],"head":0}"#).unwrap();
+        assert_eq!(
+            render_delayed(delay)[..],
+            [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]
+        );
+
+        let delay: SampleDelay =
+            serde_json::from_str(r#"{"ring_buffer":[0.0,0.0,0.0,0.0],"head":0,"min_delay":2}"#)
+                .unwrap();
+        assert_eq!(
+            render_delayed(delay)[..],
+            [0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn feedback_loop_matches_recursion() {
+        for delay in [1, 3] {
+            for block_size in [1, 4, 7, 64] {
+                let (x, y) = render_comb(delay, block_size);
+
+                let mut expected = vec![0.0; x.len()];
+                for n in 0..x.len() {
+                    let past = if n >= delay { expected[n - delay] } else { 0.0 };
+                    expected[n] = x[n] + past * 0.5;
+                }
+
+                assert_eq!(*y, *expected, "delay {delay}, block size {block_size}");
+            }
+        }
+    }
+}
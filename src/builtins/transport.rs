@@ -0,0 +1,714 @@
+//! Tempo, transport and clock processors.
+
+use crate::prelude::*;
+
+/// The number of MIDI clock messages per quarter note.
+pub const MIDI_CLOCK_PPQN: u32 = 24;
+
+// how quickly the tempo estimate follows the spacing of incoming MIDI clock messages (0 to 1)
+const CLOCK_SMOOTHING: f64 = 0.1;
+
+/// A musical time signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeSignature {
+    /// The number of beats per bar.
+    pub numerator: u8,
+    /// The note value of a beat (4 for quarter notes, 8 for eighth notes, etc.).
+    pub denominator: u8,
+}
+
+impl TimeSignature {
+    /// Creates a new time signature.
+    pub fn new(numerator: u8, denominator: u8) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Returns the length of a bar in quarter notes.
+    pub fn bar_length(&self) -> Float {
+        self.numerator as Float * 4.0 / self.denominator.max(1) as Float
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::new(4, 4)
+    }
+}
+
+/// A processor that keeps track of musical time: the tempo, whether the transport is playing, and the playhead position.
+///
+/// Positions are measured in quarter notes ("beats") from the start of the song. While playing, the position advances at the tempo given by `bpm`.
+///
+/// When MIDI clock messages (`0xF8`) arrive at the `midi` input, the transport follows them instead, estimating the tempo from their spacing and interpolating the position between them.
+/// MIDI start (`0xFA`), continue (`0xFB`), stop (`0xFC`) and song position pointer (`0xF2`) messages control playback in the same way as the `play` and `position` inputs.
+///
+/// Hosts that know their own transport state (see [`EmbeddedRuntime`]) can drive the `bpm`, `play` and `position` inputs with params instead.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `bpm` | `Float` | The tempo in quarter notes per minute, when not following MIDI clock. |
+/// | `1` | `play` | `Bool` | Starts (`true`) or stops (`false`) playback. |
+/// | `2` | `position` | `Float` | Moves the playhead to the given position in quarter notes when it changes. |
+/// | `3` | `midi` | `Midi` | MIDI clock and transport messages to follow. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `beat` | `Float` | The playhead position in quarter notes. |
+/// | `1` | `bar` | `Float` | The number of the current bar, counting from 0. |
+/// | `2` | `bpm` | `Float` | The current tempo. |
+/// | `3` | `playing` | `Bool` | Whether the transport is playing. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transport {
+    bpm: Float,
+    time_signature: TimeSignature,
+    playing: bool,
+    position: f64,
+    last_position_input: Option<Float>,
+
+    // MIDI clock state
+    synced: bool,
+    // the position at the last clock message
+    clock_position: f64,
+    // the estimated number of samples between clock messages
+    clock_period: Option<f64>,
+    samples_since_clock: Option<u64>,
+    // the first clock after a start message marks the start of the song and doesn't advance the position
+    skip_clock: bool,
+}
+
+impl Transport {
+    /// Creates a new `Transport` processor with the given tempo, which starts playing from the beginning.
+    pub fn new(bpm: Float) -> Self {
+        Self {
+            bpm,
+            time_signature: TimeSignature::default(),
+            playing: true,
+            position: 0.0,
+            last_position_input: None,
+            synced: false,
+            clock_position: 0.0,
+            clock_period: None,
+            samples_since_clock: None,
+            skip_clock: false,
+        }
+    }
+
+    /// Sets the time signature, which determines the `bar` output.
+    pub fn with_time_signature(mut self, time_signature: TimeSignature) -> Self {
+        self.time_signature = time_signature;
+        self
+    }
+
+    /// Sets whether the transport is playing when it starts.
+    pub fn with_playing(mut self, playing: bool) -> Self {
+        self.playing = playing;
+        self
+    }
+
+    fn locate(&mut self, position: f64) {
+        self.position = position;
+        self.clock_position = position;
+    }
+
+    fn handle_midi(&mut self, message: &MidiMessage, sample_rate: Float) {
+        match message.data[0] {
+            0xF8 => {
+                self.synced = true;
+                if let Some(samples) = self.samples_since_clock {
+                    let samples = samples.max(1) as f64;
+                    self.clock_period = Some(match self.clock_period {
+                        Some(period) => period + (samples - period) * CLOCK_SMOOTHING,
+                        None => samples,
+                    });
+                    if let Some(period) = self.clock_period {
+                        self.bpm = (60.0 * sample_rate as f64 / (period * MIDI_CLOCK_PPQN as f64))
+                            as Float;
+                    }
+                }
+                self.samples_since_clock = Some(0);
+
+                if self.playing {
+                    if self.skip_clock {
+                        self.skip_clock = false;
+                    } else {
+                        self.clock_position += 1.0 / MIDI_CLOCK_PPQN as f64;
+                    }
+                }
+            }
+            0xFA => {
+                self.synced = true;
+                self.playing = true;
+                self.skip_clock = true;
+                self.locate(0.0);
+            }
+            0xFB => {
+                self.synced = true;
+                self.playing = true;
+            }
+            0xFC => {
+                self.playing = false;
+                self.clock_position = self.position;
+            }
+            0xF2 => {
+                // the song position pointer counts sixteenth notes
+                let sixteenths =
+                    (message.data[1] as u16 & 0x7F) | ((message.data[2] as u16 & 0x7F) << 7);
+                self.locate(sixteenths as f64 / 4.0);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for Transport {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("bpm", SignalType::Float),
+            SignalSpec::new("play", SignalType::Bool),
+            SignalSpec::new("position", SignalType::Float),
+            SignalSpec::new("midi", SignalType::Midi),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("beat", SignalType::Float),
+            SignalSpec::new("bar", SignalType::Float),
+            SignalSpec::new("bpm", SignalType::Float),
+            SignalSpec::new("playing", SignalType::Bool),
+        ]
+    }
+
+    fn allocate(&mut self, _sample_rate: Float, _max_block_size: usize) {
+        self.locate(0.0);
+        self.clock_period = None;
+        self.samples_since_clock = None;
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        let sample_rate = inputs.sample_rate();
+        let bar_length = self.time_signature.bar_length() as f64;
+
+        for (bpm, play, position, midi, beat_out, bar_out, bpm_out, playing_out) in iter_proc_io_as!(
+            inputs as [Float, bool, Float, MidiMessage],
+            outputs as [Float, Float, Float, bool]
+        ) {
+            if let Some(play) = play {
+                if *play && !self.playing {
+                    self.clock_position = self.position;
+                }
+                self.playing = *play;
+            }
+            if *position != self.last_position_input {
+                self.last_position_input = *position;
+                if let Some(position) = position {
+                    self.locate(*position as f64);
+                }
+            }
+            if let Some(midi) = midi {
+                self.handle_midi(midi, sample_rate);
+            }
+
+            if self.synced {
+                // interpolate between clock messages, without passing the position of the next one
+                let fraction = match (self.clock_period, self.samples_since_clock) {
+                    (Some(period), Some(samples)) if self.playing && !self.skip_clock => {
+                        (samples as f64 / period).min(1.0)
+                    }
+                    _ => 0.0,
+                };
+                self.position = self.clock_position + fraction / MIDI_CLOCK_PPQN as f64;
+            } else if let Some(bpm) = bpm {
+                self.bpm = *bpm;
+            }
+
+            *beat_out = Some(self.position as Float);
+            *bar_out = Some((self.position / bar_length).floor() as Float);
+            *bpm_out = Some(self.bpm);
+            *playing_out = Some(self.playing);
+
+            if let Some(samples) = &mut self.samples_since_clock {
+                *samples += 1;
+            }
+            if self.playing && !self.synced {
+                self.position += self.bpm as f64 / 60.0 / sample_rate as f64;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A processor that outputs the phase of a position in beats within a repeating musical length, e.g. for tempo-synced LFOs.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `beat` | `Float` | The position in quarter notes, usually from a [`Transport`]. |
+/// | `1` | `length` | `Float` | The length of a cycle in quarter notes. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `phase` | `Float` | The phase within the current cycle, from 0 to 1. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BeatPhase {
+    length: Float,
+}
+
+impl BeatPhase {
+    /// Creates a new `BeatPhase` processor with the given cycle length in quarter notes.
+    pub fn new(length: Float) -> Self {
+        Self { length }
+    }
+}
+
+impl Default for BeatPhase {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for BeatPhase {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("beat", SignalType::Float),
+            SignalSpec::new("length", SignalType::Float),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("phase", SignalType::Float)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (beat, length, phase) in iter_proc_io_as!(
+            inputs as [Float, Float],
+            outputs as [Float]
+        ) {
+            self.length = length.unwrap_or(self.length);
+
+            *phase = beat.map(|beat| (beat / self.length.max(Float::EPSILON)).rem_euclid(1.0));
+        }
+
+        Ok(())
+    }
+}
+
+/// A processor that generates a single-sample pulse at regular musical intervals, locked to a position in beats.
+///
+/// A pulse is output whenever the position crosses a multiple of the interval, including when playback starts.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `beat` | `Float` | The position in quarter notes, usually from a [`Transport`]. |
+/// | `1` | `interval` | `Float` | The interval between pulses in quarter notes (e.g. `0.25` for sixteenth notes). |
+/// | `2` | `playing` | `Bool` | Whether to output pulses. Defaults to `true`. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Bool` | The pulse signal. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TempoMetro {
+    interval: Float,
+    // the index of the interval the position was in at the last sample
+    last: Option<i64>,
+}
+
+impl TempoMetro {
+    /// Creates a new `TempoMetro` processor with the given interval in quarter notes.
+    pub fn new(interval: Float) -> Self {
+        Self {
+            interval,
+            last: None,
+        }
+    }
+}
+
+impl Default for TempoMetro {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for TempoMetro {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("beat", SignalType::Float),
+            SignalSpec::new("interval", SignalType::Float),
+            SignalSpec::new("playing", SignalType::Bool),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Bool)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (beat, interval, playing, out) in iter_proc_io_as!(
+            inputs as [Float, Float, bool],
+            outputs as [bool]
+        ) {
+            *out = None;
+            self.interval = interval.unwrap_or(self.interval);
+
+            if !playing.unwrap_or(true) {
+                self.last = None;
+                continue;
+            }
+            let Some(beat) = beat else {
+                continue;
+            };
+
+            // a little slack, so that positions that should land exactly on a pulse aren't missed by rounding errors
+            let index = (beat / self.interval.max(Float::EPSILON) + 1e-6).floor() as i64;
+            if self.last != Some(index) {
+                self.last = Some(index);
+                *out = Some(true);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A processor that outputs every `divisor`-th pulse of a clock signal, starting with the first.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `clock` | `Bool` | The input clock pulses. |
+/// | `1` | `divisor` | `Int` | The number of input pulses per output pulse. |
+/// | `2` | `reset` | `Bool` | Resets the count, so that the next input pulse is output. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Bool` | The divided clock pulses. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockDivider {
+    divisor: i64,
+    count: i64,
+}
+
+impl ClockDivider {
+    /// Creates a new `ClockDivider` processor with the given divisor.
+    pub fn new(divisor: i64) -> Self {
+        Self { divisor, count: 0 }
+    }
+}
+
+impl Default for ClockDivider {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for ClockDivider {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("clock", SignalType::Bool),
+            SignalSpec::new("divisor", SignalType::Int),
+            SignalSpec::new("reset", SignalType::Bool),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Bool)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (clock, divisor, reset, out) in iter_proc_io_as!(
+            inputs as [bool, i64, bool],
+            outputs as [bool]
+        ) {
+            *out = None;
+            self.divisor = divisor.unwrap_or(self.divisor).max(1);
+
+            if let Some(true) = reset {
+                self.count = 0;
+            }
+
+            if let Some(true) = clock {
+                if self.count % self.divisor == 0 {
+                    *out = Some(true);
+                }
+                self.count = (self.count + 1) % self.divisor;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A processor that outputs `multiplier` evenly spaced pulses for every pulse of a clock signal.
+///
+/// The spacing is based on the time between the last two input pulses, so the output follows tempo changes one input pulse later.
+/// Until the second input pulse arrives, only the input pulses themselves are output.
+///
+/// # Inputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `clock` | `Bool` | The input clock pulses. |
+/// | `1` | `multiplier` | `Int` | The number of output pulses per input pulse. |
+///
+/// # Outputs
+///
+/// | Index | Name | Type | Description |
+/// | --- | --- | --- | --- |
+/// | `0` | `out` | `Bool` | The multiplied clock pulses. |
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockMultiplier {
+    multiplier: i64,
+    // the number of samples between the last two input pulses
+    period: Option<u64>,
+    // the number of samples since the last input pulse
+    since: Option<u64>,
+    // the number of pulses output since the last input pulse
+    emitted: i64,
+}
+
+impl ClockMultiplier {
+    /// Creates a new `ClockMultiplier` processor with the given multiplier.
+    pub fn new(multiplier: i64) -> Self {
+        Self {
+            multiplier,
+            period: None,
+            since: None,
+            emitted: 0,
+        }
+    }
+}
+
+impl Default for ClockMultiplier {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Processor for ClockMultiplier {
+    fn input_spec(&self) -> Vec<SignalSpec> {
+        vec![
+            SignalSpec::new("clock", SignalType::Bool),
+            SignalSpec::new("multiplier", SignalType::Int),
+        ]
+    }
+
+    fn output_spec(&self) -> Vec<SignalSpec> {
+        vec![SignalSpec::new("out", SignalType::Bool)]
+    }
+
+    fn process(
+        &mut self,
+        inputs: ProcessorInputs,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        for (clock, multiplier, out) in iter_proc_io_as!(
+            inputs as [bool, i64],
+            outputs as [bool]
+        ) {
+            *out = None;
+            self.multiplier = multiplier.unwrap_or(self.multiplier).max(1);
+
+            if let Some(true) = clock {
+                if let Some(since) = self.since {
+                    self.period = Some(since);
+                }
+                self.since = Some(0);
+                self.emitted = 1;
+                *out = Some(true);
+            } else if let (Some(period), Some(since)) = (self.period, self.since) {
+                if self.emitted < self.multiplier {
+                    let next = (period as f64 * self.emitted as f64 / self.multiplier as f64)
+                        .round() as u64;
+                    if since >= next {
+                        self.emitted += 1;
+                        *out = Some(true);
+                    }
+                }
+            }
+
+            if let Some(since) = &mut self.since {
+                *since += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const SAMPLE_RATE: Float = 1000.0;
+
+    // renders the beat, bar and bpm outputs of the transport
+    fn render(transport: Transport, seconds: f64, block_size: usize) -> Box<[Box<[Float]>]> {
+        let graph = GraphBuilder::new();
+        let transport = graph.add(transport);
+        for output in 0..3 {
+            let out = graph.add_audio_output();
+            transport.output(output).connect(&out.input(0));
+        }
+        graph
+            .build_runtime()
+            .run_offline(Duration::from_secs_f64(seconds), SAMPLE_RATE, block_size)
+            .unwrap()
+    }
+
+    #[test]
+    fn position_advances_at_the_tempo() {
+        let transport = || Transport::new(120.0).with_time_signature(TimeSignature::new(3, 4));
+        let expected = render(transport(), 3.0, 64);
+
+        // 120 bpm is 2 beats per second
+        for (n, &beat) in expected[0].iter().enumerate() {
+            assert!((beat - n as Float * 2.0 / SAMPLE_RATE).abs() < 1e-4);
+        }
+        // bars of 3 beats, away from the bar line where rounding could go either way
+        assert_eq!(expected[1][1000], 0.0);
+        assert_eq!(expected[1][2000], 1.0);
+        assert_eq!(expected[1][2999], 1.0);
+        assert!(expected[2].iter().all(|&bpm| bpm == 120.0));
+
+        for block_size in [1, 7, 100] {
+            assert_eq!(render(transport(), 3.0, block_size), expected);
+        }
+    }
+
+    #[test]
+    fn stopped_transport_holds_its_position() {
+        let outputs = render(Transport::new(120.0).with_playing(false), 0.5, 64);
+        assert!(outputs[0].iter().all(|&beat| beat == 0.0));
+    }
+
+    #[test]
+    fn tempo_changes_take_effect_from_the_next_block() {
+        let graph = GraphBuilder::new();
+        let bpm = graph.add_param(Param::new::<Float>("bpm", 120.0));
+        let transport = graph.add(Transport::default());
+        bpm.output(0).connect(&transport.input(0));
+        let beat = graph.add_audio_output();
+        transport.output(0).connect(&beat.input(0));
+        let bpm_out = graph.add_audio_output();
+        transport.output(2).connect(&bpm_out.input(0));
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(SAMPLE_RATE, 100);
+        let process = |runtime: &mut EmbeddedRuntime| {
+            let mut beat = [0.0f32; 100];
+            let mut bpm = [0.0f32; 100];
+            runtime
+                .process(&[], &mut [&mut beat, &mut bpm], &[])
+                .unwrap();
+            (beat, bpm)
+        };
+
+        let (beat, bpm) = process(&mut runtime);
+        assert!(bpm.iter().all(|&bpm| bpm == 120.0));
+        assert!((beat[99] - 99.0 * 0.002).abs() < 1e-5);
+
+        runtime.param_named("bpm").unwrap().send(60.0 as Float);
+        let (beat, bpm) = process(&mut runtime);
+        assert!(bpm.iter().all(|&bpm| bpm == 60.0));
+        // the first block ran at 2 beats per second, and the second at 1
+        for (n, &beat) in beat.iter().enumerate() {
+            assert!((beat - (0.2 + n as f32 * 0.001)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn follows_midi_clock() {
+        const CLOCK_PERIOD: usize = 20;
+
+        let graph = GraphBuilder::new();
+        let midi = graph.add_midi_input("midi");
+        let transport = graph.add(Transport::new(60.0));
+        midi.output(0).connect(&transport.input(3));
+        let beat = graph.add_audio_output();
+        transport.output(0).connect(&beat.input(0));
+        let bpm = graph.add_audio_output();
+        transport.output(2).connect(&bpm.input(0));
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(SAMPLE_RATE, 64);
+
+        // clock messages every 20 samples, rendered in blocks that don't line up with them
+        let total = 200;
+        let mut beats = vec![0.0f32; total];
+        let mut bpms = vec![0.0f32; total];
+        for start in (0..total).step_by(50) {
+            let clocks: Vec<MidiEvent> = (start..start + 50)
+                .filter(|n| n % CLOCK_PERIOD == 0)
+                .map(|n| MidiEvent::new(n - start, MidiMessage::new([0xF8, 0, 0])))
+                .collect();
+            let (beat, bpm) = (&mut beats[start..start + 50], &mut bpms[start..start + 50]);
+            runtime.process(&[], &mut [beat, bpm], &clocks).unwrap();
+        }
+
+        // 24 clocks per beat, 20 samples apart at 1000 Hz
+        let clock_bpm = 60.0 * SAMPLE_RATE as f32 / (CLOCK_PERIOD * 24) as f32;
+        assert!(bpms[..CLOCK_PERIOD].iter().all(|&bpm| bpm == 60.0));
+        assert!(bpms[CLOCK_PERIOD..]
+            .iter()
+            .all(|&bpm| (bpm - clock_bpm).abs() < 1e-3));
+
+        // the position moves by one clock per clock message, and is interpolated in between once the period is known
+        for (n, &beat) in beats.iter().enumerate().skip(CLOCK_PERIOD) {
+            let clocks =
+                (n / CLOCK_PERIOD + 1) as f32 + (n % CLOCK_PERIOD) as f32 / CLOCK_PERIOD as f32;
+            assert!((beat - clocks / 24.0).abs() < 1e-5, "{n}: {beat}");
+        }
+    }
+}
@@ -11,7 +11,7 @@ use super::lerp;
 
 /// A processor that does nothing.
 ///
-/// This is used for audio inputs to the graph, since a buffer will be allocated for it, which will be filled by the audio backend.
+/// This is used for inputs to the graph, since a buffer will be allocated for it, which will be filled by the audio backend or the enclosing [`SubGraph`].
 ///
 /// # Inputs
 ///
@@ -21,10 +21,26 @@ use super::lerp;
 ///
 /// | Index | Name | Type | Description |
 /// | --- | --- | --- | --- |
-/// | `0` | `out` | `Float` | The output signal. |
-#[derive(Clone, Debug, Default)]
+/// | `0` | `out` | `Any` | The output signal. |
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Null;
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Null {
+    signal_type: SignalType,
+}
+
+impl Null {
+    /// Create a new `Null` processor with the given output signal type.
+    pub fn new(signal_type: SignalType) -> Self {
+        Self { signal_type }
+    }
+}
+
+impl Default for Null {
+    fn default() -> Self {
+        Self::new(SignalType::Float)
+    }
+}
 
 #[cfg_attr(feature = "serde", typetag::serde)]
 impl Processor for Null {
@@ -33,7 +49,12 @@ impl Processor for Null {
     }
 
     fn output_spec(&self) -> Vec<SignalSpec> {
-        vec![SignalSpec::new("out", SignalType::Float)]
+        vec![SignalSpec::new("out", self.signal_type)]
+    }
+
+    fn is_steady(&self) -> bool {
+        // as a graph input, the output is written from outside the graph, and the runtime checks it for changes
+        true
     }
 
     fn process(
@@ -81,11 +102,20 @@ impl Processor for Passthrough {
         vec![SignalSpec::new("out", self.signal_type)]
     }
 
+    fn is_steady(&self) -> bool {
+        true
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
-        outputs: ProcessorOutputs,
+        mut outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        if let (Some(in_signal), Some(out)) = (inputs.dense_input(0), outputs.dense_output(0)) {
+            out.fill_dense(|out| out.copy_from_slice(in_signal));
+            return Ok(());
+        }
+
         for (in_signal, mut out_signal) in iter_proc_io_as!(inputs as [Any], outputs as [Any]) {
             if let Some(in_signal) = in_signal {
                 out_signal.clone_from_ref(in_signal);
@@ -134,13 +164,17 @@ impl Processor for Cast {
         vec![SignalSpec::new("out", self.to)]
     }
 
+    fn is_steady(&self) -> bool {
+        true
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
         for (in_signal, mut out_signal) in iter_proc_io_as!(inputs as [Any], outputs as [Any]) {
-            let Some(in_signal) = in_signal else {
+            let Some(in_signal) = in_signal.filter(|signal| signal.is_some()) else {
                 out_signal.set_none();
                 continue;
             };
@@ -470,12 +504,8 @@ impl Processor for Changed {
             self.threshold = threshold.unwrap_or(self.threshold);
 
             match (self.last, in_signal) {
-                (Some(last), Some(in_signal)) => {
-                    if (last - in_signal).abs() > self.threshold {
-                        *out_signal = Some(true);
-                    } else {
-                        *out_signal = None;
-                    }
+                (Some(last), Some(in_signal)) if (last - in_signal).abs() > self.threshold => {
+                    *out_signal = Some(true);
                 }
                 (None, Some(_)) if self.include_none => {
                     *out_signal = Some(true);
@@ -573,6 +603,11 @@ impl SignalTx {
     pub fn send(&self, message: AnySignal) {
         self.tx.try_send(message).ok();
     }
+
+    /// Sends a message to the receiver, returning `false` if it was dropped because the receiver's queue is full (see [`MIDI_PARAM_CAPACITY`]).
+    pub fn try_send(&self, message: AnySignal) -> bool {
+        self.tx.try_send(message).is_ok()
+    }
 }
 
 /// A processor that receives a signal from a corresponding [`SignalTx`] transmitter.
@@ -600,6 +635,10 @@ impl SignalRx {
     pub fn recv(&self) -> Option<AnySignal> {
         self.rx.try_recv().ok()
     }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rx.is_empty()
+    }
 }
 
 /// A wrapper around a [`SignalRx`] receiver that stores the last received message. Used as part of a [`Param`] processor.
@@ -636,6 +675,10 @@ impl ParamRx {
     pub fn last(&self) -> Option<AnySignal> {
         self.last.try_lock().ok()?.clone()
     }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rx.is_empty()
+    }
 }
 
 /// Creates a new set of connected [`SignalTx`] and [`SignalRx`] transmitters and receivers.
@@ -649,9 +692,25 @@ pub(crate) fn param_channel() -> (SignalTx, ParamRx) {
     (SignalTx::new(tx), ParamRx::new(SignalRx::new(rx)))
 }
 
+/// The number of messages a MIDI [`Param`] can queue before further messages are dropped.
+///
+/// MIDI params use a bounded channel, so that sending to them never allocates, e.g. from a host's audio callback.
+pub const MIDI_PARAM_CAPACITY: usize = 1024;
+
 #[derive(Clone, Debug)]
 struct ParamChannel(SignalTx, ParamRx);
 
+impl ParamChannel {
+    fn for_type(signal_type: SignalType) -> Self {
+        if signal_type == SignalType::Midi {
+            let (tx, rx) = crossbeam_channel::bounded(MIDI_PARAM_CAPACITY);
+            Self(SignalTx::new(tx), ParamRx::new(SignalRx::new(rx)))
+        } else {
+            Self::default()
+        }
+    }
+}
+
 impl Default for ParamChannel {
     fn default() -> Self {
         let (tx, rx) = param_channel();
@@ -661,6 +720,8 @@ impl Default for ParamChannel {
 
 /// A processor that can be used to control a parameter from outside the graph.
 ///
+/// The last value sent is held until a new one arrives, except for MIDI messages, which are events and are only output on the sample they arrive at.
+///
 /// # Inputs
 ///
 /// | Index | Name | Type | Description |
@@ -686,7 +747,7 @@ impl Param {
     pub fn new<S: Signal>(name: impl Into<String>, initial_value: impl Into<Option<S>>) -> Self {
         let this = Self {
             name: name.into(),
-            channel: ParamChannel::default(),
+            channel: ParamChannel::for_type(S::signal_type()),
             signal_type: S::signal_type(),
             minimum: None,
             maximum: None,
@@ -727,6 +788,16 @@ impl Param {
         self.signal_type
     }
 
+    /// Returns the minimum value of the parameter, if it is bounded.
+    pub fn minimum(&self) -> Option<Float> {
+        self.minimum
+    }
+
+    /// Returns the maximum value of the parameter, if it is bounded.
+    pub fn maximum(&self) -> Option<Float> {
+        self.maximum
+    }
+
     /// Returns the transmitter for the parameter.
     pub fn tx(&self) -> &SignalTx {
         &self.channel.0
@@ -744,7 +815,13 @@ impl Param {
 
     /// Sends a value to the parameter.
     pub fn send(&self, message: impl Signal) {
-        let message = message.into_any_signal();
+        self.send_any(message.into_any_signal());
+    }
+
+    /// Sends a type-erased value to the parameter.
+    ///
+    /// The value should be of the parameter's signal type; see [`AnySignal::cast`] for converting it.
+    pub fn send_any(&self, message: AnySignal) {
         match (message, self.minimum, self.maximum) {
             (AnySignal::Float(Some(value)), Some(min), Some(max)) => {
                 self.tx()
@@ -807,11 +884,18 @@ impl Processor for Param {
         vec![SignalSpec::new("get", self.signal_type)]
     }
 
+    fn is_steady(&self) -> bool {
+        // the output only changes when a new value is sent
+        self.rx().is_empty()
+    }
+
     fn process(
         &mut self,
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError> {
+        let hold = self.signal_type != SignalType::Midi;
+
         for (set, mut get) in iter_proc_io_as!(inputs as [Any], outputs as [Any]) {
             if let Some(set) = set {
                 self.tx().send(set.to_owned());
@@ -819,7 +903,7 @@ impl Processor for Param {
 
             if let Some(msg) = self.rx_mut().recv() {
                 get.clone_from_ref(msg.as_ref());
-            } else if let Some(last) = self.rx().last() {
+            } else if let Some(last) = self.rx().last().filter(|_| hold) {
                 get.clone_from_ref(last.as_ref());
             } else {
                 get.set_none();
@@ -872,7 +956,7 @@ impl<'de> serde::Deserialize<'de> for Param {
 
         let param = Param {
             name: de.name,
-            channel: ParamChannel::default(),
+            channel: ParamChannel::for_type(de.signal_type),
             signal_type: de.signal_type,
             minimum: de.minimum,
             maximum: de.maximum,
@@ -1327,3 +1411,65 @@ impl Processor for OrElse {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn midi_params_output_each_message_once() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let level = graph.add_param(Param::new::<Float>("level", None));
+        level.output(0).connect(&out.input(0));
+        let midi_out = graph.add_midi_output("out");
+        let midi = graph.add_midi_input("midi");
+        midi_out.input(0).connect(midi.output(0));
+
+        let mut runtime = graph.build_runtime();
+        let note_on = MidiMessage::note_on(0, 60, 100);
+        let params = runtime.graph();
+        params.param_named("level").unwrap().send(0.5 as Float);
+        params.param_named("midi").unwrap().send(note_on);
+
+        let (outputs, midi) = runtime
+            .run_offline_with_midi(Duration::from_millis(12), 1000.0, 4)
+            .unwrap();
+
+        // a MIDI message is an event, so it is output on a single sample
+        assert_eq!(
+            midi[0],
+            [MidiOutEvent {
+                time: 0,
+                message: note_on
+            }]
+        );
+        // other values are held until the next one arrives
+        assert_eq!(*outputs[0], [0.5; 12]);
+    }
+
+    #[test]
+    fn cast_passes_none_through() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let flag = graph.add_param(Param::new::<bool>("flag", None));
+        flag.output(0)
+            .cast(SignalType::Float)
+            .output(0)
+            .connect(&out.input(0));
+
+        let mut runtime = graph.build_runtime();
+        let outputs = runtime
+            .run_offline(Duration::from_millis(4), 1000.0, 4)
+            .unwrap();
+        assert_eq!(*outputs[0], [0.0; 4]);
+
+        runtime.graph().param_named("flag").unwrap().send(true);
+        let outputs = runtime
+            .run_offline(Duration::from_millis(4), 1000.0, 4)
+            .unwrap();
+        assert_eq!(*outputs[0], [1.0; 4]);
+    }
+}
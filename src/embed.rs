@@ -0,0 +1,520 @@
+//! An API for driving a [`Runtime`] from another audio engine, such as a plugin wrapper or a game engine's audio callback.
+//!
+//! The host calls [`EmbeddedRuntime::prepare`] whenever its sample rate or maximum block size changes,
+//! and [`EmbeddedRuntime::process`] from its audio callback with plain sample slices and the MIDI events for the block.
+
+use crate::{
+    graph::Graph,
+    prelude::{Param, SignalTx, TapReader},
+    runtime::{Runtime, RuntimeError, RuntimeResult},
+    signal::{Float, MidiMessage, Signal, SignalBuffer},
+};
+
+/// A MIDI message to be delivered at a sample offset within a block.
+///
+/// Messages at the same offset are delivered on consecutive samples. SysEx messages are delivered as one event per packet; see [`MidiMessage::sysex_packets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiEvent {
+    /// The index of the sample in the block at which the message arrives.
+    pub offset: usize,
+    /// The MIDI message.
+    pub message: MidiMessage,
+}
+
+impl MidiEvent {
+    /// Creates a new MIDI event at the given sample offset.
+    pub fn new(offset: usize, message: MidiMessage) -> Self {
+        Self { offset, message }
+    }
+}
+
+/// A [`Runtime`] that is driven by a host with plain sample slices.
+///
+/// The graph's audio inputs and outputs (see [`Graph::add_audio_input`] and [`Graph::add_audio_output`]) are mapped to the host's channels in order,
+/// and MIDI events are sent to all of the graph's MIDI inputs (see [`Graph::add_midi_input`]).
+///
+/// Blocks are split at the offsets of the MIDI events, so that each event arrives at its sample. Blocks longer than the prepared maximum block size are split as well.
+/// [`EmbeddedRuntime::process`] doesn't allocate, as long as the graph's processors don't.
+#[derive(Clone)]
+pub struct EmbeddedRuntime {
+    rt: Runtime,
+    midi_inputs: Vec<SignalTx>,
+    max_block_size: usize,
+    dropped_midi_events: usize,
+}
+
+impl EmbeddedRuntime {
+    /// Creates a new `EmbeddedRuntime` for the given graph.
+    pub fn new(graph: Graph) -> Self {
+        Self::from(Runtime::new(graph))
+    }
+
+    /// Returns the number of input channels the host must provide.
+    #[inline]
+    pub fn num_inputs(&self) -> usize {
+        self.rt.graph().num_audio_inputs()
+    }
+
+    /// Returns the number of output channels the host must provide.
+    #[inline]
+    pub fn num_outputs(&self) -> usize {
+        self.rt.graph().num_audio_outputs()
+    }
+
+    /// Returns the current sample rate.
+    #[inline]
+    pub fn sample_rate(&self) -> Float {
+        self.rt.sample_rate()
+    }
+
+    /// Returns the maximum block size the runtime was prepared for.
+    #[inline]
+    pub fn max_block_size(&self) -> usize {
+        self.max_block_size
+    }
+
+    /// Returns the number of MIDI events that were dropped because too many were waiting to be delivered (see [`EmbeddedRuntime::process`]).
+    #[inline]
+    pub fn dropped_midi_events(&self) -> usize {
+        self.dropped_midi_events
+    }
+
+    /// Returns a reference to the underlying runtime.
+    #[inline]
+    pub fn runtime(&self) -> &Runtime {
+        &self.rt
+    }
+
+    /// Returns a mutable reference to the underlying runtime.
+    #[inline]
+    pub fn runtime_mut(&mut self) -> &mut Runtime {
+        &mut self.rt
+    }
+
+    /// Returns a reference to the [`Param`] with the given name.
+    #[inline]
+    pub fn param_named(&self, name: &str) -> Option<&Param> {
+        self.rt.param_named(name)
+    }
+
+    /// Returns the reader of the tap with the given name (see [`Graph::add_tap`](crate::graph::Graph::add_tap)).
+    #[inline]
+    pub fn tap_named(&self, name: &str) -> Option<TapReader> {
+        self.rt.tap_named(name)
+    }
+
+    /// Prepares the runtime for the given sample rate and maximum block size.
+    ///
+    /// This allocates, so it must be called before processing starts and whenever the host's configuration changes, but not from the audio callback.
+    pub fn prepare(&mut self, sample_rate: Float, max_block_size: usize) {
+        self.max_block_size = max_block_size.max(1);
+        self.rt
+            .allocate_for_block_size(sample_rate, self.max_block_size);
+    }
+
+    /// Processes one block of audio.
+    ///
+    /// All input and output slices must have the same length. `midi` must be sorted by offset; events at or past the end of the block are delivered at its last sample.
+    ///
+    /// The graph's MIDI inputs output one message per sample, so events are delivered one per sample from their offset:
+    /// several events at the same offset arrive on consecutive samples, and the events after them are delayed until the earlier ones have been delivered, possibly into the next block.
+    /// At most [`MIDI_PARAM_CAPACITY`](crate::builtins::MIDI_PARAM_CAPACITY) events can be waiting to be delivered. Further events, e.g. from a large SysEx dump, are dropped and counted by [`EmbeddedRuntime::dropped_midi_events`].
+    ///
+    /// Returns an error if the number of channels doesn't match the graph, or if the runtime hasn't been prepared.
+    pub fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        midi: &[MidiEvent],
+    ) -> RuntimeResult<()> {
+        self.check_channels(inputs.len(), outputs.len())?;
+
+        let block_size = outputs
+            .first()
+            .map(|output| output.len())
+            .or_else(|| inputs.first().map(|input| input.len()))
+            .unwrap_or_default();
+        debug_assert!(inputs.iter().all(|input| input.len() == block_size));
+        debug_assert!(outputs.iter().all(|output| output.len() == block_size));
+
+        self.process_segments(
+            block_size,
+            midi,
+            |channel, start, buffer| {
+                for (sample, &value) in buffer.iter_mut().zip(&inputs[channel][start..]) {
+                    *sample = Some(value as Float);
+                }
+            },
+            |channel, start, buffer| {
+                for (value, sample) in outputs[channel][start..].iter_mut().zip(buffer) {
+                    *value = sample.unwrap_or_default() as f32;
+                }
+            },
+        )
+    }
+
+    /// Processes one block of interleaved audio, with [`EmbeddedRuntime::num_inputs`] channels in `input` and [`EmbeddedRuntime::num_outputs`] channels in `output`.
+    ///
+    /// The number of frames is taken from `output`, and `input` must have the same number of frames. See [`EmbeddedRuntime::process`] for how MIDI events are delivered.
+    ///
+    /// Returns an error if the lengths of the buffers don't match, or if the runtime hasn't been prepared.
+    pub fn process_interleaved(
+        &mut self,
+        input: &[f32],
+        output: &mut [f32],
+        midi: &[MidiEvent],
+    ) -> RuntimeResult<()> {
+        let num_inputs = self.num_inputs();
+        let num_outputs = self.num_outputs();
+        let num_frames = output
+            .len()
+            .checked_div(num_outputs)
+            .or_else(|| input.len().checked_div(num_inputs))
+            .unwrap_or_default();
+        if input.len() != num_frames * num_inputs {
+            return Err(RuntimeError::ChannelMismatch(
+                num_frames * num_inputs,
+                input.len(),
+            ));
+        }
+        if output.len() != num_frames * num_outputs {
+            return Err(RuntimeError::ChannelMismatch(
+                num_frames * num_outputs,
+                output.len(),
+            ));
+        }
+
+        self.process_segments(
+            num_frames,
+            midi,
+            |channel, start, buffer| {
+                let frames = input[start * num_inputs..].chunks_exact(num_inputs);
+                for (sample, frame) in buffer.iter_mut().zip(frames) {
+                    *sample = Some(frame[channel] as Float);
+                }
+            },
+            |channel, start, buffer| {
+                let frames = output[start * num_outputs..].chunks_exact_mut(num_outputs);
+                for (frame, sample) in frames.zip(buffer) {
+                    frame[channel] = sample.unwrap_or_default() as f32;
+                }
+            },
+        )
+    }
+
+    fn check_channels(&self, num_inputs: usize, num_outputs: usize) -> RuntimeResult<()> {
+        if num_inputs != self.num_inputs() {
+            return Err(RuntimeError::ChannelMismatch(self.num_inputs(), num_inputs));
+        }
+        if num_outputs != self.num_outputs() {
+            return Err(RuntimeError::ChannelMismatch(
+                self.num_outputs(),
+                num_outputs,
+            ));
+        }
+        Ok(())
+    }
+
+    // Runs the graph over `block_size` samples, in segments split at the MIDI events and at the maximum block size.
+    // `read_input` fills the graph's input buffer for a channel starting at a sample, and `write_output` copies an output buffer back.
+    fn process_segments(
+        &mut self,
+        block_size: usize,
+        midi: &[MidiEvent],
+        mut read_input: impl FnMut(usize, usize, &mut [Option<Float>]),
+        mut write_output: impl FnMut(usize, usize, &[Option<Float>]),
+    ) -> RuntimeResult<()> {
+        if self.max_block_size == 0 {
+            return Err(RuntimeError::NeedsAlloc);
+        }
+        if block_size == 0 {
+            return Ok(());
+        }
+
+        let mut events = midi.iter().peekable();
+        let mut start = 0;
+        while start < block_size {
+            // deliver the events due at this sample
+            while let Some(event) =
+                events.next_if(|event| event.offset.min(block_size - 1) <= start)
+            {
+                let mut delivered = true;
+                for tx in &self.midi_inputs {
+                    delivered &= tx.try_send(event.message.into_any_signal());
+                }
+                if !delivered {
+                    self.dropped_midi_events += 1;
+                }
+            }
+
+            let mut end = (start + self.max_block_size).min(block_size);
+            if let Some(event) = events.peek() {
+                end = end.min(event.offset.min(block_size - 1));
+            }
+            let len = end - start;
+
+            if len != self.rt.block_size() {
+                self.rt.set_block_size(len)?;
+            }
+
+            for i in 0..self.num_inputs() {
+                let Some(SignalBuffer::Float(buffer)) = self.rt.get_input_mut(i) else {
+                    return Err(RuntimeError::ChannelMismatch(self.num_inputs(), i));
+                };
+                read_input(i, start, &mut buffer[..len]);
+            }
+
+            self.rt.process()?;
+
+            for i in 0..self.num_outputs() {
+                let Some(SignalBuffer::Float(buffer)) = self.rt.get_output(i) else {
+                    return Err(RuntimeError::ChannelMismatch(self.num_outputs(), i));
+                };
+                write_output(i, start, &buffer[..len]);
+            }
+
+            start = end;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Runtime> for EmbeddedRuntime {
+    fn from(rt: Runtime) -> Self {
+        let midi_inputs = rt
+            .graph()
+            .midi_input_iter()
+            .map(|(_, param)| param.tx().clone())
+            .collect();
+        Self {
+            rt,
+            midi_inputs,
+            max_block_size: 0,
+            dropped_midi_events: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    // processes `total` samples with the host's varying block sizes, calling `f` with the range of each block
+    fn for_each_block(total: usize, mut f: impl FnMut(std::ops::Range<usize>)) {
+        let block_sizes = [256, 128, 200, 64, 37];
+        let mut position = 0;
+        for len in block_sizes.iter().cycle() {
+            if position >= total {
+                break;
+            }
+            let len = (*len).min(total - position);
+            f(position..position + len);
+            position += len;
+        }
+    }
+
+    #[test]
+    fn matches_run_offline() {
+        let graph = GraphBuilder::new();
+        let out_l = graph.add_audio_output();
+        let out_r = graph.add_audio_output();
+        let sine = graph.add(SineOscillator::new(440.0));
+        sine.output(0).connect(&out_l.input(0));
+        let saw = graph.add(BlSawOscillator::new(110.0)) * 0.3;
+        saw.output(0).connect(&out_r.input(0));
+        let graph = graph.build();
+
+        let expected = Runtime::new(graph.clone())
+            .run_offline(Duration::from_millis(100), 48_000.0, 64)
+            .unwrap();
+        let total = expected[0].len();
+
+        let mut runtime = EmbeddedRuntime::new(graph);
+        // smaller than some of the host's blocks, so those are split
+        runtime.prepare(48_000.0, 128);
+        let mut left = vec![0.0f32; total];
+        let mut right = vec![0.0f32; total];
+        for_each_block(total, |range| {
+            let (l, r) = (&mut left[range.clone()], &mut right[range]);
+            runtime.process(&[], &mut [l, r], &[]).unwrap();
+        });
+
+        for (actual, expected) in [left, right].iter().zip(expected.iter()) {
+            for (actual, expected) in actual.iter().zip(expected.iter()) {
+                assert_eq!(*actual, *expected as f32);
+            }
+        }
+    }
+
+    #[test]
+    fn interleaved_matches_planar() {
+        let graph = GraphBuilder::new();
+        let in_l = graph.add_audio_input();
+        let in_r = graph.add_audio_input();
+        let out_l = graph.add_audio_output();
+        let out_r = graph.add_audio_output();
+        let out_c = graph.add_audio_output();
+        let l = in_l.clone() * 0.5;
+        l.output(0).connect(&out_l.input(0));
+        let r = in_r.clone() * 0.25;
+        r.output(0).connect(&out_r.input(0));
+        let c = in_l - in_r;
+        c.output(0).connect(&out_c.input(0));
+        let graph = graph.build();
+
+        let total = 1000;
+        let input_l: Vec<f32> = (0..total).map(|i| (i as f32 * 0.01).sin()).collect();
+        let input_r: Vec<f32> = (0..total).map(|i| (i as f32 * 0.03).cos()).collect();
+
+        let mut planar = EmbeddedRuntime::new(graph.clone());
+        planar.prepare(48_000.0, 128);
+        let mut outputs = vec![vec![0.0f32; total]; 3];
+        for_each_block(total, |range| {
+            let [a, b, c] = &mut outputs[..] else {
+                unreachable!()
+            };
+            let outputs = &mut [
+                &mut a[range.clone()],
+                &mut b[range.clone()],
+                &mut c[range.clone()],
+            ];
+            planar
+                .process(&[&input_l[range.clone()], &input_r[range]], outputs, &[])
+                .unwrap();
+        });
+
+        let mut interleaved = EmbeddedRuntime::new(graph);
+        interleaved.prepare(48_000.0, 128);
+        let input: Vec<f32> = input_l
+            .iter()
+            .zip(&input_r)
+            .flat_map(|(&l, &r)| [l, r])
+            .collect();
+        let mut output = vec![0.0f32; total * 3];
+        for_each_block(total, |range| {
+            interleaved
+                .process_interleaved(
+                    &input[range.start * 2..range.end * 2],
+                    &mut output[range.start * 3..range.end * 3],
+                    &[],
+                )
+                .unwrap();
+        });
+
+        for (i, frame) in output.chunks_exact(3).enumerate() {
+            assert_eq!(frame, [outputs[0][i], outputs[1][i], outputs[2][i]]);
+        }
+        assert_eq!(outputs[0][10], input_l[10] * 0.5);
+        assert_eq!(outputs[2][10], input_l[10] - input_r[10]);
+    }
+
+    #[test]
+    fn midi_events_are_sample_accurate() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let midi = graph.add_midi_input("midi");
+        let note = graph.add(MidiNote::default());
+        note.input(0).connect(midi.output(0));
+        note.output(0).connect(&out.input(0));
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(48_000.0, 64);
+
+        let note_on = |note| MidiMessage::new([0x90, note, 100]);
+        // (block length, events) for each block
+        let blocks = [
+            (100, vec![MidiEvent::new(37, note_on(60))]),
+            // two events at the same offset are delivered on consecutive samples
+            (
+                100,
+                vec![
+                    MidiEvent::new(0, note_on(62)),
+                    MidiEvent::new(0, note_on(64)),
+                ],
+            ),
+            // events past the end of the block are delivered at its last sample
+            (100, vec![MidiEvent::new(250, note_on(67))]),
+        ];
+
+        let mut output = Vec::new();
+        for (len, events) in &blocks {
+            let mut block = vec![0.0f32; *len];
+            runtime.process(&[], &mut [&mut block], events).unwrap();
+            output.extend(block);
+        }
+
+        let expected: Vec<f32> = (0..300)
+            .map(|i| match i {
+                0..37 => 0.0,
+                37..100 => 60.0,
+                100 => 62.0,
+                101..299 => 64.0,
+                _ => 67.0,
+            })
+            .collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn counts_midi_events_that_overflow_the_inputs() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let midi = graph.add_midi_input("midi");
+        let note = graph.add(MidiNote::default());
+        note.input(0).connect(midi.output(0));
+        note.output(0).connect(&out.input(0));
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(48_000.0, 64);
+
+        // more events at one offset than the inputs can queue
+        let events: Vec<MidiEvent> = (0..MIDI_PARAM_CAPACITY + 10)
+            .map(|i| MidiEvent::new(0, MidiMessage::new([0x90, (i % 128) as u8, 100])))
+            .collect();
+        let mut block = [0.0f32; 64];
+        runtime.process(&[], &mut [&mut block], &events).unwrap();
+        assert_eq!(runtime.dropped_midi_events(), 10);
+
+        // the queued events keep being delivered one per sample
+        assert_eq!(block[..3], [0.0, 1.0, 2.0]);
+        runtime.process(&[], &mut [&mut block], &[]).unwrap();
+        assert_eq!(block[0], 64.0);
+        assert_eq!(runtime.dropped_midi_events(), 10);
+    }
+
+    #[test]
+    fn inputs_wake_up_when_a_block_starts_with_the_held_value() {
+        let graph = GraphBuilder::new();
+        let input = graph.add_audio_input();
+        let output = graph.add_audio_output();
+        let doubled = input * 2.0;
+        doubled.output(0).connect(&output.input(0));
+
+        let mut runtime = EmbeddedRuntime::new(graph.build());
+        runtime.prepare(48_000.0, 64);
+
+        // silence puts the input to sleep
+        let silence = [0.0f32; 64];
+        let mut out = [0.0f32; 64];
+        for _ in 0..3 {
+            runtime.process(&[&silence], &mut [&mut out], &[]).unwrap();
+        }
+
+        // a sine starting at 0 begins with the held value, but must still reach the output
+        let mut phase = 0;
+        for _ in 0..4 {
+            let sine: Vec<f32> = (phase..phase + 64)
+                .map(|n| (std::f32::consts::TAU * 440.0 * n as f32 / 48_000.0).sin())
+                .collect();
+            phase += 64;
+
+            runtime.process(&[&sine], &mut [&mut out], &[]).unwrap();
+            for (out, sine) in out.iter().zip(&sine) {
+                assert_eq!(*out, sine * 2.0);
+            }
+        }
+        assert!(out.iter().any(|out| out.abs() > 1.9));
+    }
+}
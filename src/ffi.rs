@@ -0,0 +1,457 @@
+//! A C API for using raug from other languages.
+//!
+//! Graphs are loaded from patch files, which are graphs serialized as JSON with the `serde` feature (e.g. with `serde_json::to_writer(file, &graph)`).
+//! They are run through an [`EmbeddedRuntime`], so the host drives processing from its own audio callback with interleaved `float` buffers.
+//!
+//! The shared library is built by the `raug-ffi` crate in this workspace, as `libraug_ffi`:
+//!
+//! ```sh
+//! cargo build --release -p raug-ffi
+//! ```
+//!
+//! The header for this API is `include/raug.h`, generated with [cbindgen](https://github.com/mozilla/cbindgen) from this module:
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/raug.h src/ffi.rs
+//! ```
+//!
+//! Functions that can fail return a [`RaugStatus`], or a null pointer, and set a message that can be read with [`raug_last_error`] on the same thread.
+//! Panics are caught at the API boundary and reported as [`RaugStatus::Panic`].
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_double, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    ptr,
+};
+
+use crate::{
+    embed::{EmbeddedRuntime, MidiEvent},
+    graph::Graph,
+    runtime::RuntimeError,
+    signal::{Float, MidiMessage, Signal},
+};
+
+/// The result of a call to the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaugStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8,
+    /// No param with the given name exists.
+    ParamNotFound,
+    /// The param has a different signal type than the value.
+    TypeMismatch,
+    /// The buffers didn't match the number of channels of the graph.
+    ChannelMismatch,
+    /// [`raug_runtime_prepare`] hasn't been called.
+    NotPrepared,
+    /// An error occurred while processing the graph.
+    Process,
+    /// A panic occurred inside raug.
+    Panic,
+}
+
+/// A graph loaded from a patch, ready to be processed by the host.
+///
+/// This is an opaque type; it is created with [`raug_runtime_from_patch_file`] or [`raug_runtime_from_patch_json`], and must be freed with [`raug_runtime_free`].
+pub struct RaugRuntime {
+    rt: EmbeddedRuntime,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+fn runtime_status(error: RuntimeError) -> RaugStatus {
+    let status = match error {
+        RuntimeError::ChannelMismatch(..) => RaugStatus::ChannelMismatch,
+        RuntimeError::NeedsAlloc => RaugStatus::NotPrepared,
+        _ => RaugStatus::Process,
+    };
+    set_last_error(error);
+    status
+}
+
+// Runs `f`, turning panics into `RaugStatus::Panic`.
+fn guard(f: impl FnOnce() -> RaugStatus) -> RaugStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(status) => status,
+        Err(_) => {
+            set_last_error("panic in raug");
+            RaugStatus::Panic
+        }
+    }
+}
+
+// Reads a C string argument, recording an error if it is null or not UTF-8.
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, RaugStatus> {
+    if s.is_null() {
+        set_last_error("null string argument");
+        return Err(RaugStatus::NullPointer);
+    }
+    // SAFETY: the caller guarantees that `s` is a valid, nul-terminated string
+    unsafe { CStr::from_ptr(s) }.to_str().map_err(|e| {
+        set_last_error(e);
+        RaugStatus::InvalidUtf8
+    })
+}
+
+fn runtime_from_json(json: &str) -> *mut RaugRuntime {
+    let result = catch_unwind(|| serde_json::from_str::<Graph>(json));
+    match result {
+        Ok(Ok(graph)) => Box::into_raw(Box::new(RaugRuntime {
+            rt: EmbeddedRuntime::new(graph),
+        })),
+        Ok(Err(e)) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set_last_error("panic in raug");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns a message describing the last error that occurred on the calling thread, or null if there was none.
+///
+/// The string is owned by raug and stays valid until the next call into raug on the same thread.
+#[no_mangle]
+pub extern "C" fn raug_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Loads a graph from the patch file at `path`.
+///
+/// Returns null on failure; see [`raug_last_error`].
+///
+/// # Safety
+///
+/// `path` must be null or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_from_patch_file(path: *const c_char) -> *mut RaugRuntime {
+    // SAFETY: forwarded from the caller
+    let path = match unsafe { str_arg(path) } {
+        Ok(path) => path,
+        Err(_) => return ptr::null_mut(),
+    };
+    match std::fs::read_to_string(Path::new(path)) {
+        Ok(json) => runtime_from_json(&json),
+        Err(e) => {
+            set_last_error(format!("{path}: {e}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Loads a graph from a patch given as a JSON string.
+///
+/// Returns null on failure; see [`raug_last_error`].
+///
+/// # Safety
+///
+/// `json` must be null or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_from_patch_json(json: *const c_char) -> *mut RaugRuntime {
+    // SAFETY: forwarded from the caller
+    match unsafe { str_arg(json) } {
+        Ok(json) => runtime_from_json(json),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Frees a runtime. Does nothing if `runtime` is null.
+///
+/// # Safety
+///
+/// `runtime` must be null or a pointer returned by one of the `raug_runtime_from_*` functions that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_free(runtime: *mut RaugRuntime) {
+    if !runtime.is_null() {
+        // SAFETY: the caller guarantees that `runtime` came from `Box::into_raw` and is only freed once
+        drop(unsafe { Box::from_raw(runtime) });
+    }
+}
+
+/// Returns the number of interleaved input channels the graph expects, or 0 if `runtime` is null.
+///
+/// # Safety
+///
+/// `runtime` must be null or a valid runtime.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_num_inputs(runtime: *const RaugRuntime) -> usize {
+    // SAFETY: the caller guarantees that `runtime` is null or valid
+    unsafe { runtime.as_ref() }.map_or(0, |runtime| runtime.rt.num_inputs())
+}
+
+/// Returns the number of interleaved output channels the graph produces, or 0 if `runtime` is null.
+///
+/// # Safety
+///
+/// `runtime` must be null or a valid runtime.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_num_outputs(runtime: *const RaugRuntime) -> usize {
+    // SAFETY: the caller guarantees that `runtime` is null or valid
+    unsafe { runtime.as_ref() }.map_or(0, |runtime| runtime.rt.num_outputs())
+}
+
+/// Prepares the runtime for the given sample rate and maximum number of frames per call to [`raug_runtime_process_interleaved`].
+///
+/// This allocates, so it must not be called from the audio callback. Larger blocks are still accepted, but are processed in several steps.
+///
+/// # Safety
+///
+/// `runtime` must be null or a valid runtime.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_prepare(
+    runtime: *mut RaugRuntime,
+    sample_rate: c_double,
+    max_block_size: usize,
+) -> RaugStatus {
+    // SAFETY: the caller guarantees that `runtime` is null or valid
+    let Some(runtime) = (unsafe { runtime.as_mut() }) else {
+        set_last_error("null runtime");
+        return RaugStatus::NullPointer;
+    };
+    guard(|| {
+        runtime.rt.prepare(sample_rate as Float, max_block_size);
+        RaugStatus::Ok
+    })
+}
+
+// Sends `value` to the param named `name`, if the param has the same signal type.
+unsafe fn set_param<S: Signal>(
+    runtime: *mut RaugRuntime,
+    name: *const c_char,
+    value: S,
+) -> RaugStatus {
+    // SAFETY: the caller guarantees that `runtime` is null or valid
+    let Some(runtime) = (unsafe { runtime.as_mut() }) else {
+        set_last_error("null runtime");
+        return RaugStatus::NullPointer;
+    };
+    // SAFETY: forwarded from the caller
+    let name = match unsafe { str_arg(name) } {
+        Ok(name) => name,
+        Err(status) => return status,
+    };
+    guard(|| match runtime.rt.param_named(name) {
+        Some(param) if param.signal_type() == S::signal_type() => {
+            param.send(value);
+            RaugStatus::Ok
+        }
+        Some(param) => {
+            set_last_error(format!(
+                "param {name} is of type {:?}, not {:?}",
+                param.signal_type(),
+                S::signal_type()
+            ));
+            RaugStatus::TypeMismatch
+        }
+        None => {
+            set_last_error(format!("no param named {name}"));
+            RaugStatus::ParamNotFound
+        }
+    })
+}
+
+/// Sets the `Float` param named `name` to `value`. The new value takes effect at the start of the next processed sample.
+///
+/// Returns [`RaugStatus::TypeMismatch`] if the param is not a `Float` param; use [`raug_runtime_set_param_int`] or [`raug_runtime_set_param_bool`] for `Int` and `Bool` params.
+///
+/// # Safety
+///
+/// `runtime` must be null or a valid runtime, and `name` must be null or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_set_param(
+    runtime: *mut RaugRuntime,
+    name: *const c_char,
+    value: c_double,
+) -> RaugStatus {
+    // SAFETY: forwarded from the caller
+    unsafe { set_param(runtime, name, value as Float) }
+}
+
+/// Sets the `Int` param named `name` to `value`. The new value takes effect at the start of the next processed sample.
+///
+/// Returns [`RaugStatus::TypeMismatch`] if the param is not an `Int` param.
+///
+/// # Safety
+///
+/// `runtime` must be null or a valid runtime, and `name` must be null or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_set_param_int(
+    runtime: *mut RaugRuntime,
+    name: *const c_char,
+    value: i64,
+) -> RaugStatus {
+    // SAFETY: forwarded from the caller
+    unsafe { set_param(runtime, name, value) }
+}
+
+/// Sets the `Bool` param named `name` to `value`. The new value takes effect at the start of the next processed sample.
+///
+/// Returns [`RaugStatus::TypeMismatch`] if the param is not a `Bool` param.
+///
+/// # Safety
+///
+/// `runtime` must be null or a valid runtime, and `name` must be null or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_set_param_bool(
+    runtime: *mut RaugRuntime,
+    name: *const c_char,
+    value: bool,
+) -> RaugStatus {
+    // SAFETY: forwarded from the caller
+    unsafe { set_param(runtime, name, value) }
+}
+
+/// Processes `num_frames` frames of interleaved audio.
+///
+/// `input` must hold `num_frames * raug_runtime_num_inputs(runtime)` samples and `output` must have room for `num_frames * raug_runtime_num_outputs(runtime)` samples.
+/// Either may be null if the corresponding channel count is 0.
+///
+/// `midi_offsets` holds the frame offsets of `num_midi` MIDI events in ascending order, and `midi_bytes` their 3-byte messages (`3 * num_midi` bytes); both may be null if `num_midi` is 0.
+/// Shorter messages are padded with zeros, and SysEx messages are passed as consecutive events, one per packet: a byte with the number of message bytes in the packet (1 or 2), followed by those bytes.
+/// This doesn't allocate as long as the graph's processors don't and `num_midi` is at most 64.
+///
+/// # Safety
+///
+/// The pointers must be valid for the lengths above, and `runtime` must be null or a valid runtime.
+#[no_mangle]
+pub unsafe extern "C" fn raug_runtime_process_interleaved(
+    runtime: *mut RaugRuntime,
+    input: *const f32,
+    output: *mut f32,
+    num_frames: usize,
+    midi_offsets: *const usize,
+    midi_bytes: *const u8,
+    num_midi: usize,
+) -> RaugStatus {
+    // SAFETY: the caller guarantees that `runtime` is null or valid
+    let Some(runtime) = (unsafe { runtime.as_mut() }) else {
+        set_last_error("null runtime");
+        return RaugStatus::NullPointer;
+    };
+    let num_inputs = num_frames * runtime.rt.num_inputs();
+    let num_outputs = num_frames * runtime.rt.num_outputs();
+    if (input.is_null() && num_inputs > 0)
+        || (output.is_null() && num_outputs > 0)
+        || ((midi_offsets.is_null() || midi_bytes.is_null()) && num_midi > 0)
+    {
+        set_last_error("null buffer");
+        return RaugStatus::NullPointer;
+    }
+
+    // SAFETY: the caller guarantees that the buffers are valid for these lengths, and they are non-null unless empty
+    let (input, output, midi_offsets, midi_bytes) = unsafe {
+        (
+            slice_or_empty(input, num_inputs),
+            slice_or_empty_mut(output, num_outputs),
+            slice_or_empty(midi_offsets, num_midi),
+            slice_or_empty(midi_bytes, num_midi * 3),
+        )
+    };
+
+    guard(|| {
+        let midi: smallvec::SmallVec<[_; 64]> = midi_offsets
+            .iter()
+            .zip(midi_bytes.chunks_exact(3))
+            .map(|(&offset, bytes)| {
+                MidiEvent::new(offset, MidiMessage::new([bytes[0], bytes[1], bytes[2]]))
+            })
+            .collect();
+
+        match runtime.rt.process_interleaved(input, output, &midi) {
+            Ok(()) => RaugStatus::Ok,
+            Err(e) => runtime_status(e),
+        }
+    })
+}
+
+unsafe fn slice_or_empty<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        // SAFETY: the caller guarantees that `ptr` is valid for `len` elements
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+}
+
+unsafe fn slice_or_empty_mut<'a, T>(ptr: *mut T, len: usize) -> &'a mut [T] {
+    if len == 0 {
+        &mut []
+    } else {
+        // SAFETY: the caller guarantees that `ptr` is valid for `len` elements
+        unsafe { std::slice::from_raw_parts_mut(ptr, len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn params_are_set_with_their_own_type() {
+        let graph = GraphBuilder::new();
+        graph.add_param(Param::new::<Float>("gain", 0.5));
+        graph.add_param(Param::new::<i64>("steps", 1));
+        graph.add_param(Param::new::<bool>("mute", false));
+        let json = CString::new(serde_json::to_string(&graph.build()).unwrap()).unwrap();
+
+        // SAFETY: the runtime is valid until it is freed, and the names are nul-terminated
+        unsafe {
+            let rt = raug_runtime_from_patch_json(json.as_ptr());
+            assert!(!rt.is_null());
+
+            assert_eq!(
+                raug_runtime_set_param(rt, c"gain".as_ptr(), 2.0),
+                RaugStatus::Ok
+            );
+            assert_eq!(
+                raug_runtime_set_param_int(rt, c"steps".as_ptr(), 3),
+                RaugStatus::Ok
+            );
+            assert_eq!(
+                raug_runtime_set_param_bool(rt, c"mute".as_ptr(), true),
+                RaugStatus::Ok
+            );
+
+            for status in [
+                raug_runtime_set_param(rt, c"steps".as_ptr(), 2.0),
+                raug_runtime_set_param(rt, c"mute".as_ptr(), 1.0),
+                raug_runtime_set_param_int(rt, c"gain".as_ptr(), 2),
+                raug_runtime_set_param_bool(rt, c"steps".as_ptr(), true),
+            ] {
+                assert_eq!(status, RaugStatus::TypeMismatch);
+            }
+            let error = CStr::from_ptr(raug_last_error()).to_str().unwrap();
+            assert_eq!(error, "param steps is of type Int, not Bool");
+
+            assert_eq!(
+                raug_runtime_set_param_int(rt, c"missing".as_ptr(), 1),
+                RaugStatus::ParamNotFound
+            );
+
+            raug_runtime_free(rt);
+        }
+    }
+}
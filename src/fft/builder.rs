@@ -109,9 +109,9 @@ impl FftGraphBuilder {
             imag.output_spec()[0].signal_type,
             FftSignalType::RealBuf(_)
         ));
-        let node = self.add(ComplexToPolar::default());
-        self.connect(&real, 0, &node, 0);
-        self.connect(&imag, 0, &node, 1);
+        let node = self.add(ComplexToPolar);
+        self.connect(real, 0, &node, 0);
+        self.connect(imag, 0, &node, 1);
         let mag = node.output(0).make_node();
         let phase = node.output(1).make_node();
         (mag, phase)
@@ -130,8 +130,8 @@ impl FftGraphBuilder {
             FftSignalType::RealBuf(_)
         ));
         let node = self.add(ComplexFromPolar);
-        self.connect(&mag, 0, &node, 0);
-        self.connect(&phase, 0, &node, 1);
+        self.connect(mag, 0, &node, 0);
+        self.connect(phase, 0, &node, 1);
         node
     }
 }
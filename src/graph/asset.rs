@@ -2,7 +2,7 @@ use std::sync::{Arc, Mutex, MutexGuard};
 
 use rustc_hash::FxHashMap;
 
-use crate::prelude::*;
+use crate::{prelude::*, resample::ResampleQuality};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -39,25 +39,78 @@ impl<'a> AssetRef<'a> {
     }
 }
 
+/// A buffer that is resampled to the runtime's sample rate when the graph is allocated.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ResampleOnLoad {
+    original: Buffer<Float>,
+    sample_rate: Float,
+    quality: ResampleQuality,
+    // the sample rate the asset was last resampled to; not serialized, so deserialized assets are resampled again
+    #[cfg_attr(feature = "serde", serde(skip))]
+    resampled_to: Option<Float>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Assets {
     assets: FxHashMap<String, Arc<Mutex<Asset>>>,
+    resample: FxHashMap<String, ResampleOnLoad>,
 }
 
 impl Assets {
     pub fn new() -> Self {
         Self {
             assets: FxHashMap::default(),
+            resample: FxHashMap::default(),
         }
     }
 
-    pub fn get(&self, name: &str) -> Option<AssetRef> {
+    pub fn get(&self, name: &str) -> Option<AssetRef<'_>> {
         self.assets.get(name).map(AssetRef)
     }
 
     pub fn insert(&mut self, name: String, asset: Asset) {
+        self.resample.remove(&name);
         self.assets.insert(name, Arc::new(Mutex::new(asset)));
     }
+
+    pub fn insert_resampled(
+        &mut self,
+        name: String,
+        buffer: Buffer<Float>,
+        sample_rate: Float,
+        quality: ResampleQuality,
+    ) {
+        self.insert(name.clone(), Asset::Buffer(buffer.clone()));
+        self.resample.insert(
+            name,
+            ResampleOnLoad {
+                original: buffer,
+                sample_rate,
+                quality,
+                resampled_to: None,
+            },
+        );
+    }
+
+    /// Resamples the buffers added with [`Assets::insert_resampled`] to the given sample rate, if they aren't already.
+    pub(crate) fn resample_to(&mut self, sample_rate: Float) {
+        for (name, entry) in self.resample.iter_mut() {
+            if entry.resampled_to == Some(sample_rate) {
+                continue;
+            }
+            entry.resampled_to = Some(sample_rate);
+
+            let buffer = if entry.sample_rate == sample_rate {
+                entry.original.clone()
+            } else {
+                entry
+                    .original
+                    .resample(entry.sample_rate, sample_rate, entry.quality)
+            };
+            *self.assets[name].lock().unwrap() = Asset::Buffer(buffer);
+        }
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -65,26 +118,86 @@ mod serde_impl {
     use super::*;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+    #[derive(Serialize)]
+    struct AssetsSer<'a> {
+        assets: FxHashMap<&'a String, Asset>,
+        resample: &'a FxHashMap<String, ResampleOnLoad>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum AssetsDe {
+        WithResample {
+            assets: FxHashMap<String, Asset>,
+            resample: FxHashMap<String, ResampleOnLoad>,
+        },
+        // graphs serialized before resampled assets were
+        Plain(FxHashMap<String, Asset>),
+    }
+
     impl Serialize for Assets {
         fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            // resampled assets are stored as their originals, and resampled again when the graph is allocated
             let assets = self
                 .assets
                 .iter()
+                .filter(|(name, _)| !self.resample.contains_key(*name))
                 .map(|(name, asset)| (name, asset.lock().unwrap().clone()))
-                .collect::<FxHashMap<_, _>>();
+                .collect();
 
-            assets.serialize(serializer)
+            AssetsSer {
+                assets,
+                resample: &self.resample,
+            }
+            .serialize(serializer)
         }
     }
 
     impl<'de> Deserialize<'de> for Assets {
         fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-            let assets = FxHashMap::deserialize(deserializer)?
+            let (assets, resample) = match AssetsDe::deserialize(deserializer)? {
+                AssetsDe::WithResample { assets, resample } => (assets, resample),
+                AssetsDe::Plain(assets) => (assets, FxHashMap::default()),
+            };
+
+            let mut assets: FxHashMap<_, _> = assets
                 .into_iter()
                 .map(|(name, asset)| (name, Arc::new(Mutex::new(asset))))
                 .collect();
+            for (name, entry) in &resample {
+                assets.insert(
+                    name.clone(),
+                    Arc::new(Mutex::new(Asset::Buffer(entry.original.clone()))),
+                );
+            }
+
+            Ok(Self { assets, resample })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
 
-            Ok(Self { assets })
+    #[test]
+    fn deserialized_assets_are_resampled_again() {
+        let graph = GraphBuilder::new();
+        let buffer = Buffer::from_slice(&[1.0; 100]);
+        graph.add_asset_resampled("ones", buffer, 24_000.0, ResampleQuality::default());
+        let mut runtime = graph.build_runtime();
+        runtime.allocate_for_block_size(48_000.0, 64);
+
+        let json = serde_json::to_string(runtime.graph()).unwrap();
+        let graph: Graph = serde_json::from_str(&json).unwrap();
+        let mut runtime = Runtime::new(graph);
+
+        for sample_rate in [96_000.0, 12_000.0] {
+            runtime.allocate_for_block_size(sample_rate, 64);
+            let asset = runtime.graph().assets().get("ones").unwrap();
+            let asset = asset.try_lock().unwrap();
+            let len = asset.as_buffer().unwrap().len();
+            assert_eq!(len, (100.0 * sample_rate / 24_000.0) as usize);
         }
     }
 }
@@ -10,14 +10,17 @@ use petgraph::{
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
-    prelude::{Null, Param, Passthrough},
-    processor::{Processor, ProcessorError},
-    signal::{Float, MidiMessage, SignalType},
+    builtins::tap::tap_reader,
+    prelude::{MidiOut, MidiOutRx, Null, Param, Passthrough, SubGraph, Tap, TapReader},
+    processor::{Processor, ProcessorError, SignalSpec},
+    resample::ResampleQuality,
+    signal::{Buffer, Float, MidiMessage, SignalType},
 };
 
 pub mod asset;
 pub mod edge;
 pub mod node;
+pub mod preset;
 
 /// The type of graph indices.
 pub type GraphIx = u32;
@@ -79,6 +82,8 @@ pub type GraphConstructionResult<T> = Result<T, GraphConstructionError>;
 /// A directed graph of [`Processor`]s connected by [`Edge`]s.
 #[derive(Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// the derived implementations are wrapped below, to fix up graphs serialized by older versions
+#[cfg_attr(feature = "serde", serde(remote = "Self"))]
 pub struct Graph {
     pub(crate) digraph: DiGraph,
 
@@ -91,10 +96,29 @@ pub struct Graph {
     // MIDI input params
     midi_params: Vec<NodeIndex>,
 
+    // MIDI output nodes
+    #[cfg_attr(feature = "serde", serde(default))]
+    midi_outputs: Vec<NodeIndex>,
+
+    // named module instances (sub-graphs)
+    #[cfg_attr(feature = "serde", serde(default))]
+    modules: FxHashMap<String, NodeIndex>,
+
+    // named taps
+    #[cfg_attr(feature = "serde", serde(default))]
+    taps: FxHashMap<String, NodeIndex>,
+
     // cached input/output nodes
     input_nodes: Vec<NodeIndex>,
     output_nodes: Vec<NodeIndex>,
 
+    // names and types of the inputs/outputs, parallel to `input_nodes`/`output_nodes`
+    // (missing from graphs serialized before inputs and outputs were named, see `fill_missing_specs`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    input_specs: Vec<SignalSpec>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    output_specs: Vec<SignalSpec>,
+
     // cached visitor state for graph traversal
     #[cfg_attr(feature = "serde", serde(skip))]
     visitor: DfsPostOrder<NodeIndex, FxHashSet<NodeIndex>>,
@@ -102,6 +126,39 @@ pub struct Graph {
 
     // cached strongly connected components (feedback loops)
     sccs: Vec<Vec<NodeIndex>>,
+
+    // cached processing schedules for the feedback loops, parallel to `sccs` (recomputed when the graph is loaded)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) feedback_schedules: Vec<FeedbackSchedule>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Graph {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Graph::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Graph {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut graph = Graph::deserialize(deserializer)?;
+        graph.fill_missing_specs();
+        graph.detect_sccs();
+        Ok(graph)
+    }
+}
+
+/// The processing schedule of a feedback loop (a strongly connected component with more than one node).
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FeedbackSchedule {
+    /// The number of samples that can be processed at once, or `None` if the loop contains a cycle without any delay and must be processed sample by sample.
+    pub(crate) sub_block_size: Option<usize>,
+    /// The delay nodes that break every cycle in the loop.
+    /// Their outputs are written before, and their inputs read after, the rest of the loop is processed.
+    pub(crate) delay_nodes: Vec<NodeIndex>,
+    /// The remaining nodes of the loop in processing order.
+    pub(crate) nodes: Vec<NodeIndex>,
 }
 
 impl Graph {
@@ -133,19 +190,57 @@ impl Graph {
         self.assets.insert(name.into(), asset);
     }
 
+    /// Adds a buffer recorded at the given sample rate to the graph, to be resampled to the runtime's sample rate when the graph is allocated.
+    pub fn add_asset_resampled(
+        &mut self,
+        name: impl Into<String>,
+        buffer: Buffer<Float>,
+        sample_rate: Float,
+        quality: ResampleQuality,
+    ) {
+        self.assets
+            .insert_resampled(name.into(), buffer, sample_rate, quality);
+    }
+
     /// Adds an audio input node to the graph.
+    ///
+    /// The input is named after its index.
     pub fn add_audio_input(&mut self) -> NodeIndex {
-        let idx = self.digraph.add_node(ProcessorNode::new(Null));
-        self.input_nodes.push(idx);
-        idx
+        let name = self.input_nodes.len().to_string();
+        self.add_input(name, SignalType::Float)
     }
 
     /// Adds an audio output node to the graph.
+    ///
+    /// The output is named after its index.
     pub fn add_audio_output(&mut self) -> NodeIndex {
+        let name = self.output_nodes.len().to_string();
+        self.add_output(name, SignalType::Float)
+    }
+
+    /// Adds an input node with the given name and signal type to the graph.
+    ///
+    /// When the graph is used in a [`SubGraph`], this becomes one of its inputs.
+    /// Only `Float` inputs can be fed by an audio backend.
+    pub fn add_input(&mut self, name: impl Into<String>, signal_type: SignalType) -> NodeIndex {
         let idx = self
             .digraph
-            .add_node(ProcessorNode::new(Passthrough::new(SignalType::Float)));
+            .add_node(ProcessorNode::new(Null::new(signal_type)));
+        self.input_nodes.push(idx);
+        self.input_specs.push(SignalSpec::new(name, signal_type));
+        idx
+    }
+
+    /// Adds an output node with the given name and signal type to the graph.
+    ///
+    /// When the graph is used in a [`SubGraph`], this becomes one of its outputs.
+    /// Only `Float` outputs can be sent to an audio backend.
+    pub fn add_output(&mut self, name: impl Into<String>, signal_type: SignalType) -> NodeIndex {
+        let idx = self
+            .digraph
+            .add_node(ProcessorNode::new(Passthrough::new(signal_type)));
         self.output_nodes.push(idx);
+        self.output_specs.push(SignalSpec::new(name, signal_type));
         idx
     }
 
@@ -162,6 +257,25 @@ impl Graph {
         index
     }
 
+    /// Adds a [`SubGraph`] node to the graph and registers it under the given instance name.
+    ///
+    /// The parameters of the sub-graph can then be looked up as `<name>/<param>` with [`Graph::param_named`].
+    pub fn add_module(&mut self, name: impl Into<String>, subgraph: SubGraph) -> NodeIndex {
+        let index = self.add_processor(subgraph);
+        self.modules.insert(name.into(), index);
+        index
+    }
+
+    /// Adds a [`Tap`] node to the graph and registers it under its name.
+    ///
+    /// Its reader can then be looked up with [`Graph::tap_named`].
+    pub fn add_tap(&mut self, tap: impl Tap) -> NodeIndex {
+        let name = tap.tap_name().to_string();
+        let index = self.add_processor(tap);
+        self.taps.insert(name, index);
+        index
+    }
+
     /// Adds a MIDI input node to the graph.
     pub fn add_midi_input(&mut self, name: impl Into<String>) -> NodeIndex {
         let param = Param::new::<MidiMessage>(name, None);
@@ -170,6 +284,15 @@ impl Graph {
         index
     }
 
+    /// Adds a MIDI output node to the graph.
+    ///
+    /// The MIDI messages sent to its input are sent out of the graph; see [`Graph::midi_output_iter`].
+    pub fn add_midi_output(&mut self, name: impl Into<String>) -> NodeIndex {
+        let index = self.add_processor(MidiOut::new(name));
+        self.midi_outputs.push(index);
+        index
+    }
+
     /// Connects two nodes in the graph.
     ///
     /// If the edge already exists, this function does nothing.
@@ -310,10 +433,77 @@ impl Graph {
     }
 
     /// Returns the parameter with the specified name.
+    ///
+    /// Parameters of module instances (see [`Graph::add_module`]) are found by their hierarchical name, such as `voice1/cutoff`.
     #[inline]
     pub fn param_named(&self, name: &str) -> Option<&Param> {
-        self.param_index(name)
-            .map(|idx| (*self.digraph[idx].processor()).downcast_ref().unwrap())
+        if let Some(idx) = self.param_index(name) {
+            return Some((*self.digraph[idx].processor()).downcast_ref().unwrap());
+        }
+
+        let (module, param) = name.split_once('/')?;
+        self.module_named(module)?.graph().param_named(param)
+    }
+
+    /// Returns the names of all parameters in the graph, including the hierarchical names of parameters in module instances.
+    pub fn param_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.params.keys().cloned().collect();
+        for (module, &idx) in &self.modules {
+            if let Some(subgraph) = (*self.digraph[idx].processor()).downcast_ref::<SubGraph>() {
+                names.extend(
+                    subgraph
+                        .graph()
+                        .param_names()
+                        .into_iter()
+                        .map(|param| format!("{}/{}", module, param)),
+                );
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Returns the reader of the [`Tap`] with the specified name.
+    ///
+    /// Taps of module instances (see [`Graph::add_module`]) are found by their hierarchical name, such as `voice1/level`.
+    pub fn tap_named(&self, name: &str) -> Option<TapReader> {
+        if let Some(&idx) = self.taps.get(name) {
+            return tap_reader(self.digraph[idx].processor()).map(|(_, reader)| reader);
+        }
+
+        let (module, tap) = name.split_once('/')?;
+        self.module_named(module)?.graph().tap_named(tap)
+    }
+
+    /// Returns the names of all taps in the graph, including the hierarchical names of taps in module instances.
+    pub fn tap_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.taps.keys().cloned().collect();
+        for (module, &idx) in &self.modules {
+            if let Some(subgraph) = (*self.digraph[idx].processor()).downcast_ref::<SubGraph>() {
+                names.extend(
+                    subgraph
+                        .graph()
+                        .tap_names()
+                        .into_iter()
+                        .map(|tap| format!("{}/{}", module, tap)),
+                );
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Returns the index of the module instance with the specified name.
+    #[inline]
+    pub fn module_index(&self, name: &str) -> Option<NodeIndex> {
+        self.modules.get(name).copied()
+    }
+
+    /// Returns the module instance with the specified name.
+    #[inline]
+    pub fn module_named(&self, name: &str) -> Option<&SubGraph> {
+        self.module_index(name)
+            .and_then(|idx| (*self.digraph[idx].processor()).downcast_ref())
     }
 
     /// Returns the index of the MIDI input with the specified name.
@@ -342,6 +532,31 @@ impl Graph {
             })
     }
 
+    /// Returns the index of the MIDI output with the specified name.
+    #[inline]
+    pub fn midi_output_index(&self, name: &str) -> Option<NodeIndex> {
+        self.midi_output_iter()
+            .position(|(output, _)| output == name)
+            .map(|i| self.midi_outputs[i])
+    }
+
+    /// Returns the number of MIDI outputs in the graph.
+    #[inline]
+    pub fn num_midi_outputs(&self) -> usize {
+        self.midi_outputs.len()
+    }
+
+    /// Returns an iterator over the names and receivers of the MIDI outputs in the graph, in the order they were added.
+    #[inline]
+    pub fn midi_output_iter(&self) -> impl Iterator<Item = (&str, MidiOutRx)> + '_ {
+        self.midi_outputs.iter().map(|&idx| {
+            let output = (*self.digraph[idx].processor())
+                .downcast_ref::<MidiOut>()
+                .unwrap();
+            (output.name(), output.rx().clone())
+        })
+    }
+
     /// Returns the indices of the audio inputs in the graph.
     #[inline]
     pub fn input_indices(&self) -> &[NodeIndex] {
@@ -354,6 +569,32 @@ impl Graph {
         &self.output_nodes
     }
 
+    /// Returns the names and signal types of the inputs in the graph.
+    #[inline]
+    pub fn input_specs(&self) -> &[SignalSpec] {
+        &self.input_specs
+    }
+
+    /// Returns the names and signal types of the outputs in the graph.
+    #[inline]
+    pub fn output_specs(&self) -> &[SignalSpec] {
+        &self.output_specs
+    }
+
+    /// Names the inputs and outputs of a graph deserialized without their specs after their index, as [`Graph::add_audio_input`] would.
+    pub(crate) fn fill_missing_specs(&mut self) {
+        for i in self.input_specs.len()..self.input_nodes.len() {
+            let signal_type = self.digraph[self.input_nodes[i]].output_spec()[0].signal_type;
+            self.input_specs
+                .push(SignalSpec::new(i.to_string(), signal_type));
+        }
+        for i in self.output_specs.len()..self.output_nodes.len() {
+            let signal_type = self.digraph[self.output_nodes[i]].output_spec()[0].signal_type;
+            self.output_specs
+                .push(SignalSpec::new(i.to_string(), signal_type));
+        }
+    }
+
     #[inline]
     pub(crate) fn sccs(&self) -> &[Vec<NodeIndex>] {
         &self.sccs
@@ -363,6 +604,86 @@ impl Graph {
     pub(crate) fn detect_sccs(&mut self) {
         self.sccs = petgraph::algo::kosaraju_scc(&self.digraph);
         self.sccs.reverse();
+
+        self.feedback_schedules = self
+            .sccs
+            .iter()
+            .map(|scc| {
+                if scc.len() > 1 {
+                    self.schedule_feedback_loop(scc)
+                } else {
+                    FeedbackSchedule::default()
+                }
+            })
+            .collect();
+    }
+
+    /// Finds the largest sub-block size that a feedback loop can be processed in.
+    ///
+    /// For each delay length found in the loop (longest first), the nodes with at least that much delay are cut out of the loop.
+    /// If the rest of the loop is then acyclic, it can be processed in sub-blocks of that length.
+    fn schedule_feedback_loop(&self, scc: &[NodeIndex]) -> FeedbackSchedule {
+        let mut delays: Vec<usize> = scc
+            .iter()
+            .map(|&node| self.digraph[node].feedback_delay())
+            .filter(|&delay| delay > 0)
+            .collect();
+        delays.sort_unstable_by(|a, b| b.cmp(a));
+        delays.dedup();
+
+        for sub_block_size in delays {
+            let (delay_nodes, rest): (Vec<_>, Vec<_>) = scc
+                .iter()
+                .partition(|&&node| self.digraph[node].feedback_delay() >= sub_block_size);
+
+            if let Some(nodes) = self.topological_order(&rest) {
+                return FeedbackSchedule {
+                    sub_block_size: Some(sub_block_size),
+                    delay_nodes,
+                    nodes,
+                };
+            }
+        }
+
+        FeedbackSchedule {
+            sub_block_size: None,
+            delay_nodes: Vec::new(),
+            nodes: scc.to_vec(),
+        }
+    }
+
+    /// Returns the given nodes in topological order, considering only the edges between them, or `None` if they contain a cycle.
+    fn topological_order(&self, nodes: &[NodeIndex]) -> Option<Vec<NodeIndex>> {
+        let mut in_degrees: FxHashMap<NodeIndex, usize> =
+            nodes.iter().map(|&node| (node, 0)).collect();
+        for &node in nodes {
+            for edge in self.digraph.edges_directed(node, Direction::Outgoing) {
+                if let Some(in_degree) = in_degrees.get_mut(&edge.target()) {
+                    *in_degree += 1;
+                }
+            }
+        }
+
+        let mut ready: Vec<NodeIndex> = nodes
+            .iter()
+            .copied()
+            .filter(|node| in_degrees[node] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(nodes.len());
+
+        while let Some(node) = ready.pop() {
+            order.push(node);
+            for edge in self.digraph.edges_directed(node, Direction::Outgoing) {
+                if let Some(in_degree) = in_degrees.get_mut(&edge.target()) {
+                    *in_degree -= 1;
+                    if *in_degree == 0 {
+                        ready.push(edge.target());
+                    }
+                }
+            }
+        }
+
+        (order.len() == nodes.len()).then_some(order)
     }
 
     #[inline]
@@ -400,11 +721,16 @@ impl Graph {
 
     /// Calls [`Processor::allocate()`] on each node in the graph.
     pub fn allocate(&mut self, sample_rate: Float, max_block_size: usize) {
+        self.assets.resample_to(sample_rate);
+
         self.visit(|graph, node| -> Result<(), ()> {
             graph.digraph[node].allocate(sample_rate, max_block_size);
             Ok(())
         })
         .unwrap();
+
+        // feedback delays may depend on the sample rate, so reschedule the feedback loops
+        self.detect_sccs();
     }
 
     /// Calls [`Processor::resize_buffers()`] on each node in the graph.
@@ -421,3 +747,100 @@ impl Graph {
         write!(writer, "{:?}", petgraph::dot::Dot::new(&self.digraph))
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn loads_graphs_serialized_before_named_inputs_and_outputs() {
+        // an audio input doubled into an audio output, as serialized before graphs had named inputs and outputs
+        let json = r#"
+        {
+            "digraph": {
+                "nodes": [
+                    {"processor": {"type": "Null"}, "input_spec": [], "output_spec": [{"name": "out", "signal_type": "Float"}]},
+                    {"processor": {"type": "Passthrough", "signal_type": "Float"}, "input_spec": [{"name": "in", "signal_type": "Float"}], "output_spec": [{"name": "out", "signal_type": "Float"}]},
+                    {"processor": {"type": "Constant", "value": {"Float": 2.0}}, "input_spec": [], "output_spec": [{"name": "out", "signal_type": "Float"}]},
+                    {"processor": {"type": "Mul", "a": {"Float": null}, "b": {"Float": null}}, "input_spec": [{"name": "a", "signal_type": "Float"}, {"name": "b", "signal_type": "Float"}], "output_spec": [{"name": "out", "signal_type": "Float"}]}
+                ],
+                "node_holes": [],
+                "edge_property": "directed",
+                "edges": [
+                    [0, 3, {"source_output": 0, "target_input": 0, "source_output_name": "out", "target_input_name": "a"}],
+                    [2, 3, {"source_output": 0, "target_input": 1, "source_output_name": "out", "target_input_name": "b"}],
+                    [3, 1, {"source_output": 0, "target_input": 0, "source_output_name": "out", "target_input_name": "in"}]
+                ]
+            },
+            "assets": {},
+            "params": {},
+            "midi_params": [],
+            "input_nodes": [0],
+            "output_nodes": [1],
+            "visit_path": [0, 2, 3, 1],
+            "sccs": [[0], [2], [3], [1]]
+        }
+        "#;
+
+        let graph: Graph = serde_json::from_str(json).unwrap();
+        // the specs are filled in when the graph is loaded, so they are there before a runtime is built
+        for specs in [graph.input_specs(), graph.output_specs()] {
+            assert_eq!(specs.len(), 1);
+            assert_eq!(specs[0].name, "0");
+            assert_eq!(specs[0].signal_type, SignalType::Float);
+        }
+        let sub_graph = SubGraph::new(graph.clone());
+        for spec in [sub_graph.input_spec(), sub_graph.output_spec()] {
+            assert_eq!(spec.len(), 1);
+            assert_eq!(spec[0].name, "0");
+            assert_eq!(spec[0].signal_type, SignalType::Float);
+        }
+
+        let mut runtime = Runtime::new(graph);
+        runtime.allocate_for_block_size(48_000.0, 4);
+        let input = runtime.get_input_mut(0).unwrap();
+        for (i, sample) in input.as_type_mut::<Float>().unwrap().iter_mut().enumerate() {
+            *sample = Some(i as Float);
+        }
+        runtime.process().unwrap();
+        let output = runtime.get_output(0).unwrap();
+        assert_eq!(
+            output.as_type::<Float>().unwrap()[..],
+            [Some(0.0), Some(2.0), Some(4.0), Some(6.0)]
+        );
+    }
+
+    #[test]
+    fn reschedules_feedback_loops_when_loaded() {
+        let graph = GraphBuilder::new();
+        let out = graph.add_audio_output();
+        let delayed = graph.add(Passthrough::new(SignalType::Float));
+        let y = graph.add(SineOscillator::new(30.0)) + delayed.clone() * 0.5;
+        y.output(0).connect_feedback(&delayed.input(0), 3);
+        y.output(0).connect(&out.input(0));
+        let graph = graph.build();
+
+        let json = serde_json::to_string(&graph).unwrap();
+        assert!(!json.contains("feedback_schedules"));
+
+        let loaded: Graph = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.feedback_schedules.len(), loaded.sccs().len());
+        let schedule = loaded
+            .feedback_schedules
+            .iter()
+            .find(|schedule| !schedule.nodes.is_empty())
+            .unwrap();
+        assert_eq!(schedule.sub_block_size, Some(3));
+
+        let mut expected = Runtime::new(graph);
+        let mut loaded = Runtime::new(loaded);
+        let expected = expected
+            .run_offline(Duration::from_millis(50), 1000.0, 16)
+            .unwrap();
+        let loaded = loaded
+            .run_offline(Duration::from_millis(50), 1000.0, 16)
+            .unwrap();
+        assert_eq!(expected, loaded);
+    }
+}
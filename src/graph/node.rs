@@ -104,4 +104,31 @@ impl ProcessorNode {
     ) -> Result<(), ProcessorError> {
         self.processor.process(inputs, outputs)
     }
+
+    /// Returns `true` if the processor's outputs will stay the same for as long as its inputs do.
+    #[inline]
+    pub fn is_steady(&self) -> bool {
+        self.processor.is_steady()
+    }
+
+    /// Returns the minimum number of samples by which the processor's outputs lag behind its inputs.
+    #[inline]
+    pub fn feedback_delay(&self) -> usize {
+        self.processor.feedback_delay()
+    }
+
+    /// Writes the output signals for the current sub-block of a feedback loop from the processor's internal state.
+    #[inline]
+    pub fn process_delay_outputs(
+        &mut self,
+        outputs: ProcessorOutputs,
+    ) -> Result<(), ProcessorError> {
+        self.processor.process_delay_outputs(outputs)
+    }
+
+    /// Passes the input signals for the current sub-block of a feedback loop to the processor.
+    #[inline]
+    pub fn process_delay_inputs(&mut self, inputs: ProcessorInputs) -> Result<(), ProcessorError> {
+        self.processor.process_delay_inputs(inputs)
+    }
 }
@@ -0,0 +1,128 @@
+//! Snapshots of parameter values.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    builtins::Param,
+    signal::{AnySignal, Signal, SignalType},
+};
+
+use super::Graph;
+
+/// A set of values for the [`Param`]s of a graph, stored by param name.
+///
+/// Presets can be captured from a graph's current param values with [`Preset::capture`] and sent back to the params with [`Preset::apply`].
+/// With the `serde` feature, they can be saved to and loaded from files.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Preset {
+    values: BTreeMap<String, AnySignal>,
+}
+
+impl Preset {
+    /// Creates a new, empty preset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value for the param with the given name.
+    pub fn with_value(mut self, name: impl Into<String>, value: impl Signal) -> Self {
+        self.set(name, value.into_any_signal());
+        self
+    }
+
+    /// Sets the value for the param with the given name.
+    pub fn set(&mut self, name: impl Into<String>, value: AnySignal) {
+        self.values.insert(name.into(), value);
+    }
+
+    /// Returns the value for the param with the given name.
+    pub fn get(&self, name: &str) -> Option<&AnySignal> {
+        self.values.get(name)
+    }
+
+    /// Removes the value for the param with the given name.
+    pub fn remove(&mut self, name: &str) -> Option<AnySignal> {
+        self.values.remove(name)
+    }
+
+    /// Returns the number of values in the preset.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if the preset has no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns an iterator over the param names and values in the preset.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AnySignal)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Captures the current values of all params in the graph, including those of its module instances.
+    ///
+    /// MIDI params are skipped, since they carry events rather than values.
+    pub fn capture(graph: &Graph) -> Self {
+        let mut preset = Self::new();
+        for name in graph.param_names() {
+            if let Some(param) = graph.param_named(&name) {
+                preset.capture_param(&name, param);
+            }
+        }
+        preset
+    }
+
+    /// Stores the current value of the given param under the given name.
+    ///
+    /// Does nothing if the param has no value yet or is a MIDI param.
+    pub fn capture_param(&mut self, name: impl Into<String>, param: &Param) {
+        if param.signal_type() == SignalType::Midi {
+            return;
+        }
+        if let Some(value) = param.last() {
+            self.set(name, value);
+        }
+    }
+
+    /// Sends the preset's values to the params of the graph with matching names.
+    ///
+    /// Params that aren't in the preset keep their current values. Returns the number of params that were set.
+    pub fn apply(&self, graph: &Graph) -> usize {
+        self.values
+            .keys()
+            .filter(|name| {
+                graph
+                    .param_named(name)
+                    .is_some_and(|param| self.apply_param(name, param))
+            })
+            .count()
+    }
+
+    /// Sends the value stored under the given name to the given param, converting it to the param's signal type.
+    ///
+    /// Returns `false` if the preset has no value under the name, or if it can't be converted.
+    pub fn apply_param(&self, name: &str, param: &Param) -> bool {
+        let Some(value) = self.get(name) else {
+            return false;
+        };
+        match value.cast(param.signal_type()) {
+            Some(value) => {
+                param.send_any(value);
+                true
+            }
+            None => {
+                log::warn!(
+                    "Can't apply preset value {:?} to param {} of type {:?}",
+                    value,
+                    name,
+                    param.signal_type()
+                );
+                false
+            }
+        }
+    }
+}
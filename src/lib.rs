@@ -6,11 +6,21 @@
 
 pub mod builder;
 pub mod builtins;
+pub mod embed;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod graph;
+pub mod loudness;
+#[cfg(feature = "osc")]
+pub mod osc;
 pub mod processor;
+pub mod resample;
 pub mod runtime;
 pub mod signal;
+pub mod smf;
 pub mod util;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 #[cfg(feature = "fft")]
 pub mod fft;
@@ -25,21 +35,30 @@ extern crate self as raug;
 pub mod prelude {
     pub use crate::builder::{
         graph_builder::GraphBuilder,
+        module::{Module, ModuleArgs, ModuleBuilder, ModuleError, ModuleRegistry},
         node_builder::{Input, IntoNode, Node, Output},
     };
     pub use crate::builtins::*;
-    pub use crate::graph::Graph;
+    pub use crate::embed::{EmbeddedRuntime, MidiEvent};
+    pub use crate::graph::{preset::Preset, Graph};
+    pub use crate::loudness::{LoudnessAnalyzer, LoudnessReport};
     pub use crate::processor::{
         Processor, ProcessorError, ProcessorInputs, ProcessorOutputs, SignalSpec,
     };
+    pub use crate::resample::{ResampleQuality, Resampler};
     pub use crate::runtime::{AudioBackend, AudioDevice, MidiPort, Runtime, RuntimeHandle};
     pub use crate::signal::{
-        AnySignal, Buffer, Float, List, MidiMessage, Signal, SignalBuffer, SignalType, PI, TAU,
+        AnySignal, Buffer, Float, List, MidiMessage, MidiMessageKind, Signal, SignalBuffer,
+        SignalType, PI, TAU,
     };
+    pub use crate::smf::MidiFile;
     pub use crate::util::*;
     pub use raug_macros::{iter_proc_io_as, split_outputs};
     pub use std::time::Duration;
 
+    #[cfg(feature = "osc")]
+    pub use crate::osc::OscServer;
+
     #[cfg(feature = "fft")]
     pub use crate::fft::{
         builder::{FftGraphBuilder, FftNode},
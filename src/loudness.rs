@@ -0,0 +1,616 @@
+//! Loudness measurement per ITU-R BS.1770 and EBU R128.
+//!
+//! [`LoudnessAnalyzer`] K-weights each channel, sums their weighted mean squares over 100 ms steps, and derives from them:
+//!
+//! - the momentary loudness, over the last 400 ms,
+//! - the short-term loudness, over the last 3 s,
+//! - the integrated loudness, over 400 ms blocks with 75% overlap, gated at -70 LUFS and 10 LU below the ungated mean,
+//! - the loudness range (LRA), the spread between the 10th and 95th percentiles of the short-term loudness, gated at -70 LUFS and 20 LU below the ungated mean,
+//! - the true peak, measured on the signal oversampled to at least 192 kHz.
+//!
+//! The gated measurements are computed from histograms with 0.1 LU bins, so the analyzer never allocates after it is created,
+//! and is used by the [`LoudnessMeter`](crate::builtins::LoudnessMeter) processor as well as for offline analysis with [`analyze`] and [`normalize`].
+
+use crate::{
+    resample::{ResampleQuality, Resampler},
+    signal::{Float, PI},
+};
+
+/// The loudness below which blocks are excluded from the gated measurements, in LUFS.
+pub const ABSOLUTE_GATE: Float = -70.0;
+
+/// The target loudness of EBU R128 deliverables, in LUFS.
+pub const EBU_R128_TARGET: Float = -23.0;
+
+// the histograms cover -70 to +30 LUFS in 0.1 LU bins
+const HISTOGRAM_BINS: usize = 1000;
+const HISTOGRAM_STEP: Float = 0.1;
+
+/// Converts a weighted mean square to a loudness in LUFS.
+#[inline]
+pub fn energy_to_loudness(energy: Float) -> Float {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Converts a loudness in LUFS to a weighted mean square.
+#[inline]
+pub fn loudness_to_energy(loudness: Float) -> Float {
+    Float::powf(10.0, (loudness + 0.691) / 10.0)
+}
+
+/// A single biquad stage in transposed direct form II.
+#[derive(Debug, Clone, Default)]
+struct Stage {
+    b: [Float; 3],
+    a: [Float; 2],
+    z: [Float; 2],
+}
+
+impl Stage {
+    #[inline]
+    fn process(&mut self, x: Float) -> Float {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The K-weighting filter of BS.1770: a high shelf modelling the acoustic effect of the head, followed by a highpass (the "RLB" curve).
+///
+/// The coefficients are derived for any sample rate, and match the ones given by the standard at 48 kHz.
+#[derive(Debug, Clone)]
+pub struct KWeighting {
+    shelf: Stage,
+    highpass: Stage,
+}
+
+impl KWeighting {
+    /// Creates a new `KWeighting` filter for the given sample rate.
+    pub fn new(sample_rate: Float) -> Self {
+        let f0 = 1681.974450955533;
+        let gain = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = Float::powf(10.0, gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Stage {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        };
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Stage {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        };
+
+        Self { shelf, highpass }
+    }
+
+    /// Filters a single sample.
+    #[inline]
+    pub fn process(&mut self, sample: Float) -> Float {
+        self.highpass.process(self.shelf.process(sample))
+    }
+
+    /// Clears the filter's state.
+    pub fn reset(&mut self) {
+        self.shelf.z = [0.0; 2];
+        self.highpass.z = [0.0; 2];
+    }
+}
+
+/// A histogram of loudness values, with the sum of the energies in each bin for computing gated means.
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: Box<[usize]>,
+    energies: Box<[Float]>,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; HISTOGRAM_BINS].into_boxed_slice(),
+            energies: vec![0.0; HISTOGRAM_BINS].into_boxed_slice(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.fill(0);
+        self.energies.fill(0.0);
+    }
+
+    #[inline]
+    fn bin(loudness: Float) -> usize {
+        (((loudness - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize).min(HISTOGRAM_BINS - 1)
+    }
+
+    #[inline]
+    fn bin_center(bin: usize) -> Float {
+        ABSOLUTE_GATE + (bin as Float + 0.5) * HISTOGRAM_STEP
+    }
+
+    /// Adds the energy, unless its loudness is below the absolute gate.
+    #[inline]
+    fn add(&mut self, energy: Float) {
+        let loudness = energy_to_loudness(energy);
+        if loudness < ABSOLUTE_GATE || !loudness.is_finite() {
+            return;
+        }
+        let bin = Self::bin(loudness);
+        self.counts[bin] += 1;
+        self.energies[bin] += energy;
+    }
+
+    /// Returns the first bin that passes the relative gate, `offset` LU below the mean of all the values.
+    fn relative_gate(&self, offset: Float) -> Option<usize> {
+        let count: usize = self.counts.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let energy: Float = self.energies.iter().sum();
+        let threshold = energy_to_loudness(energy / count as Float) - offset;
+        Some(if threshold < ABSOLUTE_GATE {
+            0
+        } else {
+            Self::bin(threshold)
+        })
+    }
+
+    /// Returns the loudness of the mean energy of the values that pass the relative gate.
+    fn gated_loudness(&self, offset: Float) -> Float {
+        let Some(gate) = self.relative_gate(offset) else {
+            return Float::NEG_INFINITY;
+        };
+        let count: usize = self.counts[gate..].iter().sum();
+        if count == 0 {
+            return Float::NEG_INFINITY;
+        }
+        let energy: Float = self.energies[gate..].iter().sum();
+        energy_to_loudness(energy / count as Float)
+    }
+
+    /// Returns the spread between the 10th and 95th percentiles of the values that pass the relative gate.
+    fn range(&self, offset: Float) -> Float {
+        let Some(gate) = self.relative_gate(offset) else {
+            return 0.0;
+        };
+        let count: usize = self.counts[gate..].iter().sum();
+        if count == 0 {
+            return 0.0;
+        }
+
+        let percentile = |p: Float| {
+            let rank = ((count - 1) as Float * p).round() as usize;
+            let mut seen = 0;
+            for (bin, &n) in self.counts.iter().enumerate().skip(gate) {
+                seen += n;
+                if seen > rank {
+                    return Self::bin_center(bin);
+                }
+            }
+            Self::bin_center(HISTOGRAM_BINS - 1)
+        };
+
+        percentile(0.95) - percentile(0.10)
+    }
+}
+
+/// A streaming loudness analyzer for any number of channels.
+///
+/// Frames of one sample per channel are pushed with [`LoudnessAnalyzer::process_frame`], which doesn't allocate.
+/// Loudness values are in LUFS, and are negative infinity until enough signal has been measured.
+#[derive(Debug, Clone)]
+pub struct LoudnessAnalyzer {
+    sample_rate: Float,
+    weights: Box<[Float]>,
+    filters: Box<[KWeighting]>,
+    true_peak: Box<[Resampler]>,
+
+    step_len: usize,
+    step_pos: usize,
+    step_energy: Float,
+    // the energies of the last 30 steps (3 s), oldest at `steps_pos`
+    steps: [Float; 30],
+    steps_pos: usize,
+    num_steps: usize,
+
+    momentary: Float,
+    short_term: Float,
+    max_momentary: Float,
+    max_short_term: Float,
+    blocks: Histogram,
+    short_term_blocks: Histogram,
+
+    sample_peak: Float,
+    true_peak_max: Float,
+}
+
+impl LoudnessAnalyzer {
+    /// Creates a new `LoudnessAnalyzer` for the given number of channels and sample rate, with all channels weighted equally.
+    pub fn new(num_channels: usize, sample_rate: Float) -> Self {
+        // oversample by at least 4x below 48 kHz, so that inter-sample peaks are caught
+        let factor = (192000.0 / sample_rate).ceil().clamp(1.0, 4.0);
+        Self {
+            sample_rate,
+            weights: vec![1.0; num_channels].into_boxed_slice(),
+            filters: vec![KWeighting::new(sample_rate); num_channels].into_boxed_slice(),
+            true_peak: vec![
+                Resampler::new(
+                    sample_rate,
+                    sample_rate * factor,
+                    ResampleQuality::Medium
+                );
+                num_channels
+            ]
+            .into_boxed_slice(),
+            step_len: ((sample_rate * 0.1).round() as usize).max(1),
+            step_pos: 0,
+            step_energy: 0.0,
+            steps: [0.0; 30],
+            steps_pos: 0,
+            num_steps: 0,
+            momentary: Float::NEG_INFINITY,
+            short_term: Float::NEG_INFINITY,
+            max_momentary: Float::NEG_INFINITY,
+            max_short_term: Float::NEG_INFINITY,
+            blocks: Histogram::new(),
+            short_term_blocks: Histogram::new(),
+            sample_peak: 0.0,
+            true_peak_max: 0.0,
+        }
+    }
+
+    /// Sets the weight of each channel's energy in the sum, e.g. `[1.0, 1.0, 1.0, 0.0, 1.41, 1.41]` for 5.1 audio with the LFE channel excluded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of weights doesn't match the number of channels.
+    pub fn with_channel_weights(mut self, weights: &[Float]) -> Self {
+        assert_eq!(
+            weights.len(),
+            self.weights.len(),
+            "Expected one weight per channel"
+        );
+        self.weights.copy_from_slice(weights);
+        self
+    }
+
+    /// Returns the number of channels.
+    #[inline]
+    pub fn num_channels(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Returns the sample rate.
+    #[inline]
+    pub fn sample_rate(&self) -> Float {
+        self.sample_rate
+    }
+
+    /// Clears all measurements, as if the analyzer had just been created.
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().for_each(KWeighting::reset);
+        self.true_peak.iter_mut().for_each(Resampler::reset);
+        self.step_pos = 0;
+        self.step_energy = 0.0;
+        self.steps = [0.0; 30];
+        self.steps_pos = 0;
+        self.num_steps = 0;
+        self.momentary = Float::NEG_INFINITY;
+        self.short_term = Float::NEG_INFINITY;
+        self.max_momentary = Float::NEG_INFINITY;
+        self.max_short_term = Float::NEG_INFINITY;
+        self.blocks.clear();
+        self.short_term_blocks.clear();
+        self.sample_peak = 0.0;
+        self.true_peak_max = 0.0;
+    }
+
+    /// Pushes one sample of each channel.
+    ///
+    /// Returns `true` if a 100 ms step was completed, updating the momentary and short-term loudness.
+    /// Missing channels are treated as silence, and extra samples are ignored.
+    #[inline]
+    pub fn process_frame(&mut self, frame: &[Float]) -> bool {
+        for (ch, &sample) in frame.iter().enumerate().take(self.weights.len()) {
+            let weighted = self.filters[ch].process(sample);
+            self.step_energy += self.weights[ch] * weighted * weighted;
+
+            self.sample_peak = self.sample_peak.max(sample.abs());
+            let true_peak = &mut self.true_peak_max;
+            self.true_peak[ch].process(&[sample], |s| *true_peak = true_peak.max(s.abs()));
+        }
+
+        self.step_pos += 1;
+        if self.step_pos < self.step_len {
+            return false;
+        }
+
+        self.steps[self.steps_pos] = self.step_energy / self.step_len as Float;
+        self.steps_pos = (self.steps_pos + 1) % self.steps.len();
+        self.num_steps += 1;
+        self.step_pos = 0;
+        self.step_energy = 0.0;
+
+        if self.num_steps >= 4 {
+            let energy = (1..=4)
+                .map(|i| self.steps[(self.steps_pos + self.steps.len() - i) % self.steps.len()])
+                .sum::<Float>()
+                / 4.0;
+            self.momentary = energy_to_loudness(energy);
+            self.max_momentary = self.max_momentary.max(self.momentary);
+            self.blocks.add(energy);
+        }
+
+        if self.num_steps >= self.steps.len() {
+            let energy = self.steps.iter().sum::<Float>() / self.steps.len() as Float;
+            self.short_term = energy_to_loudness(energy);
+            self.max_short_term = self.max_short_term.max(self.short_term);
+            self.short_term_blocks.add(energy);
+        }
+
+        true
+    }
+
+    /// Returns the momentary loudness, over the last 400 ms.
+    #[inline]
+    pub fn momentary(&self) -> Float {
+        self.momentary
+    }
+
+    /// Returns the short-term loudness, over the last 3 s.
+    #[inline]
+    pub fn short_term(&self) -> Float {
+        self.short_term
+    }
+
+    /// Returns the largest momentary loudness measured so far.
+    #[inline]
+    pub fn max_momentary(&self) -> Float {
+        self.max_momentary
+    }
+
+    /// Returns the largest short-term loudness measured so far.
+    #[inline]
+    pub fn max_short_term(&self) -> Float {
+        self.max_short_term
+    }
+
+    /// Returns the integrated loudness of everything measured so far.
+    pub fn integrated(&self) -> Float {
+        self.blocks.gated_loudness(10.0)
+    }
+
+    /// Returns the loudness range of everything measured so far, in LU.
+    pub fn loudness_range(&self) -> Float {
+        self.short_term_blocks.range(20.0)
+    }
+
+    /// Returns the largest absolute sample value measured so far.
+    #[inline]
+    pub fn sample_peak(&self) -> Float {
+        self.sample_peak
+    }
+
+    /// Returns the largest absolute value of the oversampled signal measured so far.
+    ///
+    /// The oversampling filter delays the signal by a few samples, so the most recent samples are not yet included.
+    #[inline]
+    pub fn true_peak(&self) -> Float {
+        self.true_peak_max.max(self.sample_peak)
+    }
+
+    /// Returns a summary of everything measured so far.
+    pub fn report(&self) -> LoudnessReport {
+        LoudnessReport {
+            integrated: self.integrated(),
+            loudness_range: self.loudness_range(),
+            max_momentary: self.max_momentary,
+            max_short_term: self.max_short_term,
+            sample_peak: 20.0 * self.sample_peak.log10(),
+            true_peak: 20.0 * self.true_peak().log10(),
+        }
+    }
+}
+
+/// A summary of the loudness of a signal, as returned by [`analyze`] or [`LoudnessAnalyzer::report`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoudnessReport {
+    /// The integrated loudness, in LUFS.
+    pub integrated: Float,
+    /// The loudness range, in LU.
+    pub loudness_range: Float,
+    /// The largest momentary loudness, in LUFS.
+    pub max_momentary: Float,
+    /// The largest short-term loudness, in LUFS.
+    pub max_short_term: Float,
+    /// The sample peak, in dBFS.
+    pub sample_peak: Float,
+    /// The true peak, in dBTP.
+    pub true_peak: Float,
+}
+
+/// Measures the loudness of the given channels, e.g. the output of [`Runtime::run_offline`](crate::runtime::Runtime::run_offline).
+///
+/// The channels are all weighted equally. If they have different lengths, the missing samples are treated as silence.
+pub fn analyze<C: AsRef<[Float]>>(channels: &[C], sample_rate: Float) -> LoudnessReport {
+    let mut analyzer = LoudnessAnalyzer::new(channels.len(), sample_rate);
+    let len = channels
+        .iter()
+        .map(|ch| ch.as_ref().len())
+        .max()
+        .unwrap_or_default();
+
+    let mut frame = vec![0.0; channels.len()];
+    for i in 0..len {
+        for (sample, ch) in frame.iter_mut().zip(channels) {
+            *sample = ch.as_ref().get(i).copied().unwrap_or_default();
+        }
+        analyzer.process_frame(&frame);
+    }
+
+    // flush the oversampling filters so that peaks at the very end are included
+    frame.fill(0.0);
+    for _ in 0..analyzer.true_peak.first().map_or(0, |r| r.latency() + 1) {
+        for (ch, resampler) in analyzer.true_peak.iter_mut().enumerate() {
+            let true_peak = &mut analyzer.true_peak_max;
+            resampler.process(&frame[ch..=ch], |s| *true_peak = true_peak.max(s.abs()));
+        }
+    }
+
+    analyzer.report()
+}
+
+/// Scales the given channels so that their integrated loudness is `target` LUFS, e.g. [`EBU_R128_TARGET`].
+///
+/// If `true_peak_ceiling` is given, in dBTP, the gain is reduced as needed so that the true peak doesn't exceed it, leaving the result quieter than the target.
+/// Silent signals are left unchanged.
+///
+/// Returns the report of the channels before scaling, and the linear gain that was applied.
+pub fn normalize<C: AsMut<[Float]> + AsRef<[Float]>>(
+    channels: &mut [C],
+    sample_rate: Float,
+    target: Float,
+    true_peak_ceiling: Option<Float>,
+) -> (LoudnessReport, Float) {
+    let report = analyze(channels, sample_rate);
+    if !report.integrated.is_finite() {
+        return (report, 1.0);
+    }
+
+    let mut gain_db = target - report.integrated;
+    if let Some(ceiling) = true_peak_ceiling {
+        if report.true_peak.is_finite() {
+            gain_db = gain_db.min(ceiling - report.true_peak);
+        }
+    }
+
+    let gain = Float::powf(10.0, gain_db / 20.0);
+    for ch in channels.iter_mut() {
+        for sample in ch.as_mut() {
+            *sample *= gain;
+        }
+    }
+
+    (report, gain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a sine at the given frequency and peak level in dBFS
+    fn sine(freq: Float, level: Float, seconds: Float, sample_rate: Float) -> Vec<Float> {
+        let amplitude = Float::powf(10.0, level / 20.0);
+        (0..(seconds * sample_rate) as usize)
+            .map(|i| amplitude * (2.0 * PI * freq * i as Float / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_in_one_channel_measures_minus_3_lufs() {
+        // BS.1770: a 0 dBFS 997 Hz sine in one channel reads -3.01 LKFS
+        for sample_rate in [44100.0, 48000.0, 96000.0] {
+            let left = sine(997.0, 0.0, 1.0, sample_rate);
+            let right = vec![0.0; left.len()];
+            let report = analyze(&[left, right], sample_rate);
+            assert!(
+                (report.integrated + 3.01).abs() < 0.1,
+                "{} Hz: {}",
+                sample_rate,
+                report.integrated
+            );
+        }
+    }
+
+    #[test]
+    fn stereo_sine_at_minus_23_dbfs_measures_minus_23_lufs() {
+        // EBU Tech 3341, test 1
+        let channel = sine(1000.0, -23.0, 5.0, 48000.0);
+        let mut analyzer = LoudnessAnalyzer::new(2, 48000.0);
+        for &sample in &channel {
+            analyzer.process_frame(&[sample, sample]);
+        }
+        assert!((analyzer.momentary() + 23.0).abs() < 0.1);
+        assert!((analyzer.short_term() + 23.0).abs() < 0.1);
+        assert!((analyzer.integrated() + 23.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn quiet_passages_are_gated() {
+        // after EBU Tech 3341, test 3, in mono: the quiet passages fall below the relative gate
+        let mut channel = sine(1000.0, -33.0, 2.0, 48000.0);
+        channel.extend(sine(1000.0, -20.0, 20.0, 48000.0));
+        channel.extend(sine(1000.0, -33.0, 2.0, 48000.0));
+        let report = analyze(&[channel], 48000.0);
+        assert!(
+            (report.integrated + 23.0).abs() < 0.1,
+            "{}",
+            report.integrated
+        );
+    }
+
+    #[test]
+    fn loudness_range_spans_the_short_term_levels() {
+        // after EBU Tech 3342, test 1, in mono
+        let mut channel = sine(1000.0, -20.0, 10.0, 48000.0);
+        channel.extend(sine(1000.0, -30.0, 10.0, 48000.0));
+        let report = analyze(&[channel], 48000.0);
+        assert!(
+            (report.loudness_range - 10.0).abs() < 1.0,
+            "{}",
+            report.loudness_range
+        );
+    }
+
+    #[test]
+    fn true_peak_includes_inter_sample_peaks() {
+        // a quarter of the sample rate, sampled 45 degrees off its peaks
+        let sample_rate = 48000.0;
+        let channel: Vec<Float> = (0..4800)
+            .map(|i| (PI / 2.0 * i as Float + PI / 4.0).sin())
+            .collect();
+        let report = analyze(&[channel], sample_rate);
+        assert!(
+            (report.sample_peak + 3.01).abs() < 0.1,
+            "{}",
+            report.sample_peak
+        );
+        assert!(report.true_peak.abs() < 0.5, "{}", report.true_peak);
+    }
+
+    #[test]
+    fn normalize_reaches_the_target() {
+        let mut channels = [sine(440.0, -6.0, 1.0, 48000.0)];
+        let (before, gain) = normalize(&mut channels, 48000.0, EBU_R128_TARGET, None);
+        assert!(gain < 1.0);
+        let after = analyze(&channels, 48000.0);
+        assert!((after.integrated - EBU_R128_TARGET).abs() < 0.1);
+        assert!((after.integrated - before.integrated - 20.0 * gain.log10()).abs() < 0.01);
+
+        // the true peak ceiling wins over the target
+        let mut channels = [sine(440.0, -40.0, 1.0, 48000.0)];
+        normalize(&mut channels, 48000.0, 0.0, Some(-1.0));
+        let after = analyze(&channels, 48000.0);
+        assert!((after.true_peak + 1.0).abs() < 0.1, "{}", after.true_peak);
+
+        // silence is left alone
+        let mut channels = [vec![0.0; 4800]];
+        let (report, gain) = normalize(&mut channels, 48000.0, EBU_R128_TARGET, None);
+        assert_eq!(report.integrated, Float::NEG_INFINITY);
+        assert_eq!(gain, 1.0);
+    }
+}
@@ -0,0 +1,805 @@
+//! Controlling graph parameters over OSC (Open Sound Control).
+//!
+//! [`OscServer`] listens for OSC messages on a UDP socket and sends their arguments to the [`Param`]s of a graph, so that a running patch can be controlled from tools such as TouchOSC.
+//! A param is addressed by its name under the server's prefix, which is `/raug/param` by default, so the param `cutoff` is set by sending a float to `/raug/param/cutoff`, and the param `cutoff` of the module instance `voice1` by sending to `/raug/param/voice1/cutoff`.
+//!
+//! The server can also send param values back out, both in reply to queries (messages without arguments) and, if [`OscServer::with_feedback`] is used, whenever a value changes.
+//! The feedback also carries the readings of the graph's [`MeterTap`](crate::builtins::MeterTap)s, as the peak and RMS level under `/raug/meter` by default, so the tap `level` is sent as `/raug/meter/level <peak> <rms>`.
+//!
+//! The OSC messages and bundles themselves are available as [`OscMessage`] and [`OscPacket`], for use with other transports.
+
+use std::{
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    builtins::{MeterReader, Param, TapReader},
+    graph::Graph,
+    signal::{AnySignal, Float, List, MidiMessage, SignalType},
+};
+
+/// The address prefix under which an [`OscServer`] exposes params by default.
+pub const DEFAULT_OSC_PREFIX: &str = "/raug/param";
+
+/// The address prefix under which an [`OscServer`] sends meter readings by default.
+pub const DEFAULT_OSC_METER_PREFIX: &str = "/raug/meter";
+
+/// The largest packet an [`OscServer`] can receive, in bytes.
+const MAX_PACKET_SIZE: usize = 65_536;
+
+/// Errors that can occur when decoding OSC packets or running an [`OscServer`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum OscError {
+    /// An I/O error occurred.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The packet ended in the middle of a field.
+    #[error("Unexpected end of packet")]
+    UnexpectedEnd,
+
+    /// A string wasn't null-terminated or wasn't valid UTF-8.
+    #[error("Invalid string")]
+    InvalidString,
+
+    /// The packet doesn't start with an address or `#bundle`.
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    /// The message has an argument type that isn't supported.
+    #[error("Unknown type tag: {0}")]
+    UnknownTypeTag(char),
+}
+
+/// A single argument of an [`OscMessage`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    /// A 32-bit integer (`i`).
+    Int(i32),
+    /// A 64-bit integer (`h`).
+    Long(i64),
+    /// A 32-bit float (`f`).
+    Float(f32),
+    /// A 64-bit float (`d`).
+    Double(f64),
+    /// A string (`s`) or symbol (`S`).
+    String(String),
+    /// A blob of bytes (`b`).
+    Blob(Vec<u8>),
+    /// True (`T`).
+    True,
+    /// False (`F`).
+    False,
+    /// Nil (`N`).
+    Nil,
+    /// An impulse, or "bang" (`I`).
+    Impulse,
+    /// A MIDI message (`m`): the port ID followed by the status and data bytes.
+    Midi([u8; 4]),
+    /// An ASCII character (`c`).
+    Char(char),
+    /// An NTP time tag (`t`).
+    TimeTag(u64),
+}
+
+impl OscArg {
+    /// Returns the type tag of the argument.
+    pub fn type_tag(&self) -> char {
+        match self {
+            Self::Int(_) => 'i',
+            Self::Long(_) => 'h',
+            Self::Float(_) => 'f',
+            Self::Double(_) => 'd',
+            Self::String(_) => 's',
+            Self::Blob(_) => 'b',
+            Self::True => 'T',
+            Self::False => 'F',
+            Self::Nil => 'N',
+            Self::Impulse => 'I',
+            Self::Midi(_) => 'm',
+            Self::Char(_) => 'c',
+            Self::TimeTag(_) => 't',
+        }
+    }
+
+    /// Converts the argument to the closest [`AnySignal`].
+    ///
+    /// Impulses become `true`, so they can trigger [`Bool`](SignalType::Bool) params, and MIDI arguments become [`MidiMessage`]s.
+    /// Blobs and time tags have no signal equivalent, and nil has no value, so they return `None`.
+    pub fn to_signal(&self) -> Option<AnySignal> {
+        match self {
+            Self::Int(i) => Some(AnySignal::Int(Some(*i as i64))),
+            Self::Long(i) => Some(AnySignal::Int(Some(*i))),
+            Self::Float(f) => Some(AnySignal::Float(Some(*f as Float))),
+            Self::Double(f) => Some(AnySignal::Float(Some(*f as Float))),
+            Self::String(s) => Some(AnySignal::String(Some(s.clone()))),
+            Self::Char(c) => Some(AnySignal::String(Some(c.to_string()))),
+            Self::True | Self::Impulse => Some(AnySignal::Bool(Some(true))),
+            Self::False => Some(AnySignal::Bool(Some(false))),
+            Self::Midi([_, status, data1, data2]) => Some(AnySignal::Midi(Some(MidiMessage {
+                data: [*status, *data1, *data2],
+            }))),
+            Self::Blob(_) | Self::Nil | Self::TimeTag(_) => None,
+        }
+    }
+
+    /// Converts the argument to a signal of the given type, using [`AnySignal::cast`] if the types differ.
+    pub fn to_signal_of_type(&self, signal_type: SignalType) -> Option<AnySignal> {
+        self.to_signal()?.cast(signal_type)
+    }
+
+    /// Converts a signal to OSC arguments.
+    ///
+    /// Floats are sent as 32-bit floats and integers as 32-bit integers when they fit, since those are the types most OSC clients understand.
+    /// Lists are flattened into one argument per element, and signals without a value become nil.
+    pub fn from_signal(signal: &AnySignal) -> Vec<Self> {
+        match signal {
+            AnySignal::List(Some(list)) => list.iter().flat_map(Self::from_signal).collect(),
+            signal => vec![Self::from_scalar(signal)],
+        }
+    }
+
+    fn from_scalar(signal: &AnySignal) -> Self {
+        match signal {
+            AnySignal::Float(Some(f)) => Self::Float(*f as f32),
+            AnySignal::Int(Some(i)) => i32::try_from(*i).map_or(Self::Long(*i), Self::Int),
+            AnySignal::Bool(Some(true)) => Self::True,
+            AnySignal::Bool(Some(false)) => Self::False,
+            AnySignal::String(Some(s)) => Self::String(s.clone()),
+            AnySignal::Midi(Some(msg)) => Self::Midi([0, msg.data[0], msg.data[1], msg.data[2]]),
+            _ => Self::Nil,
+        }
+    }
+}
+
+/// An OSC message: an address pattern and a list of arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    /// The address of the message, such as `/raug/param/cutoff`.
+    pub address: String,
+    /// The arguments of the message.
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    /// Creates a new message with the given address and no arguments.
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Adds an argument to the message.
+    pub fn with_arg(mut self, arg: OscArg) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Converts the arguments of the message to a signal of the given type.
+    ///
+    /// [`List`](SignalType::List) params receive all arguments as a list; other params receive the first argument.
+    /// Returns `None` if there are no arguments, or if they can't be converted.
+    pub fn to_signal_of_type(&self, signal_type: SignalType) -> Option<AnySignal> {
+        if signal_type == SignalType::List {
+            let signals: Vec<AnySignal> = self.args.iter().filter_map(OscArg::to_signal).collect();
+            if signals.is_empty() {
+                return None;
+            }
+            return Some(AnySignal::List(Some(List::from_slice(&signals))));
+        }
+
+        self.args.first()?.to_signal_of_type(signal_type)
+    }
+
+    /// Encodes the message into an OSC packet.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes);
+        bytes
+    }
+
+    /// Decodes a single message from an OSC packet.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OscError> {
+        match OscPacket::from_bytes(bytes)? {
+            OscPacket::Message(message) => Ok(message),
+            OscPacket::Bundle { .. } => Err(OscError::InvalidAddress("#bundle".to_string())),
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        write_string(bytes, &self.address);
+
+        let mut tags = String::with_capacity(self.args.len() + 1);
+        tags.push(',');
+        tags.extend(self.args.iter().map(OscArg::type_tag));
+        write_string(bytes, &tags);
+
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(i) => bytes.extend_from_slice(&i.to_be_bytes()),
+                OscArg::Long(i) => bytes.extend_from_slice(&i.to_be_bytes()),
+                OscArg::Float(f) => bytes.extend_from_slice(&f.to_be_bytes()),
+                OscArg::Double(f) => bytes.extend_from_slice(&f.to_be_bytes()),
+                OscArg::String(s) => write_string(bytes, s),
+                OscArg::Blob(blob) => {
+                    bytes.extend_from_slice(&(blob.len() as i32).to_be_bytes());
+                    bytes.extend_from_slice(blob);
+                    pad(bytes);
+                }
+                OscArg::Midi(midi) => bytes.extend_from_slice(midi),
+                OscArg::Char(c) => bytes.extend_from_slice(&(*c as u32).to_be_bytes()),
+                OscArg::TimeTag(t) => bytes.extend_from_slice(&t.to_be_bytes()),
+                OscArg::True | OscArg::False | OscArg::Nil | OscArg::Impulse => {}
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader, address: String) -> Result<Self, OscError> {
+        // some old implementations omit the type tag string entirely
+        if reader.is_empty() {
+            return Ok(Self::new(address));
+        }
+
+        let tags = reader.string()?;
+        let Some(tags) = tags.strip_prefix(',') else {
+            return Err(OscError::InvalidString);
+        };
+
+        let mut args = Vec::with_capacity(tags.len());
+        for tag in tags.chars() {
+            let arg = match tag {
+                'i' => OscArg::Int(i32::from_be_bytes(reader.array()?)),
+                'h' => OscArg::Long(i64::from_be_bytes(reader.array()?)),
+                'f' => OscArg::Float(f32::from_be_bytes(reader.array()?)),
+                'd' => OscArg::Double(f64::from_be_bytes(reader.array()?)),
+                's' | 'S' => OscArg::String(reader.string()?),
+                'b' => {
+                    let len = i32::from_be_bytes(reader.array()?);
+                    let len = usize::try_from(len).map_err(|_| OscError::UnexpectedEnd)?;
+                    let blob = reader.take(len)?.to_vec();
+                    reader.align()?;
+                    OscArg::Blob(blob)
+                }
+                'T' => OscArg::True,
+                'F' => OscArg::False,
+                'N' => OscArg::Nil,
+                'I' => OscArg::Impulse,
+                'm' => OscArg::Midi(reader.array()?),
+                'c' => {
+                    let c = u32::from_be_bytes(reader.array()?);
+                    OscArg::Char(char::from_u32(c).ok_or(OscError::InvalidString)?)
+                }
+                't' => OscArg::TimeTag(u64::from_be_bytes(reader.array()?)),
+                tag => return Err(OscError::UnknownTypeTag(tag)),
+            };
+            args.push(arg);
+        }
+
+        Ok(Self { address, args })
+    }
+}
+
+/// An OSC packet, which is either a single message or a bundle of packets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscPacket {
+    /// A single message.
+    Message(OscMessage),
+
+    /// A bundle of packets.
+    Bundle {
+        /// The NTP time tag at which the bundle should take effect, where `1` means immediately.
+        time_tag: u64,
+        /// The packets in the bundle.
+        packets: Vec<OscPacket>,
+    },
+}
+
+impl OscPacket {
+    /// Encodes the packet.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes);
+        bytes
+    }
+
+    /// Decodes a packet.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OscError> {
+        Self::read(&mut Reader { bytes, pos: 0 })
+    }
+
+    /// Returns an iterator over the messages in the packet, including those in nested bundles.
+    pub fn messages(&self) -> Box<dyn Iterator<Item = &OscMessage> + '_> {
+        match self {
+            Self::Message(message) => Box::new(std::iter::once(message)),
+            Self::Bundle { packets, .. } => Box::new(packets.iter().flat_map(Self::messages)),
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Message(message) => message.write(bytes),
+            Self::Bundle { time_tag, packets } => {
+                write_string(bytes, "#bundle");
+                bytes.extend_from_slice(&time_tag.to_be_bytes());
+                for packet in packets {
+                    let start = bytes.len();
+                    bytes.extend_from_slice(&[0; 4]);
+                    packet.write(bytes);
+                    let len = (bytes.len() - start - 4) as i32;
+                    bytes[start..start + 4].copy_from_slice(&len.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, OscError> {
+        let address = reader.string()?;
+
+        if address == "#bundle" {
+            let time_tag = u64::from_be_bytes(reader.array()?);
+            let mut packets = Vec::new();
+            while !reader.is_empty() {
+                let len = i32::from_be_bytes(reader.array()?);
+                let len = usize::try_from(len).map_err(|_| OscError::UnexpectedEnd)?;
+                let bytes = reader.take(len)?;
+                packets.push(Self::read(&mut Reader { bytes, pos: 0 })?);
+            }
+            return Ok(Self::Bundle { time_tag, packets });
+        }
+
+        if !address.starts_with('/') {
+            return Err(OscError::InvalidAddress(address));
+        }
+
+        Ok(Self::Message(OscMessage::read(reader, address)?))
+    }
+}
+
+impl From<OscMessage> for OscPacket {
+    fn from(message: OscMessage) -> Self {
+        Self::Message(message)
+    }
+}
+
+fn pad(bytes: &mut Vec<u8>) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(0);
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, s: &str) {
+    bytes.extend_from_slice(s.as_bytes());
+    bytes.push(0);
+    pad(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], OscError> {
+        let end = self.pos.checked_add(len).ok_or(OscError::UnexpectedEnd)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(OscError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], OscError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn align(&mut self) -> Result<(), OscError> {
+        let padding = (4 - self.pos % 4) % 4;
+        self.take(padding)?;
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, OscError> {
+        let rest = &self.bytes[self.pos.min(self.bytes.len())..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(OscError::InvalidString)?;
+        let s = std::str::from_utf8(&rest[..len]).map_err(|_| OscError::InvalidString)?;
+        self.pos += len + 1;
+        self.align()?;
+        Ok(s.to_string())
+    }
+}
+
+/// A server that sets graph [`Param`]s from OSC messages received over UDP.
+///
+/// See the [module-level documentation](self) for how params are addressed.
+///
+/// # Example
+///
+/// ```no_run
+/// use raug::prelude::*;
+///
+/// let graph = GraphBuilder::new();
+/// let _cutoff = graph.add_param(Param::bounded("cutoff", 1000.0, 20.0, 20000.0));
+/// // ...
+/// let runtime = graph.build_runtime();
+///
+/// // `/raug/param/cutoff 440.0` now sets the cutoff
+/// let osc = OscServer::bind("0.0.0.0:9000")
+///     .unwrap()
+///     .with_graph(runtime.graph())
+///     .with_feedback("192.168.1.20:9001")
+///     .unwrap()
+///     .run()
+///     .unwrap();
+/// ```
+pub struct OscServer {
+    socket: UdpSocket,
+    prefix: String,
+    params: FxHashMap<String, Param>,
+    meter_prefix: String,
+    meters: FxHashMap<String, MeterReader>,
+    feedback: Option<SocketAddr>,
+    feedback_interval: Duration,
+    sent: FxHashMap<String, AnySignal>,
+}
+
+impl OscServer {
+    /// Binds a new server to the given UDP address, such as `0.0.0.0:9000`.
+    ///
+    /// The server has no params until they're added with [`OscServer::with_graph`] or [`OscServer::with_param`].
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, OscError> {
+        Ok(Self {
+            socket: UdpSocket::bind(address)?,
+            prefix: DEFAULT_OSC_PREFIX.to_string(),
+            params: FxHashMap::default(),
+            meter_prefix: DEFAULT_OSC_METER_PREFIX.to_string(),
+            meters: FxHashMap::default(),
+            feedback: None,
+            feedback_interval: Duration::from_millis(50),
+            sent: FxHashMap::default(),
+        })
+    }
+
+    /// Sets the address prefix under which params are exposed (default: `/raug/param`).
+    ///
+    /// An empty prefix exposes the param `cutoff` as `/cutoff`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the address prefix under which meter readings are sent (default: `/raug/meter`).
+    pub fn with_meter_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.meter_prefix = prefix.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Exposes all params and meter taps of the graph, including those of its module instances.
+    pub fn with_graph(mut self, graph: &Graph) -> Self {
+        for name in graph.param_names() {
+            if let Some(param) = graph.param_named(&name) {
+                self.params.insert(name, param.clone());
+            }
+        }
+        for name in graph.tap_names() {
+            if let Some(TapReader::Meter(reader)) = graph.tap_named(&name) {
+                self.meters.insert(name, reader);
+            }
+        }
+        self
+    }
+
+    /// Exposes a single param under its name.
+    pub fn with_param(mut self, param: &Param) -> Self {
+        self.params.insert(param.name().to_string(), param.clone());
+        self
+    }
+
+    /// Sends the readings of a meter under the given name, such as a [`MeterTap`](crate::builtins::MeterTap) looked up with [`RuntimeHandle::tap`](crate::runtime::RuntimeHandle::tap).
+    ///
+    /// The server consumes the meter's readings, so other readers of the same meter miss the readings it sends.
+    pub fn with_meter(mut self, name: impl Into<String>, meter: &MeterReader) -> Self {
+        self.meters.insert(name.into(), meter.clone());
+        self
+    }
+
+    /// Sends the value of each param to the given address whenever it changes, along with the new readings of each meter.
+    ///
+    /// This keeps the controls and meters of an OSC client in sync with the patch.
+    pub fn with_feedback(mut self, address: impl ToSocketAddrs) -> Result<Self, OscError> {
+        self.feedback = address.to_socket_addrs()?.next();
+        Ok(self)
+    }
+
+    /// Sets how often the params and meters are checked for changes to send as feedback (default: 50 ms).
+    pub fn with_feedback_interval(mut self, interval: Duration) -> Self {
+        self.feedback_interval = interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Returns the local address the server is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr, OscError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Returns the OSC address of the param with the given name.
+    pub fn param_address(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+
+    /// Returns the OSC address the readings of the meter with the given name are sent to.
+    pub fn meter_address(&self, name: &str) -> String {
+        format!("{}/{}", self.meter_prefix, name)
+    }
+
+    /// Starts listening for messages on a background thread.
+    ///
+    /// The server stops when the returned handle is stopped or dropped.
+    pub fn run(mut self) -> Result<OscServerHandle, OscError> {
+        self.socket.set_read_timeout(Some(self.feedback_interval))?;
+        let local_addr = self.socket.local_addr()?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = OscServerHandle {
+            running: running.clone(),
+            local_addr,
+        };
+
+        std::thread::spawn(move || {
+            let mut buf = vec![0; MAX_PACKET_SIZE];
+            let mut last_feedback = Instant::now();
+
+            while running.load(Ordering::Relaxed) {
+                match self.socket.recv_from(&mut buf) {
+                    Ok((len, from)) => self.handle_packet(&buf[..len], from),
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                        ) => {}
+                    Err(err) => log::error!("OSC receive error: {}", err),
+                }
+
+                if last_feedback.elapsed() >= self.feedback_interval {
+                    last_feedback = Instant::now();
+                    self.send_feedback();
+                }
+            }
+        });
+
+        Ok(handle)
+    }
+
+    fn handle_packet(&mut self, bytes: &[u8], from: SocketAddr) {
+        let packet = match OscPacket::from_bytes(bytes) {
+            Ok(packet) => packet,
+            Err(err) => {
+                log::warn!("Invalid OSC packet from {}: {}", from, err);
+                return;
+            }
+        };
+
+        // bundles are applied as soon as they arrive, regardless of their time tag
+        for message in packet.messages() {
+            self.handle_message(message, from);
+        }
+    }
+
+    fn handle_message(&self, message: &OscMessage, from: SocketAddr) {
+        let Some(name) = message
+            .address
+            .strip_prefix(&self.prefix)
+            .and_then(|name| name.strip_prefix('/'))
+        else {
+            log::debug!("Ignoring OSC message to {}", message.address);
+            return;
+        };
+
+        let Some(param) = self.params.get(name) else {
+            log::warn!("No param named {} for OSC message", name);
+            return;
+        };
+
+        // a message without arguments asks for the param's current value
+        if message.args.is_empty() {
+            if let Some(value) = param.last() {
+                self.send_value(name, &value, from);
+            }
+            return;
+        }
+
+        match message.to_signal_of_type(param.signal_type()) {
+            Some(value) => param.send_any(value),
+            None => log::warn!(
+                "Can't convert OSC arguments {:?} to {:?} for param {}",
+                message.args,
+                param.signal_type(),
+                name
+            ),
+        }
+    }
+
+    fn send_feedback(&mut self) {
+        let Some(target) = self.feedback else {
+            return;
+        };
+
+        for (name, param) in &self.params {
+            let Some(value) = param.last() else {
+                continue;
+            };
+            // MIDI params only hold events, so there is no value to keep in sync
+            if value.signal_type() == SignalType::Midi || self.sent.get(name) == Some(&value) {
+                continue;
+            }
+            self.send_value(name, &value, target);
+            self.sent.insert(name.clone(), value);
+        }
+
+        for (name, meter) in &self.meters {
+            // the readings since the last feedback, combined into one
+            let Some(reading) = meter.read() else {
+                continue;
+            };
+            let message = OscMessage::new(self.meter_address(name))
+                .with_arg(OscArg::Float(reading.peak as f32))
+                .with_arg(OscArg::Float(reading.rms as f32));
+            self.send_message(&message, target);
+        }
+    }
+
+    fn send_value(&self, name: &str, value: &AnySignal, target: SocketAddr) {
+        let message = OscMessage {
+            address: self.param_address(name),
+            args: OscArg::from_signal(value),
+        };
+        self.send_message(&message, target);
+    }
+
+    fn send_message(&self, message: &OscMessage, target: SocketAddr) {
+        if let Err(err) = self.socket.send_to(&message.to_bytes(), target) {
+            log::error!("OSC send error: {}", err);
+        }
+    }
+}
+
+/// A handle to a running [`OscServer`] that can be used to stop it.
+#[must_use = "The OSC server handle must be kept alive for the server to continue running"]
+pub struct OscServerHandle {
+    running: Arc<AtomicBool>,
+    local_addr: SocketAddr,
+}
+
+impl OscServerHandle {
+    /// Returns the local address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stops the server. It finishes within one feedback interval.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for OscServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{GraphBuilder, MeterTap};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // receives messages on the client socket until one for the address carries the expected arguments
+    fn recv_until(client: &UdpSocket, address: &str, args: &[OscArg]) {
+        let mut buf = vec![0; MAX_PACKET_SIZE];
+        let start = Instant::now();
+        while start.elapsed() < TIMEOUT {
+            let Ok(len) = client.recv(&mut buf) else {
+                continue;
+            };
+            let message = OscMessage::from_bytes(&buf[..len]).unwrap();
+            if message.address == address && message.args == args {
+                return;
+            }
+        }
+        panic!("no reply to {} with {:?}", address, args);
+    }
+
+    #[test]
+    fn sets_params_and_sends_feedback() {
+        let param = Param::bounded("cutoff", 1000.0, 20.0, 20000.0);
+        param.recv();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let server = OscServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_param(&param)
+            .with_feedback(client.local_addr().unwrap())
+            .unwrap()
+            .with_feedback_interval(Duration::from_millis(5))
+            .run()
+            .unwrap();
+        let address = "/raug/param/cutoff";
+
+        // the initial value is sent as feedback
+        recv_until(&client, address, &[OscArg::Float(1000.0)]);
+
+        let message = OscMessage::new(address).with_arg(OscArg::Float(440.0));
+        client
+            .send_to(&message.to_bytes(), server.local_addr())
+            .unwrap();
+        // the graph would receive the value while processing
+        let start = Instant::now();
+        while param.recv().is_none() {
+            assert!(start.elapsed() < TIMEOUT, "the param wasn't set");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(param.last(), Some(AnySignal::Float(Some(440.0))));
+
+        // the new value is sent as feedback, and in reply to a query
+        recv_until(&client, address, &[OscArg::Float(440.0)]);
+        client
+            .send_to(&OscMessage::new(address).to_bytes(), server.local_addr())
+            .unwrap();
+        recv_until(&client, address, &[OscArg::Float(440.0)]);
+
+        server.stop();
+    }
+
+    #[test]
+    fn sends_meter_readings_as_feedback() {
+        let graph = GraphBuilder::new();
+        let meter = graph.add_tap(MeterTap::new("level").with_window(0.01));
+        meter.input("in").connect(graph.constant(0.5).output(0));
+        let mut runtime = graph.build_runtime();
+        runtime
+            .run_offline(Duration::from_millis(20), 1000.0, 10)
+            .unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let server = OscServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_graph(runtime.graph())
+            .with_feedback(client.local_addr().unwrap())
+            .unwrap()
+            .with_feedback_interval(Duration::from_millis(5))
+            .run()
+            .unwrap();
+
+        recv_until(
+            &client,
+            "/raug/meter/level",
+            &[OscArg::Float(0.5), OscArg::Float(0.5)],
+        );
+
+        server.stop();
+    }
+}
@@ -1,6 +1,6 @@
 //! Audio processing utilities and types.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, ops::Range};
 
 use downcast_rs::{impl_downcast, Downcast};
 use itertools::Either;
@@ -9,8 +9,8 @@ use thiserror::Error;
 use crate::{
     graph::asset::{AssetRef, Assets},
     signal::{
-        AnySignal, AnySignalMut, AnySignalRef, Float, List, MidiMessage, Signal, SignalBuffer,
-        SignalType,
+        AnySignal, AnySignalMut, AnySignalRef, Buffer, Float, List, MidiMessage, Signal,
+        SignalBuffer, SignalType,
     },
     GraphSerde,
 };
@@ -70,6 +70,10 @@ pub enum ProcessorError {
     #[error("FFT error: {0}")]
     Fft(#[from] crate::fft::FftError),
 
+    /// The operation is not supported by the processor.
+    #[error("Unsupported operation: {0}")]
+    Unsupported(&'static str),
+
     #[error("Other error")]
     Other,
 }
@@ -130,11 +134,19 @@ where
 /// The mode in which a processor should process signals.
 ///
 /// - `Block` means the processor processes the entire block of samples at once.
+/// - `SubBlock` means the processor processes a contiguous range of samples within the block.
 /// - `Sample` means the processor processes each sample individually.
 #[derive(Debug, Clone, Copy)]
 pub enum ProcessMode {
     /// The processor should process the entire block of samples at once.
     Block,
+    /// The processor should process the given range of samples within the block.
+    SubBlock(
+        /// The index of the first sample of the range within the block.
+        usize,
+        /// The number of samples in the range.
+        usize,
+    ),
     /// The processor should process the sample at the given index.
     Sample(
         /// The index of the current sample within the block.
@@ -142,11 +154,25 @@ pub enum ProcessMode {
     ),
 }
 
+impl ProcessMode {
+    /// Returns the range of sample indices that should be processed within a block of the given size.
+    #[inline]
+    pub fn sample_range(&self, block_size: usize) -> Range<usize> {
+        match *self {
+            ProcessMode::Block => 0..block_size,
+            ProcessMode::SubBlock(start, len) => start..start + len,
+            ProcessMode::Sample(sample_index) => sample_index..sample_index + 1,
+        }
+    }
+}
+
 /// The output of a [`Processor`].
 #[derive(Debug)]
 pub enum ProcessorOutput<'a> {
     /// A block of signals.
     Block(&'a mut SignalBuffer),
+    /// A range of signals within a block, given by its start index and length.
+    SubBlock(&'a mut SignalBuffer, usize, usize),
     /// A single sample.
     Sample(&'a mut SignalBuffer, usize),
 }
@@ -157,6 +183,7 @@ impl<'a> ProcessorOutput<'a> {
     pub fn signal_type(&self) -> SignalType {
         match self {
             ProcessorOutput::Block(buffer) => buffer.signal_type(),
+            ProcessorOutput::SubBlock(buffer, _, _) => buffer.signal_type(),
             ProcessorOutput::Sample(buffer, _) => buffer.signal_type(),
         }
     }
@@ -166,6 +193,7 @@ impl<'a> ProcessorOutput<'a> {
     pub fn len(&self) -> usize {
         match self {
             ProcessorOutput::Block(buffer) => buffer.len(),
+            ProcessorOutput::SubBlock(_, _, len) => *len,
             ProcessorOutput::Sample(buffer, _) => buffer.len(),
         }
     }
@@ -175,6 +203,7 @@ impl<'a> ProcessorOutput<'a> {
     pub fn is_empty(&self) -> bool {
         match self {
             ProcessorOutput::Block(buffer) => buffer.is_empty(),
+            ProcessorOutput::SubBlock(_, _, len) => *len == 0,
             ProcessorOutput::Sample(buffer, _) => buffer.is_empty(),
         }
     }
@@ -183,9 +212,12 @@ impl<'a> ProcessorOutput<'a> {
     #[inline]
     pub fn iter_mut(&'a mut self) -> impl Iterator<Item = AnySignalMut<'a>> {
         match self {
-            ProcessorOutput::Block(buffer) => Either::Left(buffer.iter_mut()),
+            ProcessorOutput::Block(buffer) => Ternary::A(buffer.iter_mut()),
+            ProcessorOutput::SubBlock(buffer, start, len) => {
+                Ternary::B(buffer.iter_mut().skip(*start).take(*len))
+            }
             ProcessorOutput::Sample(buffer, sample_index) => {
-                Either::Right(std::iter::once(buffer.get_mut(*sample_index).unwrap()))
+                Ternary::C(std::iter::once(buffer.get_mut(*sample_index).unwrap()))
             }
         }
     }
@@ -197,6 +229,9 @@ impl<'a> ProcessorOutput<'a> {
             ProcessorOutput::Block(buffer) => {
                 Either::Left(buffer.as_type_mut().unwrap().iter_mut())
             }
+            ProcessorOutput::SubBlock(buffer, start, len) => {
+                Either::Left(buffer.as_type_mut().unwrap()[*start..*start + *len].iter_mut())
+            }
             ProcessorOutput::Sample(buffer, sample_index) => Either::Right(std::iter::once(
                 &mut buffer.as_type_mut::<S>().unwrap()[*sample_index],
             )),
@@ -212,6 +247,13 @@ impl<'a> ProcessorOutput<'a> {
     pub fn get_as<S: Signal>(&self, index: usize) -> Option<&Option<S>> {
         match self {
             ProcessorOutput::Block(buffer) => buffer.as_type::<S>().unwrap().get(index),
+            ProcessorOutput::SubBlock(buffer, start, len) => {
+                if index < *len {
+                    buffer.as_type::<S>().unwrap().get(*start + index)
+                } else {
+                    None
+                }
+            }
             ProcessorOutput::Sample(buffer, sample_index) => {
                 buffer.as_type::<S>().unwrap().get(*sample_index)
             }
@@ -229,6 +271,9 @@ impl<'a> ProcessorOutput<'a> {
             ProcessorOutput::Block(buffer) => {
                 buffer.set(index, value.into());
             }
+            ProcessorOutput::SubBlock(buffer, start, _) => {
+                buffer.set(*start + index, value.into());
+            }
             ProcessorOutput::Sample(buffer, sample_index) => {
                 buffer.set(*sample_index, value.into())
             }
@@ -246,6 +291,9 @@ impl<'a> ProcessorOutput<'a> {
             ProcessorOutput::Block(buffer) => {
                 buffer.as_type_mut::<S>().unwrap()[index] = value.into();
             }
+            ProcessorOutput::SubBlock(buffer, start, _) => {
+                buffer.as_type_mut::<S>().unwrap()[*start + index] = value.into();
+            }
             ProcessorOutput::Sample(buffer, sample_index) => {
                 buffer.as_type_mut::<S>().unwrap()[*sample_index] = value.into();
             }
@@ -257,6 +305,7 @@ impl<'a> ProcessorOutput<'a> {
     pub fn set_none(&mut self, index: usize) {
         match self {
             ProcessorOutput::Block(buffer) => buffer.set_none(index),
+            ProcessorOutput::SubBlock(buffer, start, _) => buffer.set_none(*start + index),
             ProcessorOutput::Sample(buffer, sample_index) => buffer.set_none(*sample_index),
         }
     }
@@ -266,6 +315,9 @@ impl<'a> ProcessorOutput<'a> {
     pub fn fill_as<S: Signal + Clone>(&mut self, value: impl Into<Option<S>>) {
         match self {
             ProcessorOutput::Block(buffer) => buffer.as_type_mut::<S>().unwrap().fill(value.into()),
+            ProcessorOutput::SubBlock(buffer, start, len) => {
+                buffer.as_type_mut::<S>().unwrap()[*start..*start + *len].fill(value.into())
+            }
             ProcessorOutput::Sample(buffer, sample_index) => {
                 buffer.as_type_mut::<S>().unwrap()[*sample_index] = value.into();
             }
@@ -277,6 +329,11 @@ impl<'a> ProcessorOutput<'a> {
     pub fn fill(&mut self, value: AnySignal) {
         match self {
             ProcessorOutput::Block(buffer) => buffer.fill(value),
+            ProcessorOutput::SubBlock(buffer, start, len) => {
+                for index in *start..*start + *len {
+                    buffer.set(index, value.as_ref());
+                }
+            }
             ProcessorOutput::Sample(buffer, sample_index) => {
                 buffer.set(*sample_index, value.as_ref());
             }
@@ -353,7 +410,7 @@ impl<'a, 'b> ProcessorInputs<'a, 'b> {
 
     /// Returns the asset with the given name, if it exists.
     #[inline]
-    pub fn asset(&self, name: &str) -> Result<AssetRef, ProcessorError> {
+    pub fn asset(&self, name: &str) -> Result<AssetRef<'_>, ProcessorError> {
         self.assets
             .get(name)
             .ok_or_else(|| ProcessorError::AssetNotFound(name.into()))
@@ -368,15 +425,27 @@ impl<'a, 'b> ProcessorInputs<'a, 'b> {
             .copied()
     }
 
+    /// Returns the input signal at the given index as a contiguous slice of samples, if it is a dense [`Float`] signal (see [`Buffer::as_dense`]).
+    ///
+    /// Dense inputs are only available when the processor is processing the whole block.
+    #[inline]
+    pub fn dense_input(&self, index: usize) -> Option<&'b [Float]> {
+        if !matches!(self.mode, ProcessMode::Block) {
+            return None;
+        }
+        self.input(index)?.as_type::<Float>()?.as_dense()
+    }
+
     /// Returns an iterator over the input signal at the given index.
     #[inline]
-    pub fn iter_input(&self, index: usize) -> impl Iterator<Item = Option<AnySignalRef>> {
+    pub fn iter_input(&self, index: usize) -> impl Iterator<Item = Option<AnySignalRef<'_>>> {
         let buffer = &self.inputs[index];
         if let Some(buffer) = buffer.as_ref() {
             if let ProcessMode::Sample(sample_index) = self.mode {
                 Ternary::B(std::iter::once(Some(buffer.get(sample_index).unwrap())))
             } else {
-                Ternary::A(buffer.iter().map(Some))
+                let range = self.mode.sample_range(buffer.len());
+                Ternary::A(buffer.iter().skip(range.start).take(range.len()).map(Some))
             }
         } else {
             Ternary::C(std::iter::repeat(None))
@@ -407,7 +476,8 @@ impl<'a, 'b> ProcessorInputs<'a, 'b> {
                 })
             }
         } else if buffer.signal_type().is_compatible_with(&S::signal_type()) {
-            Ok(Ternary::A(buffer.as_type::<S>().unwrap().iter()))
+            let range = self.mode.sample_range(buffer.len());
+            Ok(Ternary::A(buffer.as_type::<S>().unwrap()[range].iter()))
         } else {
             Err(ProcessorError::InputSpecMismatch {
                 index,
@@ -423,7 +493,17 @@ impl<'a, 'b> ProcessorInputs<'a, 'b> {
         &self,
         index: usize,
     ) -> Result<impl Iterator<Item = Option<Float>> + '_, ProcessorError> {
-        Self::iter_input_as::<Float>(self, index).map(|iter| iter.copied())
+        // dense inputs are read from their samples, without writing their entries
+        let dense = self
+            .input(index)
+            .and_then(|input| input.as_type::<Float>()?.as_dense());
+        if let Some(samples) = dense {
+            let range = self.mode.sample_range(samples.len());
+            return Ok(Either::Left(
+                samples[range].iter().map(|&sample| Some(sample)),
+            ));
+        }
+        Self::iter_input_as::<Float>(self, index).map(|iter| Either::Right(iter.copied()))
     }
 
     /// Returns an iterator over the input signal at the given index, if it is an [`i64`] signal.
@@ -502,10 +582,14 @@ impl<'a> ProcessorOutputs<'a> {
     /// Returns the output signal at the given index.
     #[inline]
     pub fn output(&mut self, index: usize) -> ProcessorOutput<'_> {
-        if let ProcessMode::Sample(sample_index) = self.mode {
-            ProcessorOutput::Sample(&mut self.outputs[index], sample_index)
-        } else {
-            ProcessorOutput::Block(&mut self.outputs[index])
+        match self.mode {
+            ProcessMode::Block => ProcessorOutput::Block(&mut self.outputs[index]),
+            ProcessMode::SubBlock(start, len) => {
+                ProcessorOutput::SubBlock(&mut self.outputs[index], start, len)
+            }
+            ProcessMode::Sample(sample_index) => {
+                ProcessorOutput::Sample(&mut self.outputs[index], sample_index)
+            }
         }
     }
 
@@ -515,14 +599,24 @@ impl<'a> ProcessorOutputs<'a> {
         &self.output_spec[index]
     }
 
+    /// Returns the output signal at the given index, so that it can be filled with [`Buffer::fill_dense`], if it is a [`Float`] signal and the processor is processing the whole block.
+    #[inline]
+    pub fn dense_output(&mut self, index: usize) -> Option<&mut Buffer<Float>> {
+        if !matches!(self.mode, ProcessMode::Block) {
+            return None;
+        }
+        self.outputs.get_mut(index)?.as_type_mut::<Float>()
+    }
+
     /// Returns an iterator over the output signal at the given index.
     #[inline]
-    pub fn iter_output_mut(&mut self, index: usize) -> impl Iterator<Item = AnySignalMut> {
+    pub fn iter_output_mut(&mut self, index: usize) -> impl Iterator<Item = AnySignalMut<'_>> {
         let output = &mut self.outputs[index];
         if let ProcessMode::Sample(sample_index) = self.mode {
             Either::Left(std::iter::once(output.get_mut(sample_index).unwrap()))
         } else {
-            Either::Right(output.iter_mut())
+            let range = self.mode.sample_range(output.len());
+            Either::Right(output.iter_mut().skip(range.start).take(range.len()))
         }
     }
 
@@ -557,7 +651,8 @@ impl<'a> ProcessorOutputs<'a> {
                         actual,
                     })?;
 
-            Ok(Either::Right(output.iter_mut()))
+            let range = self.mode.sample_range(output.len());
+            Ok(Either::Right(output[range].iter_mut()))
         }
     }
 
@@ -697,6 +792,46 @@ where
         inputs: ProcessorInputs,
         outputs: ProcessorOutputs,
     ) -> Result<(), ProcessorError>;
+
+    /// Returns `true` if the processor's outputs will keep the values of the last processed sample for as long as its inputs keep theirs.
+    ///
+    /// The runtime uses this to put idle nodes to sleep: a steady node whose connected inputs all come from sleeping nodes, and whose outputs were constant over the last block, is not processed, and its outputs keep their values.
+    /// Stateless processors, and processors whose state has settled (e.g. a released envelope or a delay line holding a single value), should return `true`.
+    fn is_steady(&self) -> bool {
+        false
+    }
+
+    /// Returns the minimum number of samples by which the processor's outputs lag behind its inputs.
+    ///
+    /// When a feedback loop in the graph passes through processors with a nonzero delay, the runtime processes the loop in sub-blocks as large as those delays allow instead of sample by sample.
+    /// In that case, [`Processor::process_delay_outputs()`] and [`Processor::process_delay_inputs()`] are called for each sub-block instead of [`Processor::process()`].
+    ///
+    /// Processors that return a nonzero delay must implement both of those methods.
+    fn feedback_delay(&self) -> usize {
+        0
+    }
+
+    /// Writes the output signals for the current sub-block, using only inputs received in previous calls to [`Processor::process_delay_inputs()`].
+    ///
+    /// The runtime guarantees that the sub-block is no longer than [`Processor::feedback_delay()`].
+    ///
+    /// This function is NOT ALLOWED to allocate memory.
+    #[allow(unused)]
+    fn process_delay_outputs(&mut self, outputs: ProcessorOutputs) -> Result<(), ProcessorError> {
+        Err(ProcessorError::Unsupported(
+            "process_delay_outputs() must be implemented by processors with a nonzero feedback delay",
+        ))
+    }
+
+    /// Receives the input signals for the current sub-block, after its outputs were written by [`Processor::process_delay_outputs()`].
+    ///
+    /// This function is NOT ALLOWED to allocate memory.
+    #[allow(unused)]
+    fn process_delay_inputs(&mut self, inputs: ProcessorInputs) -> Result<(), ProcessorError> {
+        Err(ProcessorError::Unsupported(
+            "process_delay_inputs() must be implemented by processors with a nonzero feedback delay",
+        ))
+    }
 }
 impl_downcast!(Processor);
 
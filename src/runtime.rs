@@ -1,6 +1,7 @@
 //! The audio graph processing runtime.
 
 use std::{
+    ops::Range,
     sync::{mpsc, Arc, Mutex},
     time::Duration,
 };
@@ -11,8 +12,11 @@ use rustc_hash::{FxBuildHasher, FxHashMap};
 
 use crate::{
     debug_once,
-    graph::{Graph, GraphRunError, GraphRunErrorType, NodeIndex},
-    prelude::{Param, ProcessorInputs, SignalSpec},
+    graph::{FeedbackSchedule, Graph, GraphRunError, GraphRunErrorType, NodeIndex},
+    prelude::{
+        LoudnessReport, MidiOutEvent, MidiOutRx, Param, ProcessorInputs, SignalSpec,
+        SysExAssembler, TapReader,
+    },
     processor::{ProcessMode, ProcessorError, ProcessorOutputs},
     signal::{Float, MidiMessage, SignalBuffer},
 };
@@ -48,7 +52,7 @@ pub enum RuntimeError {
     #[error("Unsupported sample format: {0}")]
     UnsupportedSampleFormat(cpal::SampleFormat),
 
-    /// An error occurred while initializing MIDI input.
+    /// An error occurred while initializing MIDI input or output.
     MidirInitError(#[from] midir::InitError),
 
     /// The requested MIDI port is unavailable.
@@ -58,6 +62,9 @@ pub enum RuntimeError {
     /// An error occurred while connecting to a MIDI port.
     MidiConnectError(#[from] midir::ConnectError<midir::MidiInput>),
 
+    /// An error occurred while connecting to a MIDI output port.
+    MidiOutputConnectError(#[from] midir::ConnectError<midir::MidiOutput>),
+
     /// An error occurred while running the audio graph.
     GraphRunError(#[from] GraphRunError),
 
@@ -70,6 +77,10 @@ pub enum RuntimeError {
     /// The number of channels in the audio stream does not match the number of outputs in the graph.
     #[error("Channel mismatch: expected {0} channels, got {1}")]
     ChannelMismatch(usize, usize),
+
+    /// The samples to process are not within the current block.
+    #[error("Samples {0}..{1} are outside the block of {2} samples")]
+    OutOfBlock(usize, usize, usize),
 }
 
 /// Result type for runtime operations.
@@ -122,16 +133,41 @@ pub(crate) struct NodeBuffers {
     input_spec: Vec<SignalSpec>,
     output_spec: Vec<SignalSpec>,
     outputs: Vec<SignalBuffer>,
+    // whether the node was skipped in the last block because it was idle
+    #[cfg_attr(feature = "serde", serde(skip))]
+    asleep: bool,
+    // for graph inputs, which are written from outside the graph: the last sample of the previous block
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_input: Option<SignalBuffer>,
 }
 
 impl NodeBuffers {
     fn resize(&mut self, block_size: usize) {
         for (spec, buffer) in self.output_spec.iter().zip(&mut self.outputs) {
-            buffer.resize_with_hint(block_size, &spec.signal_type);
+            if buffer.signal_type().is_compatible_with(&spec.signal_type) {
+                // new samples repeat the last one, so that the outputs of sleeping nodes stay valid
+                if !buffer.resize_hold(block_size) {
+                    // the new samples are empty, so the node has to run again to fill them
+                    self.asleep = false;
+                }
+            } else {
+                buffer.resize_with_hint(block_size, &spec.signal_type);
+            }
         }
     }
 }
 
+/// Which of a processor's processing methods to call for a node.
+#[derive(Debug, Clone, Copy)]
+enum NodePhase {
+    /// [`Processor::process()`](crate::processor::Processor::process)
+    Process,
+    /// [`Processor::process_delay_outputs()`](crate::processor::Processor::process_delay_outputs)
+    DelayOutputs,
+    /// [`Processor::process_delay_inputs()`](crate::processor::Processor::process_delay_inputs)
+    DelayInputs,
+}
+
 /// The audio graph processing runtime.
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -141,11 +177,23 @@ pub struct Runtime {
     sample_rate: Float,
     block_size: usize,
     max_block_size: usize,
+    #[cfg_attr(feature = "serde", serde(default = "sleep_enabled_by_default"))]
+    sleep_enabled: bool,
+    // forces every node to be processed in the next block, e.g. after the graph was allocated
+    #[cfg_attr(feature = "serde", serde(skip))]
+    wake_all: bool,
+}
+
+#[cfg(feature = "serde")]
+fn sleep_enabled_by_default() -> bool {
+    true
 }
 
 impl Runtime {
     /// Creates a new runtime from the given graph.
     pub fn new(mut graph: Graph) -> Self {
+        graph.fill_missing_specs();
+
         let mut buffer_cache =
             FxHashMap::with_capacity_and_hasher(graph.digraph().node_count(), FxBuildHasher);
 
@@ -161,12 +209,19 @@ impl Runtime {
                     outputs.push(buffer);
                 }
 
+                let last_input = graph
+                    .input_indices()
+                    .contains(&node_id)
+                    .then(|| SignalBuffer::new_of_type(&output_spec[0].signal_type, 1));
+
                 buffer_cache.insert(
                     node_id,
                     NodeBuffers {
                         input_spec: node.input_spec().to_vec(),
                         output_spec: output_spec.to_vec(),
                         outputs,
+                        asleep: false,
+                        last_input,
                     },
                 );
 
@@ -180,6 +235,8 @@ impl Runtime {
             sample_rate: 0.0,
             block_size: 0,
             max_block_size: 0,
+            sleep_enabled: true,
+            wake_all: true,
         }
     }
 
@@ -212,6 +269,8 @@ impl Runtime {
         for buffers in self.buffer_cache.values_mut() {
             buffers.resize(max_block_size);
         }
+
+        self.wake_all = true;
     }
 
     /// Resets the runtime for the given sample rate and block size.
@@ -238,6 +297,35 @@ impl Runtime {
         Ok(())
     }
 
+    /// Enables or disables putting idle nodes to sleep. Enabled by default.
+    ///
+    /// When enabled, a node whose processor is [steady](crate::processor::Processor::is_steady), whose connected inputs all come from sleeping nodes, and whose outputs were constant over the last block, is skipped, and its outputs keep their values.
+    /// Sparse patches (e.g. a synth with most of its voices silent) then only pay for the nodes that are actually doing something.
+    #[inline]
+    pub fn set_sleep_enabled(&mut self, enabled: bool) {
+        self.sleep_enabled = enabled;
+        if !enabled {
+            for buffers in self.buffer_cache.values_mut() {
+                buffers.asleep = false;
+            }
+        }
+    }
+
+    /// Returns `true` if idle nodes are put to sleep. See [`Runtime::set_sleep_enabled`].
+    #[inline]
+    pub fn sleep_enabled(&self) -> bool {
+        self.sleep_enabled
+    }
+
+    /// Returns the number of nodes that were skipped in the last processed block because they were idle.
+    #[inline]
+    pub fn num_sleeping_nodes(&self) -> usize {
+        self.buffer_cache
+            .values()
+            .filter(|buffers| buffers.asleep)
+            .count()
+    }
+
     /// Returns a reference to the audio graph.
     #[inline]
     pub fn graph(&self) -> &Graph {
@@ -256,22 +344,155 @@ impl Runtime {
         for i in 0..self.graph.sccs().len() {
             if self.graph.sccs()[i].len() == 1 {
                 let node_id = self.graph.sccs()[i][0];
-                self.process_node(node_id, ProcessMode::Block)?;
-            } else {
-                let nodes = self.graph.sccs()[i].clone();
-                for sample_index in 0..self.block_size {
-                    for &node_id in &nodes {
-                        self.process_node(node_id, ProcessMode::Sample(sample_index))?;
-                    }
+                if self.can_sleep(node_id) {
+                    self.buffer_cache.get_mut(&node_id).unwrap().asleep = true;
+                } else {
+                    self.process_node(node_id, ProcessMode::Block)?;
+                    self.buffer_cache.get_mut(&node_id).unwrap().asleep = false;
                 }
+            } else {
+                // take the schedule out of the graph so that we can borrow the runtime mutably without cloning it
+                let schedule = std::mem::take(&mut self.graph.feedback_schedules[i]);
+                let result = self.process_feedback_loop(&schedule, 0..self.block_size);
+                self.graph.feedback_schedules[i] = schedule;
+                result?;
+            }
+        }
+
+        for &node_id in self.graph.input_indices() {
+            let buffers = self.buffer_cache.get_mut(&node_id).unwrap();
+            if let (Some(last_input), Some(value)) =
+                (&mut buffers.last_input, buffers.outputs[0].iter().last())
+            {
+                last_input.set(0, value);
             }
         }
 
+        self.wake_all = false;
+
         Ok(())
     }
 
+    /// Runs the audio graph for the samples of the current block selected by `mode`, leaving the other samples of its buffers untouched.
+    ///
+    /// Use this to drive a graph from within a feedback loop of an outer graph, which processes its nodes a sub-block (or a sample) at a time.
+    /// Unlike [`Runtime::set_block_size`], this never resizes the buffers of the graph, and the sample indices are those of the whole block.
+    /// Nodes are never put to sleep while processing part of a block.
     #[cfg_attr(feature = "profiling", inline(never))]
+    pub fn process_with_mode(&mut self, mode: ProcessMode) -> RuntimeResult<()> {
+        let range = mode.sample_range(self.block_size);
+        if range.end > self.block_size {
+            return Err(RuntimeError::OutOfBlock(
+                range.start,
+                range.end,
+                self.block_size,
+            ));
+        }
+        if range.len() == self.block_size {
+            return self.process();
+        }
+
+        for i in 0..self.graph.sccs().len() {
+            if self.graph.sccs()[i].len() == 1 {
+                let node_id = self.graph.sccs()[i][0];
+                self.process_node(node_id, mode)?;
+                self.buffer_cache.get_mut(&node_id).unwrap().asleep = false;
+            } else {
+                let schedule = std::mem::take(&mut self.graph.feedback_schedules[i]);
+                let result = self.process_feedback_loop(&schedule, range.clone());
+                self.graph.feedback_schedules[i] = schedule;
+                result?;
+            }
+        }
+
+        // the sleep checks compare whole blocks, so the next block is processed in full
+        self.wake_all = true;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the node's outputs would not change if it were processed for the current block.
+    #[inline]
+    fn can_sleep(&self, node_id: NodeIndex) -> bool {
+        if !self.sleep_enabled || self.wake_all {
+            return false;
+        }
+
+        if !self.graph.digraph()[node_id].is_steady() {
+            return false;
+        }
+
+        let inputs_asleep = self
+            .graph
+            .digraph()
+            .edges_directed(node_id, Direction::Incoming)
+            .all(|edge| self.buffer_cache[&edge.source()].asleep);
+        if !inputs_asleep {
+            return false;
+        }
+
+        let buffers = &self.buffer_cache[&node_id];
+        if let Some(last_input) = &buffers.last_input {
+            // graph inputs are written from outside the graph, so every sample of the new block must still hold the value the last block ended with
+            return buffers.outputs[0].is_constant()
+                && buffers.outputs[0].get(0) == last_input.get(0);
+        }
+        buffers.asleep || buffers.outputs.iter().all(SignalBuffer::is_constant)
+    }
+
+    #[cfg_attr(feature = "profiling", inline(never))]
+    fn process_feedback_loop(
+        &mut self,
+        schedule: &FeedbackSchedule,
+        range: Range<usize>,
+    ) -> RuntimeResult<()> {
+        let Some(sub_block_size) = schedule.sub_block_size else {
+            // the loop contains a cycle without any delay, so it has to be processed sample by sample
+            for sample_index in range {
+                for &node_id in &schedule.nodes {
+                    self.process_node(node_id, ProcessMode::Sample(sample_index))?;
+                }
+            }
+            return Ok(());
+        };
+
+        let mut start = range.start;
+        while start < range.end {
+            let len = sub_block_size.min(range.end - start);
+            let mode = if len == self.block_size {
+                ProcessMode::Block
+            } else {
+                ProcessMode::SubBlock(start, len)
+            };
+
+            for &node_id in &schedule.delay_nodes {
+                self.process_node_phase(node_id, mode, NodePhase::DelayOutputs)?;
+            }
+            for &node_id in &schedule.nodes {
+                self.process_node(node_id, mode)?;
+            }
+            for &node_id in &schedule.delay_nodes {
+                self.process_node_phase(node_id, mode, NodePhase::DelayInputs)?;
+            }
+
+            start += len;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
     fn process_node(&mut self, node_id: NodeIndex, mode: ProcessMode) -> RuntimeResult<()> {
+        self.process_node_phase(node_id, mode, NodePhase::Process)
+    }
+
+    #[cfg_attr(feature = "profiling", inline(never))]
+    fn process_node_phase(
+        &mut self,
+        node_id: NodeIndex,
+        mode: ProcessMode,
+        phase: NodePhase,
+    ) -> RuntimeResult<()> {
         let num_inputs = self.buffer_cache[&node_id].input_spec.len();
 
         let mut inputs: smallvec::SmallVec<[_; 8]> = smallvec::smallvec![None; num_inputs];
@@ -296,17 +517,22 @@ impl Runtime {
             debug_once!(format!("{}_spilled", node_id.index()) => "Input array for {} ({}) spilled over to the heap (has {} inputs > 8)", node.name(), node_id.index(), num_inputs);
         }
 
-        let result = node.process(
-            ProcessorInputs::new(
-                &buffers.input_spec,
-                &inputs[..],
-                &self.graph.assets,
-                mode,
-                self.sample_rate,
-                self.block_size,
-            ),
-            ProcessorOutputs::new(&buffers.output_spec, &mut buffers.outputs, mode),
+        let processor_inputs = ProcessorInputs::new(
+            &buffers.input_spec,
+            &inputs[..],
+            &self.graph.assets,
+            mode,
+            self.sample_rate,
+            self.block_size,
         );
+        let processor_outputs =
+            ProcessorOutputs::new(&buffers.output_spec, &mut buffers.outputs, mode);
+
+        let result = match phase {
+            NodePhase::Process => node.process(processor_inputs, processor_outputs),
+            NodePhase::DelayOutputs => node.process_delay_outputs(processor_outputs),
+            NodePhase::DelayInputs => node.process_delay_inputs(processor_inputs),
+        };
 
         if let Err(err) = result {
             let node = self.graph.digraph.node_weight(node_id).unwrap();
@@ -348,6 +574,12 @@ impl Runtime {
         self.graph.param_named(name)
     }
 
+    /// Returns the reader of the tap with the given name (see [`Graph::add_tap`]).
+    #[inline]
+    pub fn tap_named(&self, name: &str) -> Option<TapReader> {
+        self.graph.tap_named(name)
+    }
+
     /// Runs the audio graph offline for the given duration and sample rate, returning the output buffers.
     pub fn run_offline(
         &mut self,
@@ -355,7 +587,23 @@ impl Runtime {
         sample_rate: Float,
         block_size: usize,
     ) -> RuntimeResult<Box<[Box<[Float]>]>> {
-        self.run_offline_inner(duration, sample_rate, block_size, false)
+        self.run_offline_inner(duration, sample_rate, block_size, false, None)
+    }
+
+    /// Runs the audio graph offline for the given duration and sample rate, returning the output buffers and the messages sent to each MIDI output (see [`Graph::add_midi_output`]).
+    ///
+    /// The MIDI messages are in the order of [`Graph::midi_output_iter`], and their times are in samples from the start of the render.
+    #[allow(clippy::type_complexity)]
+    pub fn run_offline_with_midi(
+        &mut self,
+        duration: Duration,
+        sample_rate: Float,
+        block_size: usize,
+    ) -> RuntimeResult<(Box<[Box<[Float]>]>, Vec<Vec<MidiOutEvent>>)> {
+        let mut midi = vec![Vec::new(); self.graph.num_midi_outputs()];
+        let outputs =
+            self.run_offline_inner(duration, sample_rate, block_size, false, Some(&mut midi))?;
+        Ok((outputs, midi))
     }
 
     /// Runs the audio graph offline for the given duration and sample rate, returning the output buffers.
@@ -367,7 +615,7 @@ impl Runtime {
         sample_rate: Float,
         block_size: usize,
     ) -> RuntimeResult<Box<[Box<[Float]>]>> {
-        self.run_offline_inner(duration, sample_rate, block_size, true)
+        self.run_offline_inner(duration, sample_rate, block_size, true, None)
     }
 
     fn run_offline_inner(
@@ -376,12 +624,22 @@ impl Runtime {
         sample_rate: Float,
         block_size: usize,
         add_delay: bool,
+        mut midi: Option<&mut Vec<Vec<MidiOutEvent>>>,
     ) -> RuntimeResult<Box<[Box<[Float]>]>> {
         let secs = duration.as_secs_f64() as Float;
         let samples = (sample_rate * secs) as usize;
 
         self.allocate_for_block_size(sample_rate, block_size);
 
+        let midi_outputs: Vec<MidiOutRx> =
+            self.graph.midi_output_iter().map(|(_, rx)| rx).collect();
+        if midi.is_some() {
+            // discard anything sent before the render started
+            for rx in &midi_outputs {
+                rx.try_iter().for_each(drop);
+            }
+        }
+
         let num_outputs: usize = self.graph.num_audio_outputs();
 
         let mut outputs: Box<[Box<[Float]>]> =
@@ -409,6 +667,12 @@ impl Runtime {
                 }
             }
 
+            if let Some(midi) = midi.as_deref_mut() {
+                for (rx, events) in midi_outputs.iter().zip(midi.iter_mut()) {
+                    events.extend(rx.try_iter());
+                }
+            }
+
             if add_delay {
                 std::thread::sleep(Duration::from_secs_f64(
                     actual_block_size as f64 / sample_rate as f64,
@@ -430,41 +694,32 @@ impl Runtime {
         block_size: usize,
     ) -> RuntimeResult<()> {
         let outputs = self.run_offline(duration, sample_rate, block_size)?;
+        write_wav_file(file_path, &outputs, sample_rate)
+    }
 
-        let num_channels = outputs.len();
-
-        if num_channels == 0 {
-            log::warn!("No output channels to write to file");
-            return Ok(());
-        }
-
-        let num_samples = outputs[0].len();
-
-        let mut samples = vec![0.0; num_samples * num_channels];
-
-        for sample_index in 0..num_samples {
-            for channel_index in 0..num_channels {
-                let i = sample_index * num_channels + channel_index;
-                samples[i] = outputs[channel_index][sample_index];
-            }
-        }
-
-        let spec = hound::WavSpec {
-            channels: num_channels as u16,
-            sample_rate: sample_rate as u32,
-            bits_per_sample: 32,
-            sample_format: hound::SampleFormat::Float,
-        };
-
-        let mut writer = hound::WavWriter::create(file_path, spec)?;
-
-        for sample in samples {
-            writer.write_sample(sample as f32)?;
-        }
-
-        writer.finalize()?;
-
-        Ok(())
+    /// Runs the audio graph offline for the given duration and sample rate, normalizes the output to the given integrated loudness in LUFS, and writes it to a file.
+    ///
+    /// If `true_peak_ceiling` is given, in dBTP, the output is made quieter than the target as needed to keep its true peak below the ceiling (see [`crate::loudness::normalize`]).
+    /// Returns the loudness report of the output before normalization.
+    pub fn run_offline_to_file_normalized(
+        &mut self,
+        file_path: impl AsRef<std::path::Path>,
+        duration: Duration,
+        sample_rate: Float,
+        block_size: usize,
+        target: Float,
+        true_peak_ceiling: Option<Float>,
+    ) -> RuntimeResult<LoudnessReport> {
+        let mut outputs = self.run_offline(duration, sample_rate, block_size)?;
+        let (report, gain) =
+            crate::loudness::normalize(&mut outputs[..], sample_rate, target, true_peak_ceiling);
+        log::info!(
+            "Normalized output from {:.1} LUFS to {:.1} LUFS",
+            report.integrated,
+            report.integrated + 20.0 * gain.log10()
+        );
+        write_wav_file(file_path, &outputs, sample_rate)?;
+        Ok(report)
     }
 
     /// Runs the audio graph in real-time for the given duration.
@@ -487,6 +742,17 @@ impl Runtime {
         backend: AudioBackend,
         device: AudioDevice,
         midi_port: Option<MidiPort>,
+    ) -> RuntimeResult<RuntimeHandle> {
+        self.run_with_midi(backend, device, midi_port, None)
+    }
+
+    /// Starts running the audio graph in real-time, like [`Runtime::run`], and also sends the messages of the graph's MIDI outputs (see [`Graph::add_midi_output`]) to the given MIDI output port.
+    pub fn run_with_midi(
+        &mut self,
+        backend: AudioBackend,
+        device: AudioDevice,
+        midi_port: Option<MidiPort>,
+        midi_output_port: Option<MidiPort>,
     ) -> RuntimeResult<RuntimeHandle> {
         let (kill_tx, kill_rx) = mpsc::channel();
 
@@ -542,15 +808,7 @@ impl Runtime {
         let midi_connection = midir::MidiInput::new("raug midir input")?;
 
         let midi_port = if let Some(midi_port) = midi_port {
-            let midi_port = match &midi_port {
-                MidiPort::Default => midi_connection.ports().into_iter().next(),
-                MidiPort::Index(index) => midi_connection.ports().into_iter().nth(*index),
-                MidiPort::Name(name) => midi_connection
-                    .ports()
-                    .into_iter()
-                    .find(|port| midi_connection.port_name(port).unwrap().contains(name)),
-            }
-            .ok_or(RuntimeError::MidiPortUnavailable(midi_port))?;
+            let midi_port = find_midi_port(&midi_connection, midi_port)?;
 
             log::info!(
                 "Using MIDI port: {:?}",
@@ -566,6 +824,26 @@ impl Runtime {
             None
         };
 
+        let midi_out = if let Some(midi_output_port) = midi_output_port {
+            let midi_output = midir::MidiOutput::new("raug midir output")?;
+            let midi_output_port = find_midi_port(&midi_output, midi_output_port)?;
+
+            log::info!(
+                "Using MIDI output port: {:?}",
+                midi_output
+                    .port_name(&midi_output_port)
+                    .as_ref()
+                    .map(|s| s.as_str())
+                    .unwrap_or("unknown")
+            );
+
+            Some(midi_output.connect(&midi_output_port, "raug midir output")?)
+        } else {
+            None
+        };
+        let midi_outputs: Vec<MidiOutRx> =
+            self.graph.midi_output_iter().map(|(_, rx)| rx).collect();
+
         self.allocate_for_block_size(audio_rate, audio_rate as usize / 10);
 
         let audio_runtime = self.clone();
@@ -579,7 +857,15 @@ impl Runtime {
                     log::debug!("MIDI message: {:2x?}", message);
 
                     for (_name, param) in midi_runtime.graph().midi_input_iter() {
-                        param.send(MidiMessage::new([message[0], message[1], message[2]]));
+                        if message.first() == Some(&0xF0) {
+                            for packet in MidiMessage::sysex_packets(message) {
+                                param.send(packet);
+                            }
+                        } else if let Some(message) = MidiMessage::from_bytes(message) {
+                            param.send(message);
+                        } else {
+                            log::warn!("Ignoring malformed MIDI message: {:2x?}", message);
+                        }
                     }
                 },
                 (),
@@ -590,9 +876,20 @@ impl Runtime {
             None
         };
 
+        let taps = self
+            .graph
+            .tap_names()
+            .into_iter()
+            .filter_map(|name| {
+                let reader = self.graph.tap_named(&name)?;
+                Some((name, reader))
+            })
+            .collect();
+
         let handle = RuntimeHandle {
             kill_tx,
             midi_in: Arc::new(Mutex::new(midi_in)),
+            taps: Arc::new(taps),
         };
 
         std::thread::spawn(move || -> RuntimeResult<()> {
@@ -633,12 +930,40 @@ impl Runtime {
                 }
             };
 
+            let mut midi_out = midi_out;
+            // SysEx messages are sent whole, so they're reassembled from their packets first
+            // (this isn't the audio thread, so the buffers can be generous)
+            let mut sysex: Vec<SysExAssembler> = midi_outputs
+                .iter()
+                .map(|_| SysExAssembler::new(1 << 16))
+                .collect();
             loop {
                 if kill_rx.try_recv().is_ok() {
                     drop(stream);
+                    if let Some(midi_out) = midi_out.take() {
+                        midi_out.close();
+                    }
                     break;
                 }
 
+                if let Some(midi_out) = &mut midi_out {
+                    for (rx, sysex) in midi_outputs.iter().zip(sysex.iter_mut()) {
+                        for event in rx.try_iter() {
+                            let bytes = if event.message.is_sysex() {
+                                match sysex.push(&event.message) {
+                                    Some(bytes) => bytes,
+                                    None => continue,
+                                }
+                            } else {
+                                event.message.as_bytes()
+                            };
+                            if let Err(e) = midi_out.send(bytes) {
+                                log::error!("Error sending MIDI message: {}", e);
+                            }
+                        }
+                    }
+                }
+
                 std::thread::yield_now();
             }
 
@@ -693,16 +1018,85 @@ impl Runtime {
     }
 }
 
+/// Writes the given output buffers to a WAV file as interleaved 32-bit float samples.
+fn write_wav_file(
+    file_path: impl AsRef<std::path::Path>,
+    outputs: &[Box<[Float]>],
+    sample_rate: Float,
+) -> RuntimeResult<()> {
+    let num_channels = outputs.len();
+
+    if num_channels == 0 {
+        log::warn!("No output channels to write to file");
+        return Ok(());
+    }
+
+    let num_samples = outputs[0].len();
+
+    let mut samples = vec![0.0; num_samples * num_channels];
+
+    for (channel_index, output) in outputs.iter().enumerate() {
+        for (sample_index, sample) in output.iter().enumerate() {
+            samples[sample_index * num_channels + channel_index] = *sample;
+        }
+    }
+
+    let spec = hound::WavSpec {
+        channels: num_channels as u16,
+        sample_rate: sample_rate as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = hound::WavWriter::create(file_path, spec)?;
+
+    for sample in samples {
+        writer.write_sample(sample as f32)?;
+    }
+
+    writer.finalize()?;
+
+    Ok(())
+}
+
+fn find_midi_port<T: midir::MidiIO>(io: &T, port: MidiPort) -> RuntimeResult<T::Port> {
+    match &port {
+        MidiPort::Default => io.ports().into_iter().next(),
+        MidiPort::Index(index) => io.ports().into_iter().nth(*index),
+        MidiPort::Name(name) => io.ports().into_iter().find(|p| {
+            io.port_name(p)
+                .map(|port_name| port_name.contains(name))
+                .unwrap_or(false)
+        }),
+    }
+    .ok_or(RuntimeError::MidiPortUnavailable(port))
+}
+
 /// A handle to the runtime that can be used to stop it.
 #[must_use = "The runtime handle must be kept alive for the runtime to continue running"]
 #[derive(Clone)]
 pub struct RuntimeHandle {
     midi_in: Arc<Mutex<Option<midir::MidiInputConnection<()>>>>,
     kill_tx: mpsc::Sender<()>,
+    taps: Arc<FxHashMap<String, TapReader>>,
 }
 
 impl RuntimeHandle {
-    /// Stops the runtime. This will close the audio stream and MIDI input.
+    /// Returns the reader of the tap with the given name (see [`Graph::add_tap`]), for reading its meters or frames from the control thread.
+    #[inline]
+    pub fn tap(&self, name: &str) -> Option<&TapReader> {
+        self.taps.get(name)
+    }
+
+    /// Returns an iterator over the names and readers of the graph's taps.
+    #[inline]
+    pub fn taps(&self) -> impl Iterator<Item = (&str, &TapReader)> + '_ {
+        self.taps
+            .iter()
+            .map(|(name, reader)| (name.as_str(), reader))
+    }
+
+    /// Stops the runtime. This will close the audio stream and MIDI input and output.
     pub fn stop(&self) {
         self.kill_tx.send(()).ok();
         if let Ok(mut midi_in) = self.midi_in.lock() {
@@ -1,11 +1,15 @@
 //! Signal types and operations.
 
 use std::{
+    cell::UnsafeCell,
     fmt::Debug,
     ops::{Deref, DerefMut},
     path::Path,
+    sync::atomic::{AtomicU8, Ordering},
 };
 
+use crate::{loudness::LoudnessReport, resample::ResampleQuality};
+
 #[cfg(feature = "f32_samples")]
 /// The floating-point sample type.
 pub type Float = f32;
@@ -32,15 +36,63 @@ pub const TAU: Float = std::f64::consts::TAU;
 /// The signals are stored as a [`Vec`] of [`Option<T>`] to allow for missing values.
 ///
 /// This type implements [`Deref`] and [`DerefMut`] so that it can be used as a slice of [`Option<T>`].
-#[derive(PartialEq, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+///
+/// A `Buffer<Float>` can also be *dense*: every signal is [`Some`], and the samples are stored as a contiguous slice of [`Float`]s instead (see [`Buffer::as_dense`] and [`Buffer::fill_dense`]).
+/// Processors can work on dense buffers without checking each sample, which lets the compiler vectorize their loops.
+/// The signals of a dense buffer are only written from its samples when they are first read as [`Option`]s.
 pub struct Buffer<T: Signal> {
-    buf: Vec<Option<T>>,
+    // while the buffer is dense, the signals are out of date until `DenseStorage::sync` writes them
+    buf: UnsafeCell<Vec<Option<T>>>,
+    // the samples of a dense buffer (only `Float` buffers store any)
+    dense: T::Dense,
+}
+
+// SAFETY: the signals are only written through a shared reference by `DenseStorage::sync`, which lets a single thread write them, and hands
+// them out to the other threads once they are written
+unsafe impl<T: Signal> Sync for Buffer<T> {}
+
+impl<T: Signal + Clone> Clone for Buffer<T> {
+    fn clone(&self) -> Self {
+        Buffer {
+            buf: UnsafeCell::new(self.signals().clone()),
+            dense: self.dense.duplicate(),
+        }
+    }
+}
+
+impl<T: Signal + PartialEq> PartialEq for Buffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.signals() == other.signals()
+    }
 }
 
 impl<T: Signal> Debug for Buffer<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_list().entries(self.buf.iter()).finish()
+        f.debug_list().entries(self.signals().iter()).finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Signal + serde::Serialize> serde::Serialize for Buffer<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Buffer", 1)?;
+        state.serialize_field("buf", self.signals())?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Signal + serde::Deserialize<'de>> serde::Deserialize<'de> for Buffer<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Buffer")]
+        struct Signals<T> {
+            buf: Vec<Option<T>>,
+        }
+
+        Ok(Self::from_vec(Signals::deserialize(deserializer)?.buf))
     }
 }
 
@@ -52,7 +104,30 @@ impl<T: Signal> Buffer<T> {
         for _ in 0..length {
             buf.push(None);
         }
-        Buffer { buf }
+        Self::from_vec(buf)
+    }
+
+    #[inline]
+    fn from_vec(buf: Vec<Option<T>>) -> Self {
+        Buffer {
+            buf: UnsafeCell::new(buf),
+            dense: T::Dense::default(),
+        }
+    }
+
+    #[inline]
+    fn signals(&self) -> &Vec<Option<T>> {
+        self.dense.sync(&self.buf);
+        // SAFETY: the signals are up to date, so they are no longer written through a shared reference
+        unsafe { &*self.buf.get() }
+    }
+
+    // gives mutable access to the signals, which may no longer match the dense samples
+    #[inline]
+    fn signals_mut(&mut self) -> &mut Vec<Option<T>> {
+        let signals = self.buf.get_mut();
+        self.dense.make_sparse(signals);
+        signals
     }
 
     /// Clones the slice into a new buffer. All elements are wrapped in `Some`.
@@ -61,9 +136,7 @@ impl<T: Signal> Buffer<T> {
     where
         T: Clone,
     {
-        Buffer {
-            buf: value.iter().map(|v| Some(v.clone())).collect(),
-        }
+        Self::from_vec(value.iter().map(|v| Some(v.clone())).collect())
     }
 
     /// Copies the other buffer into this buffer using a memcpy.
@@ -76,22 +149,35 @@ impl<T: Signal> Buffer<T> {
     where
         T: Copy,
     {
-        self.buf.copy_from_slice(value.as_ref());
+        self.signals_mut().copy_from_slice(value.as_ref());
     }
 }
 
 impl Buffer<Float> {
     /// Loads a buffer from a WAV file.
+    ///
+    /// Only the first channel is loaded. The samples are not resampled, see [`Buffer::load_wav_with_sample_rate`].
     pub fn load_wav(path: impl AsRef<Path>) -> Result<Self, hound::Error> {
+        Ok(Self::load_wav_with_sample_rate(path)?.0)
+    }
+
+    /// Loads a buffer from a WAV file, along with the sample rate it was recorded at.
+    ///
+    /// The sample rate can be passed to [`GraphBuilder::add_asset_resampled`](crate::builder::graph_builder::GraphBuilder::add_asset_resampled)
+    /// to play the buffer at the right pitch regardless of the runtime's sample rate.
+    pub fn load_wav_with_sample_rate(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, Float), hound::Error> {
         let reader = hound::WavReader::open(path)?;
-        if reader.spec().channels == 1 {
+        let sample_rate = reader.spec().sample_rate as Float;
+        let buffer = if reader.spec().channels == 1 {
             let samples: Result<Vec<_>, hound::Error> = reader
                 .into_samples::<f32>()
                 .map(|sample| Ok(sample?.into()))
                 .collect();
             let samples = samples?;
 
-            Ok(Buffer::from_slice(&samples))
+            Buffer::from_slice(&samples)
         } else {
             let channels = reader.spec().channels;
 
@@ -102,8 +188,18 @@ impl Buffer<Float> {
                 .collect();
             let samples = samples?;
 
-            Ok(Buffer::from_slice(&samples))
-        }
+            Buffer::from_slice(&samples)
+        };
+
+        Ok((buffer, sample_rate))
+    }
+
+    /// Converts the buffer from one sample rate to another, keeping its duration. [`None`] entries are treated as silence.
+    pub fn resample(&self, from_rate: Float, to_rate: Float, quality: ResampleQuality) -> Self {
+        let samples: Vec<Float> = self.iter().map(|s| s.unwrap_or_default()).collect();
+        Buffer::from_slice(&crate::resample::resample(
+            &samples, from_rate, to_rate, quality,
+        ))
     }
 
     /// Saves the buffer to a WAV file. [`None`] entries are written as silence.
@@ -115,20 +211,43 @@ impl Buffer<Float> {
             sample_format: hound::SampleFormat::Float,
         };
         let mut writer = hound::WavWriter::create(path, spec)?;
-        for sample in self.buf.iter() {
+        for sample in self.iter() {
             writer.write_sample(sample.unwrap_or_default() as f32)?;
         }
         writer.finalize()?;
         Ok(())
     }
 
+    /// Measures the loudness of the buffer as a single channel (see [`crate::loudness`]). [`None`] entries are treated as silence.
+    pub fn loudness(&self, sample_rate: Float) -> LoudnessReport {
+        let samples: Vec<Float> = self.iter().map(|s| s.unwrap_or_default()).collect();
+        crate::loudness::analyze(&[samples], sample_rate)
+    }
+
+    /// Scales the buffer so that its integrated loudness is `target` LUFS, with an optional true peak ceiling in dBTP (see [`crate::loudness::normalize`]).
+    ///
+    /// Returns the report of the buffer before scaling, and the linear gain that was applied.
+    pub fn normalize_loudness(
+        &mut self,
+        sample_rate: Float,
+        target: Float,
+        true_peak_ceiling: Option<Float>,
+    ) -> (LoudnessReport, Float) {
+        let samples: Vec<Float> = self.iter().map(|s| s.unwrap_or_default()).collect();
+        let (report, gain) =
+            crate::loudness::normalize(&mut [samples], sample_rate, target, true_peak_ceiling);
+        for sample in self.signals_mut().iter_mut().flatten() {
+            *sample *= gain;
+        }
+        (report, gain)
+    }
+
     /// Returns the maximum value in the buffer out of all entries that are [`Some`].
     ///
     /// If the buffer is empty, this returns [`Float::MIN`].
     #[inline]
     pub fn max(&self) -> Float {
-        self.buf
-            .iter()
+        self.iter()
             .flatten()
             .copied()
             .fold(Float::MIN, |a, b| a.max(b))
@@ -139,8 +258,7 @@ impl Buffer<Float> {
     /// If the buffer is empty, this returns [`Float::MAX`].
     #[inline]
     pub fn min(&self) -> Float {
-        self.buf
-            .iter()
+        self.iter()
             .flatten()
             .copied()
             .fold(Float::MAX, |a, b| a.min(b))
@@ -151,7 +269,7 @@ impl Buffer<Float> {
     /// If the buffer is empty, this returns `0.0`.
     #[inline]
     pub fn sum(&self) -> Float {
-        self.buf.iter().flatten().copied().fold(0.0, |a, b| a + b)
+        self.iter().flatten().copied().fold(0.0, |a, b| a + b)
     }
 
     /// Returns the mean of all entries that are [`Some`].
@@ -169,8 +287,7 @@ impl Buffer<Float> {
     /// If the buffer is empty, this returns `0.0`.
     #[inline]
     pub fn rms(&self) -> Float {
-        self.buf
-            .iter()
+        self.iter()
             .flatten()
             .copied()
             .fold(0.0, |a, b| a + b * b)
@@ -187,7 +304,6 @@ impl Buffer<Float> {
         }
         let mean = self.mean();
         let sum = self
-            .buf
             .iter()
             .flatten()
             .copied()
@@ -202,27 +318,94 @@ impl Buffer<Float> {
     pub fn stddev(&self) -> Float {
         self.variance().sqrt()
     }
+
+    /// Returns the samples as a contiguous slice if the buffer is dense, i.e. every entry is known to be [`Some`].
+    ///
+    /// A buffer becomes dense when it is filled with [`Buffer::fill_dense`], and stops being dense as soon as its entries are accessed mutably.
+    #[inline]
+    pub fn as_dense(&self) -> Option<&[Float]> {
+        self.is_dense().then_some(&self.dense.samples[..])
+    }
+
+    /// Returns `true` if the buffer is dense (see [`Buffer::as_dense`]).
+    #[inline]
+    pub fn is_dense(&self) -> bool {
+        self.dense.state.load(Ordering::Relaxed) != DenseSamples::SPARSE
+    }
+
+    /// Fills the buffer with samples computed by `f` on a contiguous slice, and marks it as dense.
+    ///
+    /// The slice has the same length as the buffer, and its contents are unspecified when `f` is called.
+    /// Only the samples are written; the entries of the buffer are written from them when they are first read.
+    /// This doesn't allocate, unless the buffer has grown since it was last dense or resized by a [`SignalBuffer`].
+    #[inline]
+    pub fn fill_dense(&mut self, f: impl FnOnce(&mut [Float])) {
+        let len = self.buf.get_mut().len();
+        self.dense.samples.resize(len, 0.0);
+        f(&mut self.dense.samples);
+        *self.dense.state.get_mut() = DenseSamples::DENSE;
+    }
+
+    /// Copies the other buffer into this one, including its dense samples if it is dense.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers have different lengths.
+    #[inline]
+    pub fn copy_dense_from(&mut self, other: &Self) {
+        match other.as_dense() {
+            Some(samples) => self.fill_dense(|out| out.copy_from_slice(samples)),
+            None => self.copy_from(other),
+        }
+    }
+
+    // makes sure that filling the buffer densely won't allocate
+    #[inline]
+    fn reserve_dense(&mut self) {
+        let len = self.buf.get_mut().len();
+        let samples = &mut self.dense.samples;
+        samples.reserve(len.saturating_sub(samples.len()));
+    }
+
+    // resizes the buffer, repeating its last entry, and keeps it dense if it was
+    #[inline]
+    fn resize_hold(&mut self, length: usize) {
+        match self.dense.samples.last().copied() {
+            Some(last) if self.is_dense() => {
+                // out of date signals are written from the samples when they are read, so only their length matters
+                self.buf.get_mut().resize(length, Some(last));
+                self.reserve_dense();
+                self.dense.samples.resize(length, last);
+            }
+            _ => {
+                let signals = self.signals_mut();
+                let last = signals.last().copied().flatten();
+                signals.resize(length, last);
+                self.reserve_dense();
+            }
+        }
+    }
 }
 
 impl<T: Signal> Deref for Buffer<T> {
     type Target = [Option<T>];
     #[inline]
     fn deref(&self) -> &Self::Target {
-        self.buf.as_ref()
+        self.signals()
     }
 }
 
 impl<T: Signal> DerefMut for Buffer<T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.buf.as_mut()
+        self.signals_mut()
     }
 }
 
 impl<T: Signal> AsRef<[Option<T>]> for Buffer<T> {
     #[inline]
     fn as_ref(&self) -> &[Option<T>] {
-        self.buf.as_ref()
+        self.signals()
     }
 }
 
@@ -232,7 +415,7 @@ impl<'a, T: Signal> IntoIterator for &'a Buffer<T> {
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        self.buf.iter()
+        self.signals().iter()
     }
 }
 
@@ -242,7 +425,118 @@ impl<'a, T: Signal> IntoIterator for &'a mut Buffer<T> {
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        self.buf.iter_mut()
+        self.signals_mut().iter_mut()
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for () {}
+    impl Sealed for super::DenseSamples {}
+}
+
+/// Where a [`Buffer`] keeps its samples while it is dense (see [`Buffer::as_dense`]).
+///
+/// Only [`Float`] buffers can be dense, in [`DenseSamples`]; the other signal types store nothing.
+#[doc(hidden)]
+pub trait DenseStorage<T>: sealed::Sealed + Default + Send + Sync {
+    /// Writes the signals from the dense samples, if they are out of date.
+    fn sync(&self, signals: &UnsafeCell<Vec<Option<T>>>);
+
+    /// Writes the signals from the dense samples if they are out of date, and stops being dense, before the signals are modified.
+    fn make_sparse(&mut self, signals: &mut [Option<T>]);
+
+    /// Copies the dense samples for a clone of the buffer, whose signals are up to date.
+    fn duplicate(&self) -> Self;
+}
+
+impl<T> DenseStorage<T> for () {
+    #[inline]
+    fn sync(&self, _signals: &UnsafeCell<Vec<Option<T>>>) {}
+
+    #[inline]
+    fn make_sparse(&mut self, _signals: &mut [Option<T>]) {}
+
+    #[inline]
+    fn duplicate(&self) -> Self {}
+}
+
+/// The samples of a dense [`Buffer<Float>`](Buffer).
+#[doc(hidden)]
+#[derive(Default)]
+pub struct DenseSamples {
+    samples: Vec<Float>,
+    state: AtomicU8,
+}
+
+impl DenseSamples {
+    // the signals are the only contents of the buffer
+    const SPARSE: u8 = 0;
+    // the samples are the contents of the buffer, and the signals are out of date
+    const DENSE: u8 = 1;
+    // a thread is writing the signals from the samples
+    const WRITING: u8 = 2;
+    // the samples are the contents of the buffer, and the signals match them
+    const SYNCED: u8 = 3;
+
+    #[cold]
+    fn write_signals(&self, signals: &UnsafeCell<Vec<Option<Float>>>) {
+        loop {
+            match self.state.compare_exchange_weak(
+                Self::DENSE,
+                Self::WRITING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    // SAFETY: only the thread that moved the state from `DENSE` to `WRITING` gets here, and no references to the
+                    // signals exist while they are out of date, since `Buffer` only hands them out once they are written
+                    let signals = unsafe { &mut *signals.get() };
+                    for (signal, sample) in signals.iter_mut().zip(&self.samples) {
+                        *signal = Some(*sample);
+                    }
+                    self.state.store(Self::SYNCED, Ordering::Release);
+                    return;
+                }
+                Err(Self::SPARSE | Self::SYNCED) => return,
+                // another thread is writing the signals, or the exchange failed spuriously
+                Err(_) => std::hint::spin_loop(),
+            }
+        }
+    }
+}
+
+impl DenseStorage<Float> for DenseSamples {
+    #[inline]
+    fn sync(&self, signals: &UnsafeCell<Vec<Option<Float>>>) {
+        let state = self.state.load(Ordering::Acquire);
+        if state != Self::SPARSE && state != Self::SYNCED {
+            self.write_signals(signals);
+        }
+    }
+
+    #[inline]
+    fn make_sparse(&mut self, signals: &mut [Option<Float>]) {
+        let state = self.state.get_mut();
+        if *state == Self::SPARSE {
+            return;
+        }
+        if *state == Self::DENSE {
+            for (signal, sample) in signals.iter_mut().zip(&self.samples) {
+                *signal = Some(*sample);
+            }
+        }
+        *state = Self::SPARSE;
+    }
+
+    fn duplicate(&self) -> Self {
+        // keep the reserved space, so that the clone can be filled densely without allocating
+        let mut samples = Vec::with_capacity(self.samples.capacity());
+        samples.extend_from_slice(&self.samples);
+        DenseSamples {
+            samples,
+            state: AtomicU8::new(self.state.load(Ordering::Relaxed)),
+        }
     }
 }
 
@@ -292,12 +586,12 @@ impl List {
     }
 
     /// Returns a reference to the signal at the given index.
-    pub fn get(&self, index: usize) -> Option<AnySignalRef> {
+    pub fn get(&self, index: usize) -> Option<AnySignalRef<'_>> {
         self.0.get(index).map(AnySignal::as_ref)
     }
 
     /// Returns a mutable reference to the signal at the given index.
-    pub fn get_mut(&mut self, index: usize) -> Option<AnySignalMut> {
+    pub fn get_mut(&mut self, index: usize) -> Option<AnySignalMut<'_>> {
         self.0.get_mut(index).map(AnySignal::as_mut)
     }
 
@@ -343,7 +637,14 @@ impl<T: Signal> FromIterator<T> for List {
     }
 }
 
-/// A 3-byte MIDI message.
+/// A MIDI message of up to 3 bytes.
+///
+/// Shorter messages, such as program changes or real-time messages like MIDI clock, are padded with zeros; see [`MidiMessage::num_bytes`].
+///
+/// System exclusive (SysEx) messages, which can be any length, are carried as a sequence of packets on consecutive samples.
+/// The first byte of a packet is the number of bytes of the raw message it holds (1 or 2), which can't be mistaken for the status byte of another message, and the bytes follow, padded with zeros.
+/// The first packet holds the `0xF0` byte and the last packet holds the terminating `0xF7` byte.
+/// See [`MidiMessage::sysex_packets`] and [`SysExAssembler`](crate::builtins::SysExAssembler).
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MidiMessage {
@@ -357,6 +658,68 @@ impl MidiMessage {
         Self { data }
     }
 
+    /// Creates a MIDI message from its raw bytes, if they are a complete message of 1 to 3 bytes.
+    ///
+    /// SysEx messages have to be split into packets with [`MidiMessage::sysex_packets`] instead.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() || bytes.len() > 3 {
+            return None;
+        }
+        let mut data = [0; 3];
+        data[..bytes.len()].copy_from_slice(bytes);
+        let message = Self::new(data);
+        (bytes[0] >= 0x80 && !matches!(bytes[0], 0xF0 | 0xF7) && message.num_bytes() == bytes.len())
+            .then_some(message)
+    }
+
+    /// Splits a complete SysEx message, from the `0xF0` byte to the `0xF7` byte, into the packets that carry it in a MIDI signal.
+    pub fn sysex_packets(bytes: &[u8]) -> impl Iterator<Item = Self> + '_ {
+        bytes.chunks(2).map(|chunk| {
+            let mut data = [chunk.len() as u8, 0, 0];
+            data[1..=chunk.len()].copy_from_slice(chunk);
+            Self::new(data)
+        })
+    }
+
+    /// Returns the number of bytes in the message.
+    ///
+    /// This is 1 for real-time messages (e.g. MIDI clock), 2 for program change, channel pressure, time code and song select messages, and 3 for other messages.
+    /// For a SysEx packet, it is the number of bytes of the SysEx message that the packet holds.
+    pub fn num_bytes(&self) -> usize {
+        match self.data[0] {
+            0x01 | 0x02 => self.data[0] as usize,
+            0xC0..=0xDF | 0xF1 | 0xF3 => 2,
+            0xF2 | 0x80..=0xBF | 0xE0..=0xEF => 3,
+            _ => 1,
+        }
+    }
+
+    /// Returns the bytes of the message, without padding.
+    ///
+    /// For a SysEx packet, these are the bytes of the SysEx message that the packet holds.
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.is_sysex() {
+            &self.data[1..=self.num_bytes()]
+        } else {
+            &self.data[..self.num_bytes()]
+        }
+    }
+
+    /// Returns `true` if this is a packet of a SysEx message.
+    pub fn is_sysex(&self) -> bool {
+        matches!(self.data[0], 0x01 | 0x02)
+    }
+
+    /// Returns `true` if this is the first packet of a SysEx message.
+    pub fn is_sysex_start(&self) -> bool {
+        self.is_sysex() && self.data[1] == 0xF0
+    }
+
+    /// Returns `true` if this is the last packet of a SysEx message.
+    pub fn is_sysex_end(&self) -> bool {
+        self.is_sysex() && self.as_bytes().contains(&0xF7)
+    }
+
     /// Returns the status byte of the MIDI message.
     pub fn status(&self) -> u8 {
         self.data[0] & 0xF0
@@ -376,6 +739,143 @@ impl MidiMessage {
     pub fn data2(&self) -> u8 {
         self.data[2]
     }
+
+    /// Creates a note on message. The channel (0 to 15), note and velocity are masked to their valid ranges.
+    pub fn note_on(channel: u8, note: u8, velocity: u8) -> Self {
+        Self::new([0x90 | (channel & 0x0F), note & 0x7F, velocity & 0x7F])
+    }
+
+    /// Creates a note off message.
+    pub fn note_off(channel: u8, note: u8, velocity: u8) -> Self {
+        Self::new([0x80 | (channel & 0x0F), note & 0x7F, velocity & 0x7F])
+    }
+
+    /// Creates a polyphonic key pressure (aftertouch) message.
+    pub fn poly_pressure(channel: u8, note: u8, pressure: u8) -> Self {
+        Self::new([0xA0 | (channel & 0x0F), note & 0x7F, pressure & 0x7F])
+    }
+
+    /// Creates a control change message.
+    pub fn control_change(channel: u8, controller: u8, value: u8) -> Self {
+        Self::new([0xB0 | (channel & 0x0F), controller & 0x7F, value & 0x7F])
+    }
+
+    /// Creates a program change message.
+    pub fn program_change(channel: u8, program: u8) -> Self {
+        Self::new([0xC0 | (channel & 0x0F), program & 0x7F, 0])
+    }
+
+    /// Creates a channel pressure (aftertouch) message.
+    pub fn channel_pressure(channel: u8, pressure: u8) -> Self {
+        Self::new([0xD0 | (channel & 0x0F), pressure & 0x7F, 0])
+    }
+
+    /// Creates a pitch bend message from a 14-bit bend amount (0 to 16383, centered at 8192).
+    pub fn pitch_bend(channel: u8, value: u16) -> Self {
+        let value = value.min(0x3FFF);
+        Self::new([
+            0xE0 | (channel & 0x0F),
+            (value & 0x7F) as u8,
+            (value >> 7) as u8,
+        ])
+    }
+
+    /// Returns `true` if this is a note on message with a non-zero velocity.
+    pub fn is_note_on(&self) -> bool {
+        self.status() == 0x90 && self.data2() > 0
+    }
+
+    /// Returns `true` if this is a note off message, or a note on message with a velocity of 0.
+    pub fn is_note_off(&self) -> bool {
+        self.status() == 0x80 || (self.status() == 0x90 && self.data2() == 0)
+    }
+
+    /// Returns `true` if this is a channel message (note, controller, program, pressure or pitch bend), as opposed to a system message.
+    pub fn is_channel_message(&self) -> bool {
+        (0x80..0xF0).contains(&self.data[0])
+    }
+
+    /// Decodes the message.
+    pub fn kind(&self) -> MidiMessageKind {
+        let [_, data1, data2] = self.data;
+        match self.status() {
+            _ if self.is_sysex() => MidiMessageKind::SysEx,
+            _ if self.is_note_off() => MidiMessageKind::NoteOff {
+                note: data1,
+                velocity: data2,
+            },
+            0x90 => MidiMessageKind::NoteOn {
+                note: data1,
+                velocity: data2,
+            },
+            0xA0 => MidiMessageKind::PolyPressure {
+                note: data1,
+                pressure: data2,
+            },
+            0xB0 => MidiMessageKind::ControlChange {
+                controller: data1,
+                value: data2,
+            },
+            0xC0 => MidiMessageKind::ProgramChange { program: data1 },
+            0xD0 => MidiMessageKind::ChannelPressure { pressure: data1 },
+            0xE0 => MidiMessageKind::PitchBend {
+                value: (data1 as u16 & 0x7F) | ((data2 as u16 & 0x7F) << 7),
+            },
+            _ => MidiMessageKind::System,
+        }
+    }
+}
+
+/// The decoded contents of a [`MidiMessage`], as returned by [`MidiMessage::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessageKind {
+    /// A note off message, or a note on message with a velocity of 0.
+    NoteOff {
+        /// The note number.
+        note: u8,
+        /// The release velocity.
+        velocity: u8,
+    },
+    /// A note on message with a non-zero velocity.
+    NoteOn {
+        /// The note number.
+        note: u8,
+        /// The velocity.
+        velocity: u8,
+    },
+    /// A polyphonic key pressure (aftertouch) message.
+    PolyPressure {
+        /// The note number.
+        note: u8,
+        /// The pressure.
+        pressure: u8,
+    },
+    /// A control change message.
+    ControlChange {
+        /// The controller number.
+        controller: u8,
+        /// The controller value.
+        value: u8,
+    },
+    /// A program change message.
+    ProgramChange {
+        /// The program number.
+        program: u8,
+    },
+    /// A channel pressure (aftertouch) message.
+    ChannelPressure {
+        /// The pressure.
+        pressure: u8,
+    },
+    /// A pitch bend message.
+    PitchBend {
+        /// The 14-bit bend amount, from 0 to 16383 and centered at 8192.
+        value: u16,
+    },
+    /// A packet of a SysEx message.
+    SysEx,
+    /// Any other system message, such as MIDI clock or transport messages.
+    System,
 }
 
 impl Deref for MidiMessage {
@@ -393,6 +893,10 @@ impl DerefMut for MidiMessage {
 
 /// A type that can be stored in a [`Buffer`] and processed by a [`Processor`](crate::processor::Processor).
 pub trait Signal: Sized + Debug + Send + Sync + PartialEq + 'static {
+    /// Where a [`Buffer`] of the signal keeps its samples while it is dense.
+    #[doc(hidden)]
+    type Dense: DenseStorage<Self>;
+
     /// The type of the signal.
     fn signal_type() -> SignalType;
 
@@ -403,10 +907,12 @@ pub trait Signal: Sized + Debug + Send + Sync + PartialEq + 'static {
     fn try_from_any_signal(signal: AnySignal) -> Option<Self>;
 
     /// Attempts to convert an [`AnySignal`] into the signal type.
-    fn try_from_any_signal_ref(signal: AnySignalRef) -> Option<&Option<Self>>;
+    fn try_from_any_signal_ref(signal: AnySignalRef<'_>) -> Option<&Option<Self>>;
 
     /// Attempts to convert a mutable [`AnySignal`] into a mutable signal of the signal type.
-    fn try_from_any_signal_mut(signal: AnySignalMut) -> Result<&mut Option<Self>, AnySignalMut>;
+    fn try_from_any_signal_mut(
+        signal: AnySignalMut<'_>,
+    ) -> Result<&mut Option<Self>, AnySignalMut<'_>>;
 
     /// Attempts to convert a [`SignalBuffer`] into a buffer of the signal type.
     fn try_convert_buffer(buffer: &SignalBuffer) -> Option<&Buffer<Self>>;
@@ -416,8 +922,10 @@ pub trait Signal: Sized + Debug + Send + Sync + PartialEq + 'static {
 }
 
 macro_rules! impl_signal {
-    ($name:ident, $typ:expr, $variant:ident) => {
+    ($name:ident, $typ:expr, $variant:ident, $dense:ty) => {
         impl Signal for $name {
+            type Dense = $dense;
+
             fn signal_type() -> SignalType {
                 $typ
             }
@@ -436,7 +944,7 @@ macro_rules! impl_signal {
             }
 
             #[inline]
-            fn try_from_any_signal_ref(signal: AnySignalRef) -> Option<&Option<Self>>
+            fn try_from_any_signal_ref(signal: AnySignalRef<'_>) -> Option<&Option<Self>>
             where
                 Self: Sized,
             {
@@ -448,8 +956,8 @@ macro_rules! impl_signal {
 
             #[inline]
             fn try_from_any_signal_mut(
-                signal: AnySignalMut,
-            ) -> Result<&mut Option<Self>, AnySignalMut>
+                signal: AnySignalMut<'_>,
+            ) -> Result<&mut Option<Self>, AnySignalMut<'_>>
             where
                 Self: Sized,
             {
@@ -478,12 +986,12 @@ macro_rules! impl_signal {
     };
 }
 
-impl_signal!(Float, SignalType::Float, Float);
-impl_signal!(bool, SignalType::Bool, Bool);
-impl_signal!(i64, SignalType::Int, Int);
-impl_signal!(String, SignalType::String, String);
-impl_signal!(List, SignalType::List, List);
-impl_signal!(MidiMessage, SignalType::Midi, Midi);
+impl_signal!(Float, SignalType::Float, Float, DenseSamples);
+impl_signal!(bool, SignalType::Bool, Bool, ());
+impl_signal!(i64, SignalType::Int, Int, ());
+impl_signal!(String, SignalType::String, String, ());
+impl_signal!(List, SignalType::List, List, ());
+impl_signal!(MidiMessage, SignalType::Midi, Midi, ());
 
 /// A type that can hold any signal type.
 #[derive(Debug, Clone, PartialEq)]
@@ -516,7 +1024,7 @@ impl AnySignal {
             SignalType::Int => AnySignal::Int(None),
             SignalType::Bool => AnySignal::Bool(None),
             SignalType::String => AnySignal::String(None),
-            SignalType::List { .. } => AnySignal::List(None),
+            SignalType::List => AnySignal::List(None),
             SignalType::Midi => AnySignal::Midi(None),
         }
     }
@@ -619,7 +1127,7 @@ impl AnySignal {
 
     /// Returns a reference to the signal.
     #[inline]
-    pub fn as_ref(&self) -> AnySignalRef {
+    pub fn as_ref(&self) -> AnySignalRef<'_> {
         match self {
             Self::Float(float) => AnySignalRef::Float(float),
             Self::Int(int) => AnySignalRef::Int(int),
@@ -632,7 +1140,7 @@ impl AnySignal {
 
     /// Returns a mutable reference to the signal.
     #[inline]
-    pub fn as_mut(&mut self) -> AnySignalMut {
+    pub fn as_mut(&mut self) -> AnySignalMut<'_> {
         match self {
             Self::Float(float) => AnySignalMut::Float(float),
             Self::Int(int) => AnySignalMut::Int(int),
@@ -947,7 +1455,11 @@ impl SignalBuffer {
     /// Creates a new buffer of the given type with the given length filled with `None`.
     pub fn new_of_type(signal_type: &SignalType, length: usize) -> Self {
         match signal_type {
-            SignalType::Float => Self::Float(Buffer::zeros(length)),
+            SignalType::Float => {
+                let mut buffer = Buffer::zeros(length);
+                buffer.reserve_dense();
+                Self::Float(buffer)
+            }
             SignalType::Int => Self::Int(Buffer::zeros(length)),
             SignalType::Bool => Self::Bool(Buffer::zeros(length)),
             SignalType::String => Self::String(Buffer::zeros(length)),
@@ -1014,12 +1526,25 @@ impl SignalBuffer {
     pub fn resize(&mut self, length: usize, value: impl Into<AnySignal>) {
         let value = value.into();
         match (self, value) {
-            (Self::Float(buffer), AnySignal::Float(value)) => buffer.buf.resize(length, value),
-            (Self::Int(buffer), AnySignal::Int(value)) => buffer.buf.resize(length, value),
-            (Self::Bool(buffer), AnySignal::Bool(value)) => buffer.buf.resize(length, value),
-            (Self::String(buffer), AnySignal::String(value)) => buffer.buf.resize(length, value),
-            (Self::List(buffer), AnySignal::List(value)) => buffer.buf.resize(length, value),
-            (Self::Midi(buffer), AnySignal::Midi(value)) => buffer.buf.resize(length, value),
+            (Self::Float(buffer), AnySignal::Float(value)) => {
+                buffer.signals_mut().resize(length, value);
+                buffer.reserve_dense();
+            }
+            (Self::Int(buffer), AnySignal::Int(value)) => {
+                buffer.signals_mut().resize(length, value)
+            }
+            (Self::Bool(buffer), AnySignal::Bool(value)) => {
+                buffer.signals_mut().resize(length, value)
+            }
+            (Self::String(buffer), AnySignal::String(value)) => {
+                buffer.signals_mut().resize(length, value)
+            }
+            (Self::List(buffer), AnySignal::List(value)) => {
+                buffer.signals_mut().resize(length, value)
+            }
+            (Self::Midi(buffer), AnySignal::Midi(value)) => {
+                buffer.signals_mut().resize(length, value)
+            }
             _ => panic!("Cannot resize buffer with value of different type"),
         }
     }
@@ -1045,13 +1570,49 @@ impl SignalBuffer {
     /// Resizes the buffer to the given length, filling the new elements with `None`.
     pub fn resize_default(&mut self, length: usize) {
         match self {
-            Self::Float(buffer) => buffer.buf.resize(length, None),
-            Self::Int(buffer) => buffer.buf.resize(length, None),
-            Self::Bool(buffer) => buffer.buf.resize(length, None),
-            Self::String(buffer) => buffer.buf.resize(length, None),
-            Self::List(buffer) => buffer.buf.resize(length, None),
-            Self::Midi(buffer) => buffer.buf.resize(length, None),
+            Self::Float(buffer) => {
+                buffer.signals_mut().resize(length, None);
+                buffer.reserve_dense();
+            }
+            Self::Int(buffer) => buffer.signals_mut().resize(length, None),
+            Self::Bool(buffer) => buffer.signals_mut().resize(length, None),
+            Self::String(buffer) => buffer.signals_mut().resize(length, None),
+            Self::List(buffer) => buffer.signals_mut().resize(length, None),
+            Self::Midi(buffer) => buffer.signals_mut().resize(length, None),
+        }
+    }
+
+    /// Resizes the buffer to the given length, filling the new elements with a copy of the last element (or `None` if the buffer is empty).
+    ///
+    /// Only [`Copy`] signals are held. New elements of `String` and `List` buffers are `None`, since cloning the last element would allocate.
+    /// Returns `true` if the new elements hold the last element.
+    pub fn resize_hold(&mut self, length: usize) -> bool {
+        match self {
+            Self::Float(buffer) => buffer.resize_hold(length),
+            Self::Int(buffer) => {
+                let last = buffer.last().copied().flatten();
+                buffer.signals_mut().resize(length, last);
+            }
+            Self::Bool(buffer) => {
+                let last = buffer.last().copied().flatten();
+                buffer.signals_mut().resize(length, last);
+            }
+            Self::Midi(buffer) => {
+                let last = buffer.last().copied().flatten();
+                buffer.signals_mut().resize(length, last);
+            }
+            Self::String(buffer) => {
+                let held = length <= buffer.len();
+                buffer.signals_mut().resize(length, None);
+                return held;
+            }
+            Self::List(buffer) => {
+                let held = length <= buffer.len();
+                buffer.signals_mut().resize(length, None);
+                return held;
+            }
         }
+        true
     }
 
     /// Resizes the buffer based on the given type hint.
@@ -1064,6 +1625,21 @@ impl SignalBuffer {
         }
     }
 
+    /// Returns `true` if all signals in the buffer are equal.
+    pub fn is_constant(&self) -> bool {
+        match self {
+            Self::Float(buffer) => match buffer.as_dense() {
+                Some(samples) => samples.windows(2).all(|w| w[0] == w[1]),
+                None => buffer.windows(2).all(|w| w[0] == w[1]),
+            },
+            Self::Int(buffer) => buffer.windows(2).all(|w| w[0] == w[1]),
+            Self::Bool(buffer) => buffer.windows(2).all(|w| w[0] == w[1]),
+            Self::String(buffer) => buffer.windows(2).all(|w| w[0] == w[1]),
+            Self::List(buffer) => buffer.windows(2).all(|w| w[0] == w[1]),
+            Self::Midi(buffer) => buffer.windows(2).all(|w| w[0] == w[1]),
+        }
+    }
+
     /// Fills the buffer with `None`.
     pub fn fill_default(&mut self) {
         match self {
@@ -1088,7 +1664,7 @@ impl SignalBuffer {
 
     /// Returns a reference to the signal at the given index.
     #[inline]
-    pub fn get(&self, index: usize) -> Option<AnySignalRef> {
+    pub fn get(&self, index: usize) -> Option<AnySignalRef<'_>> {
         match self {
             Self::Float(buffer) => buffer.get(index).map(AnySignalRef::Float),
             Self::Int(buffer) => buffer.get(index).map(AnySignalRef::Int),
@@ -1101,7 +1677,7 @@ impl SignalBuffer {
 
     /// Returns a mutable reference to the signal at the given index.
     #[inline]
-    pub fn get_mut(&mut self, index: usize) -> Option<AnySignalMut> {
+    pub fn get_mut(&mut self, index: usize) -> Option<AnySignalMut<'_>> {
         match self {
             Self::Float(buffer) => buffer.get_mut(index).map(AnySignalMut::Float),
             Self::Int(buffer) => buffer.get_mut(index).map(AnySignalMut::Int),
@@ -1190,7 +1766,7 @@ impl SignalBuffer {
     pub fn clone_from(&mut self, other: &Self) {
         match (self, other) {
             (Self::Float(this), Self::Float(other)) => {
-                this.copy_from_slice(other);
+                this.copy_dense_from(other);
             }
             (Self::Int(this), Self::Int(other)) => {
                 this.copy_from_slice(other);
@@ -1220,7 +1796,7 @@ impl SignalBuffer {
     pub fn copy_from(&mut self, other: &Self) {
         match (self, other) {
             (Self::Float(this), Self::Float(other)) => {
-                this.copy_from_slice(other);
+                this.copy_dense_from(other);
             }
             (Self::Int(this), Self::Int(other)) => {
                 this.copy_from_slice(other);
@@ -1243,30 +1819,46 @@ impl SignalBuffer {
 
     /// Returns an iterator over the signals in the buffer.
     #[inline]
-    pub fn iter(&self) -> SignalBufferIter {
-        SignalBufferIter {
-            buffer: self,
-            index: 0,
-            _marker: std::marker::PhantomData,
-        }
+    pub fn iter(&self) -> SignalBufferIter<'_> {
+        let signals = match self {
+            SignalBuffer::Float(buffer) => SignalsIter::Float(buffer.iter()),
+            SignalBuffer::Int(buffer) => SignalsIter::Int(buffer.iter()),
+            SignalBuffer::Bool(buffer) => SignalsIter::Bool(buffer.iter()),
+            SignalBuffer::String(buffer) => SignalsIter::String(buffer.iter()),
+            SignalBuffer::List(buffer) => SignalsIter::List(buffer.iter()),
+            SignalBuffer::Midi(buffer) => SignalsIter::Midi(buffer.iter()),
+        };
+        SignalBufferIter { signals }
     }
 
     /// Returns a mutable iterator over the signals in the buffer.
     #[inline]
-    pub fn iter_mut(&mut self) -> SignalBufferIterMut {
-        SignalBufferIterMut {
-            buffer: self,
-            index: 0,
-            _marker: std::marker::PhantomData,
-        }
+    pub fn iter_mut(&mut self) -> SignalBufferIterMut<'_> {
+        let signals = match self {
+            SignalBuffer::Float(buffer) => SignalsIterMut::Float(buffer.iter_mut()),
+            SignalBuffer::Int(buffer) => SignalsIterMut::Int(buffer.iter_mut()),
+            SignalBuffer::Bool(buffer) => SignalsIterMut::Bool(buffer.iter_mut()),
+            SignalBuffer::String(buffer) => SignalsIterMut::String(buffer.iter_mut()),
+            SignalBuffer::List(buffer) => SignalsIterMut::List(buffer.iter_mut()),
+            SignalBuffer::Midi(buffer) => SignalsIterMut::Midi(buffer.iter_mut()),
+        };
+        SignalBufferIterMut { signals }
     }
 }
 
+// the signals of a buffer are looked up once, so that a dense buffer isn't checked for each signal
+enum SignalsIter<'a> {
+    Float(std::slice::Iter<'a, Option<Float>>),
+    Int(std::slice::Iter<'a, Option<i64>>),
+    Bool(std::slice::Iter<'a, Option<bool>>),
+    String(std::slice::Iter<'a, Option<String>>),
+    List(std::slice::Iter<'a, Option<List>>),
+    Midi(std::slice::Iter<'a, Option<MidiMessage>>),
+}
+
 /// An iterator over the signals in a buffer.
 pub struct SignalBufferIter<'a> {
-    buffer: &'a SignalBuffer,
-    index: usize,
-    _marker: std::marker::PhantomData<AnySignalRef<'a>>,
+    signals: SignalsIter<'a>,
 }
 
 impl<'a> Iterator for SignalBufferIter<'a> {
@@ -1274,19 +1866,13 @@ impl<'a> Iterator for SignalBufferIter<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.buffer.len() {
-            let signal = match self.buffer {
-                SignalBuffer::Float(buffer) => AnySignalRef::Float(&buffer[self.index]),
-                SignalBuffer::Int(buffer) => AnySignalRef::Int(&buffer[self.index]),
-                SignalBuffer::Bool(buffer) => AnySignalRef::Bool(&buffer[self.index]),
-                SignalBuffer::String(buffer) => AnySignalRef::String(&buffer[self.index]),
-                SignalBuffer::List(buffer) => AnySignalRef::List(&buffer[self.index]),
-                SignalBuffer::Midi(buffer) => AnySignalRef::Midi(&buffer[self.index]),
-            };
-            self.index += 1;
-            Some(signal)
-        } else {
-            None
+        match &mut self.signals {
+            SignalsIter::Float(iter) => iter.next().map(AnySignalRef::Float),
+            SignalsIter::Int(iter) => iter.next().map(AnySignalRef::Int),
+            SignalsIter::Bool(iter) => iter.next().map(AnySignalRef::Bool),
+            SignalsIter::String(iter) => iter.next().map(AnySignalRef::String),
+            SignalsIter::List(iter) => iter.next().map(AnySignalRef::List),
+            SignalsIter::Midi(iter) => iter.next().map(AnySignalRef::Midi),
         }
     }
 }
@@ -1296,19 +1882,22 @@ impl<'a> IntoIterator for &'a SignalBuffer {
     type IntoIter = SignalBufferIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        SignalBufferIter {
-            buffer: self,
-            index: 0,
-            _marker: std::marker::PhantomData,
-        }
+        self.iter()
     }
 }
 
+enum SignalsIterMut<'a> {
+    Float(std::slice::IterMut<'a, Option<Float>>),
+    Int(std::slice::IterMut<'a, Option<i64>>),
+    Bool(std::slice::IterMut<'a, Option<bool>>),
+    String(std::slice::IterMut<'a, Option<String>>),
+    List(std::slice::IterMut<'a, Option<List>>),
+    Midi(std::slice::IterMut<'a, Option<MidiMessage>>),
+}
+
 /// An mutable iterator over the signals in a buffer.
 pub struct SignalBufferIterMut<'a> {
-    buffer: &'a mut SignalBuffer,
-    index: usize,
-    _marker: std::marker::PhantomData<AnySignalMut<'a>>,
+    signals: SignalsIterMut<'a>,
 }
 
 impl<'a> Iterator for SignalBufferIterMut<'a> {
@@ -1316,38 +1905,13 @@ impl<'a> Iterator for SignalBufferIterMut<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.buffer.len() {
-            // SAFETY:
-            // We are borrowing the buffer mutably, so we can safely create a mutable reference to the signal.
-            // We are also only creating one mutable reference at a time, so there are no issues with aliasing.
-            // The lifetime of the mutable reference is limited to the lifetime of the iterator.
-            // This is similar to how `std::slice::IterMut` works.
-            unsafe {
-                let signal = match self.buffer {
-                    SignalBuffer::Float(buffer) => {
-                        AnySignalMut::Float(&mut *(&mut buffer[self.index] as *mut Option<Float>))
-                    }
-                    SignalBuffer::Int(buffer) => {
-                        AnySignalMut::Int(&mut *(&mut buffer[self.index] as *mut Option<i64>))
-                    }
-                    SignalBuffer::Bool(buffer) => {
-                        AnySignalMut::Bool(&mut *(&mut buffer[self.index] as *mut Option<bool>))
-                    }
-                    SignalBuffer::String(buffer) => {
-                        AnySignalMut::String(&mut *(&mut buffer[self.index] as *mut Option<String>))
-                    }
-                    SignalBuffer::List(buffer) => {
-                        AnySignalMut::List(&mut *(&mut buffer[self.index] as *mut Option<List>))
-                    }
-                    SignalBuffer::Midi(buffer) => AnySignalMut::Midi(
-                        &mut *(&mut buffer[self.index] as *mut Option<MidiMessage>),
-                    ),
-                };
-                self.index += 1;
-                Some(signal)
-            }
-        } else {
-            None
+        match &mut self.signals {
+            SignalsIterMut::Float(iter) => iter.next().map(AnySignalMut::Float),
+            SignalsIterMut::Int(iter) => iter.next().map(AnySignalMut::Int),
+            SignalsIterMut::Bool(iter) => iter.next().map(AnySignalMut::Bool),
+            SignalsIterMut::String(iter) => iter.next().map(AnySignalMut::String),
+            SignalsIterMut::List(iter) => iter.next().map(AnySignalMut::List),
+            SignalsIterMut::Midi(iter) => iter.next().map(AnySignalMut::Midi),
         }
     }
 }
@@ -1357,64 +1921,115 @@ impl<'a> IntoIterator for &'a mut SignalBuffer {
     type IntoIter = SignalBufferIterMut<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        SignalBufferIterMut {
-            buffer: self,
-            index: 0,
-            _marker: std::marker::PhantomData,
-        }
+        self.iter_mut()
     }
 }
 
 impl FromIterator<Float> for SignalBuffer {
     fn from_iter<T: IntoIterator<Item = Float>>(iter: T) -> Self {
         let iter = iter.into_iter().map(Some);
-        Self::Float(Buffer {
-            buf: iter.collect(),
-        })
+        Self::Float(Buffer::from_vec(iter.collect()))
     }
 }
 
 impl FromIterator<i64> for SignalBuffer {
     fn from_iter<T: IntoIterator<Item = i64>>(iter: T) -> Self {
         let iter = iter.into_iter().map(Some);
-        Self::Int(Buffer {
-            buf: iter.collect(),
-        })
+        Self::Int(Buffer::from_vec(iter.collect()))
     }
 }
 
 impl FromIterator<bool> for SignalBuffer {
     fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
         let iter = iter.into_iter().map(Some);
-        Self::Bool(Buffer {
-            buf: iter.collect(),
-        })
+        Self::Bool(Buffer::from_vec(iter.collect()))
     }
 }
 
 impl FromIterator<String> for SignalBuffer {
     fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
         let iter = iter.into_iter().map(Some);
-        Self::String(Buffer {
-            buf: iter.collect(),
-        })
+        Self::String(Buffer::from_vec(iter.collect()))
     }
 }
 
 impl FromIterator<List> for SignalBuffer {
     fn from_iter<T: IntoIterator<Item = List>>(iter: T) -> Self {
         let iter = iter.into_iter().map(Some);
-        Self::List(Buffer {
-            buf: iter.collect(),
-        })
+        Self::List(Buffer::from_vec(iter.collect()))
     }
 }
 
 impl FromIterator<MidiMessage> for SignalBuffer {
     fn from_iter<T: IntoIterator<Item = MidiMessage>>(iter: T) -> Self {
         let iter = iter.into_iter().map(Some);
-        Self::Midi(Buffer {
-            buf: iter.collect(),
-        })
+        Self::Midi(Buffer::from_vec(iter.collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dense_buffers_stop_being_dense_when_modified() {
+        let mut buffer = Buffer::<Float>::zeros(4);
+        assert_eq!(buffer.as_dense(), None);
+
+        buffer.fill_dense(|samples| samples.copy_from_slice(&[1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(buffer.as_dense(), Some(&[1.0, 2.0, 3.0, 4.0][..]));
+        assert_eq!(&buffer[..], &[Some(1.0), Some(2.0), Some(3.0), Some(4.0)]);
+
+        let mut copy = SignalBuffer::new_of_type(&SignalType::Float, 4);
+        copy.clone_from(&SignalBuffer::Float(buffer.clone()));
+        assert_eq!(
+            copy.as_type::<Float>().unwrap().as_dense(),
+            buffer.as_dense()
+        );
+
+        buffer[2] = None;
+        assert_eq!(buffer.as_dense(), None);
+
+        // holding the last sample keeps a buffer dense
+        let mut held = SignalBuffer::Float(Buffer::zeros(2));
+        held.as_type_mut::<Float>()
+            .unwrap()
+            .fill_dense(|samples| samples.fill(0.5));
+        held.resize_hold(4);
+        assert_eq!(
+            held.as_type::<Float>().unwrap().as_dense(),
+            Some(&[0.5; 4][..])
+        );
+        held.resize_default(6);
+        assert_eq!(held.as_type::<Float>().unwrap().as_dense(), None);
+    }
+
+    #[test]
+    fn dense_buffers_write_their_entries_when_first_read() {
+        // only float buffers store dense samples
+        assert_eq!(
+            std::mem::size_of::<Buffer<i64>>(),
+            std::mem::size_of::<Vec<Option<i64>>>()
+        );
+
+        let mut buffer = Buffer::<Float>::zeros(64);
+        buffer.fill_dense(|samples| {
+            for (i, sample) in samples.iter_mut().enumerate() {
+                *sample = i as Float;
+            }
+        });
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for (i, sample) in buffer.iter().enumerate() {
+                        assert_eq!(*sample, Some(i as Float));
+                    }
+                });
+            }
+        });
+        assert!(buffer.is_dense());
+
+        buffer.fill_dense(|samples| samples.fill(1.0));
+        assert_eq!(buffer.sum(), 64.0);
     }
 }
@@ -0,0 +1,653 @@
+//! Reading and writing Standard MIDI Files.
+//!
+//! [`MidiFile`] holds the tracks of a `.mid` file with their events at absolute tick positions.
+//! [`MidiFile::timed_events`] resolves the tempo map to give the time of each MIDI message in seconds, which is what the [`MidiFilePlayer`](crate::builtins::MidiFilePlayer) processor plays back.
+//! MIDI produced by a graph can be recorded with [`MidiFile::record`] from the messages captured by [`Runtime::run_offline_with_midi`](crate::runtime::Runtime::run_offline_with_midi).
+
+use std::path::Path;
+
+use crate::{
+    builtins::MidiOutEvent,
+    signal::{Float, MidiMessage},
+};
+
+/// The tempo assumed until the first tempo event, in microseconds per quarter note (120 BPM).
+pub const DEFAULT_TEMPO: u32 = 500_000;
+
+/// The resolution of files written by [`MidiFile::from_events`] and [`MidiFile::record`], in ticks per quarter note.
+const RECORD_TICKS_PER_QUARTER: u16 = 960;
+
+/// Errors that can occur when reading or writing a Standard MIDI File.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum MidiFileError {
+    /// An I/O error occurred.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The file doesn't start with an `MThd` header chunk.
+    #[error("Not a Standard MIDI File")]
+    NotMidiFile,
+
+    /// The file ended in the middle of a chunk or event.
+    #[error("Unexpected end of file")]
+    UnexpectedEof,
+
+    /// A channel message was found without a status byte, and there was no running status to use.
+    #[error("Missing status byte at offset {0}")]
+    MissingStatus(usize),
+
+    /// The file uses a format other than 0, 1 or 2.
+    #[error("Unsupported MIDI file format: {0}")]
+    UnsupportedFormat(u16),
+}
+
+/// How ticks are converted to time in a [`MidiFile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MidiFileTiming {
+    /// Ticks are a fraction of a quarter note, whose duration is set by the tempo events.
+    TicksPerQuarter(u16),
+    /// Ticks are a fraction of an SMPTE frame, independent of the tempo.
+    Smpte {
+        /// The number of frames per second (24, 25, 29 for 29.97 drop-frame, or 30).
+        frames_per_second: u8,
+        /// The number of ticks per frame.
+        ticks_per_frame: u8,
+    },
+}
+
+/// An event in a [`MidiFileTrack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MidiFileEvent {
+    /// A channel message. Messages with a single data byte (program change and channel pressure) have their second data byte set to 0.
+    Midi(MidiMessage),
+    /// A tempo change, in microseconds per quarter note.
+    Tempo(u32),
+    /// A system exclusive message, without the leading `0xF0` or `0xF7` byte.
+    SysEx(Vec<u8>),
+    /// Any other meta event, such as a track name or time signature.
+    Meta {
+        /// The meta event type.
+        kind: u8,
+        /// The data of the meta event.
+        data: Vec<u8>,
+    },
+}
+
+/// A track of a [`MidiFile`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiFileTrack {
+    /// The events of the track and their absolute positions in ticks, in order.
+    pub events: Vec<(u64, MidiFileEvent)>,
+}
+
+/// A Standard MIDI File.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidiFile {
+    /// The format of the file: 0 for a single track, 1 for simultaneous tracks, 2 for independent sequences.
+    pub format: u16,
+    /// How ticks are converted to time.
+    pub timing: MidiFileTiming,
+    /// The tracks of the file.
+    pub tracks: Vec<MidiFileTrack>,
+}
+
+impl MidiFile {
+    /// Loads a Standard MIDI File from the given path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MidiFileError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parses a Standard MIDI File from its bytes.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MidiFileError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != b"MThd" {
+            return Err(MidiFileError::NotMidiFile);
+        }
+        let header_len = reader.u32()? as usize;
+        let header = reader.take(header_len)?;
+        if header.len() < 6 {
+            return Err(MidiFileError::UnexpectedEof);
+        }
+        let format = u16::from_be_bytes([header[0], header[1]]);
+        if format > 2 {
+            return Err(MidiFileError::UnsupportedFormat(format));
+        }
+        let num_tracks = u16::from_be_bytes([header[2], header[3]]);
+        let timing = if header[4] & 0x80 != 0 {
+            MidiFileTiming::Smpte {
+                frames_per_second: (header[4] as i8).unsigned_abs(),
+                ticks_per_frame: header[5],
+            }
+        } else {
+            MidiFileTiming::TicksPerQuarter(u16::from_be_bytes([header[4], header[5]]))
+        };
+
+        let mut tracks = Vec::with_capacity(num_tracks as usize);
+        while tracks.len() < num_tracks as usize && !reader.is_empty() {
+            let kind = reader.take(4)?;
+            let len = reader.u32()? as usize;
+            let start = reader.pos;
+            let chunk = reader.take(len)?;
+            // unknown chunks must be skipped
+            if kind == b"MTrk" {
+                tracks.push(parse_track(chunk, start)?);
+            }
+        }
+
+        Ok(Self {
+            format,
+            timing,
+            tracks,
+        })
+    }
+
+    /// Creates a single-track (format 0) file from MIDI messages and their times in seconds.
+    ///
+    /// The file is written at 120 BPM with 960 ticks per quarter note, so the times are kept to within about a quarter of a millisecond.
+    /// SysEx messages are reassembled from their packets and stored at the time of their first packet.
+    /// Other system messages (e.g. MIDI clock) can't be stored in a MIDI file and are skipped.
+    pub fn from_events(events: impl IntoIterator<Item = (f64, MidiMessage)>) -> Self {
+        let ticks_per_second = RECORD_TICKS_PER_QUARTER as f64 * 1_000_000.0 / DEFAULT_TEMPO as f64;
+
+        let mut track = MidiFileTrack::default();
+        track.events.push((0, MidiFileEvent::Tempo(DEFAULT_TEMPO)));
+        // the start tick and the bytes after 0xF0 of the SysEx message being received
+        let mut sysex: Option<(u64, Vec<u8>)> = None;
+        for (time, message) in events {
+            let tick = (time.max(0.0) * ticks_per_second).round() as u64;
+            if message.is_sysex_start() {
+                sysex = Some((tick, Vec::new()));
+            }
+            if message.is_sysex() {
+                let Some((_, data)) = &mut sysex else {
+                    continue;
+                };
+                let bytes = message.as_bytes();
+                data.extend_from_slice(if message.is_sysex_start() {
+                    &bytes[1..]
+                } else {
+                    bytes
+                });
+                if message.is_sysex_end() {
+                    if let Some((tick, data)) = sysex.take() {
+                        track.events.push((tick, MidiFileEvent::SysEx(data)));
+                    }
+                }
+            } else if message.is_channel_message() {
+                track.events.push((tick, MidiFileEvent::Midi(message)));
+            }
+        }
+        track.events.sort_by_key(|(tick, _)| *tick);
+
+        Self {
+            format: 0,
+            timing: MidiFileTiming::TicksPerQuarter(RECORD_TICKS_PER_QUARTER),
+            tracks: vec![track],
+        }
+    }
+
+    /// Creates a single-track file from messages sent by a graph's MIDI output, whose times are in samples at the given sample rate.
+    ///
+    /// See [`MidiFile::from_events`].
+    pub fn record(events: &[MidiOutEvent], sample_rate: Float) -> Self {
+        Self::from_events(
+            events
+                .iter()
+                .map(|event| (event.time as f64 / sample_rate as f64, event.message)),
+        )
+    }
+
+    /// Saves the file to the given path.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MidiFileError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Serializes the file to the Standard MIDI File format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&self.format.to_be_bytes());
+        bytes.extend_from_slice(&(self.tracks.len() as u16).to_be_bytes());
+        match self.timing {
+            MidiFileTiming::TicksPerQuarter(ticks) => bytes.extend_from_slice(&ticks.to_be_bytes()),
+            MidiFileTiming::Smpte {
+                frames_per_second,
+                ticks_per_frame,
+            } => {
+                bytes.push((-(frames_per_second as i8)) as u8);
+                bytes.push(ticks_per_frame);
+            }
+        }
+
+        for track in &self.tracks {
+            let mut data = Vec::new();
+            let mut last_tick = 0;
+            for (tick, event) in &track.events {
+                write_vlq(&mut data, tick.saturating_sub(last_tick) as u32);
+                last_tick = last_tick.max(*tick);
+                match event {
+                    MidiFileEvent::Midi(message) => {
+                        data.extend_from_slice(message.as_bytes());
+                    }
+                    MidiFileEvent::Tempo(tempo) => {
+                        data.extend_from_slice(&[0xFF, 0x51, 3]);
+                        data.extend_from_slice(&tempo.to_be_bytes()[1..]);
+                    }
+                    MidiFileEvent::SysEx(sysex) => {
+                        data.push(0xF0);
+                        write_vlq(&mut data, sysex.len() as u32);
+                        data.extend_from_slice(sysex);
+                    }
+                    MidiFileEvent::Meta { kind, data: meta } => {
+                        data.extend_from_slice(&[0xFF, *kind]);
+                        write_vlq(&mut data, meta.len() as u32);
+                        data.extend_from_slice(meta);
+                    }
+                }
+            }
+            // end of track
+            data.extend_from_slice(&[0, 0xFF, 0x2F, 0]);
+
+            bytes.extend_from_slice(b"MTrk");
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&data);
+        }
+
+        bytes
+    }
+
+    /// Returns the MIDI messages of all tracks with their times in seconds, in order.
+    ///
+    /// Tempo changes in any track apply to all tracks. For format 2 files, whose tracks are independent sequences, only the first track is used.
+    /// Complete SysEx messages are included as the packets that carry them in a MIDI signal (see [`MidiMessage`]).
+    pub fn timed_events(&self) -> Vec<(f64, MidiMessage)> {
+        let tracks = if self.format == 2 {
+            &self.tracks[..self.tracks.len().min(1)]
+        } else {
+            &self.tracks[..]
+        };
+
+        let mut events: Vec<(u64, &MidiFileEvent)> = tracks
+            .iter()
+            .flat_map(|track| track.events.iter().map(|(tick, event)| (*tick, event)))
+            .collect();
+        // stable, so simultaneous events keep their track order
+        events.sort_by_key(|(tick, _)| *tick);
+
+        let mut timed = Vec::new();
+        let mut tempo = DEFAULT_TEMPO;
+        let mut last_tick = 0;
+        let mut last_time = 0.0;
+        for (tick, event) in events {
+            let time = last_time + self.ticks_to_seconds(tick - last_tick, tempo);
+            last_tick = tick;
+            last_time = time;
+
+            match event {
+                MidiFileEvent::Midi(message) => timed.push((time, *message)),
+                MidiFileEvent::Tempo(new_tempo) => tempo = *new_tempo,
+                MidiFileEvent::SysEx(data) if data.last() == Some(&0xF7) => {
+                    let mut bytes = Vec::with_capacity(data.len() + 1);
+                    bytes.push(0xF0);
+                    bytes.extend_from_slice(data);
+                    timed.extend(MidiMessage::sysex_packets(&bytes).map(|packet| (time, packet)));
+                }
+                _ => {}
+            }
+        }
+
+        timed
+    }
+
+    /// Returns the time of the last MIDI message in seconds.
+    pub fn duration(&self) -> f64 {
+        self.timed_events()
+            .last()
+            .map(|(time, _)| *time)
+            .unwrap_or_default()
+    }
+
+    fn ticks_to_seconds(&self, ticks: u64, tempo: u32) -> f64 {
+        match self.timing {
+            MidiFileTiming::TicksPerQuarter(ticks_per_quarter) => {
+                ticks as f64 * tempo as f64 / 1_000_000.0 / ticks_per_quarter.max(1) as f64
+            }
+            MidiFileTiming::Smpte {
+                frames_per_second,
+                ticks_per_frame,
+            } => {
+                let frames_per_second = if frames_per_second == 29 {
+                    29.97
+                } else {
+                    frames_per_second as f64
+                };
+                ticks as f64 / (frames_per_second * ticks_per_frame.max(1) as f64)
+            }
+        }
+    }
+}
+
+// the number of bytes of a channel message with the given status byte, including the status byte
+fn channel_message_len(status: u8) -> usize {
+    match status & 0xF0 {
+        0xC0 | 0xD0 => 2,
+        _ => 3,
+    }
+}
+
+fn write_vlq(data: &mut Vec<u8>, mut value: u32) {
+    let mut buf = [0; 5];
+    let mut i = buf.len() - 1;
+    buf[i] = (value & 0x7F) as u8;
+    value >>= 7;
+    while value > 0 {
+        i -= 1;
+        buf[i] = (value & 0x7F) as u8 | 0x80;
+        value >>= 7;
+    }
+    data.extend_from_slice(&buf[i..]);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MidiFileError> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(MidiFileError::UnexpectedEof)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, MidiFileError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, MidiFileError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn vlq(&mut self) -> Result<u32, MidiFileError> {
+        let mut value = 0u32;
+        // variable-length quantities are at most 4 bytes long
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+}
+
+fn parse_track(chunk: &[u8], offset: usize) -> Result<MidiFileTrack, MidiFileError> {
+    let mut reader = Reader {
+        bytes: chunk,
+        pos: 0,
+    };
+    let mut track = MidiFileTrack::default();
+    let mut tick = 0u64;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        tick += reader.vlq()? as u64;
+
+        let mut status = reader.u8()?;
+        match status {
+            0xFF => {
+                running_status = None;
+                let kind = reader.u8()?;
+                let len = reader.vlq()? as usize;
+                let data = reader.take(len)?;
+                match kind {
+                    0x2F => break,
+                    0x51 if data.len() == 3 => {
+                        let tempo = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                        track.events.push((tick, MidiFileEvent::Tempo(tempo)));
+                    }
+                    _ => track.events.push((
+                        tick,
+                        MidiFileEvent::Meta {
+                            kind,
+                            data: data.to_vec(),
+                        },
+                    )),
+                }
+            }
+            0xF0 | 0xF7 => {
+                running_status = None;
+                let len = reader.vlq()? as usize;
+                let data = reader.take(len)?;
+                track
+                    .events
+                    .push((tick, MidiFileEvent::SysEx(data.to_vec())));
+            }
+            _ => {
+                if status < 0x80 {
+                    // running status: this byte is the first data byte
+                    reader.pos -= 1;
+                    status =
+                        running_status.ok_or(MidiFileError::MissingStatus(offset + reader.pos))?;
+                }
+                running_status = Some(status);
+
+                let mut data = [status, 0, 0];
+                for byte in data.iter_mut().take(channel_message_len(status)).skip(1) {
+                    *byte = reader.u8()?;
+                }
+                track
+                    .events
+                    .push((tick, MidiFileEvent::Midi(MidiMessage::new(data))));
+            }
+        }
+    }
+
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // builds a file from the given header fields and track chunks
+    fn smf(format: u16, division: [u8; 2], tracks: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&format.to_be_bytes());
+        bytes.extend_from_slice(&(tracks.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&division);
+        for track in tracks {
+            bytes.extend_from_slice(b"MTrk");
+            bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(track);
+        }
+        bytes
+    }
+
+    fn assert_times(events: &[(f64, MidiMessage)], expected: &[(f64, MidiMessage)]) {
+        assert_eq!(events.len(), expected.len(), "{events:?}");
+        for ((time, message), (expected_time, expected_message)) in events.iter().zip(expected) {
+            assert!(
+                (time - expected_time).abs() < 1e-9,
+                "{time} != {expected_time}"
+            );
+            assert_eq!(message, expected_message);
+        }
+    }
+
+    #[test]
+    fn parses_tempo_changes_and_running_status() {
+        // 96 ticks per quarter note
+        let tempo_track = [
+            // 120 BPM
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, //
+            // 60 BPM after a quarter note
+            0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, //
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let note_track = [
+            0x00, 0x90, 0x3C, 0x64, //
+            // running status note on with velocity 0 after an eighth note
+            0x30, 0x3C, 0x00, //
+            0x30, 0x40, 0x64, //
+            // a two byte delta of 192 ticks
+            0x81, 0x40, 0x80, 0x40, 0x00, //
+            // program change, then one with running status
+            0x60, 0xC0, 0x05, //
+            0x30, 0x07, //
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let file = MidiFile::parse(&smf(1, [0x00, 0x60], &[&tempo_track, &note_track])).unwrap();
+
+        assert_eq!(file.format, 1);
+        assert_eq!(file.timing, MidiFileTiming::TicksPerQuarter(96));
+        assert_eq!(
+            file.tracks[0].events,
+            [
+                (0, MidiFileEvent::Tempo(500_000)),
+                (96, MidiFileEvent::Tempo(1_000_000)),
+            ]
+        );
+        let ticks: Vec<u64> = file.tracks[1]
+            .events
+            .iter()
+            .map(|(tick, _)| *tick)
+            .collect();
+        assert_eq!(ticks, [0, 48, 96, 288, 384, 432]);
+
+        // a quarter note lasts 0.5 seconds until tick 96, and 1 second after it
+        assert_times(
+            &file.timed_events(),
+            &[
+                (0.0, MidiMessage::note_on(0, 60, 100)),
+                (0.25, MidiMessage::note_on(0, 60, 0)),
+                (0.5, MidiMessage::note_on(0, 64, 100)),
+                (2.5, MidiMessage::note_off(0, 64, 0)),
+                (3.5, MidiMessage::program_change(0, 5)),
+                (4.0, MidiMessage::program_change(0, 7)),
+            ],
+        );
+        assert!((file.duration() - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn running_status_needs_a_previous_status() {
+        let track = [0x00, 0x3C, 0x64, 0x00, 0xFF, 0x2F, 0x00];
+        assert!(matches!(
+            MidiFile::parse(&smf(0, [0x00, 0x60], &[&track])),
+            Err(MidiFileError::MissingStatus(_))
+        ));
+    }
+
+    #[test]
+    fn smpte_timing_ignores_the_tempo() {
+        // (frames per second, ticks per frame, ticks in one second)
+        for (frames_per_second, ticks_per_frame, ticks) in [(25, 40, 1000), (29, 100, 2997)] {
+            let mut track = vec![0x00, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40];
+            track.extend_from_slice(&[0x00, 0x90, 0x3C, 0x64]);
+            write_vlq(&mut track, ticks);
+            track.extend_from_slice(&[0x80, 0x3C, 0x00]);
+            track.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+            let division = [(-(frames_per_second as i8)) as u8, ticks_per_frame];
+            let file = MidiFile::parse(&smf(0, division, &[&track])).unwrap();
+
+            assert_eq!(
+                file.timing,
+                MidiFileTiming::Smpte {
+                    frames_per_second,
+                    ticks_per_frame,
+                }
+            );
+            assert_times(
+                &file.timed_events(),
+                &[
+                    (0.0, MidiMessage::note_on(0, 60, 100)),
+                    (1.0, MidiMessage::note_off(0, 60, 0)),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let tracks = vec![
+            MidiFileTrack {
+                events: vec![
+                    (
+                        0,
+                        MidiFileEvent::Meta {
+                            kind: 0x03,
+                            data: b"lead".to_vec(),
+                        },
+                    ),
+                    (0, MidiFileEvent::Tempo(600_000)),
+                    (3840, MidiFileEvent::Tempo(400_000)),
+                ],
+            },
+            MidiFileTrack {
+                events: vec![
+                    (0, MidiFileEvent::SysEx(vec![0x7E, 0x7F, 0x09, 0x01, 0xF7])),
+                    (0, MidiFileEvent::Midi(MidiMessage::program_change(2, 40))),
+                    (0, MidiFileEvent::Midi(MidiMessage::note_on(2, 60, 100))),
+                    (480, MidiFileEvent::Midi(MidiMessage::pitch_bend(2, 0x2345))),
+                    (
+                        960,
+                        MidiFileEvent::Midi(MidiMessage::channel_pressure(2, 9)),
+                    ),
+                    // a delta that takes three bytes
+                    (
+                        960 + 20_000,
+                        MidiFileEvent::Midi(MidiMessage::note_off(2, 60, 0)),
+                    ),
+                ],
+            },
+        ];
+        let timings = [
+            MidiFileTiming::TicksPerQuarter(960),
+            MidiFileTiming::Smpte {
+                frames_per_second: 30,
+                ticks_per_frame: 80,
+            },
+        ];
+
+        for timing in timings {
+            let file = MidiFile {
+                format: 1,
+                timing,
+                tracks: tracks.clone(),
+            };
+            let parsed = MidiFile::parse(&file.to_bytes()).unwrap();
+            assert_eq!(parsed, file);
+        }
+    }
+
+    #[test]
+    fn recorded_events_keep_their_times() {
+        let events = [
+            (0.0, MidiMessage::note_on(0, 60, 100)),
+            (0.5, MidiMessage::note_off(0, 60, 0)),
+            (1.25, MidiMessage::control_change(1, 7, 64)),
+        ];
+        let file = MidiFile::parse(&MidiFile::from_events(events).to_bytes()).unwrap();
+        assert_times(&file.timed_events(), &events);
+    }
+}
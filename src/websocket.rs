@@ -0,0 +1,1041 @@
+//! A WebSocket server with a JSON protocol, for controlling graph parameters from remote UIs such as browser-based control surfaces.
+//!
+//! [`WebSocketServer`] accepts WebSocket connections and exchanges JSON text messages with each client.
+//! Every message is an object with a `type` field. Clients can send:
+//!
+//! | Type | Fields | Description |
+//! | --- | --- | --- |
+//! | `list` | | Lists the params and meters, replying with `params`. |
+//! | `get` | `name` | Gets the current value of a param, replying with `value`. |
+//! | `set` | `name`, `value` | Sets the value of a param. |
+//! | `subscribe` | `names` (optional) | Subscribes to changes of the given params and meters, or of all of them if `names` is omitted. The current values are sent straight away. |
+//! | `unsubscribe` | `names` (optional) | Unsubscribes from the given params and meters, or from all of them if `names` is omitted. |
+//! | `list_presets` | | Lists the presets, replying with `presets`. |
+//! | `load_preset` | `name` | Loads a preset, replying with `preset_loaded`. |
+//! | `save_preset` | `name` | Saves the current param values as a preset, replying with `preset_saved`. |
+//!
+//! The server can send:
+//!
+//! | Type | Fields | Description |
+//! | --- | --- | --- |
+//! | `params` | `params`, `meters` | An array of objects with the `name`, `signal_type`, `min`, `max` and `value` of each param, and an array of the names of the meters. |
+//! | `value` | `name`, `value` | The value of a param, in reply to `get` or because it changed while subscribed. |
+//! | `meter` | `name`, `peak`, `rms` | A new reading of a subscribed meter, with its peak and RMS levels (not in decibels). |
+//! | `presets` | `names` | The names of the presets. |
+//! | `preset_loaded` | `name` | A preset was loaded. |
+//! | `preset_saved` | `name` | A preset was saved. |
+//! | `error` | `message` | A request failed. |
+//!
+//! Values are plain JSON: numbers for `Float` and `Int` params, booleans for `Bool` params, strings for `String` params, arrays for `List` params, and arrays of bytes for `Midi` params.
+//! Meters are the [`MeterTap`](crate::builtins::MeterTap)s of the graph, read through their taps so that metering doesn't add work to the audio thread.
+//!
+//! For example, `{"type": "set", "name": "cutoff", "value": 440}` sets the param `cutoff`, and `{"type": "subscribe", "names": ["cutoff", "resonance"]}` makes the server send messages like `{"type": "value", "name": "resonance", "value": 0.25}` whenever the values change.
+
+use std::{
+    collections::BTreeMap,
+    io::{ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use rustc_hash::FxHashMap;
+use serde_json::Value;
+
+use crate::{
+    builtins::{MeterReader, MeterReading, Param, TapReader},
+    graph::{preset::Preset, Graph},
+    signal::{AnySignal, Float, List, MidiMessage, SignalType},
+};
+
+/// The GUID that is appended to the client's key to compute the handshake response, as defined by RFC 6455.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The largest handshake request the server accepts, in bytes.
+const MAX_HANDSHAKE_SIZE: usize = 8192;
+
+/// The largest message the server accepts, in bytes.
+const MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+/// How long a client has to complete the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Errors that can occur when running a [`WebSocketServer`] or serving a client.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum WebSocketError {
+    /// An I/O error occurred.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The client's handshake request was invalid.
+    #[error("Invalid handshake: {0}")]
+    Handshake(&'static str),
+
+    /// The client sent a frame that violates the WebSocket protocol.
+    #[error("Protocol error: {0}")]
+    Protocol(&'static str),
+
+    /// The client sent a message larger than the server accepts.
+    #[error("Message too large")]
+    MessageTooLarge,
+}
+
+/// A server that lets remote UIs list, set and subscribe to graph [`Param`]s, subscribe to meters and load presets over WebSocket.
+///
+/// See the [module-level documentation](self) for the protocol.
+///
+/// # Example
+///
+/// ```no_run
+/// use raug::prelude::*;
+/// use raug::websocket::WebSocketServer;
+///
+/// let graph = GraphBuilder::new();
+/// let _cutoff = graph.add_param(Param::bounded("cutoff", 1000.0, 20.0, 20000.0));
+/// // ...
+/// let runtime = graph.build_runtime();
+///
+/// let server = WebSocketServer::bind("127.0.0.1:9002")
+///     .unwrap()
+///     .with_graph(runtime.graph())
+///     .with_preset("dark", Preset::new().with_value("cutoff", 200.0))
+///     .run()
+///     .unwrap();
+/// ```
+pub struct WebSocketServer {
+    listener: TcpListener,
+    params: FxHashMap<String, Param>,
+    meters: FxHashMap<String, MeterReader>,
+    presets: BTreeMap<String, Preset>,
+    update_interval: Duration,
+}
+
+impl WebSocketServer {
+    /// Binds a new server to the given TCP address, such as `127.0.0.1:9002`.
+    ///
+    /// The server has no params until they're added with [`WebSocketServer::with_graph`] or [`WebSocketServer::with_param`].
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, WebSocketError> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            params: FxHashMap::default(),
+            meters: FxHashMap::default(),
+            presets: BTreeMap::new(),
+            update_interval: Duration::from_millis(50),
+        })
+    }
+
+    /// Exposes all params and meter taps of the graph, including those of its module instances.
+    pub fn with_graph(mut self, graph: &Graph) -> Self {
+        for name in graph.param_names() {
+            if let Some(param) = graph.param_named(&name) {
+                self.params.insert(name, param.clone());
+            }
+        }
+        for name in graph.tap_names() {
+            if let Some(TapReader::Meter(reader)) = graph.tap_named(&name) {
+                self.meters.insert(name, reader);
+            }
+        }
+        self
+    }
+
+    /// Exposes a single param under its name.
+    pub fn with_param(mut self, param: &Param) -> Self {
+        self.params.insert(param.name().to_string(), param.clone());
+        self
+    }
+
+    /// Exposes a meter under the given name, such as a [`MeterTap`](crate::builtins::MeterTap) looked up with [`RuntimeHandle::tap`](crate::runtime::RuntimeHandle::tap).
+    ///
+    /// The server consumes the meter's readings, so other readers of the same meter miss the readings it sends.
+    pub fn with_meter(mut self, name: impl Into<String>, meter: &MeterReader) -> Self {
+        self.meters.insert(name.into(), meter.clone());
+        self
+    }
+
+    /// Adds a preset that clients can load by name.
+    pub fn with_preset(mut self, name: impl Into<String>, preset: Preset) -> Self {
+        self.presets.insert(name.into(), preset);
+        self
+    }
+
+    /// Sets how often subscribed params and meters are checked for changes (default: 50 ms).
+    pub fn with_update_interval(mut self, interval: Duration) -> Self {
+        self.update_interval = interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Returns the local address the server is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr, WebSocketError> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Starts accepting connections on a background thread, serving each client on its own thread.
+    ///
+    /// The server and its connections stop when the returned handle is stopped or dropped.
+    pub fn run(self) -> Result<WebSocketServerHandle, WebSocketError> {
+        self.listener.set_nonblocking(true)?;
+        let local_addr = self.listener.local_addr()?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let shared = Arc::new(Shared {
+            params: self.params,
+            meters: self
+                .meters
+                .into_iter()
+                .map(|(name, reader)| {
+                    let meter = SharedMeter {
+                        reader,
+                        latest: Mutex::new(None),
+                    };
+                    (name, meter)
+                })
+                .collect(),
+            presets: Mutex::new(self.presets),
+        });
+        let handle = WebSocketServerHandle {
+            running: running.clone(),
+            local_addr,
+            shared: shared.clone(),
+        };
+
+        let listener = self.listener;
+        let update_interval = self.update_interval;
+        std::thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        let connection = Connection::new(stream, shared.clone());
+                        let running = running.clone();
+                        std::thread::spawn(move || {
+                            if let Err(err) = connection.serve(&running, update_interval) {
+                                log::warn!("WebSocket connection from {} closed: {}", peer, err);
+                            }
+                        });
+                    }
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(update_interval);
+                    }
+                    Err(err) => {
+                        log::error!("WebSocket accept error: {}", err);
+                        std::thread::sleep(update_interval);
+                    }
+                }
+            }
+        });
+
+        Ok(handle)
+    }
+}
+
+/// A handle to a running [`WebSocketServer`] that can be used to stop it.
+#[must_use = "The WebSocket server handle must be kept alive for the server to continue running"]
+pub struct WebSocketServerHandle {
+    running: Arc<AtomicBool>,
+    local_addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl WebSocketServerHandle {
+    /// Returns the local address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the server's presets, including those saved by clients.
+    pub fn presets(&self) -> BTreeMap<String, Preset> {
+        self.shared.presets.lock().unwrap().clone()
+    }
+
+    /// Stops the server and closes all connections. They finish within one update interval.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for WebSocketServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+struct Shared {
+    params: FxHashMap<String, Param>,
+    meters: FxHashMap<String, SharedMeter>,
+    presets: Mutex<BTreeMap<String, Preset>>,
+}
+
+impl Shared {
+    /// Returns the latest reading of the meter with the given name.
+    fn meter_reading(&self, name: &str) -> Option<MeterReading> {
+        let meter = self.meters.get(name)?;
+        // the connections share the readings, so the latest is kept for those that haven't seen it yet
+        let mut latest = meter.latest.lock().unwrap();
+        if let Some(reading) = meter.reader.read() {
+            *latest = Some(reading);
+        }
+        *latest
+    }
+}
+
+struct SharedMeter {
+    reader: MeterReader,
+    latest: Mutex<Option<MeterReading>>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    List,
+    Get { name: String },
+    Set { name: String, value: Value },
+    Subscribe { names: Option<Vec<String>> },
+    Unsubscribe { names: Option<Vec<String>> },
+    ListPresets,
+    LoadPreset { name: String },
+    SavePreset { name: String },
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response {
+    Params {
+        params: Vec<ParamInfo>,
+        meters: Vec<String>,
+    },
+    Value {
+        name: String,
+        value: Value,
+    },
+    Meter {
+        name: String,
+        peak: Float,
+        rms: Float,
+    },
+    Presets {
+        names: Vec<String>,
+    },
+    PresetLoaded {
+        name: String,
+    },
+    PresetSaved {
+        name: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(serde::Serialize)]
+struct ParamInfo {
+    name: String,
+    signal_type: SignalType,
+    min: Option<Float>,
+    max: Option<Float>,
+    value: Value,
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+struct Connection {
+    stream: TcpStream,
+    shared: Arc<Shared>,
+    // the last value sent for each subscribed param
+    subscriptions: FxHashMap<String, Option<AnySignal>>,
+    // the last reading sent for each subscribed meter
+    meter_subscriptions: FxHashMap<String, Option<MeterReading>>,
+    incoming: Vec<u8>,
+    // the fragments of a text message received so far
+    fragments: Option<Vec<u8>>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, shared: Arc<Shared>) -> Self {
+        Self {
+            stream,
+            shared,
+            subscriptions: FxHashMap::default(),
+            meter_subscriptions: FxHashMap::default(),
+            incoming: Vec::new(),
+            fragments: None,
+        }
+    }
+
+    fn serve(
+        mut self,
+        running: &AtomicBool,
+        update_interval: Duration,
+    ) -> Result<(), WebSocketError> {
+        self.stream.set_nonblocking(false)?;
+        self.stream.set_nodelay(true)?;
+        self.stream.set_read_timeout(Some(update_interval))?;
+        self.handshake(running)?;
+
+        let mut buf = [0; 4096];
+        let mut last_update = Instant::now();
+        while running.load(Ordering::Relaxed) {
+            match self.stream.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(len) => self.incoming.extend_from_slice(&buf[..len]),
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            while let Some(frame) = self.next_frame()? {
+                if !self.handle_frame(frame)? {
+                    return Ok(());
+                }
+            }
+
+            if last_update.elapsed() >= update_interval {
+                last_update = Instant::now();
+                self.send_updates()?;
+            }
+        }
+
+        // 1001: going away
+        self.send_frame(OPCODE_CLOSE, &1001u16.to_be_bytes())?;
+        Ok(())
+    }
+
+    fn handshake(&mut self, running: &AtomicBool) -> Result<(), WebSocketError> {
+        let start = Instant::now();
+        let mut buf = [0; 1024];
+        let header_end = loop {
+            if let Some(pos) = self.incoming.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if self.incoming.len() > MAX_HANDSHAKE_SIZE {
+                return Err(WebSocketError::Handshake("request too large"));
+            }
+            if start.elapsed() > HANDSHAKE_TIMEOUT || !running.load(Ordering::Relaxed) {
+                return Err(WebSocketError::Handshake("timed out"));
+            }
+            match self.stream.read(&mut buf) {
+                Ok(0) => return Err(WebSocketError::Handshake("connection closed")),
+                Ok(len) => self.incoming.extend_from_slice(&buf[..len]),
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        let request = String::from_utf8_lossy(&self.incoming[..header_end]).into_owned();
+        self.incoming.drain(..header_end);
+
+        let mut lines = request.lines();
+        let is_get = lines.next().is_some_and(|line| line.starts_with("GET "));
+        let mut upgrade = false;
+        let mut key = None;
+        for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+            let (name, value) = (name.trim(), value.trim());
+            if name.eq_ignore_ascii_case("upgrade") {
+                upgrade = value.eq_ignore_ascii_case("websocket");
+            } else if name.eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value);
+            }
+        }
+
+        let key = match key {
+            Some(key) if is_get && upgrade => key,
+            _ => {
+                self.stream
+                    .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+                return Err(WebSocketError::Handshake("not a WebSocket upgrade request"));
+            }
+        };
+
+        let accept = base64_encode(&sha1(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()));
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            accept
+        );
+        self.stream.write_all(response.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Parses the next complete frame from the incoming bytes, if there is one.
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebSocketError> {
+        let bytes = &self.incoming;
+        if bytes.len() < 2 {
+            return Ok(None);
+        }
+
+        let fin = bytes[0] & 0x80 != 0;
+        let opcode = bytes[0] & 0x0F;
+        let masked = bytes[1] & 0x80 != 0;
+        let (len, mut pos) = match bytes[1] & 0x7F {
+            126 if bytes.len() >= 4 => (u16::from_be_bytes([bytes[2], bytes[3]]) as u64, 4),
+            127 if bytes.len() >= 10 => (u64::from_be_bytes(bytes[2..10].try_into().unwrap()), 10),
+            126 | 127 => return Ok(None),
+            len => (len as u64, 2),
+        };
+
+        if len > MAX_MESSAGE_SIZE as u64 {
+            return Err(WebSocketError::MessageTooLarge);
+        }
+        let len = len as usize;
+
+        let mask = if masked {
+            let Some(mask) = bytes.get(pos..pos + 4) else {
+                return Ok(None);
+            };
+            pos += 4;
+            Some([mask[0], mask[1], mask[2], mask[3]])
+        } else {
+            None
+        };
+
+        if bytes.len() < pos + len {
+            return Ok(None);
+        }
+
+        let mut payload = bytes[pos..pos + len].to_vec();
+        if let Some(mask) = mask {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+        self.incoming.drain(..pos + len);
+
+        Ok(Some(Frame {
+            fin,
+            opcode,
+            payload,
+        }))
+    }
+
+    /// Handles a frame, returning `false` if the connection should be closed.
+    fn handle_frame(&mut self, frame: Frame) -> Result<bool, WebSocketError> {
+        match frame.opcode {
+            OPCODE_TEXT | OPCODE_CONTINUATION => {
+                let mut message = match (frame.opcode, self.fragments.take()) {
+                    (OPCODE_TEXT, None) => frame.payload,
+                    (OPCODE_CONTINUATION, Some(mut fragments)) => {
+                        fragments.extend_from_slice(&frame.payload);
+                        fragments
+                    }
+                    _ => return Err(WebSocketError::Protocol("unexpected continuation frame")),
+                };
+                if message.len() > MAX_MESSAGE_SIZE {
+                    return Err(WebSocketError::MessageTooLarge);
+                }
+
+                if frame.fin {
+                    let text = String::from_utf8(std::mem::take(&mut message))
+                        .map_err(|_| WebSocketError::Protocol("invalid UTF-8 in text message"))?;
+                    self.handle_text(&text)?;
+                } else {
+                    self.fragments = Some(message);
+                }
+            }
+            OPCODE_BINARY => {
+                self.send(&Response::Error {
+                    message: "Binary messages are not supported".to_string(),
+                })?;
+            }
+            OPCODE_CLOSE => {
+                // echo the status code back, as the closing handshake requires
+                let status = frame.payload.get(..2).unwrap_or_default();
+                self.send_frame(OPCODE_CLOSE, status)?;
+                return Ok(false);
+            }
+            OPCODE_PING => self.send_frame(OPCODE_PONG, &frame.payload)?,
+            OPCODE_PONG => {}
+            _ => return Err(WebSocketError::Protocol("unknown opcode")),
+        }
+
+        Ok(true)
+    }
+
+    fn handle_text(&mut self, text: &str) -> Result<(), WebSocketError> {
+        let request = match serde_json::from_str::<Request>(text) {
+            Ok(request) => request,
+            Err(err) => {
+                return self.send(&Response::Error {
+                    message: format!("Invalid request: {}", err),
+                });
+            }
+        };
+
+        let shared = self.shared.clone();
+        let response = match request {
+            Request::List => {
+                let mut params: Vec<ParamInfo> = shared
+                    .params
+                    .iter()
+                    .map(|(name, param)| ParamInfo {
+                        name: name.clone(),
+                        signal_type: param.signal_type(),
+                        min: param.minimum(),
+                        max: param.maximum(),
+                        value: param.last().as_ref().map_or(Value::Null, signal_to_json),
+                    })
+                    .collect();
+                params.sort_by(|a, b| a.name.cmp(&b.name));
+                let mut meters: Vec<String> = shared.meters.keys().cloned().collect();
+                meters.sort();
+                Response::Params { params, meters }
+            }
+            Request::Get { name } => match shared.params.get(&name) {
+                Some(param) => Response::Value {
+                    value: param.last().as_ref().map_or(Value::Null, signal_to_json),
+                    name,
+                },
+                None => unknown_param(&name),
+            },
+            Request::Set { name, value } => match shared.params.get(&name) {
+                Some(param) => match json_to_signal(&value, param.signal_type()) {
+                    Some(value) => {
+                        param.send_any(value);
+                        return Ok(());
+                    }
+                    None => Response::Error {
+                        message: format!(
+                            "Can't convert {} to {:?} for param {}",
+                            value,
+                            param.signal_type(),
+                            name
+                        ),
+                    },
+                },
+                None => unknown_param(&name),
+            },
+            Request::Subscribe { names } => {
+                let names = names.unwrap_or_else(|| {
+                    let params = shared.params.keys();
+                    params.chain(shared.meters.keys()).cloned().collect()
+                });
+                if let Some(name) = names.iter().find(|name| {
+                    !shared.params.contains_key(*name) && !shared.meters.contains_key(*name)
+                }) {
+                    Response::Error {
+                        message: format!("No param or meter named {}", name),
+                    }
+                } else {
+                    // the current values are sent with the next update
+                    for name in names {
+                        if shared.params.contains_key(&name) {
+                            self.subscriptions.insert(name, None);
+                        } else {
+                            self.meter_subscriptions.insert(name, None);
+                        }
+                    }
+                    return self.send_updates();
+                }
+            }
+            Request::Unsubscribe { names } => {
+                match names {
+                    Some(names) => {
+                        for name in names {
+                            self.subscriptions.remove(&name);
+                            self.meter_subscriptions.remove(&name);
+                        }
+                    }
+                    None => {
+                        self.subscriptions.clear();
+                        self.meter_subscriptions.clear();
+                    }
+                }
+                return Ok(());
+            }
+            Request::ListPresets => Response::Presets {
+                names: shared.presets.lock().unwrap().keys().cloned().collect(),
+            },
+            Request::LoadPreset { name } => match shared.presets.lock().unwrap().get(&name) {
+                Some(preset) => {
+                    for (param_name, param) in &shared.params {
+                        preset.apply_param(param_name, param);
+                    }
+                    Response::PresetLoaded { name }
+                }
+                None => Response::Error {
+                    message: format!("No preset named {}", name),
+                },
+            },
+            Request::SavePreset { name } => {
+                let mut preset = Preset::new();
+                for (param_name, param) in &shared.params {
+                    preset.capture_param(param_name.clone(), param);
+                }
+                shared.presets.lock().unwrap().insert(name.clone(), preset);
+                Response::PresetSaved { name }
+            }
+        };
+
+        self.send(&response)
+    }
+
+    /// Sends the values of subscribed params and the readings of subscribed meters that changed since they were last sent.
+    fn send_updates(&mut self) -> Result<(), WebSocketError> {
+        let mut updates = Vec::new();
+        for (name, sent) in &mut self.subscriptions {
+            let Some(param) = self.shared.params.get(name) else {
+                continue;
+            };
+            // MIDI params only hold events, so there is no value to keep in sync
+            if param.signal_type() == SignalType::Midi {
+                continue;
+            }
+            let value = param.last();
+            if value.is_some() && *sent != value {
+                updates.push(Response::Value {
+                    name: name.clone(),
+                    value: value.as_ref().map_or(Value::Null, signal_to_json),
+                });
+                *sent = value;
+            }
+        }
+
+        for (name, sent) in &mut self.meter_subscriptions {
+            let reading = self.shared.meter_reading(name);
+            if let Some(reading) = reading.filter(|_| *sent != reading) {
+                updates.push(Response::Meter {
+                    name: name.clone(),
+                    peak: reading.peak,
+                    rms: reading.rms,
+                });
+                *sent = Some(reading);
+            }
+        }
+
+        for update in &updates {
+            self.send(update)?;
+        }
+
+        Ok(())
+    }
+
+    fn send(&mut self, response: &Response) -> Result<(), WebSocketError> {
+        let text = serde_json::to_string(response).expect("responses always serialize");
+        self.send_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), WebSocketError> {
+        let mut frame = Vec::with_capacity(payload.len() + 10);
+        frame.push(0x80 | opcode);
+        match payload.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(payload);
+        self.stream.write_all(&frame)?;
+        Ok(())
+    }
+}
+
+fn unknown_param(name: &str) -> Response {
+    Response::Error {
+        message: format!("No param named {}", name),
+    }
+}
+
+fn signal_to_json(signal: &AnySignal) -> Value {
+    match signal {
+        AnySignal::Float(Some(f)) => {
+            serde_json::Number::from_f64(*f as f64).map_or(Value::Null, Value::Number)
+        }
+        AnySignal::Int(Some(i)) => Value::from(*i),
+        AnySignal::Bool(Some(b)) => Value::Bool(*b),
+        AnySignal::String(Some(s)) => Value::String(s.clone()),
+        AnySignal::List(Some(list)) => Value::Array(list.iter().map(signal_to_json).collect()),
+        AnySignal::Midi(Some(msg)) => msg.as_bytes().iter().map(|&b| Value::from(b)).collect(),
+        _ => Value::Null,
+    }
+}
+
+fn json_to_any_signal(value: &Value) -> Option<AnySignal> {
+    match value {
+        Value::Bool(b) => Some(AnySignal::Bool(Some(*b))),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(AnySignal::Int(Some(i))),
+            None => Some(AnySignal::Float(Some(n.as_f64()? as Float))),
+        },
+        Value::String(s) => Some(AnySignal::String(Some(s.clone()))),
+        Value::Array(items) => {
+            let signals: Vec<AnySignal> = items.iter().filter_map(json_to_any_signal).collect();
+            Some(AnySignal::List(Some(List::from_slice(&signals))))
+        }
+        Value::Null | Value::Object(_) => None,
+    }
+}
+
+fn json_to_signal(value: &Value, signal_type: SignalType) -> Option<AnySignal> {
+    match (value, signal_type) {
+        (Value::Array(items), SignalType::Midi) => {
+            let bytes = items
+                .iter()
+                .map(|item| item.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()?;
+            MidiMessage::from_bytes(&bytes).map(|msg| AnySignal::Midi(Some(msg)))
+        }
+        (value, signal_type) => json_to_any_signal(value)?.cast(signal_type),
+    }
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for chunk in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (w, bytes) in w.iter_mut().zip(chunk.chunks_exact(4)) {
+            *w = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, w) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*w);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, h) in digest.chunks_exact_mut(4).zip(h) {
+        bytes.copy_from_slice(&h.to_be_bytes());
+    }
+    digest
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{GraphBuilder, MeterTap};
+    use serde_json::json;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // connects to the server and completes the handshake, using the example key from RFC 6455
+    fn connect(address: SocketAddr) -> TcpStream {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        stream
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                  Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .unwrap();
+
+        let mut response = Vec::new();
+        let mut byte = [0];
+        while !response.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+        stream
+    }
+
+    // sends a masked text frame, as clients must
+    fn send(stream: &mut TcpStream, request: Value) {
+        let payload = request.to_string().into_bytes();
+        assert!(payload.len() <= 125);
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x80 | OPCODE_TEXT, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        stream.write_all(&frame).unwrap();
+    }
+
+    fn recv(stream: &mut TcpStream) -> Value {
+        let mut header = [0; 2];
+        stream.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], 0x80 | OPCODE_TEXT);
+        let len = match header[1] {
+            126 => {
+                let mut len = [0; 2];
+                stream.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload).unwrap();
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    // waits for the graph to receive a value from the server
+    fn wait_for_value(param: &Param) {
+        let start = Instant::now();
+        while param.recv().is_none() {
+            assert!(start.elapsed() < TIMEOUT, "the param wasn't set");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn serves_the_json_protocol() {
+        let param = Param::bounded("cutoff", 1000.0, 20.0, 20000.0);
+        param.recv();
+        let server = WebSocketServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_param(&param)
+            .with_preset("dark", Preset::new().with_value("cutoff", 200.0 as Float))
+            .with_update_interval(Duration::from_millis(5))
+            .run()
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+
+        send(&mut stream, json!({"type": "list"}));
+        let response = recv(&mut stream);
+        assert_eq!(response["type"], "params");
+        let params = response["params"].as_array().unwrap();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0]["name"], "cutoff");
+        assert_eq!(params[0]["min"], 20.0);
+        assert_eq!(params[0]["max"], 20000.0);
+        assert_eq!(params[0]["value"], 1000.0);
+
+        send(
+            &mut stream,
+            json!({"type": "set", "name": "cutoff", "value": 440}),
+        );
+        wait_for_value(&param);
+        assert_eq!(param.last(), Some(AnySignal::Float(Some(440.0))));
+
+        // the current value is sent straight away
+        send(
+            &mut stream,
+            json!({"type": "subscribe", "names": ["cutoff"]}),
+        );
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "value", "name": "cutoff", "value": 440.0})
+        );
+
+        send(&mut stream, json!({"type": "load_preset", "name": "dark"}));
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "preset_loaded", "name": "dark"})
+        );
+        wait_for_value(&param);
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "value", "name": "cutoff", "value": 200.0})
+        );
+
+        send(
+            &mut stream,
+            json!({"type": "load_preset", "name": "bright"}),
+        );
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "error", "message": "No preset named bright"})
+        );
+
+        server.stop();
+    }
+
+    #[test]
+    fn sends_meter_readings_to_subscribers() {
+        let graph = GraphBuilder::new();
+        let meter = graph.add_tap(MeterTap::new("level").with_window(0.01));
+        meter.input("in").connect(graph.constant(0.5).output(0));
+        let mut runtime = graph.build_runtime();
+        runtime
+            .run_offline(Duration::from_millis(20), 1000.0, 10)
+            .unwrap();
+
+        let server = WebSocketServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_graph(runtime.graph())
+            .with_update_interval(Duration::from_millis(5))
+            .run()
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+
+        send(&mut stream, json!({"type": "list"}));
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "params", "params": [], "meters": ["level"]})
+        );
+
+        send(
+            &mut stream,
+            json!({"type": "subscribe", "names": ["level"]}),
+        );
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "meter", "name": "level", "peak": 0.5, "rms": 0.5})
+        );
+
+        send(&mut stream, json!({"type": "subscribe", "names": ["peak"]}));
+        assert_eq!(
+            recv(&mut stream),
+            json!({"type": "error", "message": "No param or meter named peak"})
+        );
+
+        server.stop();
+    }
+}